@@ -0,0 +1,131 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Extract manual page sections from annotated comments in source files.
+//!
+//! An annotated block starts with a comment line of the form `//! man:
+//! SECTION` (or `# man: SECTION` in languages with `#` comments) and
+//! continues with every following comment line of the same kind:
+//!
+//! ```text
+//! //! man: ENVIRONMENT
+//! //! FROB_LEVEL sets the default level of frobnication.
+//! fn level() -> u8 { /* ... */ }
+//! ```
+//!
+//! Blocks for the same section are concatenated in the order they are
+//! found, so documentation can be spread across the files it describes.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let source = "# man: FILES\n# The cache lives in ~/.cache/frob.\nrm -rf ~/.cache/frob\n";
+//! let blocks = mdoc::annotations::scan(source);
+//! let mut page = Mdoc::default();
+//! mdoc::annotations::assemble(&mut page, blocks);
+//! assert_eq!(page.render(), ".Sh FILES\nThe cache lives in ~/.cache/frob.\n");
+//! ```
+
+use super::*;
+
+/// An annotated comment block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// Title of the section the block belongs to.
+    pub section: String,
+    /// Comment text, without the comment markers.
+    pub lines: Vec<String>,
+    /// Line number of the `man:` annotation, starting from 1.
+    pub line: usize,
+}
+
+const PREFIXES: [&str; 2] = ["//!", "#"];
+
+/// Find the annotated blocks in `source`.
+pub fn scan(source: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut current: Option<(&str, Block)> = None;
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some((prefix, block)) = current.as_mut() {
+            if let Some(comment) = trimmed.strip_prefix(*prefix) {
+                if !comment.trim_start().starts_with("man:") {
+                    let comment = comment.strip_prefix(' ').unwrap_or(comment);
+                    block.lines.push(comment.trim_end().to_string());
+                    continue;
+                }
+            }
+            blocks.push(current.take().unwrap().1);
+        }
+        for prefix in PREFIXES {
+            if let Some(section) = trimmed
+                .strip_prefix(prefix)
+                .and_then(|c| c.trim_start().strip_prefix("man:"))
+            {
+                current = Some((
+                    prefix,
+                    Block {
+                        section: section.trim().to_uppercase(),
+                        lines: vec![],
+                        line: i + 1,
+                    },
+                ));
+                break;
+            }
+        }
+    }
+    blocks.extend(current.map(|(_, block)| block));
+    blocks
+}
+
+/// Find the annotated blocks in each file of `paths`.
+pub fn scan_files<P: AsRef<std::path::Path>>(
+    paths: impl IntoIterator<Item = P>,
+) -> std::io::Result<Vec<Block>> {
+    let mut blocks = vec![];
+    for path in paths {
+        blocks.extend(scan(&std::fs::read_to_string(path)?));
+    }
+    Ok(blocks)
+}
+
+/// Append the blocks to `page`, one section per distinct section title, in
+/// order of first appearance.
+pub fn assemble(page: &mut Mdoc, blocks: impl IntoIterator<Item = Block>) {
+    let mut sections: Vec<(String, Vec<String>)> = vec![];
+    for block in blocks {
+        match sections
+            .iter_mut()
+            .find(|(title, _)| *title == block.section)
+        {
+            Some((_, lines)) => {
+                lines.push(String::new());
+                lines.extend(block.lines);
+            }
+            None => sections.push((block.section, block.lines)),
+        }
+    }
+    for (title, lines) in sections {
+        page.add_section(title, script::paragraphs(&lines));
+    }
+}
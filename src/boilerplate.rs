@@ -0,0 +1,494 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Ready-made sections for common manual page boilerplate.
+//!
+//! Every function returns a [`Boilerplate`] value which can be customized
+//! (for example by replacing its title with a translated one) before being
+//! added to a document with [`Boilerplate::add_to`].
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::boilerplate::*;
+//! let mut doc = Mdoc::default();
+//! reporting_bugs("https://github.com/epilys/mdoc/issues").add_to(&mut doc);
+//! assert_eq!(
+//!     doc.render(),
+//!     ".Sh REPORTING BUGS\nReport bugs at\n.Lk https://github.com/epilys/mdoc/issues .\n"
+//! );
+//! ```
+
+use super::*;
+
+/// A section with a title and a body, ready to be added to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Boilerplate {
+    /// Section title, without the `Sh` macro.
+    pub title: Str,
+    /// Section body.
+    pub lines: Vec<Line>,
+}
+
+impl Boilerplate {
+    pub fn new(title: impl Into<Str>, lines: Vec<Line>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+        }
+    }
+
+    /// Replace the section title, e.g. with a translated one.
+    pub fn with_title(mut self, title: impl Into<Str>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Replace the body text lines, keeping control lines intact.
+    ///
+    /// Text lines are replaced in order by the items of `texts`; this is
+    /// meant for localizing the prose of a section without rebuilding its
+    /// markup.
+    pub fn with_texts<S: Into<String>>(mut self, texts: impl IntoIterator<Item = S>) -> Self {
+        let mut texts = texts.into_iter();
+        for line in self.lines.iter_mut() {
+            if let Line::Text(_) = line {
+                match texts.next() {
+                    Some(t) => *line = Line::text(vec![roman(t)]),
+                    None => break,
+                }
+            }
+        }
+        self
+    }
+
+    /// Append this section to `doc`.
+    ///
+    /// The added lines are recorded as coming from this template, see
+    /// [`Origin::Template`].
+    pub fn add_to(self, doc: &mut Mdoc) {
+        let start = doc.lines.len();
+        let origin = Origin::Template(self.title.to_string());
+        doc.add_section(self.title, self.lines);
+        doc.set_origins(start..doc.lines.len(), origin);
+    }
+}
+
+fn control(name: &'static str, args: impl IntoIterator<Item = impl Into<Str>>) -> Line {
+    Line::control(name.into(), args.into_iter().map(Into::into).collect())
+}
+
+fn text(s: impl Into<String>) -> Line {
+    Line::text(vec![roman(s)])
+}
+
+/// A COPYRIGHT section for software licensed under the GNU GPL version 3 or
+/// later.
+pub fn copyright_gpl(year: impl std::fmt::Display, holder: impl std::fmt::Display) -> Boilerplate {
+    Boilerplate::new(
+        "COPYRIGHT",
+        vec![
+            text(format!("Copyright \\(co {year} {holder}.")),
+            text(
+                "This program is free software: you can redistribute it and/or modify it under \
+                 the terms of the GNU General Public License as published by the Free Software \
+                 Foundation, either version 3 of the License, or (at your option) any later \
+                 version.",
+            ),
+            text(
+                "This program is distributed in the hope that it will be useful, but WITHOUT ANY \
+                 WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A \
+                 PARTICULAR PURPOSE.",
+            ),
+        ],
+    )
+}
+
+/// A COPYRIGHT section for software licensed under the 2-clause BSD license,
+/// with the text of the license as published on the SPDX license list as
+/// `BSD-2-Clause`.
+pub fn copyright_bsd(year: impl std::fmt::Display, holder: impl std::fmt::Display) -> Boilerplate {
+    Boilerplate::new(
+        "COPYRIGHT",
+        vec![
+            text(format!("Copyright \\(co {year} {holder}.")),
+            text(
+                "Redistribution and use in source and binary forms, with or without modification, \
+                 are permitted provided that the following conditions are met:",
+            ),
+            control("Bl", ["-enum"]),
+            Line::control("It".into(), vec![]),
+            text(
+                "Redistributions of source code must retain the above copyright notice, this list \
+                 of conditions and the following disclaimer.",
+            ),
+            Line::control("It".into(), vec![]),
+            text(
+                "Redistributions in binary form must reproduce the above copyright notice, this \
+                 list of conditions and the following disclaimer in the documentation and/or \
+                 other materials provided with the distribution.",
+            ),
+            Line::control("El".into(), vec![]),
+            text(
+                "THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS \"AS IS\" \
+                 AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE \
+                 IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE \
+                 DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR \
+                 ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES \
+                 (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; \
+                 LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON \
+                 ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT \
+                 (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS \
+                 SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.",
+            ),
+        ],
+    )
+}
+
+/// The holder of a copyright and the years of their contributions, for
+/// [`copyright`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holder {
+    pub name: Str,
+    /// The year of the first contribution.
+    pub since: u32,
+    /// The year of the last contribution, or `None` if it is the current
+    /// year.
+    pub until: Option<u32>,
+}
+
+impl Holder {
+    pub fn new(name: impl Into<Str>, since: u32) -> Self {
+        Self {
+            name: name.into(),
+            since,
+            until: None,
+        }
+    }
+
+    /// Set the year of the last contribution.
+    pub fn until(self, year: u32) -> Self {
+        Self {
+            until: Some(year),
+            ..self
+        }
+    }
+
+    /// The years of the notice, as `2019\(en2024` or `2024`.
+    fn years(&self) -> String {
+        let until = self.until.unwrap_or_else(current_year);
+        if until > self.since {
+            format!("{}\\(en{until}", self.since)
+        } else {
+            self.since.to_string()
+        }
+    }
+}
+
+/// The current year, or the year of `SOURCE_DATE_EPOCH` if it is set so
+/// that builds are reproducible.
+fn current_year() -> u32 {
+    let seconds = source_date_epoch().unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    });
+    civil_date(seconds).0
+}
+
+/// The name of a license with an SPDX identifier, such as
+/// `GPL-3.0-or-later`, followed by the name of its exception, if any.
+fn license_name(id: &str) -> String {
+    let (id, exception) = match id.split_once(" WITH ") {
+        Some((id, exception)) => (id.trim(), Some(exception.trim())),
+        None => (id, None),
+    };
+    let name = match id {
+        "0BSD" => "BSD Zero Clause License",
+        "AGPL-3.0-only" => "GNU Affero General Public License version 3",
+        "AGPL-3.0-or-later" => "GNU Affero General Public License version 3 or later",
+        "Apache-2.0" => "Apache License 2.0",
+        "BSD-2-Clause" => "2-clause BSD License",
+        "BSD-3-Clause" => "3-clause BSD License",
+        "EUPL-1.2" => "European Union Public License 1.2",
+        "GPL-2.0-only" => "GNU General Public License version 2",
+        "GPL-2.0-or-later" => "GNU General Public License version 2 or later",
+        "GPL-3.0-only" => "GNU General Public License version 3",
+        "GPL-3.0-or-later" => "GNU General Public License version 3 or later",
+        "ISC" => "ISC License",
+        "LGPL-2.1-only" => "GNU Lesser General Public License version 2.1",
+        "LGPL-2.1-or-later" => "GNU Lesser General Public License version 2.1 or later",
+        "LGPL-3.0-only" => "GNU Lesser General Public License version 3",
+        "LGPL-3.0-or-later" => "GNU Lesser General Public License version 3 or later",
+        "MIT" => "MIT License",
+        "MPL-2.0" => "Mozilla Public License 2.0",
+        "Unlicense" => "Unlicense",
+        "Zlib" => "zlib License",
+        other => other,
+    };
+    match exception {
+        Some(exception) => format!("{name} with the {exception}"),
+        None => name.to_string(),
+    }
+}
+
+/// The name of a license and a link to its text on the SPDX license list.
+fn license(id: &str) -> [Line; 2] {
+    let id = id.trim();
+    let base = id.split_once(" WITH ").map_or(id, |(id, _)| id.trim());
+    [
+        text(format!("the {}, see", license_name(id))),
+        control(
+            "Lk",
+            [format!("https://spdx.org/licenses/{base}.html"), ".".into()],
+        ),
+    ]
+}
+
+/// A COPYRIGHT section with the notices of `holders` and the licenses of
+/// the SPDX license expression `spdx`, such as `MIT OR Apache-2.0`.
+///
+/// Choices between licenses with `OR` and combinations with `AND` are
+/// listed with a link to the text of each license. Other expressions, such
+/// as those with parentheses, are quoted as they are.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::boilerplate::*;
+/// let mut doc = Mdoc::default();
+/// copyright([Holder::new("Jane Doe", 2019).until(2024)], "MIT").add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh COPYRIGHT\nCopyright \\(co 2019\\(en2024 Jane Doe.\n\
+///      This program is licensed under\nthe MIT License, see\n\
+///      .Lk https://spdx.org/licenses/MIT.html .\n"
+/// );
+/// ```
+pub fn copyright(holders: impl IntoIterator<Item = Holder>, spdx: &str) -> Boilerplate {
+    let mut lines = vec![];
+    for (i, holder) in holders.into_iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::control("br".into(), vec![]));
+        }
+        lines.push(text(format!(
+            "Copyright \\(co {} {}.",
+            holder.years(),
+            holder.name
+        )));
+    }
+    let spdx = spdx.trim();
+    let (intro, ids): (&str, Vec<&str>) = if spdx.contains(['(', ')']) {
+        ("", vec![])
+    } else if spdx.contains(" OR ") && !spdx.contains(" AND ") {
+        (
+            "This program is available under your choice of the following licenses:",
+            spdx.split(" OR ").collect(),
+        )
+    } else if spdx.contains(" AND ") && !spdx.contains(" OR ") {
+        (
+            "This program is licensed under all of the following licenses:",
+            spdx.split(" AND ").collect(),
+        )
+    } else {
+        ("This program is licensed under", vec![spdx])
+    };
+    match ids.as_slice() {
+        [] => {
+            lines.push(text(
+                "This program is licensed under the license expression",
+            ));
+            lines.push(control("Ql", [spdx.to_string(), ".".into()]));
+        }
+        [id] => {
+            lines.push(text(intro));
+            lines.extend(license(id));
+        }
+        ids => {
+            lines.push(text(intro));
+            lines.push(control("Bl", ["-bullet"]));
+            for id in ids {
+                lines.push(Line::control("It".into(), vec![]));
+                lines.extend(license(id));
+            }
+            lines.push(Line::control("El".into(), vec![]));
+        }
+    }
+    Boilerplate::new("COPYRIGHT", lines)
+}
+
+/// A REPORTING BUGS section pointing to an issue tracker.
+pub fn reporting_bugs(url: impl Into<Str>) -> Boilerplate {
+    Boilerplate::new(
+        "REPORTING BUGS",
+        vec![
+            text("Report bugs at"),
+            control("Lk", [url.into(), ".".into()]),
+        ],
+    )
+}
+
+/// A FILES section listing the conventional per-user configuration and cache
+/// directories of `app` on Linux and macOS.
+pub fn config_files(app: &str) -> Boilerplate {
+    let entry = |path: String, what: &str| {
+        [
+            control("It", ["Pa".to_string(), path]),
+            text(what.to_string()),
+        ]
+    };
+    let mut lines = vec![control("Bl", ["-tag", "-width", "Ds"])];
+    lines.extend(entry(
+        format!("~/.config/{app}/"),
+        "Configuration directory on Linux and other Unix systems.",
+    ));
+    lines.extend(entry(
+        format!("~/Library/Application Support/{app}/"),
+        "Configuration directory on macOS.",
+    ));
+    lines.extend(entry(
+        format!("~/.cache/{app}/"),
+        "Cache directory on Linux and other Unix systems.",
+    ));
+    lines.extend(entry(
+        format!("~/Library/Caches/{app}/"),
+        "Cache directory on macOS.",
+    ));
+    lines.push(Line::control("El".into(), vec![]));
+    Boilerplate::new("FILES", lines)
+}
+
+/// A FILES section documenting the XDG base directories used by `app`.
+///
+/// Each entry documents the path under its `XDG_*` environment variable
+/// and the fallback used when the variable is unset, as specified by the
+/// XDG Base Directory Specification. `config_file` is the name of the
+/// configuration file inside the configuration directory, e.g.
+/// `config.toml`.
+pub fn xdg_files(app: &str, config_file: &str) -> Boilerplate {
+    let entry = |var: &'static str, fallback: &str, path: &str, what: &str| {
+        [
+            control("It", ["Pa".to_string(), format!("${var}/{app}/{path}")]),
+            text(what.to_string()),
+            text("If"),
+            control("Ev", [var]),
+            text("is unset,"),
+            control("Pa", [format!("{fallback}/{app}/{path}")]),
+            text("is used instead."),
+        ]
+    };
+    let mut lines = vec![control("Bl", ["-tag", "-width", "Ds"])];
+    lines.extend(entry(
+        "XDG_CONFIG_HOME",
+        "~/.config",
+        config_file,
+        "Configuration file.",
+    ));
+    lines.extend(entry(
+        "XDG_CACHE_HOME",
+        "~/.cache",
+        "",
+        "Cache directory; its contents can be removed at any time.",
+    ));
+    lines.extend(entry(
+        "XDG_STATE_HOME",
+        "~/.local/state",
+        "",
+        "State directory, for history and logs that persist between runs.",
+    ));
+    lines.extend(entry(
+        "XDG_DATA_HOME",
+        "~/.local/share",
+        "",
+        "Data directory.",
+    ));
+    lines.push(Line::control("El".into(), vec![]));
+    Boilerplate::new("FILES", lines)
+}
+
+/// A shell supported by [`shell_completions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    PowerShell,
+}
+
+impl Shell {
+    /// The name of the shell executable.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Elvish => "elvish",
+            Self::PowerShell => "pwsh",
+        }
+    }
+
+    /// The conventional system-wide installation path of a completion
+    /// file for `name`.
+    pub fn default_path(self, name: &str) -> String {
+        match self {
+            Self::Bash => format!("/usr/share/bash-completion/completions/{name}"),
+            Self::Zsh => format!("/usr/share/zsh/site-functions/_{name}"),
+            Self::Fish => format!("/usr/share/fish/vendor_completions.d/{name}.fish"),
+            Self::Elvish => format!("/usr/share/elvish/lib/{name}.elv"),
+            Self::PowerShell => format!("/usr/share/powershell/Modules/{name}/{name}.ps1"),
+        }
+    }
+
+    fn enable_hint(self) -> &'static str {
+        match self {
+            Self::Bash => {
+                "Loaded on demand by bash-completion; otherwise source the file from ~/.bashrc."
+            }
+            Self::Zsh => "The directory must be in fpath before compinit is called in ~/.zshrc.",
+            Self::Fish => "Loaded automatically by fish.",
+            Self::Elvish => "Import the module from ~/.config/elvish/rc.elv with use.",
+            Self::PowerShell => "Dot-source the file from the PowerShell profile.",
+        }
+    }
+}
+
+/// A SHELL COMPLETION section documenting where the completion files of
+/// `name` are installed and how to enable them in each shell.
+///
+/// `completions` pairs each shell with the installed path of its completion
+/// file; use [`Shell::default_path`] for the conventional locations.
+pub fn shell_completions(name: &str, completions: &[(Shell, String)]) -> Boilerplate {
+    let mut lines = vec![
+        text(format!(
+            "Completion scripts for {name} are installed for the following shells:"
+        )),
+        control("Bl", ["-tag", "-width", "Ds"]),
+    ];
+    for (shell, path) in completions {
+        lines.push(control("It", ["Cm", shell.name()]));
+        lines.push(control("Pa", [path.clone()]));
+        lines.push(text(shell.enable_hint()));
+    }
+    lines.push(Line::control("El".into(), vec![]));
+    Boilerplate::new("SHELL COMPLETION", lines)
+}
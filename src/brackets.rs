@@ -0,0 +1,149 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Bracketed groups of optional arguments in synopses written as usage
+//! text, such as `frob [-v] [-o [file]]`, and their `Op`, `Oo` and `Oc`
+//! macro lines.
+
+use super::*;
+
+/// An item of a synopsis: the words of a macro line, or a bracketed group
+/// of items.
+enum Item {
+    Words(Vec<Str>),
+    Group(Vec<Item>),
+}
+
+impl Item {
+    /// The arguments of an `Op` line with the items of a group, if only
+    /// its last item is a group, which the `Op` line can end with too.
+    fn op(items: &[Item]) -> Option<Vec<Str>> {
+        let mut words = vec![];
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                Self::Words(w) => words.extend(w.iter().cloned()),
+                Self::Group(group) if i + 1 == items.len() => {
+                    words.push("Op".into());
+                    words.extend(Self::op(group)?);
+                }
+                Self::Group(_) => return None,
+            }
+        }
+        Some(words)
+    }
+
+    /// Append the lines of the item to `lines`: an `Op` line for a group,
+    /// or an `Oo` and `Oc` block if a nested group isn't the last thing in
+    /// it, since `Op` encloses the rest of its line.
+    fn lines(self, lines: &mut Vec<Line>) {
+        let items = match self {
+            Self::Words(mut words) => {
+                let name = words.remove(0);
+                return lines.push(Line::control(name, words));
+            }
+            Self::Group(items) => items,
+        };
+        if let Some(words) = Self::op(&items) {
+            lines.push(Line::control("Op".into(), words));
+            return;
+        }
+        lines.push(Line::control("Oo".into(), vec![]));
+        for item in items {
+            item.lines(lines);
+        }
+        lines.push(Line::control("Oc".into(), vec![]));
+    }
+}
+
+/// The items of a synopsis line, as its brackets are opened and closed.
+pub(crate) struct Groups {
+    /// The items of the open groups, innermost last, after the items
+    /// outside of any group.
+    open: Vec<Vec<Item>>,
+}
+
+impl Groups {
+    pub(crate) fn new() -> Self {
+        Self { open: vec![vec![]] }
+    }
+
+    fn items(&mut self) -> &mut Vec<Item> {
+        self.open.last_mut().expect("the outermost items")
+    }
+
+    /// Open a group.
+    pub(crate) fn open(&mut self) {
+        self.open.push(vec![]);
+    }
+
+    /// Close the innermost group, or return `false` if none is open.
+    pub(crate) fn close(&mut self) -> bool {
+        if self.open.len() == 1 {
+            return false;
+        }
+        let group = self.open.pop().expect("a group is open");
+        self.items().push(Item::Group(group));
+        true
+    }
+
+    /// Whether any group is still open.
+    pub(crate) fn is_open(&self) -> bool {
+        self.open.len() > 1
+    }
+
+    /// Close the groups that are still open.
+    #[cfg(feature = "markdown")]
+    pub(crate) fn close_all(&mut self) {
+        while self.close() {}
+    }
+
+    /// Drop the groups that are still open, with their items.
+    pub(crate) fn drop_open(&mut self) {
+        self.open.truncate(1);
+    }
+
+    /// Add a macro line with its `words`.
+    pub(crate) fn push(&mut self, words: Vec<Str>) {
+        self.items().push(Item::Words(words));
+    }
+
+    /// Add an ellipsis or a bar, which follows the preceding argument, or
+    /// is written on its own after a group.
+    pub(crate) fn follow(&mut self, word: Str) {
+        let items = self.items();
+        match items.last_mut() {
+            Some(Item::Words(last)) => last.push(word),
+            Some(Item::Group(_)) => items.push(Item::Words(vec!["No".into(), word])),
+            None => items.push(Item::Words(vec!["Ar".into(), word])),
+        }
+    }
+
+    /// The lines of the synopsis, without the groups that are still open.
+    pub(crate) fn lines(mut self) -> Vec<Line> {
+        self.drop_open();
+        let mut lines = vec![];
+        for item in self.open.pop().expect("the outermost items") {
+            item.lines(&mut lines);
+        }
+        lines
+    }
+}
@@ -0,0 +1,93 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Generating pages from build scripts.
+//!
+//! [`generate_to`] writes rendered pages to a directory, usually
+//! [`out_dir`], named after their name and section as man(1) expects, so
+//! that `build.rs` files don't have to reimplement the file plumbing.
+//!
+//! # Example
+//!
+//! ```no_run
+//! // build.rs
+//! # use mdoc::*;
+//! let page = Mdoc::parse(".Dd $Mdocdate$\n.Dt FROB 1\n.Os\n.Sh NAME\n.Nm frob\n.Nd frobnicate\n");
+//! let dir = build_helper::out_dir().unwrap().join("man");
+//! for path in build_helper::generate_to(&dir, [&page]).unwrap() {
+//!     println!("cargo:warning=wrote {}", path.display());
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+/// The `OUT_DIR` of the build script being run.
+pub fn out_dir() -> std::io::Result<PathBuf> {
+    std::env::var_os("OUT_DIR")
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "OUT_DIR is not set, which it is only for build scripts",
+            )
+        })
+}
+
+/// The file name of `page`: its name, or else its lowercased title, and its
+/// section, as in `frob.1`.
+pub fn file_name(page: &Mdoc) -> String {
+    let name = match page.name.0.as_ref() {
+        "" => page.title.title.0.to_lowercase(),
+        name => name.to_string(),
+    };
+    format!("{name}.{}", page.title.section.0)
+}
+
+/// Write `pages` to `dir`, creating it if needed, and return the paths of
+/// the files written.
+///
+/// Pages are stamped with their [provenance], and existing files without a
+/// stamp or edited since they were generated are not overwritten: an error
+/// is returned instead, see [`provenance::check_overwrite`]. Pages without
+/// a date are dated by `SOURCE_DATE_EPOCH` if it is set, see
+/// [`RenderOptions::default_date`].
+pub fn generate_to<'a>(
+    dir: &Path,
+    pages: impl IntoIterator<Item = &'a Mdoc>,
+) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let options = RenderOptions {
+        provenance: Some(provenance::Provenance::default()),
+        default_date: DocumentDate::from_source_date_epoch(),
+        ..RenderOptions::default()
+    };
+    let mut ret = vec![];
+    for page in pages {
+        let path = dir.join(file_name(page));
+        provenance::check_overwrite(&path)?;
+        let source = page.render_with(&options);
+        std::fs::write(&path, parser::encode(&source, page.encoding()))?;
+        ret.push(path);
+    }
+    Ok(ret)
+}
@@ -0,0 +1,238 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Section 8 pages for daemons and other system administration utilities.
+//!
+//! A [`Daemon`] describes the operational details that every daemon page
+//! documents the same way: the privileges it needs, how it is started by
+//! the service manager, its run-time files and the signals it handles.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::daemon::*;
+//! let frobd = Daemon::new("frobd")
+//!     .privileges("It must be started as root and drops privileges after binding its socket.")
+//!     .pid_file("/var/run/frobd.pid")
+//!     .socket("/var/run/frobd.sock")
+//!     .systemd_unit("frobd.service")
+//!     .signal("SIGHUP", "Reload the configuration file.");
+//! let page = frobd.page(description!("frobnication daemon"));
+//! let text = page.render();
+//! assert!(text.contains(".Dt FROBD 8\n"));
+//! assert!(text.contains(".Sh SIGNALS\n.Bl -tag -width Ds\n.It Dv SIGHUP\n"));
+//! assert!(text.contains(".It Pa /var/run/frobd.pid\n"));
+//! ```
+
+use super::*;
+use crate::boilerplate::Boilerplate;
+
+/// A declarative description of a daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Daemon {
+    pub name: String,
+    pub privileges: Option<String>,
+    pub config_file: Option<String>,
+    pub pid_file: Option<String>,
+    pub sockets: Vec<String>,
+    pub rc_script: Option<String>,
+    pub systemd_unit: Option<String>,
+    pub signals: Vec<(String, String)>,
+}
+
+impl Daemon {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Describe the privileges the daemon requires, as a sentence.
+    pub fn privileges(mut self, privileges: impl Into<String>) -> Self {
+        self.privileges = Some(privileges.into());
+        self
+    }
+
+    pub fn config_file(mut self, path: impl Into<String>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    pub fn pid_file(mut self, path: impl Into<String>) -> Self {
+        self.pid_file = Some(path.into());
+        self
+    }
+
+    /// Add a socket the daemon listens on.
+    pub fn socket(mut self, path: impl Into<String>) -> Self {
+        self.sockets.push(path.into());
+        self
+    }
+
+    /// The rc.d script that starts the daemon, e.g. `/etc/rc.d/frobd`.
+    pub fn rc_script(mut self, path: impl Into<String>) -> Self {
+        self.rc_script = Some(path.into());
+        self
+    }
+
+    /// The systemd unit that starts the daemon, e.g. `frobd.service`.
+    pub fn systemd_unit(mut self, unit: impl Into<String>) -> Self {
+        self.systemd_unit = Some(unit.into());
+        self
+    }
+
+    /// Add a signal the daemon handles, e.g. `SIGHUP`.
+    pub fn signal(mut self, signal: impl Into<String>, action: impl Into<String>) -> Self {
+        self.signals.push((signal.into(), action.into()));
+        self
+    }
+
+    /// Paragraphs on privileges and service manager integration, for the
+    /// DESCRIPTION section.
+    pub fn description(&self) -> Vec<Line> {
+        let mut lines = vec![];
+        if let Some(privileges) = &self.privileges {
+            lines.push(Line::control("Pp".into(), vec![]));
+            lines.push(Line::text(vec![roman(privileges.as_str())]));
+        }
+        if self.rc_script.is_some() || self.systemd_unit.is_some() {
+            lines.push(Line::control("Pp".into(), vec![]));
+            lines.push(Line::text(vec![
+                Inline::Name,
+                roman(" is normally started at boot time by"),
+            ]));
+            if let Some(rc_script) = &self.rc_script {
+                lines.push(Line::control("Xr".into(), vec!["rc.d".into(), "8".into()]));
+                lines.push(Line::text(vec![roman("using")]));
+                if self.systemd_unit.is_some() {
+                    lines.push(Line::control("Pa".into(), vec![rc_script.clone().into()]));
+                    lines.push(Line::text(vec![roman("or by")]));
+                } else {
+                    lines.push(Line::control(
+                        "Pa".into(),
+                        vec![rc_script.clone().into(), ".".into()],
+                    ));
+                }
+            }
+            if let Some(unit) = &self.systemd_unit {
+                lines.push(Line::control(
+                    "Xr".into(),
+                    vec!["systemd".into(), "1".into()],
+                ));
+                lines.push(Line::text(vec![roman("using the")]));
+                lines.push(Line::control("Pa".into(), vec![unit.clone().into()]));
+                lines.push(Line::text(vec![roman("unit.")]));
+            }
+        }
+        lines
+    }
+
+    /// A SIGNALS section listing the handled signals.
+    pub fn signals(&self) -> Boilerplate {
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for (signal, action) in &self.signals {
+            lines.push(Line::control(
+                "It".into(),
+                vec!["Dv".into(), signal.clone().into()],
+            ));
+            lines.push(Line::text(vec![roman(action.as_str())]));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        Boilerplate::new("SIGNALS", lines)
+    }
+
+    /// The configuration, pid, socket and service files of the daemon, with
+    /// a description of each.
+    fn paths(&self) -> Vec<(&str, &'static str)> {
+        let mut files: Vec<(&str, &'static str)> = vec![];
+        if let Some(path) = &self.config_file {
+            files.push((path, "Configuration file."));
+        }
+        if let Some(path) = &self.pid_file {
+            files.push((path, "Process ID of the running daemon."));
+        }
+        for path in &self.sockets {
+            files.push((path, "Control socket."));
+        }
+        if let Some(path) = &self.rc_script {
+            files.push((path, "Startup script."));
+        }
+        files
+    }
+
+    /// A FILES section listing the configuration, pid, socket and service
+    /// files of the daemon.
+    pub fn files(&self) -> Boilerplate {
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for (path, what) in self.paths() {
+            lines.push(Line::control(
+                "It".into(),
+                vec!["Pa".into(), path.to_string().into()],
+            ));
+            lines.push(Line::text(vec![roman(what)]));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        Boilerplate::new("FILES", lines)
+    }
+
+    /// A section 8 page skeleton with SYNOPSIS, DESCRIPTION, SIGNALS and
+    /// FILES sections assembled from this descriptor. SIGNALS and FILES are
+    /// left out if the daemon has no signals or files.
+    pub fn page(&self, description: Description) -> Mdoc {
+        let mut page = self.skeleton(description);
+        if !self.paths().is_empty() {
+            self.files().add_to(&mut page);
+        }
+        page
+    }
+
+    /// The page without its FILES section.
+    pub(crate) fn skeleton(&self, description: Description) -> Mdoc {
+        let mut page = Mdoc::new(
+            None,
+            DocumentTitle {
+                title: Title::new(self.name.to_uppercase()),
+                section: Section::new("8"),
+                arch: None,
+            },
+            Name::new(self.name.clone()),
+            description,
+            None,
+        );
+        page.add_section("SYNOPSIS", [Line::control("Nm".into(), vec![])]);
+        let mut lines = vec![Line::text(vec![Inline::Name, roman(" is a daemon.")])];
+        lines.extend(self.description());
+        page.add_section("DESCRIPTION", lines);
+        if !self.signals.is_empty() {
+            self.signals().add_to(&mut page);
+        }
+        page
+    }
+}
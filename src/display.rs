@@ -0,0 +1,138 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use super::*;
+
+/// How the lines of a [`DisplayBlock`] are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayKind {
+    /// As-is, in a constant-width font, for code.
+    Literal,
+    /// As-is, in the normal font.
+    Unfilled,
+    /// Filled and justified.
+    Filled,
+    /// Filled, with a ragged right margin.
+    Ragged,
+    /// Filled, with each line centered.
+    Centered,
+}
+
+/// A `Bd` display block, which is always closed with `Ed`.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.display(DisplayBlock::code("$ printf '%s\\n' \\\n    .hidden").offset("indent"));
+/// assert_eq!(
+///     doc.to_mdoc(),
+///     ".Bd -literal -offset indent\n$ printf '%s\\en' \\e\n\\&    .hidden\n.Ed\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayBlock {
+    kind: DisplayKind,
+    offset: Option<String>,
+    compact: bool,
+    lines: Vec<Line>,
+}
+
+impl DisplayBlock {
+    pub fn new(kind: DisplayKind) -> Self {
+        Self {
+            kind,
+            offset: None,
+            compact: false,
+            lines: vec![],
+        }
+    }
+
+    /// A literal block with the lines of `code`.
+    pub fn code(code: &str) -> Self {
+        Self::new(DisplayKind::Literal).verbatim(code)
+    }
+
+    /// Indent the block, e.g. by `indent`.
+    pub fn offset(self, offset: impl Into<String>) -> Self {
+        Self {
+            offset: Some(offset.into()),
+            ..self
+        }
+    }
+
+    /// Don't put a blank line before the block.
+    pub fn compact(self) -> Self {
+        Self {
+            compact: true,
+            ..self
+        }
+    }
+
+    /// Append a line.
+    pub fn line(mut self, line: Line) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    /// Append a text line.
+    pub fn text(self, text: impl Into<Vec<Inline>>) -> Self {
+        self.line(Line::text(text.into()))
+    }
+
+    /// Append the lines of `text`, escaped so that they are printed as
+    /// they are: backslashes aren't interpreted as escapes and leading
+    /// periods and apostrophes as control lines.
+    pub fn verbatim(mut self, text: &str) -> Self {
+        for line in text.lines() {
+            let mut line = line.replace('\\', "\\e");
+            if line.trim_start().starts_with(['.', '\'']) {
+                line.insert_str(0, "\\&");
+            }
+            self.lines.push(Line::text(vec![roman(line)]));
+        }
+        self
+    }
+
+    /// The `Bd` and `Ed` lines of the block, around its content.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut args: Vec<Str> = vec![match self.kind {
+            DisplayKind::Literal => "-literal".into(),
+            DisplayKind::Unfilled => "-unfilled".into(),
+            DisplayKind::Filled => "-filled".into(),
+            DisplayKind::Ragged => "-ragged".into(),
+            DisplayKind::Centered => "-centered".into(),
+        }];
+        if let Some(offset) = &self.offset {
+            args.push("-offset".into());
+            args.push(offset.clone().into());
+        }
+        if self.compact {
+            args.push("-compact".into());
+        }
+        let mut lines = vec![Line::control("Bd".into(), args)];
+        lines.extend(self.lines.iter().cloned());
+        lines.push(Line::control("Ed".into(), vec![]));
+        lines
+    }
+}
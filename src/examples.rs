@@ -0,0 +1,218 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Checking that the commands of the EXAMPLES section still work.
+//!
+//! Examples are opt-in: a literal display is runnable if it follows an
+//! `.\" mdoc-example` comment, as written by [`runnable`]. [`run`] passes
+//! each command of the runnable displays of a page to an executor and
+//! collects the failures, so that a test can check them after changes to
+//! the interface of the program. The executor is up to the caller: run the
+//! commands with [`shell`], or only check that they parse, as
+//! [`from_clap::example_parser`](crate::from_clap::example_parser) does.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let mut page = Mdoc::default();
+//! page.examples(examples::runnable(["echo hi", "false"]));
+//! let failures = examples::run(&page, examples::shell);
+//! assert_eq!(failures.len(), 1);
+//! assert_eq!(failures[0].to_string(), "`false`: exit status: 1");
+//! ```
+
+use super::*;
+
+/// The comment that marks the following display as runnable.
+const MARKER: &str = "mdoc-example";
+
+/// A command of a runnable display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// The title of the section the example is in.
+    pub section: String,
+    /// The command, without its `$` prompt.
+    pub command: String,
+}
+
+/// An example that its executor reported as failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub example: Example,
+    pub message: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`: {}", self.example.command, self.message)
+    }
+}
+
+/// A runnable display of `commands`, shown after a `$` prompt.
+pub fn runnable<S: AsRef<str>>(commands: impl IntoIterator<Item = S>) -> Vec<Line> {
+    let mut lines = vec![
+        Line::control("\\\"".into(), vec![MARKER.into()]),
+        Line::control(
+            "Bd".into(),
+            vec!["-literal".into(), "-offset".into(), "indent".into()],
+        ),
+    ];
+    for command in commands {
+        let command = command.as_ref().replace('\\', "\\e");
+        lines.push(Line::text(vec![roman(format!("$ {command}"))]));
+    }
+    lines.push(Line::control("Ed".into(), vec![]));
+    lines
+}
+
+/// The commands of the runnable displays of `page`.
+///
+/// Lines starting with a `$` prompt are commands and the others their
+/// output, or if no line has a prompt, every line is a command. Lines
+/// ending with a backslash are continued on the next one.
+pub fn examples(page: &Mdoc) -> Vec<Example> {
+    let glyphs = term::Charset::Ascii.glyphs();
+    let lines = page.document_lines();
+    let mut ret = vec![];
+    let mut section = String::new();
+    let mut marked = false;
+    let mut display: Option<Vec<String>> = None;
+    for line in lines.iter() {
+        match (line, display.as_mut()) {
+            (Line::Control { name, args }, None) if name == "Sh" => {
+                section = parser::unquote(&args.join(" "));
+                marked = false;
+            }
+            (Line::Control { name, args }, None) if name == "\\\"" => {
+                marked = args.first().is_some_and(|a| a == MARKER);
+            }
+            (Line::Control { name, .. }, None) if name == "Bd" && marked => {
+                display = Some(vec![]);
+                marked = false;
+            }
+            (Line::Control { name, .. }, Some(_)) if name == "Ed" => {
+                let text = display.take().unwrap_or_default();
+                ret.extend(commands(&text).into_iter().map(|command| Example {
+                    section: section.clone(),
+                    command,
+                }));
+            }
+            (Line::Text(inlines), Some(text)) => {
+                let line: String = inlines
+                    .iter()
+                    .filter_map(|i| match i {
+                        Inline::Roman(t)
+                        | Inline::Roff(t)
+                        | Inline::Bold(t)
+                        | Inline::Italic(t) => Some(t.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                text.push(glyphs.unescape(&line));
+            }
+            (_, None) => marked = false,
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// The commands of the lines of a display.
+fn commands(lines: &[String]) -> Vec<String> {
+    let prompted = lines.iter().any(|l| l.starts_with("$ "));
+    let mut ret: Vec<String> = vec![];
+    let mut continued = false;
+    for line in lines {
+        if continued {
+            if let Some(last) = ret.last_mut() {
+                last.push(' ');
+                last.push_str(line.trim_end_matches('\\').trim());
+            }
+        } else if let Some(command) = line.strip_prefix("$ ").or((!prompted).then_some(line)) {
+            if !command.trim().is_empty() {
+                ret.push(command.trim_end_matches('\\').trim().to_string());
+            }
+        } else {
+            continue;
+        }
+        continued = line.ends_with('\\');
+    }
+    ret
+}
+
+/// Pass each command of the runnable displays of `page` to `executor`,
+/// returning those it reported as failed with their messages.
+pub fn run(page: &Mdoc, mut executor: impl FnMut(&Example) -> Result<(), String>) -> Vec<Failure> {
+    examples(page)
+        .into_iter()
+        .filter_map(|example| {
+            executor(&example)
+                .err()
+                .map(|message| Failure { example, message })
+        })
+        .collect()
+}
+
+/// Run an example with sh(1), failing if it exits unsuccessfully.
+pub fn shell(example: &Example) -> Result<(), String> {
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&example.command)
+        .output()
+        .map_err(|err| err.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().next() {
+        Some(line) => Err(format!("{}: {line}", output.status)),
+        None => Err(output.status.to_string()),
+    }
+}
+
+/// Split a command into words as sh(1) does, with single and double quotes
+/// and backslashes, but without expansions.
+pub fn words(command: &str) -> Vec<String> {
+    let mut ret = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\'' | '"', None) => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            (c, Some(q)) if c == q => quote = None,
+            ('\\', Some('\'')) => word.get_or_insert_default().push(c),
+            ('\\', _) => {
+                if let Some(c) = chars.next() {
+                    word.get_or_insert_default().push(c);
+                }
+            }
+            (c, None) if c.is_whitespace() => ret.extend(word.take()),
+            (c, _) => word.get_or_insert_default().push(c),
+        }
+    }
+    ret.extend(word);
+    ret
+}
@@ -0,0 +1,207 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Recover pages from formatted output.
+//!
+//! When the source of a page is lost, its formatted output, as printed by
+//! man(1) or nroff(1) to a pipe, can still be decoded back into a page.
+//! Bold text is printed as a character overstruck with itself (`c\bc`) and
+//! underlined text as an underscore overstruck with the character
+//! (`_\bc`); these become [`Inline::Bold`] and [`Inline::Italic`] elements.
+//! Headings at the left margin become sections.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::formatted::*;
+//! let output = "FOO(1)    General Commands Manual    FOO(1)\n\n\
+//!               N\x08NA\x08AM\x08ME\x08E\n     foo - frobnicate\n\n\
+//!               Debian    2024    FOO(1)\n";
+//! assert!(is_formatted(output));
+//! let page = from_formatted(output);
+//! assert_eq!(page.to_mdoc(), ".Dt FOO 1\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n");
+//! ```
+
+use super::*;
+
+/// Whether `source` contains overstruck characters.
+pub fn is_formatted(source: &str) -> bool {
+    source.contains('\x08')
+}
+
+/// The font of a run of decoded text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Roman,
+    Bold,
+    Italic,
+}
+
+/// Decode the overstruck characters of a line into runs of text.
+fn decode_line(line: &str) -> Vec<(Font, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut runs: Vec<(Font, String)> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let (font, c) = match (chars[i], chars.get(i + 1), chars.get(i + 2)) {
+            (a, Some('\x08'), Some(&b)) => {
+                i += 3;
+                // Characters may be overstruck more than once.
+                while chars.get(i) == Some(&'\x08') && chars.get(i + 1).is_some() {
+                    i += 2;
+                }
+                if a == '_' && b != '_' {
+                    (Font::Italic, b)
+                } else if a == b {
+                    (Font::Bold, b)
+                } else {
+                    (Font::Roman, b)
+                }
+            }
+            (c, _, _) => {
+                i += 1;
+                (Font::Roman, c)
+            }
+        };
+        match runs.last_mut() {
+            // Spaces between words of the same font don't break runs.
+            Some((last, text)) if *last == font || (c == ' ' && *last != Font::Roman) => {
+                text.push(c)
+            }
+            _ => runs.push((font, c.to_string())),
+        }
+    }
+    // Give spaces trailing a styled run back to the roman text after it.
+    let mut ret: Vec<(Font, String)> = vec![];
+    for (font, text) in runs {
+        let trimmed = text.trim_end_matches(' ');
+        let spaces = &text[trimmed.len()..];
+        match ret.last_mut() {
+            Some((Font::Roman, last)) if font == Font::Roman => last.push_str(&text),
+            _ if font == Font::Roman || spaces.is_empty() => ret.push((font, text)),
+            _ => {
+                ret.push((font, trimmed.to_string()));
+                ret.push((Font::Roman, spaces.to_string()));
+            }
+        }
+    }
+    ret
+}
+
+/// Decode formatted output into a page.
+pub fn from_formatted(source: &str) -> Mdoc {
+    from_formatted_with_warnings(source, &mut Warnings::new())
+}
+
+/// Like [`from_formatted`], reporting the guesses that may be wrong to
+/// `warnings`.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn from_formatted_with_warnings(source: &str, warnings: &mut Warnings) -> Mdoc {
+    let mut page = Mdoc::default();
+    let lines: Vec<Vec<(Font, String)>> = source.lines().map(decode_line).collect();
+    let plain = |runs: &[(Font, String)]| runs.iter().map(|(_, t)| t.as_str()).collect::<String>();
+    let mut body = &lines[..];
+    // The header, e.g. `LS(1)  General Commands Manual  LS(1)`, and the
+    // footer, which ends with the same page reference.
+    let mut reference = None;
+    if let Some(header) = body.first().map(|l| plain(l)) {
+        let first = header.split_whitespace().next().unwrap_or_default();
+        if let Some((title, section)) = first.strip_suffix(')').and_then(|f| f.split_once('(')) {
+            page.control("Dt".into(), [title, section]);
+            reference = Some(first.to_string());
+            body = &body[1..];
+        }
+    }
+    if reference.is_none() {
+        warnings.push("no header line with the title and section of the page");
+    }
+    if let Some(reference) = reference {
+        while body.last().is_some_and(|l| plain(l).trim().is_empty()) {
+            body = &body[..body.len() - 1];
+        }
+        if body
+            .last()
+            .is_some_and(|l| plain(l).trim_end().ends_with(&reference))
+        {
+            body = &body[..body.len() - 1];
+        }
+    }
+    let mut section = String::new();
+    // Whether a paragraph break is due before the next text line.
+    let mut paragraph = false;
+    for runs in body {
+        let text = plain(runs);
+        if text.trim().is_empty() {
+            paragraph = true;
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        let all_bold = runs
+            .iter()
+            .all(|(font, t)| *font == Font::Bold || t.trim().is_empty());
+        if indent == 0 {
+            // Text at the left margin is taken to be a heading even if it
+            // isn't bold, as when the output was stripped of overstrikes.
+            if !all_bold {
+                warnings.push(format!("heading is not bold: {}", text.trim()));
+            }
+            section = text.trim().to_string();
+            page.control("Sh".into(), [section.as_str()]);
+            paragraph = false;
+            continue;
+        }
+        if indent == 3 && all_bold {
+            page.control("Ss".into(), [text.trim()]);
+            paragraph = false;
+            continue;
+        }
+        if section == "NAME" {
+            if let Some((name, description)) = text.trim().split_once(" - ") {
+                page.control("Nm".into(), [name]);
+                page.control("Nd".into(), [description]);
+                continue;
+            }
+        }
+        if std::mem::take(&mut paragraph) {
+            page.control("Pp".into(), []);
+        }
+        let mut inlines = vec![];
+        for (i, (font, text)) in runs.iter().enumerate() {
+            let text = if i == 0 {
+                text.trim_start().to_string()
+            } else {
+                text.clone()
+            };
+            if text.is_empty() {
+                continue;
+            }
+            inlines.push(match font {
+                Font::Roman => roman(text),
+                Font::Bold => bold(text),
+                Font::Italic => italic(text),
+            });
+        }
+        page.text(inlines);
+    }
+    page
+}
@@ -20,72 +20,752 @@
 //
 // SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
 
-use std::borrow::Cow;
-
 use super::*;
 
 impl From<::clap::Command> for Mdoc {
     fn from(cmd: ::clap::Command) -> Self {
-        let mut m = Mdoc::new(
-            None,
-            DocumentTitle {
-                title: title! {cmd.get_display_name().unwrap_or_else(|| cmd.get_name()).to_string() },
-                section: section! { "1" },
-                arch: None,
-            },
-            name! { cmd.get_bin_name().unwrap_or_else(|| cmd.get_name()).to_string() },
-            description! { cmd.get_about().unwrap_or_default().to_string() },
-            None,
+        to_mdoc(cmd, FlagStyle::default())
+    }
+}
+
+/// Convert a [`clap::Command`] to a manual page, rendering its flags with
+/// the given style.
+pub fn to_mdoc(cmd: ::clap::Command, flag_style: FlagStyle) -> Mdoc {
+    to_mdoc_with_warnings(cmd, flag_style, &mut Warnings::new())
+}
+
+/// Like [`to_mdoc`], reporting the metadata of the command that isn't
+/// documented in the page to `warnings`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(command = cmd.get_name()))
+)]
+pub fn to_mdoc_with_warnings(
+    cmd: ::clap::Command,
+    flag_style: FlagStyle,
+    warnings: &mut Warnings,
+) -> Mdoc {
+    let options = FromClapOptions {
+        flag_style,
+        ..FromClapOptions::default()
+    };
+    page(cmd, &options, warnings, None)
+}
+
+/// Options for [`generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromClapOptions {
+    pub flag_style: FlagStyle,
+    /// The section of the pages. The default is `1`.
+    pub section: Str,
+    /// The date of the pages, or `None` to date them when they are
+    /// formatted.
+    pub date: Option<DocumentDate>,
+    /// The operating system of the pages, or `None` for the one they are
+    /// formatted on.
+    pub os: Option<OperatingSystem>,
+    /// Document the arguments and subcommands hidden from `--help` too.
+    pub include_hidden: bool,
+    /// Generate a page for each subcommand, as [`generate_all`] does,
+    /// instead of a single page.
+    pub subcommand_pages: bool,
+    /// Sections added at the end of every page, such as a
+    /// [COPYRIGHT](boilerplate::copyright) section.
+    pub extra_sections: Vec<boilerplate::Boilerplate>,
+}
+
+impl Default for FromClapOptions {
+    fn default() -> Self {
+        Self {
+            flag_style: FlagStyle::default(),
+            section: "1".into(),
+            date: None,
+            os: None,
+            include_hidden: false,
+            subcommand_pages: false,
+            extra_sections: vec![],
+        }
+    }
+}
+
+/// Convert a [`clap::Command`] to manual pages with the given options,
+/// returning the name of each page with it.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::from_clap::*;
+/// let cmd = clap::Command::new("frobd").about("frobnication daemon");
+/// let options = FromClapOptions {
+///     section: "8".into(),
+///     date: DocumentDate::from_ymd(2024, 3, 1),
+///     ..FromClapOptions::default()
+/// };
+/// let pages = generate(cmd, &options);
+/// assert_eq!(pages[0].0, "frobd");
+/// assert!(pages[0].1.to_mdoc().starts_with(".Dd March 1, 2024\n.Dt frobd 8\n"));
+/// ```
+pub fn generate(cmd: ::clap::Command, options: &FromClapOptions) -> Vec<(String, Mdoc)> {
+    generate_with_warnings(cmd, options, &mut Warnings::new())
+}
+
+/// Like [`generate`], reporting the metadata of the commands that isn't
+/// documented to `warnings`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(command = cmd.get_name()))
+)]
+pub fn generate_with_warnings(
+    cmd: ::clap::Command,
+    options: &FromClapOptions,
+    warnings: &mut Warnings,
+) -> Vec<(String, Mdoc)> {
+    let name = cmd
+        .get_bin_name()
+        .unwrap_or_else(|| cmd.get_name())
+        .to_string();
+    if !options.subcommand_pages {
+        return vec![(name, page(cmd, options, warnings, None))];
+    }
+    let mut ret = vec![];
+    pages(cmd, name, None, options, warnings, &mut ret);
+    ret
+}
+
+/// Convert a [`clap::Command`] to a main page and one page per
+/// subcommand, named after the command and its subcommand as in
+/// `git-commit(1)`, with cross references between them.
+///
+/// ```
+/// let cmd = clap::Command::new("git")
+///     .subcommand(clap::Command::new("commit").about("record changes"))
+///     .subcommand(clap::Command::new("remote").subcommand(clap::Command::new("add")));
+/// let pages = mdoc::from_clap::generate_all(cmd);
+/// let names: Vec<&str> = pages.iter().map(|(name, _)| name.as_str()).collect();
+/// assert_eq!(names, ["git", "git-commit", "git-remote", "git-remote-add"]);
+/// assert!(pages[1].1.to_mdoc().contains(".Sh SEE ALSO\n.Xr git 1\n"));
+/// ```
+pub fn generate_all(cmd: ::clap::Command) -> Vec<(String, Mdoc)> {
+    generate_all_with_warnings(cmd, FlagStyle::default(), &mut Warnings::new())
+}
+
+/// Like [`generate_all`], rendering flags with the given style and
+/// reporting the metadata of the commands that isn't documented to
+/// `warnings`.
+pub fn generate_all_with_warnings(
+    cmd: ::clap::Command,
+    flag_style: FlagStyle,
+    warnings: &mut Warnings,
+) -> Vec<(String, Mdoc)> {
+    let options = FromClapOptions {
+        flag_style,
+        subcommand_pages: true,
+        ..FromClapOptions::default()
+    };
+    generate_with_warnings(cmd, &options, warnings)
+}
+
+/// Add the page of `cmd`, called `name`, and the pages of its subcommands
+/// to `ret`.
+fn pages(
+    cmd: ::clap::Command,
+    name: String,
+    parent: Option<&str>,
+    options: &FromClapOptions,
+    warnings: &mut Warnings,
+    ret: &mut Vec<(String, Mdoc)>,
+) {
+    let subcommands: Vec<::clap::Command> = cmd
+        .get_subcommands()
+        .filter(|sub| options.include_hidden || !sub.is_hide_set())
+        .cloned()
+        .collect();
+    let cmd = cmd.display_name(name.clone()).bin_name(name.clone());
+    let mut m = page(cmd, options, warnings, Some(&name));
+    let mut see_also: Vec<&str> = parent.into_iter().collect();
+    let names: Vec<String> = subcommands
+        .iter()
+        .map(|sub| format!("{name}-{}", sub.get_name()))
+        .collect();
+    see_also.extend(names.iter().map(String::as_str));
+    for (i, page) in see_also.iter().enumerate() {
+        let mut args: Vec<Str> = vec![page.to_string().into(), options.section.clone()];
+        if i + 1 < see_also.len() {
+            args.push(",".into());
+        }
+        m.see_also([Line::control("Xr".into(), args)]);
+    }
+    ret.push((name.clone(), m));
+    for (sub, sub_name) in subcommands.into_iter().zip(names) {
+        pages(sub, sub_name, Some(&name), options, warnings, ret);
+    }
+}
+
+/// The page of `cmd`, listing its subcommands in a COMMANDS section with
+/// references to their pages if `prefix`, the name of the page of `cmd`,
+/// is given.
+fn page(
+    cmd: ::clap::Command,
+    options: &FromClapOptions,
+    warnings: &mut Warnings,
+    prefix: Option<&str>,
+) -> Mdoc {
+    let flag_style = options.flag_style;
+    let hidden = |hidden: bool| hidden && !options.include_hidden;
+    let mut m = Mdoc::new(
+        options.date.clone(),
+        DocumentTitle {
+            title: title! {cmd.get_display_name().unwrap_or_else(|| cmd.get_name()).to_string() },
+            section: Section(options.section.clone()),
+            arch: None,
+        },
+        name! { cmd.get_bin_name().unwrap_or_else(|| cmd.get_name()).to_string() },
+        description! { cmd.get_about().unwrap_or_default().to_string() },
+        options.os.clone(),
+    );
+    let mut synopsis = sections::SynopsisBuilder::new();
+    let mut option_list = ListBuilder::new(ListKind::Tag).width("Ds");
+    let mut any_option = false;
+    // The ids of the documented options and the lengths of their bodies,
+    // to record the origins of the items.
+    let mut option_origins = vec![];
+    // The items of the ENVIRONMENT section, with the names of the variables
+    // and the description of what they set.
+    let mut environment = vec![];
+    for opt in cmd.get_opts() {
+        if opt.get_long().is_none() && opt.get_short().is_none() || hidden(opt.is_hide_set()) {
+            continue;
+        }
+        let value = match opt.get_action() {
+            clap::ArgAction::Set => Some(
+                opt.get_value_names()
+                    .unwrap_or_default()
+                    .first()
+                    .map_or_else(|| "VALUE".to_string(), |val| val.to_string()),
+            ),
+            clap::ArgAction::Append => {
+                warnings.push_in(
+                    opt.get_id().as_str(),
+                    "the values of the option are not documented",
+                );
+                None
+            }
+            clap::ArgAction::SetTrue | clap::ArgAction::SetFalse => None,
+            clap::ArgAction::Count => None,
+            _ => None,
+        };
+        let flag = Flag {
+            short: opt.get_short().map(String::from),
+            long: opt.get_long().map(String::from),
+            value,
+            optional: !opt.is_required_set(),
+            style: flag_style,
+        };
+        let head = flag_args(
+            opt.get_short(),
+            opt.get_long(),
+            flag.value.as_deref(),
+            FlagContext::Sentence,
+            flag_style,
         );
-        m.control("Sh".into(), vec!["SYNOPSIS"]);
-        m.control("Nm".into(), vec![]);
-        for opt in cmd.get_opts() {
-            let mut v: Vec<Cow<'_, str>> = vec![];
-            let required = opt.is_required_set();
-            let control = if required {
-                "Fl".into()
-            } else {
-                v.push("Fl".into());
-                "Op".into()
+        let body = option_body(opt, warnings);
+        option_origins.push((opt.get_id().to_string(), body.len()));
+        option_list = option_list.item(head.clone(), body);
+        any_option = true;
+        if let Some(item) = environment_item(opt, head, "option") {
+            environment.push(item);
+        }
+        synopsis = synopsis.flag(flag);
+    }
+    let mut arguments = ListBuilder::new(ListKind::Tag).width("Ds");
+    let mut any_argument = false;
+    let mut argument_origins = vec![];
+    for opt in cmd
+        .get_positionals()
+        .filter(|opt| !hidden(opt.is_hide_set()))
+    {
+        let name = opt
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map_or_else(|| opt.get_id().to_string(), ToString::to_string);
+        let optional = !opt.is_required_set();
+        let repeated = matches!(opt.get_action(), clap::ArgAction::Append)
+            || opt.get_num_args().is_some_and(|n| n.max_values() > 1);
+        synopsis = if repeated {
+            synopsis.args(&name, optional)
+        } else {
+            synopsis.argument(&name, optional)
+        };
+        let help = opt.get_long_help().or_else(|| opt.get_help());
+        if help.is_none() {
+            warnings.push_in(opt.get_id().as_str(), "the argument has no help text");
+        }
+        let mut body: Vec<Line> = help
+            .map(|help| Line::text(vec![roman(help.to_string())]))
+            .into_iter()
+            .collect();
+        default_sentence(opt, &mut body);
+        let head: Vec<Str> = vec!["Ar".into(), name.into()];
+        if let Some(item) = environment_item(opt, head.clone(), "argument") {
+            environment.push(item);
+        }
+        argument_origins.push((opt.get_id().to_string(), body.len()));
+        arguments = arguments.item(head, body);
+        any_argument = true;
+    }
+    if prefix.is_none() {
+        for subcommand in cmd.get_subcommands() {
+            warnings.push_in(subcommand.get_name(), "subcommands are not documented");
+        }
+    }
+    if prefix.is_some() && cmd.get_subcommands().any(|sub| !hidden(sub.is_hide_set())) {
+        synopsis = synopsis.argument("command", !cmd.is_subcommand_required_set());
+    }
+    m.synopsis(synopsis);
+    m.control("Sh".into(), vec!["DESCRIPTION"]);
+    // The text shown before the usage by `--help`, then the long
+    // description.
+    for help in [cmd.get_before_help(), cmd.get_long_about()]
+        .into_iter()
+        .flatten()
+    {
+        paragraph_break(&mut m.lines);
+        m.lines.extend(paragraphs(&help.to_string()));
+    }
+    if any_argument {
+        paragraph_break(&mut m.lines);
+        m.text([roman("The arguments are as follows:")]);
+        let start = m.lines.len();
+        m.list(arguments);
+        set_item_origins(&mut m, start, argument_origins);
+    }
+    if any_option {
+        let lines = option_list.lines();
+        let start = m.lines.len() + 1;
+        m.add_section("OPTIONS", lines);
+        set_item_origins(&mut m, start, option_origins);
+    }
+    if !environment.is_empty() {
+        let list = environment.into_iter().fold(
+            ListBuilder::new(ListKind::Tag).width("Ds"),
+            |list, (head, body)| list.item(head, body),
+        );
+        m.environment(list.lines());
+    }
+    if let Some(prefix) = prefix {
+        let mut commands = ListBuilder::new(ListKind::Tag).width("Ds");
+        let mut any = false;
+        for subcommand in cmd
+            .get_subcommands()
+            .filter(|sub| !hidden(sub.is_hide_set()))
+        {
+            let text = match subcommand.get_about() {
+                Some(about) => vec![roman(format!("{about}. See"))],
+                None => vec![roman("See")],
             };
-            if let Some(long) = opt.get_long() {
-                let s = format!("-{long}");
-                v.push(Cow::Owned(s));
-                if let Some(short) = opt.get_short() {
-                    let s = format!("{short}");
-                    v.push(Cow::Borrowed("|"));
-                    v.push(Cow::Owned(s));
-                }
-            } else if let Some(short) = opt.get_short() {
-                let s = format!("{short}");
-                v.push(Cow::Owned(s));
-            } else {
-                continue;
+            commands = commands.item(
+                ["Cm".to_string(), subcommand.get_name().to_string()],
+                [
+                    Line::text(text),
+                    Line::control(
+                        "Xr".into(),
+                        vec![
+                            format!("{prefix}-{}", subcommand.get_name()).into(),
+                            options.section.clone(),
+                            ".".into(),
+                        ],
+                    ),
+                ],
+            );
+            any = true;
+        }
+        if any {
+            m.add_section("COMMANDS", commands.lines());
+        }
+    }
+    // The text shown after the options by `--help`, which is usually
+    // examples or notes.
+    if let Some(help) = cmd.get_after_help() {
+        let help = help.to_string();
+        let (heading, rest) = help.trim_start().split_once('\n').unwrap_or((&help, ""));
+        let heading = heading.trim().to_lowercase();
+        if heading.starts_with("example") && heading.ends_with(':') {
+            m.examples(paragraphs(rest));
+        } else {
+            m.add_section("NOTES", paragraphs(&help));
+        }
+    }
+    if let Some(author) = cmd.get_author() {
+        // .An Name Aq Mt user@example.com
+        m.control("Sh".into(), vec!["AUTHORS"]);
+        m.control("An".into(), author.split(' ').collect::<Vec<&str>>());
+    }
+    for section in &options.extra_sections {
+        section.clone().add_to(&mut m);
+    }
+    m
+}
+
+/// The description of an option in the OPTIONS section: its help text,
+/// split into paragraphs, followed by whether it is required and its
+/// default and possible values.
+fn option_body(opt: &clap::Arg, warnings: &mut Warnings) -> Vec<Line> {
+    let mut lines = vec![];
+    match opt.get_long_help().or_else(|| opt.get_help()) {
+        Some(help) => lines.extend(paragraphs(&help.to_string())),
+        None => warnings.push_in(opt.get_id().as_str(), "the option has no help text"),
+    }
+    if opt.is_required_set() {
+        end_sentence(&mut lines);
+        lines.push(Line::text(vec![roman("This option is required.")]));
+    }
+    if !opt.get_action().takes_values() {
+        return lines;
+    }
+    default_sentence(opt, &mut lines);
+    let possible: Vec<_> = opt
+        .get_possible_values()
+        .into_iter()
+        .filter(|v| !v.is_hide_set())
+        .collect();
+    if possible.is_empty() || opt.is_hide_possible_values_set() {
+        return lines;
+    }
+    end_sentence(&mut lines);
+    if possible.iter().any(|v| v.get_help().is_some()) {
+        // Values with their own help are listed with it.
+        lines.push(Line::text(vec![roman("The possible values are:")]));
+        let mut list = ListBuilder::new(ListKind::Tag).width("Ds");
+        for value in &possible {
+            let help = value
+                .get_help()
+                .map(|help| Line::text(vec![roman(help.to_string())]));
+            list = list.item(
+                ["Cm".to_string(), value.get_name().to_string()],
+                help.into_iter().collect::<Vec<_>>(),
+            );
+        }
+        lines.extend(list.lines());
+    } else {
+        lines.push(Line::text(vec![roman("The possible values are")]));
+        lines.push(Line::control(
+            "Cm".into(),
+            values(possible.iter().map(|v| v.get_name().into())),
+        ));
+    }
+    lines
+}
+
+/// The item of the ENVIRONMENT section for the variable `opt` is read
+/// from, if any, saying which `kind` of argument, written as `head`, it
+/// sets.
+fn environment_item(opt: &clap::Arg, head: Vec<Str>, kind: &str) -> Option<(Vec<Str>, Vec<Line>)> {
+    let env = opt.get_env().filter(|_| !opt.is_hide_env_set())?;
+    let mut head = head.into_iter();
+    let name = head.next()?;
+    Some((
+        vec!["Ev".into(), env.to_string_lossy().into_owned().into()],
+        vec![
+            Line::text(vec![roman("Used for")]),
+            Line::control(name, head.collect()),
+            Line::text(vec![roman(format!("when the {kind} is not given."))]),
+        ],
+    ))
+}
+
+/// Append a sentence stating the default values of `opt`, if it has any
+/// and they aren't hidden.
+///
+/// Values that look like constants, such as `SIGTERM`, are marked up with
+/// `Dv` and others are quoted with `Ql`.
+fn default_sentence(opt: &clap::Arg, lines: &mut Vec<Line>) {
+    let defaults = opt.get_default_values();
+    if defaults.is_empty() || opt.is_hide_default_value_set() {
+        return;
+    }
+    let defaults: Vec<_> = defaults.iter().map(|v| v.to_string_lossy()).collect();
+    let constant = defaults.iter().all(|v| {
+        v.starts_with(|c: char| c.is_ascii_uppercase())
+            && v.chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+    });
+    end_sentence(lines);
+    lines.push(Line::text(vec![roman(if defaults.len() > 1 {
+        "The defaults are"
+    } else {
+        "The default is"
+    })]));
+    lines.push(Line::control(
+        if constant { "Dv" } else { "Ql" }.into(),
+        values(defaults.into_iter()),
+    ));
+}
+
+/// Help text as lines, with paragraphs separated by blank lines as `Pp`
+/// paragraphs and indented paragraphs, such as example commands, as
+/// literal displays.
+fn paragraphs(help: &str) -> Vec<Line> {
+    let mut lines = vec![];
+    for paragraph in help
+        .split("\n\n")
+        .map(|p| p.trim_matches('\n'))
+        .filter(|p| !p.trim().is_empty())
+    {
+        if paragraph.lines().all(|l| l.starts_with([' ', '\t'])) {
+            let indent = paragraph
+                .lines()
+                .map(|l| l.len() - l.trim_start().len())
+                .min()
+                .unwrap_or_default();
+            lines.push(Line::control(
+                "Bd".into(),
+                vec!["-literal".into(), "-offset".into(), "indent".into()],
+            ));
+            for line in paragraph.lines() {
+                lines.push(Line::text(vec![roman(line[indent..].trim_end())]));
+            }
+            lines.push(Line::control("Ed".into(), vec![]));
+        } else {
+            paragraph_break(&mut lines);
+            let text = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+            lines.push(Line::text(vec![roman(text)]));
+        }
+    }
+    lines
+}
+
+/// Start a new paragraph if `lines` end with text. Displays and lists are
+/// set apart without one.
+fn paragraph_break(lines: &mut Vec<Line>) {
+    if let Some(Line::Text(_)) = lines.last() {
+        lines.push(Line::control("Pp".into(), vec![]));
+    }
+}
+
+/// End the help text with a period before more sentences are added.
+fn end_sentence(lines: &mut [Line]) {
+    if let Some(Line::Text(inlines)) = lines.last_mut() {
+        if let Some(Inline::Roman(text) | Inline::Roff(text)) = inlines.last_mut() {
+            if !text.ends_with(['.', '!', '?', ':']) {
+                text.push('.');
+            }
+        }
+    }
+}
+
+/// Record the arguments the items of the list starting at `start` document,
+/// given their ids and the lengths of their bodies in order.
+fn set_item_origins(m: &mut Mdoc, start: usize, items: Vec<(String, usize)>) {
+    // Skip the `Bl` line.
+    let mut index = start + 1;
+    for (id, len) in items {
+        m.set_origins(index..index + 1 + len, Origin::Arg(id));
+        index += 1 + len;
+    }
+}
+
+/// Values as the arguments of a macro, separated by commas and ending the
+/// sentence.
+fn values<'a>(values: impl Iterator<Item = std::borrow::Cow<'a, str>>) -> Vec<Str> {
+    let mut args: Vec<Str> = vec![];
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            args.push(",".into());
+        }
+        args.push(value.into_owned().into());
+    }
+    args.push(".".into());
+    args
+}
+
+/// A difference between the options of a command and those documented in
+/// its page, found by [`drift`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+    /// An option of the command the page doesn't document, by its flags.
+    Added(Vec<String>),
+    /// An option the page documents that the command doesn't have.
+    Removed(Vec<String>),
+    /// An option whose flags changed, keeping one of them.
+    Renamed { from: Vec<String>, to: Vec<String> },
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(flags) => write!(f, "{} is not documented", flags.join(", ")),
+            Self::Removed(flags) => write!(f, "{} no longer exists", flags.join(", ")),
+            Self::Renamed { from, to } => {
+                write!(f, "{} is now {}", from.join(", "), to.join(", "))
             }
+        }
+    }
+}
 
-            match opt.get_action() {
-                clap::ArgAction::Set => {
-                    v.push(Cow::Borrowed("Ar"));
-                    if let Some(val) = opt.get_value_names().unwrap_or_default().first() {
-                        v.push(Cow::Borrowed(val.as_str()));
-                    } else {
-                        v.push(Cow::Borrowed("VALUE"));
-                    }
+/// Compare the options of `cmd` with those documented in the lists of the
+/// DESCRIPTION and OPTIONS sections of `page`, for projects that commit
+/// their pages instead of generating them when they are built.
+///
+/// Hidden options are expected to be undocumented.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::from_clap::*;
+/// let cmd = clap::Command::new("ls")
+///     .arg(clap::Arg::new("all").short('a').long("all").action(clap::ArgAction::SetTrue))
+///     .arg(clap::Arg::new("long").short('l').action(clap::ArgAction::SetTrue));
+/// let page = Mdoc::parse(concat!(
+///     ".Sh OPTIONS\n.Bl -tag -width Ds\n",
+///     ".It Fl a , Fl -everything\nAll.\n.It Fl F\nClassify.\n.El\n",
+/// ));
+/// let drift: Vec<String> = drift(&cmd, &page).iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     drift,
+///     ["-a, --everything is now -a, --all", "-l is not documented", "-F no longer exists"]
+/// );
+/// ```
+pub fn drift(cmd: &::clap::Command, page: &Mdoc) -> Vec<Drift> {
+    let lines = page.document_lines();
+    let mut documented: Vec<Vec<String>> = vec![];
+    for (title, range) in Mdoc::sections_of(&lines) {
+        if !matches!(parser::unquote(&title).as_str(), "DESCRIPTION" | "OPTIONS") {
+            continue;
+        }
+        for line in &lines[range] {
+            if matches!(line, Line::Control { name, .. } if name == "It") {
+                let flags = lint::flags(line);
+                if !flags.is_empty() {
+                    documented.push(flags);
                 }
-                clap::ArgAction::Append => {}
-                clap::ArgAction::SetTrue | clap::ArgAction::SetFalse => {}
-                clap::ArgAction::Count => {}
-                _ => {}
             }
-            m.control(control, v.iter().map(|c| c.as_ref()));
         }
-        for _opt in cmd.get_positionals() {}
-        m.control("Sh".into(), vec!["DESCRIPTION"]);
-        if let Some(author) = cmd.get_author() {
-            // .An Name Aq Mt user@example.com
-            m.control("Sh".into(), vec!["AUTHORS"]);
-            m.control("An".into(), author.split(' ').collect::<Vec<&str>>());
+    }
+    let mut ret = vec![];
+    for opt in cmd.get_opts().filter(|opt| !opt.is_hide_set()) {
+        let flags: Vec<String> = opt
+            .get_short()
+            .map(|s| format!("-{s}"))
+            .into_iter()
+            .chain(opt.get_long().map(|l| format!("--{l}")))
+            .collect();
+        if flags.is_empty() {
+            continue;
+        }
+        let same = documented
+            .iter()
+            .position(|d| d.len() == flags.len() && d.iter().all(|f| flags.contains(f)));
+        let renamed = || {
+            documented
+                .iter()
+                .position(|d| d.iter().any(|f| flags.contains(f)))
+        };
+        match same.map(Ok).or_else(|| renamed().map(Err)) {
+            Some(Ok(i)) => {
+                documented.remove(i);
+            }
+            Some(Err(i)) => ret.push(Drift::Renamed {
+                from: documented.remove(i),
+                to: flags,
+            }),
+            None => ret.push(Drift::Added(flags)),
+        }
+    }
+    ret.extend(documented.into_iter().map(Drift::Removed));
+    ret
+}
+
+/// Generates pages with the entry points of `clap_complete::Generator`, so
+/// that build scripts can write pages next to shell completions.
+///
+/// ```no_run
+/// # use mdoc::from_clap::*;
+/// let cmd = clap::Command::new("frob").about("frobnicate files");
+/// let out_dir = std::env::var_os("OUT_DIR").unwrap();
+/// let written = Generator::default().generate_to(&cmd, out_dir).unwrap();
+/// println!("cargo:warning=wrote {}", written[0].display());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Generator {
+    pub options: FromClapOptions,
+}
+
+impl Generator {
+    pub fn new(options: FromClapOptions) -> Self {
+        Self { options }
+    }
+
+    /// The file name of the page called `name`, such as `frob.1`.
+    pub fn file_name(&self, name: &str) -> String {
+        format!("{name}.{}", self.options.section)
+    }
+
+    /// Write the page of `cmd` to `buf`.
+    pub fn generate(&self, cmd: &::clap::Command, buf: &mut dyn Write) -> std::io::Result<()> {
+        let options = FromClapOptions {
+            subcommand_pages: false,
+            ..self.options.clone()
+        };
+        for (_, page) in generate(cmd.clone(), &options) {
+            page.to_writer(buf)?;
+        }
+        Ok(())
+    }
+
+    /// Write the pages of `cmd` to `out_dir`, one per subcommand too if
+    /// [`FromClapOptions::subcommand_pages`] is set, and return their paths.
+    pub fn generate_to(
+        &self,
+        cmd: &::clap::Command,
+        out_dir: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Vec<std::path::PathBuf>> {
+        let mut ret = vec![];
+        for (name, page) in generate(cmd.clone(), &self.options) {
+            let path = out_dir.as_ref().join(self.file_name(&name));
+            std::fs::write(&path, page.render())?;
+            ret.push(path);
+        }
+        Ok(ret)
+    }
+}
+
+/// An executor for [`examples::run`] that checks that the invocations of
+/// `cmd` in an example parse, without running them.
+///
+/// The example is split into commands at `|`, `;`, `&&` and `||`, and
+/// those that start with the name of `cmd` are parsed with it. Examples
+/// that don't invoke `cmd` fail.
+///
+/// ```
+/// # use mdoc::*;
+/// let cmd = clap::Command::new("frob").arg(clap::Arg::new("verbose").short('v').action(clap::ArgAction::SetTrue));
+/// let mut page = Mdoc::default();
+/// page.examples(examples::runnable(["frob -v", "frob -x | sort"]));
+/// let failures = examples::run(&page, from_clap::example_parser(&cmd));
+/// assert_eq!(failures.len(), 1);
+/// assert!(failures[0].message.contains("unexpected argument '-x'"));
+/// ```
+pub fn example_parser(
+    cmd: &::clap::Command,
+) -> impl FnMut(&examples::Example) -> Result<(), String> + '_ {
+    move |example| {
+        let words = examples::words(&example.command);
+        let mut invoked = false;
+        for command in words.split(|w| matches!(w.as_str(), "|" | ";" | "&&" | "||")) {
+            if command.first().map(String::as_str) != Some(cmd.get_name()) {
+                continue;
+            }
+            invoked = true;
+            cmd.clone().try_get_matches_from(command).map_err(|err| {
+                err.render()
+                    .to_string()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })?;
+        }
+        if !invoked {
+            return Err(format!("{} is not invoked", cmd.get_name()));
         }
-        m
+        Ok(())
     }
 }
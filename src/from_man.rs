@@ -0,0 +1,470 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Convert legacy man(7) pages to **mdoc**.
+//!
+//! The structure of a man(7) page maps onto **mdoc** directly: `TH`
+//! becomes the prologue, `SH` and `SS` become sections, paragraphs become
+//! `Pp` and the indented paragraphs of `TP` and `IP` become lists. The
+//! font macros are guessed into semantic macros: a bold word starting with
+//! a dash is a flag, a bold word followed by a section number in
+//! parentheses is a cross reference, the name of the page is `Nm` and an
+//! italic word is an argument. Text lines, which may use font escapes, are
+//! kept as they are.
+//!
+//! Requests and macros without an equivalent are kept as
+//! [`Unknown`] lines and reported as warnings.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::from_man::*;
+//! let page = from_man(
+//!     ".TH LS 1 \"March 2024\"\n.SH NAME\nls \\- list directory contents\n\
+//!      .SH OPTIONS\n.TP\n.BR \\-a \", \" \\-\\-all\ndo not ignore entries\n",
+//! );
+//! assert_eq!(
+//!     page.to_mdoc(),
+//!     ".Dd March 2024\n.Dt LS 1\n.Os\n.Sh NAME\n.Nm ls\n.Nd list directory contents\n\
+//!      .Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl a , Fl \\-all\ndo not ignore entries\n.El\n",
+//! );
+//! ```
+
+use super::*;
+
+/// The font of a piece of text set by the font macros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Roman,
+    Bold,
+    Italic,
+}
+
+/// Convert man(7) source to a page.
+pub fn from_man(source: &str) -> Mdoc {
+    from_man_with_warnings(source, &mut Warnings::new())
+}
+
+/// Like [`from_man`], reporting the markup that couldn't be converted to
+/// `warnings`.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn from_man_with_warnings(source: &str, warnings: &mut Warnings) -> Mdoc {
+    let mut conversion = Conversion {
+        page: Mdoc::default(),
+        warnings,
+        section: String::new(),
+        name: String::new(),
+        name_text: vec![],
+        lists: vec![None],
+        literal: false,
+        tag: false,
+        font: None,
+        heading: None,
+        link: None,
+    };
+    let lines = parser::parse(source).lines;
+    for line in lines {
+        match line {
+            Line::Control { name, args } => {
+                let args: Vec<String> = args.iter().map(|a| parser::unquote(a)).collect();
+                conversion.control(&name, args);
+            }
+            Line::Text(inlines) => {
+                let text: String = inlines
+                    .iter()
+                    .map(|i| match i {
+                        Inline::Roman(t) | Inline::Roff(t) => t.as_str(),
+                        _ => "",
+                    })
+                    .collect();
+                conversion.text(text);
+            }
+            line => conversion.page.lines.push(line),
+        }
+    }
+    conversion.end_section();
+    let mut page = conversion.page;
+    parser::prologue(&mut page);
+    page
+}
+
+/// The state of a conversion.
+struct Conversion<'w> {
+    page: Mdoc,
+    warnings: &'w mut Warnings,
+    /// The title of the current section.
+    section: String,
+    /// The name of the page, from the NAME section.
+    name: String,
+    /// The text lines of the NAME section.
+    name_text: Vec<String>,
+    /// The kind of the list open at each `RS` level.
+    lists: Vec<Option<&'static str>>,
+    /// Whether a literal display is open.
+    literal: bool,
+    /// Whether the next line is the tag of a `TP` item.
+    tag: bool,
+    /// The font set for the next text line by a font macro without
+    /// arguments.
+    font: Option<Font>,
+    /// The macro of a heading whose title is on the next line.
+    heading: Option<&'static str>,
+    /// The address and text of an open `UR` or `MT` link.
+    link: Option<(&'static str, String, Vec<String>)>,
+}
+
+impl Conversion<'_> {
+    fn control(&mut self, name: &str, args: Vec<String>) {
+        match name {
+            "TH" => {
+                let date: Vec<&str> = args
+                    .get(2)
+                    .map(|d| d.split_whitespace().collect())
+                    .unwrap_or_else(|| vec!["$Mdocdate$"]);
+                self.page.control("Dd".into(), date);
+                self.page
+                    .control("Dt".into(), args.iter().take(2).map(String::as_str));
+                self.page
+                    .control("Os".into(), args.get(3).map(String::as_str));
+            }
+            "SH" | "SS" => {
+                let macro_name = if name == "SH" { "Sh" } else { "Ss" };
+                if args.is_empty() {
+                    self.heading = Some(macro_name);
+                } else {
+                    self.heading(macro_name, &args.join(" "));
+                }
+            }
+            "PP" | "LP" | "P" | "HP" => {
+                self.close_list();
+                self.page.control("Pp".into(), []);
+            }
+            "sp" => {
+                self.page.control("Pp".into(), []);
+            }
+            "TP" | "TQ" => self.tag = true,
+            "IP" => {
+                let tag = args.first().map(String::as_str).unwrap_or_default();
+                match tag {
+                    "" => {
+                        self.page.control("Pp".into(), []);
+                    }
+                    "\\(bu" | "\\[bu]" | "*" | "\\(em" | "\\(en" | "\\-" | "-" | "\u{2022}" => {
+                        self.item("bullet", vec![])
+                    }
+                    tag if tag.trim_end_matches(['.', ')']).parse::<u32>().is_ok() => {
+                        self.item("enum", vec![])
+                    }
+                    tag => self.item("tag", parser::split_args(&quoted(tag), false)),
+                }
+            }
+            "RS" => {
+                self.page
+                    .control("Bd".into(), ["-ragged", "-offset", "indent"]);
+                self.lists.push(None);
+            }
+            "RE" => {
+                if self.lists.len() > 1 {
+                    self.close_list();
+                    self.lists.pop();
+                    self.page.control("Ed".into(), []);
+                }
+            }
+            "nf" | "EX" => {
+                if !std::mem::replace(&mut self.literal, true) {
+                    self.page.control("Bd".into(), ["-literal"]);
+                }
+            }
+            "fi" | "EE" => {
+                if std::mem::take(&mut self.literal) {
+                    self.page.control("Ed".into(), []);
+                }
+            }
+            "br" => {
+                self.page.control("br".into(), []);
+            }
+            "UR" | "MT" => {
+                let macro_name = if name == "UR" { "Lk" } else { "Mt" };
+                let address = args.first().cloned().unwrap_or_default();
+                self.link = Some((macro_name, address, vec![]));
+            }
+            "UE" | "ME" => {
+                if let Some((macro_name, address, text)) = self.link.take() {
+                    let mut words = vec![address];
+                    if macro_name == "Lk" {
+                        words.extend(
+                            text.iter()
+                                .flat_map(|t| t.split_whitespace())
+                                .map(str::to_string),
+                        );
+                    }
+                    words.extend(args);
+                    self.emit(macro_name.to_string(), words);
+                }
+            }
+            "B" | "I" | "SB" | "SM" if args.is_empty() => {
+                self.font = Some(match name {
+                    "I" => Font::Italic,
+                    "SM" => Font::Roman,
+                    _ => Font::Bold,
+                });
+            }
+            "B" | "SB" => self.pieces(vec![(Font::Bold, args.join(" "))]),
+            "I" => self.pieces(vec![(Font::Italic, args.join(" "))]),
+            "SM" => self.pieces(vec![(Font::Roman, args.join(" "))]),
+            "BR" | "RB" | "BI" | "IB" | "IR" | "RI" => {
+                let fonts: Vec<Font> = name
+                    .chars()
+                    .map(|c| match c {
+                        'B' => Font::Bold,
+                        'I' => Font::Italic,
+                        _ => Font::Roman,
+                    })
+                    .collect();
+                let pieces = args
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, a)| (fonts[i % 2], a))
+                    .collect();
+                self.pieces(pieces);
+            }
+            "PD" | "DT" | "ad" | "na" | "hy" | "nh" => {}
+            "\\\"" => {
+                self.page
+                    .control(name.to_string().into(), args.iter().map(String::as_str));
+            }
+            name if name.starts_with(|c: char| c.is_ascii_lowercase()) => {
+                self.warnings.push_in(
+                    &self.section,
+                    format!("roff request {name} is kept as it is"),
+                );
+                let source = std::iter::once(format!(".{name}"))
+                    .chain(args.iter().map(|a| quoted(a)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.page
+                    .lines
+                    .push(Line::Unknown(Unknown::new(SourceLanguage::Roff, source)));
+            }
+            name => {
+                self.warnings
+                    .push_in(&self.section, format!("macro {name} is not supported"));
+                let source = std::iter::once(format!(".{name}"))
+                    .chain(args.iter().map(|a| quoted(a)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.page
+                    .lines
+                    .push(Line::Unknown(Unknown::new(SourceLanguage::Man, source)));
+            }
+        }
+    }
+
+    fn text(&mut self, text: String) {
+        if let Some(macro_name) = self.heading.take() {
+            self.heading(macro_name, text.trim());
+            return;
+        }
+        if let Some((_, _, words)) = self.link.as_mut() {
+            words.push(text);
+            return;
+        }
+        if self.section == "NAME" {
+            self.name_text.push(text);
+            return;
+        }
+        if let Some(font) = self.font.take() {
+            self.pieces(vec![(font, text)]);
+            return;
+        }
+        if std::mem::take(&mut self.tag) {
+            self.item("tag", parser::split_args(&text, false));
+            return;
+        }
+        self.page.text([roff(text)]);
+    }
+
+    fn heading(&mut self, macro_name: &'static str, title: &str) {
+        if macro_name == "Sh" {
+            self.end_section();
+            self.section = title.to_string();
+        } else {
+            self.close_list();
+        }
+        self.page.control(macro_name.into(), [title]);
+    }
+
+    /// Close the displays and lists of the section, and write the NAME
+    /// section out.
+    fn end_section(&mut self) {
+        if std::mem::take(&mut self.literal) {
+            self.page.control("Ed".into(), []);
+        }
+        while self.lists.len() > 1 {
+            self.close_list();
+            self.lists.pop();
+            self.page.control("Ed".into(), []);
+        }
+        self.close_list();
+        if self.section != "NAME" {
+            return;
+        }
+        let text = std::mem::take(&mut self.name_text).join(" ");
+        let (names, description) = text
+            .split_once(" \\- ")
+            .or_else(|| text.split_once(" - "))
+            .unwrap_or((&text, ""));
+        let names: Vec<&str> = names
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            if i + 1 < names.len() {
+                self.page.control("Nm".into(), [*name, ","]);
+            } else {
+                self.page.control("Nm".into(), [*name]);
+            }
+        }
+        if let Some(first) = names.first() {
+            self.name = first.to_string();
+        }
+        self.page.control("Nd".into(), [description.trim()]);
+    }
+
+    /// Start an item of a list of `kind`, opening the list if needed.
+    fn item(&mut self, kind: &'static str, args: Vec<String>) {
+        self.tag = false;
+        if *self.lists.last().unwrap() != Some(kind) {
+            self.close_list();
+            match kind {
+                "tag" => self.page.control("Bl".into(), ["-tag", "-width", "Ds"]),
+                kind => self
+                    .page
+                    .control("Bl".into(), [format!("-{kind}").as_str()]),
+            };
+            *self.lists.last_mut().unwrap() = Some(kind);
+        }
+        self.emit("It".to_string(), args);
+    }
+
+    fn close_list(&mut self) {
+        if self.lists.last_mut().unwrap().take().is_some() {
+            self.page.control("El".into(), []);
+        }
+    }
+
+    /// Write text set in fonts as semantic macros, or as the tag of a
+    /// pending list item.
+    fn pieces(&mut self, pieces: Vec<(Font, String)>) {
+        if self.section == "NAME" {
+            let text = pieces.into_iter().map(|(_, t)| t).collect();
+            self.name_text.push(text);
+            return;
+        }
+        let words = semantic(pieces, &self.name);
+        if std::mem::take(&mut self.tag) {
+            self.item("tag", words);
+            return;
+        }
+        let mut words = words.into_iter();
+        if let Some(first) = words.next() {
+            if phrase::is_delimiter(&first) {
+                self.emit(
+                    "No".to_string(),
+                    std::iter::once(first).chain(words).collect(),
+                );
+            } else {
+                self.emit(first, words.collect());
+            }
+        }
+    }
+
+    fn emit(&mut self, name: String, args: Vec<String>) {
+        self.page.lines.push(Line::control(
+            name.into(),
+            args.into_iter().map(Into::into).collect(),
+        ));
+    }
+}
+
+/// Guess the semantic macros for text set in fonts, as the words of a
+/// macro line.
+fn semantic(mut pieces: Vec<(Font, String)>, name: &str) -> Vec<String> {
+    let mut words = vec![];
+    for i in 0..pieces.len() {
+        let (font, text) = pieces[i].clone();
+        if text.is_empty() {
+            continue;
+        }
+        let split = |text: &str| -> Vec<String> { parser::split_args(&quoted(text), false) };
+        match font {
+            Font::Bold => {
+                let section = pieces.get(i + 1).and_then(|(font, next)| {
+                    let (section, _) = next.strip_prefix('(')?.split_once(')')?;
+                    (*font == Font::Roman && section.starts_with(|c: char| c.is_ascii_digit()))
+                        .then(|| section.to_string())
+                });
+                if let Some(section) = section {
+                    words.extend(["Xr".to_string(), text, section.clone()]);
+                    let next = &mut pieces[i + 1].1;
+                    *next = next[section.len() + 2..].to_string();
+                } else if let Some(flag) =
+                    text.strip_prefix("\\-").or_else(|| text.strip_prefix('-'))
+                {
+                    words.push("Fl".to_string());
+                    words.extend(split(flag));
+                } else if text == name {
+                    words.push("Nm".to_string());
+                } else {
+                    words.push("Sy".to_string());
+                    words.extend(split(&text));
+                }
+            }
+            Font::Italic => {
+                words.push("Ar".to_string());
+                words.extend(split(&text));
+            }
+            Font::Roman => {
+                let mut plain = false;
+                for word in text.split_whitespace() {
+                    if phrase::is_delimiter(word) {
+                        plain = false;
+                    } else if !plain {
+                        words.push("No".to_string());
+                        plain = true;
+                    }
+                    words.push(word.to_string());
+                }
+            }
+        }
+    }
+    words
+}
+
+/// An argument, quoted if it contains spaces.
+fn quoted(arg: &str) -> String {
+    if arg.contains(char::is_whitespace) {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        arg.to_string()
+    }
+}
@@ -0,0 +1,73 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Dates of pages from the history of their sources.
+//!
+//! Pages regenerated from unchanged sources should not change, but stamping
+//! them with the current date makes every regeneration a change.
+//! [`last_commit_date`] gives the date of the last commit touching a
+//! source file instead, so that the `Dd` date of a page only moves when its
+//! content does. The history is read with git(1).
+
+use std::{path::Path, process::Command};
+
+use super::*;
+
+/// The date of the last commit touching `path`, or `None` if it isn't
+/// tracked by git or has uncommitted changes, in which case the current
+/// date is the right one.
+///
+/// Errors are returned if git(1) can't be run.
+pub fn last_commit_date(path: &Path) -> std::io::Result<Option<DocumentDate>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Some(file) = path.file_name() else {
+        return Ok(None);
+    };
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .arg("--")
+            .arg(file)
+            .output()
+    };
+    let status = git(&["status", "--porcelain"])?;
+    if !status.status.success() || !status.stdout.is_empty() {
+        return Ok(None);
+    }
+    let log = git(&["log", "-1", "--format=%cd", "--date=short"])?;
+    if !log.status.success() {
+        return Ok(None);
+    }
+    // `2024-03-01`
+    let date = String::from_utf8_lossy(&log.stdout);
+    let mut parts = date.trim().splitn(3, '-').map(|p| p.parse::<u32>().ok());
+    Ok(match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(year)), Some(Some(month)), Some(Some(day))) => {
+            DocumentDate::from_ymd(year, month, day)
+        }
+        _ => None,
+    })
+}
@@ -0,0 +1,267 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Format pages with groff(1).
+//!
+//! The PDF output of [`to_pdf`] is made navigable: the document metadata is
+//! set from the page and every section and subsection gets a bookmark.
+//! Whole manuals can be printed as a single [`book`].
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let mut page = Mdoc::new(
+//!     Some(DocumentDate {
+//!         month: Month::new("July"),
+//!         day: Day::new("1,"),
+//!         year: Year::new("2024"),
+//!     }),
+//!     DocumentTitle {
+//!         title: title!("FOO"),
+//!         section: section!("1"),
+//!         arch: None,
+//!     },
+//!     name!("foo"),
+//!     description!("frobnicate"),
+//!     None,
+//! );
+//! page.control("Sh".into(), ["AUTHORS"])
+//!     .control("An".into(), ["Jane", "Doe", "Aq", "Mt", "jane@example.com"]);
+//! let source = groff::pdf_source(&page);
+//! assert!(source.contains(".pdfinfo /Title FOO(1)\n"));
+//! assert!(source.contains(".pdfinfo /Author Jane Doe\n"));
+//! assert!(source.contains(".pdfinfo /CreationDate D:20240701\n"));
+//! assert!(source.contains(".pdfbookmark 1 AUTHORS\n.Sh AUTHORS\n"));
+//! ```
+
+use std::io::Write as _;
+
+use super::*;
+
+/// The `Dt` title of the page, e.g. `FOO(1)`.
+fn title(page: &Mdoc) -> Option<String> {
+    page.lines.iter().find_map(|line| match line {
+        Line::Control { name, args } if name == "Dt" => match args.as_slice() {
+            [title, section, ..] => Some(format!("{title}({section})")),
+            [title] => Some(title.to_string()),
+            [] => None,
+        },
+        _ => None,
+    })
+}
+
+/// The names of the authors listed with `An` in the AUTHORS section.
+fn authors(page: &Mdoc) -> Vec<String> {
+    let Some((_, range)) = page.sections().into_iter().find(|(t, _)| t == "AUTHORS") else {
+        return vec![];
+    };
+    page.lines[range]
+        .iter()
+        .filter_map(|line| match line {
+            Line::Control { name, args } if name == "An" => {
+                let name = args
+                    .iter()
+                    .take_while(|a| !matches!(a.as_ref(), "Aq" | "Mt" | "," | "."))
+                    .filter(|a| !a.starts_with('-'))
+                    .map(|a| a.as_ref())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (!name.is_empty()).then_some(name)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The date of the page as a PDF date, e.g. `D:20240701`.
+fn creation_date(page: &Mdoc) -> Option<String> {
+    let (year, month, day) = page.date.as_ref()?.ymd().ok()?;
+    Some(format!("D:{year:04}{month:02}{day:02}"))
+}
+
+/// The title of a bookmark for a heading with `args`, as plain text
+/// without quotes or escapes.
+fn bookmark_title(args: &[Str]) -> String {
+    let glyphs = term::Charset::Ascii.glyphs();
+    args.iter()
+        .map(|arg| glyphs.unescape(&parser::unquote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The source of the page for `groff -Tpdf -mpdf`, with `.pdfinfo`
+/// requests for the document metadata and a `.pdfbookmark` before every
+/// section and subsection heading.
+pub fn pdf_source(page: &Mdoc) -> String {
+    let mut info = vec![];
+    if let Some(title) = title(page) {
+        info.push(format!(".pdfinfo /Title {title}\n"));
+    }
+    let authors = authors(page);
+    if !authors.is_empty() {
+        info.push(format!(".pdfinfo /Author {}\n", authors.join(", ")));
+    }
+    if let Some(date) = creation_date(page) {
+        info.push(format!(".pdfinfo /CreationDate {date}\n"));
+    }
+    page_source(page, Some(1), info)
+}
+
+/// The source of the page with `prologue` inserted before the first
+/// section, and bookmarks at `level` for sections and `level + 1` for
+/// subsections, if a level is given.
+fn page_source(page: &Mdoc, level: Option<usize>, prologue: Vec<String>) -> String {
+    let mut out = String::new();
+    let mut prologue = Some(prologue);
+    for line in page.expand_acronyms(AcronymStyle::default()).iter() {
+        if let Line::Control { name, args } = line {
+            let depth = match name.as_ref() {
+                "Sh" => Some(0),
+                "Ss" => Some(1),
+                _ => None,
+            };
+            if let Some(depth) = depth {
+                // The metadata goes after the prologue of the page.
+                out.extend(prologue.take().into_iter().flatten());
+                if let Some(level) = level {
+                    out.push_str(&format!(
+                        ".pdfbookmark {} {}\n",
+                        level + depth,
+                        bookmark_title(args)
+                    ));
+                }
+            }
+        }
+        out.push_str(&line_source(line));
+    }
+    out
+}
+
+fn line_source(line: &Line) -> String {
+    let mut buf = vec![];
+    // Writing to a Vec always works, so we discard any error.
+    line.render(&mut buf).unwrap();
+    String::from_utf8(buf).expect("output is utf8 if all input is utf8")
+}
+
+/// The one-line description of the page, from its `Nd` line.
+fn description(page: &Mdoc) -> String {
+    page.lines
+        .iter()
+        .find_map(|line| match line {
+            Line::Control { name, args } if name == "Nd" => Some(args.join(" ")),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// An output device of groff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Pdf,
+    PostScript,
+}
+
+impl Device {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::Pdf => &["-Tpdf", "-mdoc", "-mpdf"],
+            Self::PostScript => &["-Tps", "-mdoc"],
+        }
+    }
+}
+
+/// The source of a reference book of all the pages of `manual`, with a
+/// title page and a table of contents.
+///
+/// For PDF output, each page gets a bookmark, under which are the
+/// bookmarks of its sections.
+pub fn book_source(manual: &Manual, title: &str, device: Device) -> String {
+    let pdf = device == Device::Pdf;
+    let mut out = String::new();
+    if pdf {
+        out.push_str(&format!(".pdfinfo /Title {title}\n"));
+    }
+    out.push_str(&format!(".sp 3i\n.ps +8\n.ce\n{title}\n.ps\n.bp\n"));
+    if pdf {
+        out.push_str(".pdfbookmark 1 Contents\n");
+    }
+    out.push_str(".ce\nContents\n.sp\n.nf\n");
+    for page in &manual.pages {
+        out.push_str(&format!(
+            "{} \\- {}\n",
+            self::title(page).unwrap_or_default(),
+            description(page)
+        ));
+    }
+    out.push_str(".fi\n.bp\n");
+    for page in &manual.pages {
+        if pdf {
+            out.push_str(&format!(
+                ".pdfbookmark 1 {}\n",
+                bookmark_title(&[self::title(page).unwrap_or_default().into()])
+            ));
+        }
+        out.push_str(&page_source(page, pdf.then_some(2), vec![]));
+    }
+    out
+}
+
+/// Run groff with the source for `device`, writing its output to `w`.
+fn run(source: String, device: Device, w: &mut dyn Write) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("groff")
+        .args(device.args())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().expect("writer thread panicked")?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "groff exited with {}",
+            output.status
+        )));
+    }
+    w.write_all(&output.stdout)
+}
+
+/// Format the page as PDF with `groff -Tpdf -mdoc -mpdf`, writing the
+/// result to `w`.
+pub fn to_pdf(page: &Mdoc, w: &mut dyn Write) -> std::io::Result<()> {
+    run(pdf_source(page), Device::Pdf, w)
+}
+
+/// Format all the pages of `manual` as a single book, see [`book_source`],
+/// writing the result to `w`.
+pub fn book(
+    manual: &Manual,
+    title: &str,
+    device: Device,
+    w: &mut dyn Write,
+) -> std::io::Result<()> {
+    run(book_source(manual, title, device), device, w)
+}
@@ -0,0 +1,663 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Render to semantic HTML5.
+//!
+//! Sections become `<section>` elements with linkable headings, lists
+//! become definition, bullet and numbered lists, semantic macros become
+//! `<code>`, `<var>` and `<a>` elements with the name of the macro as
+//! their class for styling, and cross references link to the HTML
+//! rendering of the referenced page.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let mut page = parser::parse(".Dt FOO 1\n.Sh SEE ALSO\n.Xr bar 1\n");
+//! let html = html::HtmlRenderer::default().render(&page);
+//! assert!(html.contains("<title>FOO(1)</title>"));
+//! assert!(html.contains(
+//!     "<section class=\"Sh\">\n<h1 id=\"SEE_ALSO\">SEE ALSO</h1>\n\
+//!      <p><a class=\"Xr\" href=\"bar.1.html\">bar(1)</a></p>\n</section>\n"
+//! ));
+//! ```
+
+use super::*;
+
+/// Renders documents as HTML5.
+#[derive(Debug, Clone)]
+pub struct HtmlRenderer {
+    options: RenderOptions,
+    links: String,
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        Self::new(RenderOptions::default())
+    }
+}
+
+impl HtmlRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self {
+            options,
+            links: "%N.%S.html".to_string(),
+        }
+    }
+
+    /// The pattern of the links of cross references, where `%N` is
+    /// replaced by the name of the page and `%S` by its section.
+    pub fn links(self, pattern: impl Into<String>) -> Self {
+        Self {
+            links: pattern.into(),
+            ..self
+        }
+    }
+
+    /// Render `page` as an HTML document.
+    pub fn render(&self, page: &Mdoc) -> String {
+        let mut buf = vec![];
+        self.to_writer(page, &mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Write `page` as an HTML document to a writer.
+    pub fn to_writer(&self, page: &Mdoc, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render_html", document = %page.name.0).entered();
+        let lines = page.rendered_lines(&self.options);
+        let mut lowering = Lowering {
+            renderer: self,
+            name: page.name.0.to_string(),
+            out: String::new(),
+            title: String::new(),
+            footer: [String::new(), String::new()],
+            section: String::new(),
+            sections: 0,
+            paragraph: false,
+            names: vec![],
+            lists: vec![],
+            displays: vec![],
+            reference: None,
+        };
+        if lowering.name.is_empty() {
+            lowering.name = lines
+                .iter()
+                .find_map(|line| match line {
+                    Line::Control { name, args } if name == "Nm" => {
+                        args.first().map(ToString::to_string)
+                    }
+                    _ => None,
+                })
+                .unwrap_or_default();
+        }
+        for line in lines.iter() {
+            lowering.line(line);
+        }
+        lowering.flush_names();
+        lowering.close_paragraph();
+        while lowering.lists.pop().is_some() || lowering.displays.pop().is_some() {}
+        for _ in 0..lowering.sections {
+            lowering.out.push_str("</section>\n");
+        }
+        let title = if lowering.title.is_empty() {
+            escape(&lowering.name)
+        } else {
+            escape(&lowering.title)
+        };
+        let mut document = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<main>\n{}</main>\n",
+            lowering.out
+        );
+        let [date, os] = &lowering.footer;
+        if !date.is_empty() || !os.is_empty() {
+            document.push_str(&format!(
+                "<footer>\n<span class=\"Dd\">{}</span>\n<span class=\"Os\">{}</span>\n</footer>\n",
+                escape(date),
+                escape(os)
+            ));
+        }
+        document.push_str("</body>\n</html>\n");
+        self.options
+            .newline
+            .writer(w)
+            .write_all(document.as_bytes())
+    }
+}
+
+/// An open `Bl` list.
+struct List {
+    kind: String,
+    /// Whether an item is open.
+    item: bool,
+}
+
+/// The state of the lowering of one document.
+struct Lowering<'a> {
+    renderer: &'a HtmlRenderer,
+    name: String,
+    out: String,
+    /// The `Dt` title, e.g. `FOO(1)`.
+    title: String,
+    /// The date and operating system of the footer.
+    footer: [String; 2],
+    section: String,
+    /// The number of open `<section>` elements.
+    sections: usize,
+    /// Whether a `<p>` element is open.
+    paragraph: bool,
+    /// The `Nm` names of the NAME section, waiting for its `Nd`.
+    names: Vec<String>,
+    lists: Vec<List>,
+    /// Whether each open `Bd` display is preformatted.
+    displays: Vec<bool>,
+    /// The fields of an open `Rs` reference.
+    reference: Option<Vec<(String, String)>>,
+}
+
+impl Lowering<'_> {
+    fn line(&mut self, line: &Line) {
+        match line {
+            Line::Figure(figure) => {
+                self.close_paragraph();
+                self.out.push_str(&format!(
+                    "<figure>\n<img src=\"{}\" alt=\"{}\">\n<figcaption>{}</figcaption>\n</figure>\n",
+                    escape(&figure.src),
+                    escape(&figure.caption),
+                    escape(&figure.caption)
+                ));
+            }
+            Line::Admonition(admonition) => {
+                self.close_paragraph();
+                let class = match admonition.kind {
+                    AdmonitionKind::Note => "note",
+                    AdmonitionKind::Warning => "warning",
+                    AdmonitionKind::Caution => "caution",
+                };
+                self.out.push_str(&format!(
+                    "<aside class=\"{class}\">\n<p><strong>{}</strong> {}</p>\n</aside>\n",
+                    admonition.kind.label(),
+                    self.inlines(&admonition.text)
+                ));
+            }
+            Line::Changed(line) => {
+                self.out.push_str("<ins class=\"changed\">");
+                self.line(line);
+                self.out.push_str("</ins>\n");
+            }
+            Line::Unknown(unknown) => {
+                // Nothing else can be read as HTML, so the source is kept as
+                // a comment.
+                self.out.push_str(&format!(
+                    "<!-- {} -->\n",
+                    unknown.source.replace("--", "- -")
+                ));
+            }
+            Line::Text(inlines) => {
+                let text = self.inlines(inlines);
+                self.text(&text);
+            }
+            Line::Control { name, args } => self.control(name, args),
+        }
+    }
+
+    fn control(&mut self, name: &str, args: &[Str]) {
+        let args: Vec<Str> = args
+            .iter()
+            .filter(|arg| !arg.starts_with("\\\n"))
+            .cloned()
+            .collect();
+        let joined = || args.join(" ");
+        if name == "Rs" {
+            self.reference = Some(vec![]);
+            return;
+        }
+        if let Some(fields) = self.reference.as_mut() {
+            if let Some(field) = name.strip_prefix('%') {
+                fields.push((field.to_string(), joined()));
+                return;
+            }
+            if name == "Re" {
+                let fields = self.reference.take().unwrap();
+                self.close_paragraph();
+                self.out
+                    .push_str(&format!("<p class=\"Rs\">{}</p>\n", reference(&fields)));
+                return;
+            }
+        }
+        match name {
+            "Dd" => {
+                self.footer[0] = joined()
+                    .trim_start_matches("$Mdocdate")
+                    .trim_start_matches(':')
+                    .trim_end_matches('$')
+                    .trim()
+                    .to_string()
+            }
+            "Dt" => {
+                self.title = match args.as_slice() {
+                    [title, section, ..] => format!("{title}({section})"),
+                    _ => joined(),
+                }
+            }
+            "Os" => self.footer[1] = joined(),
+            "Sh" | "Ss" => {
+                self.flush_names();
+                self.close_paragraph();
+                while self.lists.pop().is_some() || self.displays.pop().is_some() {}
+                let title = joined();
+                let (level, depth) = if name == "Sh" { (1, 0) } else { (2, 1) };
+                while self.sections > depth {
+                    self.out.push_str("</section>\n");
+                    self.sections -= 1;
+                }
+                // A subsection before the first section is at the top.
+                self.sections = self.sections.max(depth);
+                if name == "Sh" {
+                    self.section = title.clone();
+                }
+                self.out.push_str(&format!(
+                    "<section class=\"{name}\">\n<h{level} id=\"{}\">{}</h{level}>\n",
+                    anchor(&title),
+                    escape(&title)
+                ));
+                self.sections += 1;
+            }
+            "Pp" | "Lp" => self.close_paragraph(),
+            "Nm" if self.section == "NAME" => self.names.extend(
+                args.iter()
+                    .filter(|a| !phrase::is_closing(a))
+                    .map(|a| a.to_string()),
+            ),
+            "Nd" => {
+                let names = std::mem::take(&mut self.names);
+                let names = if names.is_empty() {
+                    vec![self.name.clone()]
+                } else {
+                    names
+                };
+                let names: Vec<String> = names
+                    .iter()
+                    .map(|n| phrase::Markup::call(self, "Nm", &[n]))
+                    .collect();
+                let text = format!("{} — {}", names.join(", "), self.phrase(&args));
+                self.text(&text);
+            }
+            "Nm" if self.section == "SYNOPSIS" => {
+                self.close_paragraph();
+                let text = phrase::macro_line(self, "Nm", &args);
+                self.text(&text);
+            }
+            "Bd" => {
+                self.close_paragraph();
+                let preformatted = args.iter().any(|a| a == "-literal" || a == "-unfilled");
+                let mut class = args
+                    .first()
+                    .map(|kind| format!("Bd Bd{kind}"))
+                    .unwrap_or_else(|| "Bd".to_string());
+                if args.iter().any(|a| a == "-offset") {
+                    class.push_str(" Bd-indent");
+                }
+                if preformatted {
+                    self.out.push_str(&format!("<pre class=\"{class}\">"));
+                } else {
+                    self.out.push_str(&format!("<div class=\"{class}\">\n"));
+                }
+                self.displays.push(preformatted);
+            }
+            "Ed" => {
+                let Some(preformatted) = self.displays.pop() else {
+                    return;
+                };
+                if preformatted {
+                    self.out.push_str("</pre>\n");
+                } else {
+                    self.close_paragraph();
+                    self.out.push_str("</div>\n");
+                }
+            }
+            "D1" => {
+                self.close_paragraph();
+                let text = self.phrase(&args);
+                self.out
+                    .push_str(&format!("<div class=\"Bd-indent\">{text}</div>\n"));
+            }
+            "Dl" => {
+                self.close_paragraph();
+                let text = self.phrase(&args);
+                self.out.push_str(&format!(
+                    "<div class=\"Bd-indent\"><code class=\"Li\">{text}</code></div>\n"
+                ));
+            }
+            "Bl" => {
+                self.close_paragraph();
+                let kind = args
+                    .iter()
+                    .find(|a| {
+                        a.starts_with('-')
+                            && !matches!(a.as_ref(), "-width" | "-offset" | "-compact")
+                    })
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| "-tag".to_string());
+                let mut class = format!("Bl{kind}");
+                if args.iter().any(|a| a == "-compact") {
+                    class.push_str(" Bl-compact");
+                }
+                let element = element(&kind);
+                self.out
+                    .push_str(&format!("<{element} class=\"{class}\">\n"));
+                self.lists.push(List { kind, item: false });
+            }
+            "It" => self.item(&args),
+            "El" => {
+                self.close_paragraph();
+                let Some(list) = self.lists.pop() else {
+                    return;
+                };
+                let element = element(&list.kind);
+                if list.item {
+                    self.out.push_str(match element {
+                        "dl" => "</dd>\n",
+                        "table" => "</tr>\n",
+                        _ => "</li>\n",
+                    });
+                }
+                self.out.push_str(&format!("</{element}>\n"));
+            }
+            "br" => self.out.push_str("<br>\n"),
+            "sp" => self.close_paragraph(),
+            // Keeps, spacing mode and roff requests don't apply to HTML.
+            "Bk" | "Ek" | "Sm" | "Tg" => {}
+            _ if !name.starts_with(|c: char| c.is_ascii_uppercase()) => {}
+            _ => {
+                let text = phrase::macro_line(self, name, &args);
+                self.text(&text);
+            }
+        }
+    }
+
+    fn item(&mut self, args: &[Str]) {
+        self.close_paragraph();
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        let element = element(&list.kind);
+        let was_open = std::mem::replace(&mut list.item, true);
+        match element {
+            "dl" => {
+                if was_open {
+                    self.out.push_str("</dd>\n");
+                }
+                let head = self.phrase(args);
+                self.out.push_str(&format!("<dt>{head}</dt>\n<dd>"));
+            }
+            "table" => {
+                if was_open {
+                    self.out.push_str("</tr>\n");
+                }
+                let cells: Vec<String> = args
+                    .split(|a| a == "Ta")
+                    .map(|cell| format!("<td>{}</td>", self.phrase(cell)))
+                    .collect();
+                self.out.push_str(&format!("<tr>{}", cells.join("")));
+            }
+            _ => {
+                if was_open {
+                    self.out.push_str("</li>\n");
+                }
+                self.out.push_str("<li>");
+            }
+        }
+    }
+
+    /// Write the NAME line for `Nm` names without an `Nd`.
+    fn flush_names(&mut self) {
+        if !self.names.is_empty() {
+            let names: Vec<String> = std::mem::take(&mut self.names)
+                .iter()
+                .map(|n| phrase::Markup::call(self, "Nm", &[n]))
+                .collect();
+            self.text(&names.join(", "));
+        }
+    }
+
+    /// Write text, in a paragraph unless it is preformatted.
+    fn text(&mut self, html: &str) {
+        if self.displays.last() == Some(&true) {
+            self.out.push_str(html);
+            self.out.push('\n');
+            return;
+        }
+        if self.paragraph {
+            self.out.push('\n');
+        } else {
+            self.out.push_str("<p>");
+            self.paragraph = true;
+        }
+        self.out.push_str(html);
+    }
+
+    fn close_paragraph(&mut self) {
+        if std::mem::take(&mut self.paragraph) {
+            self.out.push_str("</p>\n");
+        }
+    }
+
+    /// The inline elements of a text line as HTML.
+    fn inlines(&self, inlines: &[Inline]) -> String {
+        let mut ret = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Roman(text) | Inline::Roff(text) | Inline::Acronym(text) => {
+                    ret.push_str(&escape(text))
+                }
+                Inline::Bold(text) => ret.push_str(&format!("<b>{}</b>", escape(text))),
+                Inline::Italic(text) => ret.push_str(&format!("<i>{}</i>", escape(text))),
+                Inline::LineBreak => ret.push_str("<br>\n"),
+                Inline::Name | Inline::Flag { .. } => {
+                    ret.push_str(&self.phrase(&inline.macro_args(&self.renderer.options)))
+                }
+                Inline::IndexTerm(term) => {
+                    ret.push_str(&format!("<a id=\"{}\"></a>", anchor(term)))
+                }
+            }
+        }
+        ret
+    }
+
+    fn phrase(&self, args: &[Str]) -> String {
+        phrase::phrase(self, args)
+    }
+}
+
+impl phrase::Markup for Lowering<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text(&self, text: &str) -> String {
+        escape(text)
+    }
+
+    fn enclosure(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        Some(match name {
+            "Op" | "Bq" => ("[", "]"),
+            "Brq" => ("{", "}"),
+            "Pq" => ("(", ")"),
+            "Aq" => ("&lt;", "&gt;"),
+            "Dq" => ("“", "”"),
+            "Sq" => ("‘", "’"),
+            "Ql" => ("‘<code class=\"Li\">", "</code>’"),
+            "Qq" => ("&quot;", "&quot;"),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, name: &str, words: &[&str]) -> String {
+        let text = escape(&words.join(" "));
+        let element = |element: &str| format!("<{element} class=\"{name}\">{text}</{element}>");
+        match name {
+            "Fl" => format!("<code class=\"Fl\">-{}</code>", escape(&words.join(" -"))),
+            "Ar" if words.is_empty() => "<var class=\"Ar\">file ...</var>".to_string(),
+            "Nm" if words.is_empty() => {
+                format!("<code class=\"Nm\">{}</code>", escape(&self.name))
+            }
+            "Xr" => match words {
+                [page, section] => format!(
+                    "<a class=\"Xr\" href=\"{}\">{}({})</a>",
+                    escape(
+                        &self
+                            .renderer
+                            .links
+                            .replace("%N", page)
+                            .replace("%S", section)
+                    ),
+                    escape(page),
+                    escape(section)
+                ),
+                _ => element("code"),
+            },
+            "Sx" => format!(
+                "<a class=\"Sx\" href=\"#{}\">{text}</a>",
+                anchor(&words.join(" "))
+            ),
+            "Lk" => match words.split_first() {
+                Some((url, link_text)) => format!(
+                    "<a class=\"Lk\" href=\"{}\">{}</a>",
+                    escape(url),
+                    escape(&if link_text.is_empty() {
+                        url.to_string()
+                    } else {
+                        link_text.join(" ")
+                    })
+                ),
+                None => String::new(),
+            },
+            "Mt" => format!("<a class=\"Mt\" href=\"mailto:{text}\">{text}</a>"),
+            "Fn" => match words.split_first() {
+                Some((function, args)) => format!(
+                    "<code class=\"Fn\">{}</code>({})",
+                    escape(function),
+                    args.iter()
+                        .map(|a| format!("<var class=\"Fa\">{}</var>", escape(a)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None => String::new(),
+            },
+            "In" => format!("<code class=\"In\">#include &lt;{text}&gt;</code>"),
+            _ if words.is_empty() => String::new(),
+            "Nm" | "Cm" | "Ic" | "Fd" | "Cd" | "Ev" | "Dv" | "Er" | "Li" => element("code"),
+            "Ar" | "Va" | "Fa" | "Ft" | "Vt" => element("var"),
+            "Sy" => element("b"),
+            "Em" => element("i"),
+            "Pa" | "An" | "Ad" | "Ms" | "Tn" => element("span"),
+            _ => text,
+        }
+    }
+}
+
+/// The element of a list of the `Bl` type `kind`.
+fn element(kind: &str) -> &'static str {
+    match kind {
+        "-bullet" | "-dash" | "-hyphen" | "-item" => "ul",
+        "-enum" => "ol",
+        "-column" => "table",
+        _ => "dl",
+    }
+}
+
+/// The text of an `Rs` reference: its authors, then its other fields in
+/// order, with titles in italics.
+fn reference(fields: &[(String, String)]) -> String {
+    let authors: Vec<String> = fields
+        .iter()
+        .filter(|(field, _)| field == "A")
+        .map(|(_, value)| escape(value))
+        .collect();
+    let mut parts = vec![];
+    match authors.split_last() {
+        Some((last, [])) => parts.push(last.to_string()),
+        Some((last, rest)) => parts.push(format!("{} and {last}", rest.join(", "))),
+        None => {}
+    }
+    for (field, value) in fields.iter().filter(|(field, _)| field != "A") {
+        match field.as_str() {
+            "T" | "B" | "J" => parts.push(format!("<i>{}</i>", escape(value))),
+            "U" => parts.push(format!("<a href=\"{0}\">{0}</a>", escape(value))),
+            _ => parts.push(escape(value)),
+        }
+    }
+    format!("{}.", parts.join(", "))
+}
+
+/// The fragment identifier of a section or index term.
+pub fn anchor(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escape text for HTML, replacing the common roff escapes of **mdoc**
+/// source by the characters they stand for.
+pub fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('&') | None => {}
+                Some('-') => ret.push('-'),
+                Some('e') => ret.push('\\'),
+                Some(' ') => ret.push('\u{a0}'),
+                Some('(') => {
+                    let name: String = chars.by_ref().take(2).collect();
+                    ret.push_str(match name.as_str() {
+                        "lq" => "“",
+                        "rq" => "”",
+                        "oq" => "‘",
+                        "cq" => "’",
+                        "co" => "©",
+                        "em" => "—",
+                        "en" => "–",
+                        "bu" => "•",
+                        "aq" => "'",
+                        "dq" => "&quot;",
+                        _ => "",
+                    });
+                }
+                Some(other) => {
+                    ret.push('\\');
+                    ret.push(other);
+                }
+            },
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,359 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Input formats that can be converted to pages.
+//!
+//! Each format is an [`Importer`]. A [`Registry`] holds the importers the
+//! command line tool chooses from, by name with `--from` or by detecting
+//! the format of the input. Other crates can register their own importers
+//! to plug additional formats into the same pipeline.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::path::Path;
+//! # use mdoc::*;
+//! # use mdoc::import::*;
+//! /// Plain Old Documentation, the format of Perl.
+//! struct Pod;
+//!
+//! impl Importer for Pod {
+//!     fn name(&self) -> &str {
+//!         "pod"
+//!     }
+//!
+//!     fn detect(&self, input: &Input) -> bool {
+//!         input.source.starts_with("=head1")
+//!     }
+//!
+//!     fn import(&self, input: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+//!         let mut page = Mdoc::default();
+//!         for line in input.source.lines() {
+//!             match line.strip_prefix("=head1 ") {
+//!                 Some(heading) => page.control("Sh".into(), [heading]),
+//!                 None => page.text([roman(line)]),
+//!             };
+//!         }
+//!         Ok(page)
+//!     }
+//! }
+//!
+//! let mut registry = Registry::default();
+//! registry.register(Pod);
+//! let input = Input::new(Path::new("frob.pod"), "=head1 NAME\nfrob\n");
+//! let importer = registry.detect(&input).unwrap();
+//! assert_eq!(importer.name(), "pod");
+//! let page = importer.import(&input, &mut Warnings::new()).unwrap();
+//! assert_eq!(page.to_mdoc(), ".Sh NAME\nfrob\n");
+//! ```
+
+use std::path::Path;
+
+use super::*;
+
+pub type Error = Box<dyn std::error::Error>;
+
+/// Input to convert.
+#[derive(Debug, Clone, Copy)]
+pub struct Input<'a> {
+    /// The path of the input, or `-` for the standard input.
+    pub path: &'a Path,
+    pub source: &'a str,
+    /// The encoding the source was decoded from.
+    pub encoding: parser::Encoding,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(path: &'a Path, source: &'a str) -> Self {
+        Self {
+            path,
+            source,
+            encoding: parser::Encoding::default(),
+        }
+    }
+
+    /// The extension of the file name of the input.
+    pub fn extension(&self) -> Option<&str> {
+        self.path.extension().and_then(|e| e.to_str())
+    }
+
+    /// The first line of the source that isn't blank or a roff comment.
+    pub fn first_line(&self) -> &str {
+        self.source
+            .lines()
+            .map(str::trim_start)
+            .find(|l| !l.is_empty() && !l.starts_with(".\\\"") && !l.starts_with("'\\\""))
+            .unwrap_or_default()
+    }
+}
+
+/// A format pages can be converted from.
+pub trait Importer {
+    /// The name of the format, as given to `--from`.
+    fn name(&self) -> &str;
+
+    /// Other names of the format.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Whether `input`, judging from its file name and contents, is in this
+    /// format.
+    fn detect(&self, input: &Input) -> bool;
+
+    /// Convert `input`, reporting the parts that couldn't be converted
+    /// faithfully to `warnings`.
+    fn import(&self, input: &Input, warnings: &mut Warnings) -> Result<Mdoc, Error>;
+}
+
+/// A set of importers.
+pub struct Registry {
+    importers: Vec<Box<dyn Importer>>,
+}
+
+impl Default for Registry {
+    /// The importers of the formats built in this crate.
+    fn default() -> Self {
+        let mut ret = Self::new();
+        // Detection tries the last registered importers first, so the more
+        // specific formats go last.
+        ret.register(Annotations)
+            .register(Man)
+            .register(MdocSource)
+            .register(Json)
+            .register(Markdown)
+            .register(Script)
+            .register(Systemd)
+            .register(Formatted);
+        ret
+    }
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self { importers: vec![] }
+    }
+
+    /// Add an importer, which takes precedence over the ones already
+    /// registered, both by name and in detection.
+    pub fn register(&mut self, importer: impl Importer + 'static) -> &mut Self {
+        self.importers.push(Box::new(importer));
+        self
+    }
+
+    /// The importer of the format called `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn Importer> {
+        self.importers
+            .iter()
+            .rev()
+            .find(|i| i.name() == name || i.aliases().contains(&name))
+            .map(AsRef::as_ref)
+    }
+
+    /// The importer of the format `input` is detected to be in.
+    pub fn detect(&self, input: &Input) -> Option<&dyn Importer> {
+        self.importers
+            .iter()
+            .rev()
+            .find(|i| i.detect(input))
+            .map(AsRef::as_ref)
+    }
+
+    /// The names of the formats, in order of precedence.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for importer in self.importers.iter().rev() {
+            if !names.contains(&importer.name()) {
+                names.push(importer.name());
+            }
+        }
+        names
+    }
+}
+
+/// **mdoc** source, see [`parser`].
+struct MdocSource;
+
+impl Importer for MdocSource {
+    fn name(&self) -> &str {
+        "mdoc"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        let first = input.first_line();
+        first.starts_with(".Dd") || first.starts_with(".Dt")
+    }
+
+    fn import(&self, input: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+        let mut page = parser::parse(input.source);
+        page.set_encoding(input.encoding);
+        Ok(page)
+    }
+}
+
+/// Legacy man(7) source, see [`from_man`].
+struct Man;
+
+impl Importer for Man {
+    fn name(&self) -> &str {
+        "man"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        input.first_line().starts_with(".TH")
+    }
+
+    fn import(&self, input: &Input, warnings: &mut Warnings) -> Result<Mdoc, Error> {
+        let mut page = from_man::from_man_with_warnings(input.source, warnings);
+        page.set_encoding(input.encoding);
+        Ok(page)
+    }
+}
+
+/// The syntax tree written by the `json` output, see [`json`].
+struct Json;
+
+impl Importer for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        input.extension() == Some("json") || input.first_line().starts_with('{')
+    }
+
+    fn import(&self, input: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+        let mut page = json::from_json(input.source)?;
+        page.set_encoding(input.encoding);
+        Ok(page)
+    }
+}
+
+/// Markdown in the dialect of ronn(1), see [`markdown`].
+struct Markdown;
+
+impl Importer for Markdown {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["md"]
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        matches!(input.extension(), Some("md" | "markdown"))
+            || input.first_line().starts_with("# ")
+            || input.first_line().starts_with("---")
+    }
+
+    #[cfg(feature = "markdown")]
+    fn import(&self, input: &Input, warnings: &mut Warnings) -> Result<Mdoc, Error> {
+        let mut page = markdown::from_markdown_with_warnings(input.source, warnings);
+        page.set_encoding(input.encoding);
+        Ok(page)
+    }
+
+    #[cfg(not(feature = "markdown"))]
+    fn import(&self, _: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+        Err("conversion from markdown requires the `markdown` feature".into())
+    }
+}
+
+/// The comment header of a shell script, see [`script`].
+struct Script;
+
+impl Importer for Script {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        matches!(input.extension(), Some("sh" | "bash")) || input.source.starts_with("#!")
+    }
+
+    fn import(&self, input: &Input, warnings: &mut Warnings) -> Result<Mdoc, Error> {
+        Ok(script::from_script_with_warnings(input.source, warnings)?)
+    }
+}
+
+/// A systemd unit file, see [`systemd`].
+struct Systemd;
+
+impl Importer for Systemd {
+    fn name(&self) -> &str {
+        "systemd"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        matches!(input.extension(), Some("service" | "socket"))
+    }
+
+    fn import(&self, input: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+        let file_name = input
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        Ok(systemd::Unit::parse(file_name, input.source)?.page())
+    }
+}
+
+/// A source file with `man:` comment blocks, see [`annotations`].
+struct Annotations;
+
+impl Importer for Annotations {
+    fn name(&self) -> &str {
+        "annotations"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        input.source.contains("man:")
+    }
+
+    fn import(&self, input: &Input, _: &mut Warnings) -> Result<Mdoc, Error> {
+        let mut page = Mdoc::default();
+        annotations::assemble(&mut page, annotations::scan(input.source));
+        Ok(page)
+    }
+}
+
+/// The output of man(1) with overstrike, see [`formatted`].
+struct Formatted;
+
+impl Importer for Formatted {
+    fn name(&self) -> &str {
+        "formatted"
+    }
+
+    fn detect(&self, input: &Input) -> bool {
+        formatted::is_formatted(input.source)
+    }
+
+    fn import(&self, input: &Input, warnings: &mut Warnings) -> Result<Mdoc, Error> {
+        Ok(formatted::from_formatted_with_warnings(
+            input.source,
+            warnings,
+        ))
+    }
+}
@@ -0,0 +1,604 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Export the syntax tree as JSON, and read it back with [`from_json`].
+//!
+//! The output follows the structure of mandoc's syntax tree, as printed by
+//! `mandoc -T tree`, so that web frontends can lay pages out themselves:
+//! a `meta` object holds the prologue, and `nodes` holds the sections.
+//! Sections, subsections, blocks such as `Bl` and `Bd`, and list items
+//! have `children`; the head arguments of an item are its `args`. Macro
+//! arguments and text are kept as **mdoc** source, escapes included, and
+//! roff source that isn't modeled is kept in `roff` nodes.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let page = parser::parse(".Dt FOO 1\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n");
+//! let json = json::JsonRenderer::default().render(&page);
+//! assert!(json.contains("\"title\": \"FOO\",\n    \"msec\": \"1\","));
+//! assert!(json.contains(
+//!     "{\n          \"type\": \"macro\",\n          \"name\": \"Nm\",\n          \"args\": [\"foo\"]\n        }"
+//! ));
+//! ```
+
+use super::*;
+
+/// Renders documents as JSON.
+#[derive(Debug, Clone, Default)]
+pub struct JsonRenderer {
+    options: RenderOptions,
+}
+
+impl JsonRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options }
+    }
+
+    /// Render `page` as a JSON document.
+    pub fn render(&self, page: &Mdoc) -> String {
+        let mut buf = vec![];
+        self.to_writer(page, &mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Write `page` as a JSON document to a writer.
+    pub fn to_writer(&self, page: &Mdoc, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render_json", document = %page.name.0).entered();
+        // Figures, admonitions and inline macros are lowered to control
+        // lines by rendering the page as source first.
+        let source = page.render_with(&RenderOptions {
+            provenance: None,
+            ..self.options.clone()
+        });
+        let lines = parser::parse(&source).lines;
+        let mut meta = Meta::default();
+        let mut nodes = vec![];
+        let mut lines = lines.into_iter().peekable();
+        while let Some(line) = lines.next() {
+            if let Line::Control { name, args } = &line {
+                let args = words(args);
+                match name.as_ref() {
+                    "Dd" => meta.date = Some(args.join(" ")),
+                    "Dt" => {
+                        let mut args = args.into_iter();
+                        meta.title = args.next();
+                        meta.msec = args.next();
+                        meta.arch = args.next();
+                    }
+                    "Os" => meta.os = Some(args.join(" ")),
+                    _ => {}
+                }
+                if matches!(name.as_ref(), "Dd" | "Dt" | "Os") {
+                    continue;
+                }
+            }
+            if let Some(node) = node(line, &mut lines) {
+                nodes.push(node);
+            }
+        }
+        meta.name = page.title_tuple().map(|(name, _)| name);
+        meta.description = page.one_line_description();
+        let document = Value::Object(vec![("meta", meta.value()), ("nodes", Value::Array(nodes))]);
+        let mut out = String::new();
+        document.write(&mut out, 0);
+        out.push('\n');
+        w.write_all(out.as_bytes())
+    }
+}
+
+/// The prologue of a page.
+#[derive(Default)]
+struct Meta {
+    title: Option<String>,
+    msec: Option<String>,
+    arch: Option<String>,
+    os: Option<String>,
+    date: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+impl Meta {
+    fn value(self) -> Value {
+        let field = |v: Option<String>| v.map(Value::String).unwrap_or(Value::Null);
+        Value::Object(vec![
+            ("title", field(self.title)),
+            ("msec", field(self.msec)),
+            ("arch", field(self.arch)),
+            ("os", field(self.os)),
+            ("date", field(self.date)),
+            ("name", field(self.name)),
+            ("description", field(self.description)),
+        ])
+    }
+}
+
+type Lines = std::iter::Peekable<std::vec::IntoIter<Line>>;
+
+/// The node of `line`, taking the lines of its children from `lines`.
+fn node(line: Line, lines: &mut Lines) -> Option<Value> {
+    let (name, args) = match line {
+        Line::Control { name, args } => (name, words(&args)),
+        Line::Text(inlines) => {
+            let text: String = inlines
+                .iter()
+                .map(|i| match i {
+                    Inline::Roman(t) | Inline::Roff(t) => t.as_str(),
+                    _ => "",
+                })
+                .collect();
+            return Some(Value::Object(vec![
+                ("type", Value::from("text")),
+                ("text", Value::String(text)),
+            ]));
+        }
+        Line::Unknown(unknown) => {
+            return Some(Value::Object(vec![
+                ("type", Value::from("roff")),
+                ("source", Value::String(unknown.source)),
+            ]));
+        }
+        _ => return None,
+    };
+    // Comments aren't part of the tree.
+    if name == "\\\"" {
+        return None;
+    }
+    let (kind, end): (&str, fn(&Line) -> bool) = match name.as_ref() {
+        "Sh" => ("section", |l| is_control(l, &["Sh"])),
+        "Ss" => ("subsection", |l| is_control(l, &["Sh", "Ss"])),
+        "It" => ("item", |l| is_control(l, &["It", "El", "Sh", "Ss"])),
+        "Bl" => ("block", |l| is_control(l, &["El"])),
+        "Bd" => ("block", |l| is_control(l, &["Ed"])),
+        "Bf" => ("block", |l| is_control(l, &["Ef"])),
+        "Bk" => ("block", |l| is_control(l, &["Ek"])),
+        "Rs" => ("block", |l| is_control(l, &["Re"])),
+        _ => {
+            return Some(Value::Object(vec![
+                ("type", Value::from("macro")),
+                ("name", Value::String(name.to_string())),
+                ("args", Value::strings(args)),
+            ]));
+        }
+    };
+    let mut children = vec![];
+    while let Some(next) = lines.next_if(|l| !end(l)) {
+        if let Some(child) = node(next, lines) {
+            children.push(child);
+        }
+    }
+    if kind == "block" {
+        // The end macro of the block.
+        lines.next();
+    }
+    let mut fields = vec![
+        ("type", Value::from(kind)),
+        ("name", Value::String(name.to_string())),
+    ];
+    if matches!(kind, "section" | "subsection") {
+        fields.push(("title", Value::String(args.join(" "))));
+    } else {
+        fields.push(("args", Value::strings(args)));
+    }
+    fields.push(("children", Value::Array(children)));
+    Some(Value::Object(fields))
+}
+
+/// Read a page from the JSON syntax tree written by [`JsonRenderer`].
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let page = parser::parse(".Dt FOO 1\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n");
+/// let json = json::JsonRenderer::default().render(&page);
+/// assert_eq!(json::from_json(&json).unwrap().to_mdoc(), page.to_mdoc());
+/// ```
+pub fn from_json(source: &str) -> Result<Mdoc, String> {
+    let mut reader = Reader {
+        source,
+        position: 0,
+    };
+    let document = reader.value()?;
+    reader.skip_whitespace();
+    if reader.position < source.len() {
+        return Err(reader.error("expected the end of the document"));
+    }
+    let mut page = String::new();
+    let meta = document.get("meta");
+    let field = |name: &str| meta.and_then(|meta| meta.get(name)).and_then(Json::as_str);
+    if let Some(date) = field("date") {
+        page.push_str(&format!(".Dd {date}\n"));
+    }
+    if let Some(title) = field("title") {
+        let args: Vec<String> = [Some(title), field("msec"), field("arch")]
+            .into_iter()
+            .map_while(|arg| arg.map(quoted))
+            .collect();
+        page.push_str(&format!(".Dt {}\n", args.join(" ")));
+    }
+    if let Some(os) = field("os") {
+        page.push_str(format!(".Os {os}").trim_end());
+        page.push('\n');
+    }
+    let nodes = document
+        .get("nodes")
+        .and_then(Json::as_array)
+        .ok_or("expected an array of `nodes`")?;
+    for node in nodes {
+        write_node(node, &mut page)?;
+    }
+    Ok(parser::parse(&page))
+}
+
+/// Write a node of the tree and its children out as **mdoc** source.
+fn write_node(node: &Json, out: &mut String) -> Result<(), String> {
+    let string = |key: &str| {
+        node.get(key)
+            .and_then(Json::as_str)
+            .ok_or_else(|| format!("expected a string `{key}`"))
+    };
+    let args = || -> Result<Vec<String>, String> {
+        let args = node
+            .get("args")
+            .and_then(Json::as_array)
+            .unwrap_or_default();
+        args.iter()
+            .map(|arg| {
+                arg.as_str()
+                    .map(quoted)
+                    .ok_or("expected string `args`".to_string())
+            })
+            .collect()
+    };
+    let line = |name: &str, args: &[String]| {
+        let mut line = format!(".{name}");
+        for arg in args {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        line.push('\n');
+        line
+    };
+    match string("type")? {
+        "text" => {
+            out.push_str(string("text")?);
+            out.push('\n');
+        }
+        "roff" => {
+            out.push_str(string("source")?);
+            out.push('\n');
+        }
+        "macro" => out.push_str(&line(string("name")?, &args()?)),
+        kind @ ("section" | "subsection" | "item" | "block") => {
+            let name = string("name")?;
+            if kind == "item" || kind == "block" {
+                out.push_str(&line(name, &args()?));
+            } else {
+                out.push_str(&format!(".{name} {}\n", string("title")?));
+            }
+            let children = node
+                .get("children")
+                .and_then(Json::as_array)
+                .unwrap_or_default();
+            for child in children {
+                write_node(child, out)?;
+            }
+            if kind == "block" {
+                let end = match name {
+                    "Bl" => "El",
+                    "Bd" => "Ed",
+                    "Bf" => "Ef",
+                    "Bk" => "Ek",
+                    "Rs" => "Re",
+                    other => return Err(format!("unknown block `{other}`")),
+                };
+                out.push_str(&line(end, &[]));
+            }
+        }
+        other => return Err(format!("unknown node type `{other}`")),
+    }
+    Ok(())
+}
+
+/// An argument, quoted if it is empty or contains spaces.
+fn quoted(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// A JSON value read by [`from_json`].
+enum Json {
+    Null,
+    /// A boolean or a number, which the tree doesn't use.
+    Scalar,
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The value of the field `key` of an object.
+    fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// A reader of JSON values.
+struct Reader<'a> {
+    source: &'a str,
+    /// The byte offset of the next character.
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("at byte {}: {message}", self.position)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Read `literal`, such as `true`, if it comes next.
+    fn literal(&mut self, literal: &str) -> bool {
+        let found = self.source[self.position..].starts_with(literal);
+        if found {
+            self.position += literal.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.position += 1;
+                let mut fields = vec![];
+                self.skip_whitespace();
+                if self.literal("}") {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if !self.literal(":") {
+                        return Err(self.error("expected `:`"));
+                    }
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    if self.literal("}") {
+                        return Ok(Json::Object(fields));
+                    }
+                    if !self.literal(",") {
+                        return Err(self.error("expected `,` or `}`"));
+                    }
+                }
+            }
+            Some('[') => {
+                self.position += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.literal("]") {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    if self.literal("]") {
+                        return Ok(Json::Array(values));
+                    }
+                    if !self.literal(",") {
+                        return Err(self.error("expected `,` or `]`"));
+                    }
+                }
+            }
+            Some('"') => Ok(Json::String(self.string()?)),
+            _ if self.literal("null") => Ok(Json::Null),
+            _ if self.literal("true") || self.literal("false") => Ok(Json::Scalar),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+                {
+                    self.position += 1;
+                }
+                self.source[start..self.position]
+                    .parse::<f64>()
+                    .map(|_| Json::Scalar)
+                    .map_err(|_| self.error("invalid number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.literal("\"") {
+            return Err(self.error("expected a string"));
+        }
+        let mut ret = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(ret),
+                Some('\\') => match self.next() {
+                    Some('n') => ret.push('\n'),
+                    Some('t') => ret.push('\t'),
+                    Some('r') => ret.push('\r'),
+                    Some('b') => ret.push('\u{8}'),
+                    Some('f') => ret.push('\u{c}'),
+                    Some('u') => {
+                        let mut code = self.hex()?;
+                        // A surrogate pair.
+                        if (0xd800..0xdc00).contains(&code) && self.literal("\\u") {
+                            code = 0x10000 + ((code - 0xd800) << 10) + (self.hex()? - 0xdc00);
+                        }
+                        ret.push(char::from_u32(code).ok_or_else(|| self.error("invalid escape"))?);
+                    }
+                    Some(c @ ('"' | '\\' | '/')) => ret.push(c),
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some(c) => ret.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// The four hexadecimal digits of a `\u` escape.
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self
+            .source
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("invalid escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?;
+        self.position += 4;
+        Ok(code)
+    }
+}
+
+fn is_control(line: &Line, names: &[&str]) -> bool {
+    matches!(line, Line::Control { name, .. } if names.contains(&name.as_ref()))
+}
+
+/// The arguments of a control line without their quotes and the line
+/// breaks kept by lossless parsing.
+fn words(args: &[Str]) -> Vec<String> {
+    args.iter()
+        .filter(|a| !a.starts_with("\\\n"))
+        .map(|a| parser::unquote(a))
+        .collect()
+}
+
+/// A JSON value.
+enum Value {
+    Null,
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl Value {
+    fn strings(strings: Vec<String>) -> Self {
+        Self::Array(strings.into_iter().map(Self::String).collect())
+    }
+
+    /// Write the value, indented by `depth` levels. Arrays of strings are
+    /// written on one line.
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::String(s) => {
+                out.push('"');
+                for c in s.chars() {
+                    match c {
+                        '"' => out.push_str("\\\""),
+                        '\\' => out.push_str("\\\\"),
+                        '\n' => out.push_str("\\n"),
+                        '\t' => out.push_str("\\t"),
+                        c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+                        c => out.push(c),
+                    }
+                }
+                out.push('"');
+            }
+            Self::Array(values) if values.iter().all(|v| matches!(v, Self::String(_))) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    value.write(out, depth);
+                }
+                out.push(']');
+            }
+            Self::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    out.push_str(if i > 0 { ",\n" } else { "\n" });
+                    indent(out, depth + 1);
+                    value.write(out, depth + 1);
+                }
+                out.push('\n');
+                indent(out, depth);
+                out.push(']');
+            }
+            Self::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(if i > 0 { ",\n" } else { "\n" });
+                    indent(out, depth + 1);
+                    out.push_str(&format!("\"{key}\": "));
+                    value.write(out, depth + 1);
+                }
+                out.push('\n');
+                indent(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
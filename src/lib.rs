@@ -38,8 +38,52 @@ mod tests;
 #[macro_use]
 pub mod macros;
 
+pub mod annotations;
+pub mod boilerplate;
+mod brackets;
+pub mod build_helper;
+pub mod daemon;
+mod display;
+pub use display::{DisplayBlock, DisplayKind};
+pub mod examples;
+pub mod formatted;
+pub mod from_man;
+#[cfg(feature = "git")]
+pub mod git;
+pub mod groff;
+pub mod html;
+pub mod import;
+pub mod json;
+pub mod lint;
+mod list;
+pub use list::{ListBuilder, ListKind};
+pub mod man;
+mod manual;
+mod origin;
+pub use origin::Origin;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub use manual::{gzip, read_source, AliasStyle, Generated, Manual, Snippet};
 #[cfg(feature = "clap")]
 pub mod from_clap;
+pub mod parser;
+mod phrase;
+mod progress;
+pub use progress::{PageStatus, Progress};
+pub mod provenance;
+mod reference;
+pub use reference::Reference;
+pub mod sandbox;
+pub mod script;
+pub mod sections;
+pub mod systemd;
+pub mod term;
+pub mod text;
+pub mod validate;
+mod warnings;
+pub use warnings::{Denied, Strictness, Warning, Warnings};
+mod xr;
+pub use xr::{SeeAlso, Xr};
 
 use std::borrow::Cow;
 use std::io::Write;
@@ -55,13 +99,13 @@ use std::write;
 /// ```
 /// # use mdoc::*;
 /// let doc = Mdoc::default()
-///     .control("TH", ["FOO", "1"])
-///     .control("SH", ["NAME"])
+///     .control("TH".into(), ["FOO", "1"])
+///     .control("SH".into(), ["NAME"])
 ///     .text([roman("foo - do a foo thing")])
 ///     .render();
 /// assert!(doc.ends_with(".TH FOO 1\n.SH NAME\nfoo \\- do a foo thing\n"));
 /// ```
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Mdoc {
     date: Option<DocumentDate>,
     title: DocumentTitle,
@@ -79,6 +123,9 @@ pub struct Mdoc {
     see_also: Vec<Line>,
     history: Vec<Line>,
     authors: Vec<Line>,
+    acronyms: std::collections::BTreeMap<String, String>,
+    encoding: parser::Encoding,
+    origins: origin::Origins,
     pub lines: Vec<Line>,
 }
 
@@ -130,6 +177,191 @@ macros! {
     { day: Day, Day }
     { year: Year, Year }
 }
+/// The arguments of the `Dd` line of a page dated `date`, in the canonical
+/// `Month day, year` form.
+fn date_args(date: Option<&DocumentDate>) -> Vec<Str> {
+    date.map(|d| {
+        vec![
+            d.month.0.clone(),
+            format!("{},", d.day.0.trim_end_matches(',')).into(),
+            d.year.0.clone(),
+        ]
+    })
+    .unwrap_or_else(|| vec!["$Mdocdate$".into()])
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// The number of days of `month`, counting from 1, in `year`.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The seconds since 1970-01-01 of the `SOURCE_DATE_EPOCH` environment
+/// variable, if it is set.
+pub(crate) fn source_date_epoch() -> Option<u64> {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// The `(year, month, day)` of the civil calendar `seconds` after
+/// 1970-01-01.
+pub(crate) fn civil_date(seconds: u64) -> (u32, u32, u32) {
+    // Shifted to start years in March so that leap days come last.
+    let days = seconds / 86400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year as u32, month as u32, day as u32)
+}
+
+impl DocumentDate {
+    /// The date of a calendar day, or `None` if `month` or `day` is out of
+    /// range.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let date = DocumentDate::from_ymd(2024, 3, 1).unwrap();
+    /// assert_eq!((date.month.0.as_ref(), date.day.0.as_ref()), ("March", "1"));
+    /// assert_eq!(DocumentDate::from_ymd(2023, 2, 29), None);
+    /// ```
+    pub fn from_ymd(year: u32, month: u32, day: u32) -> Option<Self> {
+        let name = MONTHS.get(month.checked_sub(1)? as usize)?;
+        if !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        Some(Self {
+            month: Month::new(*name),
+            day: Day::new(day.to_string()),
+            year: Year::new(year.to_string()),
+        })
+    }
+
+    /// The current date, from the clock of the system.
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_unix_time(seconds)
+    }
+
+    /// The date `seconds` after 1970-01-01, in UTC.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// assert_eq!(
+    ///     DocumentDate::from_unix_time(1_719_792_000),
+    ///     DocumentDate::from_ymd(2024, 7, 1).unwrap()
+    /// );
+    /// ```
+    pub fn from_unix_time(seconds: u64) -> Self {
+        let (year, month, day) = civil_date(seconds);
+        Self::from_ymd(year, month, day).expect("the civil date is valid")
+    }
+
+    /// The date of the `SOURCE_DATE_EPOCH` environment variable, which
+    /// distributions set to the time of the last change of a package so
+    /// that its builds are reproducible.
+    ///
+    /// This is meant for [`RenderOptions::default_date`].
+    pub fn from_source_date_epoch() -> Option<Self> {
+        source_date_epoch().map(Self::from_unix_time)
+    }
+
+    /// The `(year, month, day)` of the date, or why it isn't a calendar
+    /// day.
+    ///
+    /// Months are recognized by their full English names, or the first
+    /// three letters of them, in any case. A comma after the day, as in
+    /// `July 1, 2024`, is accepted.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let date = |month, day, year| DocumentDate {
+    ///     month: Month::new(month),
+    ///     day: Day::new(day),
+    ///     year: Year::new(year),
+    /// };
+    /// assert_eq!(date("Sep", "30,", "2024").ymd(), Ok((2024, 9, 30)));
+    /// assert_eq!(
+    ///     date("September", "31", "2024").ymd().unwrap_err(),
+    ///     "September 2024 has 30 days, not 31"
+    /// );
+    /// assert_eq!(date("Smarch", "1", "2024").ymd().unwrap_err(), "unknown month Smarch");
+    /// ```
+    pub fn ymd(&self) -> Result<(u32, u32, u32), String> {
+        let month_name = self.month.0.as_ref();
+        let month = MONTHS
+            .iter()
+            .position(|m| {
+                m.eq_ignore_ascii_case(month_name)
+                    || month_name.len() == 3 && m[..3].eq_ignore_ascii_case(month_name)
+            })
+            .ok_or_else(|| format!("unknown month {month_name}"))? as u32
+            + 1;
+        let year: u32 = self
+            .year
+            .0
+            .parse()
+            .map_err(|_| format!("invalid year {}", self.year.0))?;
+        let day = self.day.0.trim_end_matches(',');
+        let day: u32 = day.parse().map_err(|_| format!("invalid day {day}"))?;
+        let days = days_in_month(year, month);
+        if !(1..=days).contains(&day) {
+            return Err(format!(
+                "{} {year} has {days} days, not {day}",
+                MONTHS[month as usize - 1]
+            ));
+        }
+        Ok((year, month, day))
+    }
+
+    /// The date with its month spelled out in full and without a comma
+    /// after the day, as [`DocumentDate::from_ymd`] makes them.
+    pub fn canonical(&self) -> Result<Self, String> {
+        let (year, month, day) = self.ymd()?;
+        Ok(Self::from_ymd(year, month, day).expect("the date is valid"))
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::Date> for DocumentDate {
+    fn from(date: time::Date) -> Self {
+        Self {
+            month: Month::new(MONTHS[u8::from(date.month()) as usize - 1]),
+            day: Day::new(date.day().to_string()),
+            year: Year::new(date.year().to_string()),
+        }
+    }
+}
+
 macros! {
     DocumentTitle,
     { title: Title, Title }
@@ -137,6 +369,138 @@ macros! {
     { arch: Option<Arch>, Arch }
 
 }
+impl Section {
+    /// The manual section, see [`ManualSection`].
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// assert_eq!(section!("3p").manual_section(), ManualSection::PosixLibrary);
+    /// ```
+    pub fn manual_section(&self) -> ManualSection {
+        match self.0.parse() {
+            Ok(section) => section,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl From<ManualSection> for Section {
+    fn from(section: ManualSection) -> Self {
+        Self::new(section.to_string())
+    }
+}
+
+/// A section of the manual, such as 1 for commands.
+///
+/// Sections that aren't standard, such as the `3ssl` of OpenSSL, are kept
+/// as [`ManualSection::Other`]; those starting with the number of a
+/// standard section are still [valid](ManualSection::is_valid).
+///
+/// ```
+/// # use mdoc::*;
+/// let section: ManualSection = "8".parse().unwrap();
+/// assert_eq!(section, ManualSection::Administration);
+/// assert_eq!(section.to_string(), "8");
+/// assert!(ManualSection::Other("3ssl".into()).is_valid());
+/// assert!(!ManualSection::Other("x".into()).is_valid());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ManualSection {
+    /// 1: utilities and commands.
+    Commands,
+    /// 2: system calls.
+    SystemCalls,
+    /// 3: library functions.
+    Library,
+    /// 3p: POSIX library functions.
+    PosixLibrary,
+    /// 4: device drivers and special files.
+    Devices,
+    /// 5: file formats.
+    FileFormats,
+    /// 6: games.
+    Games,
+    /// 7: miscellaneous information, such as conventions and overviews.
+    Miscellaneous,
+    /// 8: system administration commands and daemons.
+    Administration,
+    /// 9: kernel interfaces.
+    Kernel,
+    /// n: Tcl and Tk commands.
+    Tcl,
+    /// l: local additions.
+    Local,
+    Other(String),
+}
+
+impl ManualSection {
+    /// The standard sections, in order.
+    pub const ALL: [Self; 12] = [
+        Self::Commands,
+        Self::SystemCalls,
+        Self::Library,
+        Self::PosixLibrary,
+        Self::Devices,
+        Self::FileFormats,
+        Self::Games,
+        Self::Miscellaneous,
+        Self::Administration,
+        Self::Kernel,
+        Self::Tcl,
+        Self::Local,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Commands => "1",
+            Self::SystemCalls => "2",
+            Self::Library => "3",
+            Self::PosixLibrary => "3p",
+            Self::Devices => "4",
+            Self::FileFormats => "5",
+            Self::Games => "6",
+            Self::Miscellaneous => "7",
+            Self::Administration => "8",
+            Self::Kernel => "9",
+            Self::Tcl => "n",
+            Self::Local => "l",
+            Self::Other(section) => section,
+        }
+    }
+
+    /// Whether man(1) can find pages in the section: it is standard, or a
+    /// standard number followed by a suffix, as in `3ssl` or `1m`.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Other(section) => {
+                let Some(suffix) = section.strip_prefix(|c| ('1'..='9').contains(&c)) else {
+                    return false;
+                };
+                !suffix.starts_with(|c: char| c.is_ascii_digit())
+                    && suffix.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            _ => true,
+        }
+    }
+}
+
+impl std::str::FromStr for ManualSection {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::ALL
+            .into_iter()
+            .find(|section| section.as_str() == s)
+            .unwrap_or_else(|| Self::Other(s.to_string())))
+    }
+}
+
+impl std::fmt::Display for ManualSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 macros! {
     OperatingSystem,
     {system: System, System}
@@ -146,6 +510,65 @@ macros! {
 macros! { def name, Name }
 macros! { def description, Description }
 
+impl Description {
+    /// The recommended maximum length of a description, in characters, so
+    /// that the line listed by whatis(1) fits in a terminal together with
+    /// the name of the page.
+    pub const MAX_LENGTH: usize = 60;
+
+    /// Derive the one-line description of the NAME section from a longer
+    /// description, such as the `about` string of a command.
+    ///
+    /// The first sentence is kept without its final period, with its first
+    /// word in lowercase as is the convention unless it is an acronym or a
+    /// name like `GitHub`, and it is cut at the last word that fits in
+    /// [`Description::MAX_LENGTH`] characters.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// assert_eq!(
+    ///     Description::summarize("Prints the lines of files. Lines are...").0,
+    ///     "prints the lines of files"
+    /// );
+    /// assert_eq!(Description::summarize("HTTP client").0, "HTTP client");
+    /// assert_eq!(
+    ///     Description::summarize(
+    ///         "Synchronizes the mailboxes of a local maildir with remote IMAP servers, bidirectionally"
+    ///     )
+    ///     .0,
+    ///     "synchronizes the mailboxes of a local maildir with remote"
+    /// );
+    /// ```
+    pub fn summarize(text: &str) -> Self {
+        let text = text.split("\n\n").next().unwrap_or_default();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let sentence = match text.find(". ") {
+            Some(end) => &text[..end],
+            None => text.trim_end_matches('.'),
+        };
+        let mut ret = String::new();
+        for (i, word) in sentence.split(' ').enumerate() {
+            if i > 0 && ret.chars().count() + 1 + word.chars().count() > Self::MAX_LENGTH {
+                break;
+            }
+            if i == 0 {
+                let mut chars = word.chars();
+                let first = chars.next().unwrap_or_default();
+                if chars.clone().all(|c| !c.is_uppercase()) {
+                    ret.extend(first.to_lowercase());
+                    ret.push_str(chars.as_str());
+                    continue;
+                }
+            } else {
+                ret.push(' ');
+            }
+            ret.push_str(word);
+        }
+        let ret = ret.trim_end_matches([',', ';', ':']);
+        Self::new(ret.to_string())
+    }
+}
+
 impl Mdoc {
     /// Instantiate an `Mdoc`
     pub fn new(
@@ -164,13 +587,8 @@ impl Mdoc {
             ..Default::default()
         };
 
-        ret.lines.push(Line::control(
-            "Dd".into(),
-            ret.date
-                .as_ref()
-                .map(|d| vec![d.month.0.clone(), d.day.0.clone(), d.year.0.clone()])
-                .unwrap_or_else(|| vec!["$Mdocdate$".into()]),
-        ));
+        ret.lines
+            .push(Line::control("Dd".into(), date_args(ret.date.as_ref())));
         ret.lines.push(Line::control(
             "Dt".into(),
             if let Some(arch) = ret.title.arch.as_ref() {
@@ -183,7 +601,17 @@ impl Mdoc {
                 vec![ret.title.title.0.clone(), ret.title.section.0.clone()]
             },
         ));
-        ret.lines.push(Line::control("Os".into(), vec![]));
+        ret.lines.push(Line::control(
+            "Os".into(),
+            ret.os
+                .as_ref()
+                .map(|os| {
+                    std::iter::once(os.system.0.clone())
+                        .chain(os.version.as_ref().map(|v| v.0.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ));
         ret.lines
             .push(Line::control("Sh".into(), vec!["NAME".into()]));
         ret.lines
@@ -194,6 +622,261 @@ impl Mdoc {
         ret
     }
 
+    /// Instantiate a section 7 overview page.
+    ///
+    /// Overview pages describe a topic rather than a utility or a
+    /// function, so a [`lint::Policy`] requiring a SYNOPSIS doesn't apply
+    /// to them, and their sections stay in the order they are added, both
+    /// when rendering and when lint fixes are applied, instead of being
+    /// moved to the conventional order. They usually link to the related
+    /// pages of a project with a [`PageIndex`](sections::PageIndex).
+    pub fn overview(date: Option<DocumentDate>, name: Name, description: Description) -> Self {
+        Self::new(
+            date,
+            DocumentTitle {
+                title: Title::new(name.0.to_uppercase()),
+                section: Section::new("7"),
+                arch: None,
+            },
+            name,
+            description,
+            None,
+        )
+    }
+
+    /// Parse **mdoc** source text.
+    ///
+    /// Control lines become [`Line::Control`] values, with quoted
+    /// arguments kept whole, and text lines become [`Line::Text`]. The
+    /// prologue and the NAME section are also read into the fields returned
+    /// by [`Mdoc::title`], [`Mdoc::name`] and [`Mdoc::description`], so a
+    /// parsed page can be edited and rendered again like a constructed one.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let mut page = Mdoc::parse(
+    ///     ".Dd July 1 2024\n.Dt LS 1\n.Os\n.Sh NAME\n.Nm ls\n.Nd list directory contents\n",
+    /// );
+    /// assert_eq!(page.name(), &name!("ls"));
+    /// assert_eq!(page.title().section, Section::new("1"));
+    /// page.add_section("BUGS", [Line::text(vec![roman("None known.")])]);
+    /// assert!(page.render().ends_with(".Sh BUGS\nNone known.\n"));
+    /// ```
+    pub fn parse(source: &str) -> Self {
+        parser::parse(source)
+    }
+
+    /// A section 1 page for a Cargo package, with the metadata Cargo sets in
+    /// the environment of build scripts: its name in `Dt` and `Nm`, its name
+    /// and version in `Os`, its description in `Nd` and its authors in an
+    /// AUTHORS section.
+    ///
+    /// Variables that are unset are left empty. To read the metadata of the
+    /// crate being compiled instead, use [`from_cargo_env!`].
+    pub fn from_cargo_env() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        Self::from_cargo_metadata(
+            &var("CARGO_PKG_NAME"),
+            &var("CARGO_PKG_VERSION"),
+            &var("CARGO_PKG_DESCRIPTION"),
+            &var("CARGO_PKG_AUTHORS"),
+        )
+    }
+
+    /// A section 1 page for a Cargo package, see [`Mdoc::from_cargo_env`].
+    ///
+    /// `authors` is separated by colons, as in `CARGO_PKG_AUTHORS`, and each
+    /// author can have an email address in angle brackets.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let page = Mdoc::from_cargo_metadata(
+    ///     "frob",
+    ///     "1.2.0",
+    ///     "frobnicate files",
+    ///     "Jane Doe <jane@example.com>:John Doe",
+    /// );
+    /// assert_eq!(
+    ///     page.to_mdoc(),
+    ///     ".Dd $Mdocdate$\n.Dt FROB 1\n.Os frob 1.2.0\n.Sh NAME\n.Nm frob\n.Nd frobnicate files\n\
+    ///      .Sh AUTHORS\n.An Jane Doe Aq Mt jane@example.com\n.An John Doe\n"
+    /// );
+    /// ```
+    pub fn from_cargo_metadata(
+        name: &str,
+        version: &str,
+        description: &str,
+        authors: &str,
+    ) -> Self {
+        let mut ret = Self::new(
+            None,
+            DocumentTitle {
+                title: Title::new(name.to_uppercase()),
+                section: Section::new("1"),
+                arch: None,
+            },
+            Name::new(name.to_string()),
+            Description::new(description.trim().to_string()),
+            Some(OperatingSystem {
+                system: System::new(name.to_string()),
+                version: (!version.is_empty()).then(|| Version::new(version.to_string())),
+            }),
+        );
+        let authors = authors.split(':').map(str::trim).filter(|a| !a.is_empty());
+        ret.authors(authors.map(|author| {
+            let (name, email) = match author.split_once('<') {
+                Some((name, email)) => (name.trim(), Some(email.trim_end_matches('>'))),
+                None => (author, None),
+            };
+            let mut args: Vec<Str> = name
+                .split_whitespace()
+                .map(|w| w.to_string().into())
+                .collect();
+            if let Some(email) = email {
+                args.extend(["Aq".into(), "Mt".into(), email.to_string().into()]);
+            }
+            Line::control("An".into(), args)
+        }));
+        ret
+    }
+
+    /// The title, section and architecture from the `Dt` line.
+    pub fn title(&self) -> &DocumentTitle {
+        &self.title
+    }
+
+    /// The name of the page, from the first `Nm` of the NAME section.
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// The one-line description from the NAME section.
+    pub fn description(&self) -> &Description {
+        &self.description
+    }
+
+    /// The one-line description of the page as plain text, as listed by
+    /// whatis(1).
+    ///
+    /// This is the text of the `Nd` line of the NAME section and of the
+    /// text lines following it, or the description the page was
+    /// constructed with. The NAME section of a man(7) page,
+    /// `name \- description`, is understood as well. Escapes are replaced
+    /// by ASCII text and whitespace is collapsed.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let page = Mdoc::parse(".TH LS 1\n.SH NAME\nls \\- list directory\ncontents\n.SH SYNOPSIS\n");
+    /// assert_eq!(page.one_line_description().as_deref(), Some("list directory contents"));
+    /// assert_eq!(page.title_tuple(), Some(("ls".to_string(), "1".to_string())));
+    /// ```
+    pub fn one_line_description(&self) -> Option<String> {
+        let text = self
+            .name_section()
+            .1
+            .or_else(|| (!self.description.0.is_empty()).then(|| self.description.0.to_string()))?;
+        let text = term::Charset::Ascii.glyphs().unescape(&text);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// The name and section of the page, as in `ls(1)`.
+    ///
+    /// The name is the first one given in the NAME section, falling back
+    /// to the title of the page in lowercase, and the section is the one of
+    /// the `Dt` line, or the `TH` line of a man(7) page.
+    pub fn title_tuple(&self) -> Option<(String, String)> {
+        let mut title = (
+            self.title.title.0.to_string(),
+            self.title.section.0.to_string(),
+        );
+        if title.1.is_empty() {
+            for line in &self.lines {
+                if let Line::Control { name, args } = line {
+                    if name == "Dt" || name == "TH" {
+                        let mut args = args.iter().map(|a| parser::unquote(a));
+                        title = (
+                            args.next().unwrap_or_default(),
+                            args.next().unwrap_or_default(),
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+        let name = if self.name.0.is_empty() {
+            self.name_section()
+                .0
+                .unwrap_or_else(|| title.0.to_lowercase())
+        } else {
+            self.name.0.to_string()
+        };
+        let name = term::Charset::Ascii.glyphs().unescape(&name);
+        (!name.is_empty() && !title.1.is_empty()).then_some((name, title.1))
+    }
+
+    /// The first name and the description of the NAME section of a parsed
+    /// **mdoc** or man(7) page.
+    fn name_section(&self) -> (Option<String>, Option<String>) {
+        let mut in_name = false;
+        let mut first_name = None;
+        // The text of the man(7) NAME section, or of the mdoc one from its
+        // `Nd` line on.
+        let mut text: Vec<String> = vec![];
+        let mut description = false;
+        for line in &self.lines {
+            match line {
+                Line::Control { name, args } => {
+                    let args: Vec<String> = args
+                        .iter()
+                        .filter(|a| !a.starts_with("\\\n"))
+                        .map(|a| parser::unquote(a))
+                        .collect();
+                    match name.as_ref() {
+                        "Sh" | "SH" => {
+                            if in_name {
+                                break;
+                            }
+                            in_name = args.join(" ").eq_ignore_ascii_case("NAME");
+                        }
+                        "Nm" if in_name && first_name.is_none() => {
+                            first_name = args.first().cloned();
+                        }
+                        "Nd" if in_name => {
+                            description = true;
+                            text = args;
+                        }
+                        _ => {}
+                    }
+                }
+                Line::Text(inlines) if in_name => {
+                    text.extend(inlines.iter().filter_map(|i| match i {
+                        Inline::Roman(t) | Inline::Roff(t) => Some(t.clone()),
+                        _ => None,
+                    }));
+                }
+                _ => {}
+            }
+        }
+        let text = text.join(" ");
+        if description {
+            return (first_name, Some(text));
+        }
+        // `name, other \- description`
+        match text.split_once(" \\- ").or_else(|| text.split_once(" - ")) {
+            Some((names, description)) => (
+                first_name.or_else(|| names.split(',').next().map(|n| n.trim().to_string())),
+                Some(description.to_string()),
+            ),
+            None => (first_name, None),
+        }
+    }
+
+    /// Whether this is a section 7 overview page.
+    pub fn is_overview(&self) -> bool {
+        self.title.section.0 == "7"
+    }
+
     pub fn add_section(&mut self, title: impl Into<String>, lines: impl IntoIterator<Item = Line>) {
         self.lines.push(Line::control(
             "Sh".into(),
@@ -202,44 +885,587 @@ impl Mdoc {
         self.lines.extend(lines)
     }
 
-    /// Append a control line.
-    ///
-    /// The line consist of the name of a built-in command or macro,
-    /// and some number of arguments. Arguments that contain spaces
-    /// will be enclosed with double quotation marks.
-    pub fn control<'a>(&mut self, name: Str, args: impl IntoIterator<Item = &'a str>) -> &mut Self {
-        self.lines.push(Line::control(
-            name,
-            args.into_iter().map(|s| s.to_string().into()).collect(),
-        ));
+    /// Append `lines` to the end of the section `title`, which is added if
+    /// it is missing.
+    pub fn append_to_section(&mut self, title: &str, lines: impl IntoIterator<Item = Line>) {
+        match self
+            .sections()
+            .into_iter()
+            .find(|(t, _)| t.eq_ignore_ascii_case(title))
+        {
+            Some((_, range)) => {
+                self.lines.splice(range.end..range.end, lines);
+            }
+            None => self.add_section(title, lines),
+        }
+    }
+
+    /// The lines of the document, with the sections set with the section
+    /// methods such as [`Mdoc::examples`] inserted in the conventional
+    /// order, see [`SECTION_ORDER`].
+    ///
+    /// Each of these sections goes before the first section of the
+    /// document that conventionally follows it, or is appended to the
+    /// section of the document with the same title if there is one. In
+    /// section 7 overview pages, which are arranged by topic, they are
+    /// appended at the end.
+    pub fn document_lines(&self) -> Cow<'_, [Line]> {
+        let fields = [
+            ("RETURN VALUES", &self.return_values),
+            ("ENVIRONMENT", &self.environment),
+            ("FILES", &self.files),
+            ("EXIT STATUS", &self.exit_status),
+            ("EXAMPLES", &self.examples),
+            ("ERRORS", &self.errors),
+            ("SEE ALSO", &self.see_also),
+            ("STANDARDS", &self.standards),
+            ("HISTORY", &self.history),
+            ("AUTHORS", &self.authors),
+        ];
+        if fields.iter().all(|(_, lines)| lines.is_empty()) {
+            return Cow::Borrowed(&self.lines);
+        }
+        let rank = |title: &str| SECTION_ORDER.iter().position(|t| *t == title);
+        let mut ret = self.lines.clone();
+        for (title, lines) in fields {
+            if lines.is_empty() {
+                continue;
+            }
+            let sections = Self::sections_of(&ret);
+            if let Some((_, range)) = sections.iter().find(|(t, _)| t == title) {
+                ret.splice(range.end..range.end, lines.iter().cloned());
+                continue;
+            }
+            let position = if self.is_overview() {
+                None
+            } else {
+                sections
+                    .iter()
+                    .find(|(t, _)| rank(t).is_some_and(|r| Some(r) > rank(title)))
+                    .map(|(_, range)| range.start)
+            };
+            let at = position.unwrap_or(ret.len());
+            let mut section = vec![Line::control("Sh".into(), vec![title.into()])];
+            section.extend(lines.iter().cloned());
+            ret.splice(at..at, section);
+        }
+        Cow::Owned(ret)
+    }
+
+    /// Append to the EXIT STATUS section.
+    pub fn exit_status(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.exit_status.extend(lines);
+        self
+    }
+
+    /// Append to the RETURN VALUES section.
+    pub fn return_values(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.return_values.extend(lines);
+        self
+    }
+
+    /// Append to the EXAMPLES section.
+    pub fn examples(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.examples.extend(lines);
+        self
+    }
+
+    /// Append to the ERRORS section.
+    pub fn errors(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.errors.extend(lines);
+        self
+    }
+
+    /// Append to the FILES section.
+    pub fn files(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.files.extend(lines);
+        self
+    }
+
+    /// Append to the ENVIRONMENT section.
+    pub fn environment(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.environment.extend(lines);
+        self
+    }
+
+    /// Append to the STANDARDS section.
+    pub fn standards(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.standards.extend(lines);
+        self
+    }
+
+    /// Append to the SEE ALSO section.
+    pub fn see_also(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.see_also.extend(lines);
+        self
+    }
+
+    /// Append to the HISTORY section.
+    pub fn history(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.history.extend(lines);
+        self
+    }
+
+    /// Append to the AUTHORS section.
+    pub fn authors(&mut self, lines: impl IntoIterator<Item = Line>) -> &mut Self {
+        self.authors.extend(lines);
+        self
+    }
+
+    /// The titles and line ranges of the sections of the document.
+    ///
+    /// Each range starts with the section's `Sh` line and extends up to the
+    /// next `Sh` line or the end of the document.
+    pub fn sections(&self) -> Vec<(String, std::ops::Range<usize>)> {
+        Self::sections_of(&self.lines)
+    }
+
+    fn sections_of(lines: &[Line]) -> Vec<(String, std::ops::Range<usize>)> {
+        let mut ret: Vec<(String, std::ops::Range<usize>)> = vec![];
+        for (i, line) in lines.iter().enumerate() {
+            if let Line::Control { name, args } = line {
+                if name == "Sh" {
+                    if let Some((_, range)) = ret.last_mut() {
+                        range.end = i;
+                    }
+                    ret.push((args.join(" "), i..lines.len()));
+                }
+            }
+        }
+        ret
+    }
+
+    /// Merge the sections of `other` into this document.
+    ///
+    /// Sections of `other` replace the sections of this document with the
+    /// same title, and the rest are appended. Lines of `other` before its
+    /// first section, such as its prologue, are ignored. This is meant for
+    /// augmenting generated pages with hand-written sections.
+    pub fn merge(&mut self, other: Mdoc) {
+        for (title, range) in other.sections() {
+            let lines = other.lines[range].to_vec();
+            let existing = self
+                .sections()
+                .into_iter()
+                .find(|(t, _)| t.eq_ignore_ascii_case(&title));
+            match existing {
+                Some((_, range)) => {
+                    self.lines.splice(range, lines);
+                }
+                None => self.lines.extend(lines),
+            }
+        }
+    }
+
+    /// Append a control line.
+    ///
+    /// The line consist of the name of a built-in command or macro,
+    /// and some number of arguments. Arguments that contain spaces
+    /// will be enclosed with double quotation marks, unless the macro
+    /// takes the rest of the line as its argument, as `Nd` does.
+    pub fn control<'a>(&mut self, name: Str, args: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        self.lines.push(Line::control(
+            name,
+            args.into_iter().map(|s| s.to_string().into()).collect(),
+        ));
+        self
+    }
+
+    /// Append a text line.
+    ///
+    /// The line will be rendered in a way that ensures it can't be
+    /// interpreted as a control line. The caller does not need to
+    /// ensure, for example, that the line doesn't start with a
+    /// period ("`.`") or an apostrophe ("`'`").
+    pub fn text(&mut self, inlines: impl Into<Vec<Inline>>) -> &mut Self {
+        self.lines.push(Line::text(inlines.into()));
+        self
+    }
+
+    /// The [index terms](Inline::IndexTerm) of the document, sorted and
+    /// deduplicated.
+    pub fn index_terms(&self) -> Vec<String> {
+        let mut terms = self
+            .document_lines()
+            .iter()
+            .filter_map(Line::inlines)
+            .flatten()
+            .filter_map(|inline| match inline {
+                Inline::IndexTerm(term) => Some(term.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    /// Set the tab stops of the following text lines, e.g. `["8n", "16n"]`,
+    /// for aligning tab-separated text in literal displays.
+    pub fn tab_stops<'a>(&mut self, stops: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        self.control("ta".into(), stops)
+    }
+
+    /// Append a command-line option, see [`Flag`].
+    pub fn flag(&mut self, flag: Flag) -> &mut Self {
+        self.lines.push(flag.line());
+        self
+    }
+
+    /// A reference in running text to the option `flag` of this page,
+    /// given with its dashes as in `-v` or `--verbose`.
+    ///
+    /// The option is looked up in the lists of the DESCRIPTION and OPTIONS
+    /// sections, and the reference has all the forms of the option as
+    /// documented there. References to options that aren't documented are
+    /// kept as they are, and reported by [`lint::lint`].
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let mut doc = Mdoc::parse(".Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v , Fl -verbose\nBe verbose.\n.El\n");
+    /// let verbose = doc.flag_reference("-v");
+    /// doc.text([roman("See "), verbose, roman(".")]);
+    /// assert!(doc.to_mdoc().ends_with("See\n.Fl v , Fl -verbose .\n"));
+    /// ```
+    pub fn flag_reference(&self, flag: &str) -> Inline {
+        let lines = self.document_lines();
+        let sections = Self::sections_of(&lines);
+        let forms = lint::documented_options(&lines, &sections)
+            .into_iter()
+            .map(|(flags, _)| flags)
+            .find(|flags| flags.iter().any(|f| f == flag))
+            .unwrap_or_else(|| vec![flag.to_string()]);
+        let mut short = None;
+        let mut long = None;
+        for form in forms {
+            if let Some(form) = form.strip_prefix("--") {
+                long.get_or_insert_with(|| form.to_string());
+            } else if let Some(c) = form.strip_prefix('-').and_then(|f| {
+                let mut chars = f.chars();
+                chars.next().filter(|_| chars.next().is_none())
+            }) {
+                short.get_or_insert(c);
+            } else {
+                long.get_or_insert_with(|| form.trim_start_matches('-').to_string());
+            }
+        }
+        Inline::Flag {
+            short,
+            long,
+            value: None,
+        }
+    }
+
+    /// Append a display block, see [`DisplayBlock`].
+    pub fn display(&mut self, block: DisplayBlock) -> &mut Self {
+        self.lines.extend(block.lines());
+        self
+    }
+
+    /// Set the SYNOPSIS section, see [`sections::SynopsisBuilder`].
+    ///
+    /// The content of an existing SYNOPSIS section is replaced; otherwise
+    /// the section is added after NAME.
+    pub fn synopsis(&mut self, synopsis: sections::SynopsisBuilder) -> &mut Self {
+        self.synopsis = synopsis.lines();
+        let sections = self.sections();
+        let body = self.synopsis.clone();
+        if let Some((_, range)) = sections.iter().find(|(t, _)| t == "SYNOPSIS") {
+            self.lines.splice(range.start + 1..range.end, body);
+        } else if let Some((_, range)) = sections.iter().find(|(t, _)| t == "NAME") {
+            let mut lines = vec![Line::control("Sh".into(), vec!["SYNOPSIS".into()])];
+            lines.extend(body);
+            self.lines.splice(range.end..range.end, lines);
+        } else {
+            self.add_section("SYNOPSIS", body);
+        }
+        self
+    }
+
+    /// The lines of the SYNOPSIS section set with [`Mdoc::synopsis`].
+    pub fn synopsis_lines(&self) -> &[Line] {
+        &self.synopsis
+    }
+
+    /// Check the lines of the page, including the sections set with the
+    /// section methods, against `limits`.
+    ///
+    /// Pages built in code from untrusted data, such as the help of a
+    /// plugin, can be checked before they are rendered.
+    pub fn check_limits(&self, limits: &parser::Limits) -> Result<(), parser::LimitError> {
+        limits.check(&self.document_lines())
+    }
+
+    /// Append a list, see [`ListBuilder`].
+    pub fn list(&mut self, list: ListBuilder) -> &mut Self {
+        self.lines.extend(list.lines());
+        self
+    }
+
+    /// Append a figure.
+    pub fn figure(&mut self, figure: Figure) -> &mut Self {
+        self.lines.push(Line::Figure(figure));
+        self
+    }
+
+    /// Render as **mdoc** source text that can be fed to a **mdoc** implementation.
+    pub fn render(&self) -> String {
+        let mut buf = vec![];
+        self.to_writer(&mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Render as **mdoc** source text with the given options.
+    pub fn render_with(&self, options: &RenderOptions) -> String {
+        let mut buf = vec![];
+        self.to_writer_with(&mut buf, options).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Like [`Mdoc::render_with`], reporting the content that can't be
+    /// rendered faithfully to `warnings`: control characters, which roff
+    /// can't print, and acronyms without a registered expansion.
+    pub fn render_with_warnings(&self, options: &RenderOptions, warnings: &mut Warnings) -> String {
+        for (title, range) in self.sections() {
+            for inline in self.lines[range].iter().filter_map(Line::inlines).flatten() {
+                let text = match inline {
+                    Inline::Acronym(short) => {
+                        if !self.acronyms.contains_key(short) {
+                            warnings.push_in(&title, format!("acronym {short} is not registered"));
+                        }
+                        continue;
+                    }
+                    Inline::Roman(text)
+                    | Inline::Roff(text)
+                    | Inline::Italic(text)
+                    | Inline::Bold(text) => text,
+                    _ => continue,
+                };
+                if let Some(c) = text
+                    .chars()
+                    .find(|c| c.is_control() && !matches!(c, '\n' | '\t'))
+                {
+                    warnings.push_in(&title, format!("control character {c:?} in text"));
+                }
+            }
+        }
+        self.render_with(options)
+    }
+
+    /// Render as legacy **man**(7) source text, see [`man::ManRenderer`].
+    pub fn render_man(&self) -> String {
+        man::ManRenderer::default().render(self)
+    }
+
+    /// Render as an HTML document, see [`html::HtmlRenderer`].
+    pub fn render_html(&self) -> String {
+        html::HtmlRenderer::default().render(self)
+    }
+
+    /// Render as a JSON syntax tree, see [`json::JsonRenderer`].
+    pub fn render_json(&self) -> String {
+        json::JsonRenderer::default().render(self)
+    }
+
+    /// Render as filled plain text, see [`text::TextRenderer`].
+    pub fn render_text(&self) -> String {
+        text::TextRenderer::default().render(self)
+    }
+
+    /// Render for the terminal of the standard output, styled with ANSI
+    /// escape sequences if it supports them, see
+    /// [`text::TextRenderer::terminal`].
+    pub fn render_terminal(&self) -> String {
+        text::TextRenderer::terminal().render(self)
+    }
+
+    /// Write to a writer.
+    pub fn to_writer(&self, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.to_writer_with(w, &RenderOptions::default())
+    }
+
+    /// Write to a writer with the given options.
+    pub fn to_writer_with(
+        &self,
+        w: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> Result<(), std::io::Error> {
+        let w = &mut options.newline.writer(w);
+        let lines = self.rendered_lines(options);
+        if let Some(provenance) = &options.provenance {
+            let mut content = vec![];
+            self.write_lines(&lines, &mut options.newline.writer(&mut content), options)?;
+            return provenance.write_stamped(&content, w);
+        }
+        self.write_lines(&lines, w, options)
+    }
+
+    fn write_lines(
+        &self,
+        lines: &[Line],
+        w: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render", document = %self.name.0).entered();
+        #[cfg(feature = "tracing")]
+        let mut _section = None;
+        for line in lines {
+            #[cfg(feature = "tracing")]
+            if let Line::Control { name, args } = line {
+                if name == "Sh" {
+                    // Exit the span of the previous section first, so that
+                    // sections aren't nested.
+                    _section.take();
+                    _section =
+                        Some(tracing::debug_span!("section", section = %args.join(" ")).entered());
+                }
+            }
+            line.render_with(w, options)?;
+        }
+        Ok(())
+    }
+
+    /// The encoding the document was read in and is written back in by
+    /// [`Mdoc::to_bytes`].
+    pub fn encoding(&self) -> parser::Encoding {
+        self.encoding
+    }
+
+    /// Set the date of the document, replacing the arguments of its `Dd`
+    /// line. Without a date, the page is dated when it is formatted.
+    pub fn set_date(&mut self, date: Option<DocumentDate>) -> &mut Self {
+        let args = date_args(date.as_ref());
+        if let Some(Line::Control { args: dd, .. }) = self
+            .lines
+            .iter_mut()
+            .find(|l| matches!(l, Line::Control { name, .. } if name == "Dd"))
+        {
+            *dd = args;
+        }
+        self.date = date;
+        self
+    }
+
+    /// Set the encoding of the document.
+    pub fn set_encoding(&mut self, encoding: parser::Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Render as **mdoc** source text encoded in the
+    /// [encoding](Mdoc::encoding) of the document.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        parser::encode(&self.render(), self.encoding)
+    }
+
+    /// Register the expansion of an acronym used in [`Inline::Acronym`]
+    /// elements, e.g. `TLS` and `Transport Layer Security`.
+    pub fn acronym(&mut self, short: impl Into<String>, expansion: impl Into<String>) -> &mut Self {
+        self.acronyms.insert(short.into(), expansion.into());
+        self
+    }
+
+    /// The lines of the document with registered acronyms replaced by
+    /// their short or expanded forms, according to `style`.
+    pub fn expand_acronyms(&self, style: AcronymStyle) -> Cow<'_, [Line]> {
+        let lines = self.document_lines();
+        if self.acronyms.is_empty() || style == AcronymStyle::Never {
+            return lines;
+        }
+        let mut seen = std::collections::HashSet::new();
+        let mut expand = |inlines: &mut Vec<Inline>| {
+            for inline in inlines.iter_mut() {
+                let Inline::Acronym(short) = inline else {
+                    continue;
+                };
+                let Some(expansion) = self.acronyms.get(short) else {
+                    continue;
+                };
+                if style == AcronymStyle::Always || seen.insert(short.clone()) {
+                    *inline = roman(format!("{short} ({expansion})"));
+                }
+            }
+        };
+        let mut lines = lines.into_owned();
+        for inlines in lines.iter_mut().filter_map(Line::inlines_mut) {
+            expand(inlines);
+        }
+        Cow::Owned(lines)
+    }
+
+    /// The lines of the document as rendered with `options`: with its
+    /// acronyms expanded, see [`Mdoc::expand_acronyms`], and dated by
+    /// [`RenderOptions::default_date`] if it has no date.
+    pub(crate) fn rendered_lines(&self, options: &RenderOptions) -> Cow<'_, [Line]> {
+        let mut lines = self.expand_acronyms(options.acronyms);
+        if options.provenance.is_some() {
+            let stamp = provenance::stamp_len(&lines);
+            if stamp > 0 {
+                lines.to_mut().drain(..stamp);
+            }
+        }
+        if let Some(date) = &options.default_date {
+            let undated = |line: &Line| {
+                matches!(line, Line::Control { name, args } if name == "Dd"
+                    && args.iter().all(|a| a.starts_with("\\\n") || a == "$Mdocdate$"))
+            };
+            if let Some(index) = lines.iter().position(undated) {
+                lines.to_mut()[index] = Line::control("Dd".into(), date_args(Some(date)));
+            }
+        }
+        lines
+    }
+
+    /// Mark a line as changed since the last release, see [`Line::Changed`].
+    pub fn changed(&mut self, line: Line) -> &mut Self {
+        self.lines.push(Line::Changed(Box::new(line)));
         self
     }
 
-    /// Append a text line.
-    ///
-    /// The line will be rendered in a way that ensures it can't be
-    /// interpreted as a control line. The caller does not need to
-    /// ensure, for example, that the line doesn't start with a
-    /// period ("`.`") or an apostrophe ("`'`").
-    pub fn text(&mut self, inlines: impl Into<Vec<Inline>>) -> &mut Self {
-        self.lines.push(Line::text(inlines.into()));
-        self
+    /// The titles of the sections with changed lines.
+    pub fn changed_sections(&self) -> Vec<String> {
+        self.sections()
+            .into_iter()
+            .filter(|(_, range)| {
+                self.lines[range.clone()]
+                    .iter()
+                    .any(|line| matches!(line, Line::Changed(_)))
+            })
+            .map(|(title, _)| title)
+            .collect()
     }
 
-    /// Render as **mdoc** source text that can be fed to a **mdoc** implementation.
-    pub fn render(&self) -> String {
-        let mut buf = vec![];
-        self.to_writer(&mut buf).unwrap(); // writing to a Vec always works
-        String::from_utf8(buf)
-            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    /// A CHANGES section listing the sections changed since the release
+    /// `since`, or `None` if nothing changed.
+    pub fn changes_section(&self, since: &str) -> Option<boilerplate::Boilerplate> {
+        let sections = self.changed_sections();
+        if sections.is_empty() {
+            return None;
+        }
+        let mut lines = vec![
+            Line::text(vec![roman(format!(
+                "The following sections have changed since {since}:"
+            ))]),
+            Line::control("Bl".into(), vec!["-bullet".into(), "-compact".into()]),
+        ];
+        for title in sections {
+            lines.push(Line::control("It".into(), vec![]));
+            lines.push(Line::control("Sx".into(), vec![title.into()]));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        Some(boilerplate::Boilerplate::new("CHANGES", lines))
     }
 
-    /// Write to a writer.
-    pub fn to_writer(&self, w: &mut dyn Write) -> Result<(), std::io::Error> {
-        for line in self.lines.iter() {
-            line.render(w)?;
+    /// Remove the change marks of all lines, e.g. when making a release.
+    pub fn accept_changes(&mut self) {
+        for line in self.lines.iter_mut() {
+            while let Line::Changed(inner) = line {
+                *line = std::mem::replace(inner.as_mut(), Line::Text(vec![]));
+            }
         }
-        Ok(())
     }
 
     /// Render without handling apostrophes specially.
@@ -255,10 +1481,8 @@ impl Mdoc {
     /// avoid it.
     pub fn to_mdoc(&self) -> String {
         let mut buf = vec![];
-        for line in self.lines.iter() {
-            // Writing to a Vec always works, so we discard any error.
-            line.render(&mut buf).unwrap();
-        }
+        // Writing to a Vec always works, so we discard any error.
+        self.to_writer(&mut buf).unwrap();
         String::from_utf8(buf)
             .expect("output is utf8 if all input is utf8 and our API guarantees that")
     }
@@ -295,7 +1519,8 @@ impl<R: Into<Mdoc>> Extend<R> for Mdoc {
 /// Text will be escaped for **mdoc**. No inline escape sequences will be
 /// passed to **mdoc**. The text may contain newlines, but leading periods
 /// will be escaped so that they won't be interpreted by **mdoc** as
-/// control lines.
+/// control lines, and hyphens are written as `\-`, see [`escape_hyphens`].
+/// Text read from **mdoc** source is kept as [`Inline::Roff`] instead.
 ///
 /// Note that the strings stored in the variants are stored as they're
 /// received from the API user. The Line::render function handles
@@ -309,18 +1534,457 @@ pub enum Inline {
     /// Text in the italic (slanted) font.
     Italic(String),
 
+    /// Text in the "roman" font as read from **mdoc** source, with its
+    /// escapes. It is written as is, so that parsed pages round-trip.
+    Roff(String),
+
     /// Text in a bold face font.
     Bold(String),
 
     /// A hard line break. This is an inline element so it's easy to
     /// insert a line break in a paragraph.
     LineBreak,
+
+    /// The name of the page's utility, as given to the first `Nm` macro.
+    ///
+    /// Rendered as a bare `Nm` macro on its own control line, so that
+    /// it can be used mid-sentence without repeating the name. Closing
+    /// punctuation at the start of the following roman text is passed to
+    /// the macro as delimiters, so that no space is inserted before it.
+    Name,
+
+    /// A command-line flag with a short and/or a long form, rendered with
+    /// the `Fl` macro as used in a sentence, e.g. `Fl v , Fl -verbose`.
+    Flag {
+        /// Short form, without the leading dash.
+        short: Option<char>,
+        /// Long form, without the leading dashes.
+        long: Option<String>,
+        /// Name of the flag's argument, if it takes one.
+        value: Option<String>,
+    },
+
+    /// An acronym, expanded on first use if it is registered with
+    /// [`Mdoc::acronym`], and rendered in the roman font.
+    Acronym(String),
+
+    /// A keyword for the index of the page.
+    ///
+    /// Index terms are not rendered in **mdoc** output; they are collected
+    /// with [`Mdoc::index_terms`] and [`Manual::index`].
+    IndexTerm(String),
+}
+
+impl Inline {
+    /// Whether this element is rendered as a macro on its own control line.
+    fn is_macro(&self, options: &RenderOptions) -> bool {
+        match self {
+            Self::Name | Self::Flag { .. } => true,
+            Self::Bold(_) | Self::Italic(_) => options.font_style == FontStyle::Macros,
+            _ => false,
+        }
+    }
+
+    /// The macro name and arguments of the control line this element is
+    /// rendered as, if it's a macro.
+    fn macro_args(&self, options: &RenderOptions) -> Vec<Str> {
+        match self {
+            Self::Name => vec!["Nm".into()],
+            Self::Flag { short, long, value } => flag_args(
+                *short,
+                long.as_deref(),
+                value.as_deref(),
+                FlagContext::Sentence,
+                options.flag_style,
+            ),
+            Self::Bold(text) if options.font_style == FontStyle::Macros => {
+                vec!["Sy".into(), text.clone().into()]
+            }
+            Self::Italic(text) if options.font_style == FontStyle::Macros => {
+                vec!["Em".into(), text.clone().into()]
+            }
+            Self::Roman(_)
+            | Self::Roff(_)
+            | Self::Italic(_)
+            | Self::Bold(_)
+            | Self::LineBreak
+            | Self::Acronym(_)
+            | Self::IndexTerm(_) => vec![],
+        }
+    }
+}
+
+/// The context a flag is rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagContext {
+    /// Running text, where the forms of a flag are listed: `-v, --verbose`.
+    Sentence,
+    /// A synopsis, where the forms of a flag are alternatives:
+    /// `-v | --verbose`.
+    Synopsis,
+}
+
+/// How long flags are spelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongFlags {
+    /// With a double dash, e.g. `--verbose`, rendered as `Fl -verbose`.
+    #[default]
+    Gnu,
+    /// With a single dash, e.g. `-verbose`, rendered as `Fl verbose`.
+    Bsd,
+}
+
+/// The separator between the short and the long form of a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagSeparator {
+    /// `Fl h | Fl -help`
+    Pipe,
+    /// `Fl h , Fl -help`
+    Comma,
+}
+
+/// How the argument of a flag is attached to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueStyle {
+    /// As a separate word, after the last form of the flag:
+    /// `Fl o | Fl -output Ar file`.
+    #[default]
+    Separate,
+    /// After each form, with an equals sign for the long form:
+    /// `Fl o Ar file | Fl -output Ns = Ns Ar file`.
+    Assign,
+}
+
+/// The conventions used when rendering flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlagStyle {
+    pub long: LongFlags,
+    /// Separator between short and long forms. If unset, a pipe is used in
+    /// synopses and a comma in running text.
+    pub separator: Option<FlagSeparator>,
+    pub value: ValueStyle,
+}
+
+/// The conventional order of the sections of a page, from mdoc(7).
+pub const SECTION_ORDER: &[&str] = &[
+    "NAME",
+    "LIBRARY",
+    "SYNOPSIS",
+    "DESCRIPTION",
+    "CONTEXT",
+    "IMPLEMENTATION NOTES",
+    "RETURN VALUES",
+    "ENVIRONMENT",
+    "FILES",
+    "EXIT STATUS",
+    "EXAMPLES",
+    "DIAGNOSTICS",
+    "ERRORS",
+    "SEE ALSO",
+    "STANDARDS",
+    "HISTORY",
+    "AUTHORS",
+    "CAVEATS",
+    "BUGS",
+    "SECURITY CONSIDERATIONS",
+];
+
+/// Options for rendering **mdoc** source text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// How [`Inline::Flag`] elements are rendered.
+    pub flag_style: FlagStyle,
+    /// When [`Inline::Acronym`] elements are expanded.
+    pub acronyms: AcronymStyle,
+    /// Stamp the output with its provenance, see [`provenance`]. A stamp
+    /// the page was parsed with is replaced.
+    pub provenance: Option<provenance::Provenance>,
+    /// How [`Inline::Bold`] and [`Inline::Italic`] elements are rendered.
+    pub font_style: FontStyle,
+    /// The date of pages without an explicit date, whose `Dd` is
+    /// `$Mdocdate$`, which are otherwise dated when they are formatted.
+    ///
+    /// Packages set it to [`DocumentDate::from_source_date_epoch`] so that
+    /// their builds produce identical pages.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let page = Mdoc::parse(".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n");
+    /// let options = RenderOptions {
+    ///     default_date: DocumentDate::from_ymd(2024, 7, 1),
+    ///     ..RenderOptions::default()
+    /// };
+    /// assert_eq!(page.render_with(&options), ".Dd July 1, 2024\n.Dt FOO 1\n.Os\n");
+    /// ```
+    pub default_date: Option<DocumentDate>,
+    /// The line endings of the output.
+    pub newline: Newline,
+}
+
+/// The line endings of rendered output.
+///
+/// ```
+/// # use mdoc::*;
+/// let page = Mdoc::parse(".Sh NAME\r\n.Nm foo\r\n");
+/// assert_eq!(page.render(), ".Sh NAME\n.Nm foo\n");
+/// let options = RenderOptions {
+///     newline: Newline::CrLf,
+///     ..RenderOptions::default()
+/// };
+/// assert_eq!(page.render_with(&options), ".Sh NAME\r\n.Nm foo\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Newline {
+    /// `\n`, which roff and man(1) expect.
+    #[default]
+    Lf,
+    /// `\r\n`, for tools on Windows that expect it.
+    CrLf,
+}
+
+impl Newline {
+    /// `w`, with the line endings written to it converted to this style.
+    pub(crate) fn writer<'a>(self, w: &'a mut dyn Write) -> Box<dyn Write + 'a> {
+        match self {
+            Self::Lf => Box::new(w),
+            Self::CrLf => Box::new(CrLfWriter {
+                inner: w,
+                cr: false,
+            }),
+        }
+    }
+}
+
+/// A writer that turns `\n` into `\r\n`, leaving existing `\r\n` alone.
+struct CrLfWriter<'a> {
+    inner: &'a mut dyn Write,
+    /// Whether the last byte written was `\r`.
+    cr: bool,
+}
+
+impl Write for CrLfWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut out = Vec::with_capacity(buf.len() + buf.len() / 16);
+        for &b in buf {
+            if b == b'\n' && !self.cr {
+                out.push(b'\r');
+            }
+            out.push(b);
+            self.cr = b == b'\r';
+        }
+        self.inner.write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// How bold and italic text is rendered in **mdoc** and man(7) output.
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.text([roman("Do "), bold("not"), roman(" panic.")]);
+/// assert_eq!(doc.to_mdoc(), "Do \\fBnot\\fR panic.\n");
+/// let options = RenderOptions {
+///     font_style: FontStyle::Macros,
+///     ..RenderOptions::default()
+/// };
+/// assert_eq!(doc.render_with(&options), "Do\n.Sy not\npanic.\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FontStyle {
+    /// Font escapes in the text, `\fBbold\fR` and `\fIitalic\fR`, which
+    /// keep the text on one line.
+    #[default]
+    Escapes,
+    /// Semantic macros on their own control lines: `Sy` and `Em` in
+    /// **mdoc**, `B` and `I` in man(7).
+    Macros,
+}
+
+/// When acronyms registered with [`Mdoc::acronym`] are expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AcronymStyle {
+    /// Only on first use in the document: `TLS (Transport Layer Security)`,
+    /// then `TLS`.
+    #[default]
+    FirstUse,
+    /// On every use.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// Return the `Fl` macro invocations for a flag with a short and/or a long
+/// form, e.g. `Fl v | Fl -verbose`.
+///
+/// The returned arguments start with the `Fl` macro name, so that they can
+/// be used either as a control line on their own or as arguments of
+/// another macro such as `Op`.
+pub fn flag_args(
+    short: Option<char>,
+    long: Option<&str>,
+    value: Option<&str>,
+    context: FlagContext,
+    style: FlagStyle,
+) -> Vec<Str> {
+    let mut args: Vec<Str> = vec![];
+    if let Some(short) = short {
+        args.push("Fl".into());
+        args.push(short.to_string().into());
+        if let (Some(value), ValueStyle::Assign) = (value, style.value) {
+            args.extend(["Ar".into(), value.to_string().into()]);
+        }
+    }
+    if let Some(long) = long {
+        if !args.is_empty() {
+            let separator = style.separator.unwrap_or(match context {
+                FlagContext::Sentence => FlagSeparator::Comma,
+                FlagContext::Synopsis => FlagSeparator::Pipe,
+            });
+            args.push(match separator {
+                FlagSeparator::Comma => ",".into(),
+                FlagSeparator::Pipe => "|".into(),
+            });
+        }
+        args.push("Fl".into());
+        args.push(match style.long {
+            LongFlags::Gnu => format!("-{long}").into(),
+            LongFlags::Bsd => long.to_string().into(),
+        });
+        if let (Some(value), ValueStyle::Assign) = (value, style.value) {
+            args.extend(["Ns".into(), "=".into(), "Ns".into(), "Ar".into()]);
+            args.push(value.to_string().into());
+        }
+    }
+    if args.is_empty() {
+        args.push("Fl".into());
+    }
+    if let (Some(value), ValueStyle::Separate) = (value, style.value) {
+        args.extend(["Ar".into(), value.to_string().into()]);
+    }
+    args
 }
 
-/// Turn a string slice into inline text in the roman font.
+/// A command-line option in a synopsis, emitted with [`Mdoc::flag`].
+///
+/// # Example
 ///
-/// This is equivalent to the [roman] function, but may be more
-/// convenient to use.
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.flag(Flag::short('o').with_long("output").value("file").optional())
+///     .flag(Flag::combined("abc"))
+///     .flag(Flag::long("verbose"));
+/// assert_eq!(
+///     doc.to_mdoc(),
+///     ".Op Fl o | Fl -output Ar file\n.Fl abc\n.Fl -verbose\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Flag {
+    /// Single-letter forms, e.g. `v` for `-v`, or `abc` for combined flags
+    /// such as `-abc`.
+    pub short: Option<String>,
+    /// Long form, without dashes.
+    pub long: Option<String>,
+    /// Name of the argument of the option.
+    pub value: Option<String>,
+    /// Whether the flag is wrapped in `Op`.
+    pub optional: bool,
+    pub style: FlagStyle,
+}
+
+impl Flag {
+    /// A flag with a short form, e.g. `-v`.
+    pub fn short(c: char) -> Self {
+        Self {
+            short: Some(c.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Short flags that are usually given together, e.g. `-abc`.
+    pub fn combined(chars: impl Into<String>) -> Self {
+        Self {
+            short: Some(chars.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A flag with only a long form, e.g. `--verbose`.
+    pub fn long(name: impl Into<String>) -> Self {
+        Self::default().with_long(name)
+    }
+
+    /// Add a long form to the flag.
+    pub fn with_long(self, name: impl Into<String>) -> Self {
+        Self {
+            long: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Set the name of the argument of the flag.
+    pub fn value(self, name: impl Into<String>) -> Self {
+        Self {
+            value: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Wrap the flag in `Op`.
+    pub fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    /// Set the conventions used to render the flag.
+    pub fn style(self, style: FlagStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    /// The `Fl` and `Ar` macros of the flag, without the `Op` wrapping.
+    pub fn args(&self) -> Vec<Str> {
+        match self.short.as_deref() {
+            Some(short) if short.chars().count() > 1 => {
+                let mut args: Vec<Str> = vec!["Fl".into(), short.to_string().into()];
+                if let Some(value) = &self.value {
+                    args.extend(["Ar".into(), value.clone().into()]);
+                }
+                args
+            }
+            short => flag_args(
+                short.and_then(|s| s.chars().next()),
+                self.long.as_deref(),
+                self.value.as_deref(),
+                FlagContext::Synopsis,
+                self.style,
+            ),
+        }
+    }
+
+    /// The control line for the flag.
+    pub fn line(&self) -> Line {
+        let mut args = self.args();
+        let name = if self.optional {
+            "Op".into()
+        } else {
+            args.remove(0)
+        };
+        Line::control(name, args)
+    }
+}
+
+// Turn a string slice into inline text in the roman font.
+//
+// This is equivalent to the [roman] function, but may be more
+// convenient to use.
 // impl<S: Into<String>> From<S> for Inline {
 //     fn from(s: S) -> Self {
 //         roman(s)
@@ -334,6 +1998,12 @@ pub fn roman(input: impl Into<String>) -> Inline {
     Inline::Roman(input.into())
 }
 
+/// Return some inline text in the "roman" font that is already **mdoc**
+/// source, such as a text line of a parsed page.
+pub fn roff(input: impl Into<String>) -> Inline {
+    Inline::Roff(input.into())
+}
+
 /// Return some inline text in the bold font.
 pub fn bold(input: impl Into<String>) -> Inline {
     Inline::Bold(input.into())
@@ -363,6 +2033,151 @@ pub enum Line {
 
     /// A text line.
     Text(Vec<Inline>),
+
+    /// A figure, see [`Figure`].
+    Figure(Figure),
+
+    /// A note, warning or caution, see [`admonition`].
+    Admonition(Admonition),
+
+    /// A line changed since the last release, rendered with a change bar in
+    /// the margin.
+    Changed(Box<Line>),
+
+    /// Source an importer couldn't model, see [`Unknown`].
+    Unknown(Unknown),
+}
+
+/// The language of the source of an [`Unknown`] construct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    /// Plain roff requests and preprocessor input, which are understood
+    /// alike with **mdoc** and **man** macros.
+    Roff,
+    Mdoc,
+    Man,
+}
+
+/// A construct an importer couldn't model, kept as it was read so that
+/// conversions never lose text.
+///
+/// Backends for the language of the source write it back verbatim. Others
+/// can't interpret it, so they keep it as a comment.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let page = parser::parse(".Sh NAME\n.de XX\n.  tm \\$1\n..\n");
+/// assert_eq!(page.to_mdoc(), ".Sh NAME\n.de XX\n.  tm \\$1\n..\n");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Unknown {
+    pub language: SourceLanguage,
+    /// The source lines, without a trailing newline.
+    pub source: String,
+}
+
+impl Unknown {
+    pub fn new(language: SourceLanguage, source: impl Into<String>) -> Self {
+        Self {
+            language,
+            source: source.into(),
+        }
+    }
+
+    /// Write the source if it is in `language`, or else as comment lines.
+    pub fn render_as(
+        &self,
+        language: SourceLanguage,
+        out: &mut dyn Write,
+    ) -> Result<(), std::io::Error> {
+        if matches!(self.language, SourceLanguage::Roff) || self.language == language {
+            writeln!(out, "{}", self.source)
+        } else {
+            for line in self.source.lines() {
+                writeln!(out, ".\\\" {line}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The kind of an [`Admonition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdmonitionKind {
+    Note,
+    Warning,
+    Caution,
+}
+
+impl AdmonitionKind {
+    /// The label the admonition starts with.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Note => "Note:",
+            Self::Warning => "Warning:",
+            Self::Caution => "Caution:",
+        }
+    }
+}
+
+/// A paragraph set apart from the surrounding text to draw attention to it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Admonition {
+    pub kind: AdmonitionKind,
+    pub text: Vec<Inline>,
+}
+
+impl Admonition {
+    /// The **mdoc** lines this admonition is rendered as: an indented block
+    /// starting with a bold label.
+    pub fn lines(&self) -> Vec<Line> {
+        DisplayBlock::new(DisplayKind::Filled)
+            .offset("indent")
+            .line(Line::control("Sy".into(), vec![self.kind.label().into()]))
+            .text(self.text.clone())
+            .lines()
+    }
+}
+
+/// Return a note, warning or caution paragraph.
+pub fn admonition(kind: AdmonitionKind, text: impl Into<Vec<Inline>>) -> Line {
+    Line::Admonition(Admonition {
+        kind,
+        text: text.into(),
+    })
+}
+
+/// An image with a caption.
+///
+/// Backends that can embed images, such as HTML, show the image itself.
+/// In **mdoc** output, which can't, the figure is rendered as an indented
+/// note with the caption and a link to the image.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Figure {
+    /// Path or URL of the image.
+    pub src: String,
+    pub caption: String,
+}
+
+impl Figure {
+    pub fn new(src: impl Into<String>, caption: impl Into<String>) -> Self {
+        Self {
+            src: src.into(),
+            caption: caption.into(),
+        }
+    }
+
+    /// The **mdoc** lines this figure is rendered as.
+    pub fn lines(&self) -> Vec<Line> {
+        DisplayBlock::new(DisplayKind::Ragged)
+            .offset("indent")
+            .line(Line::control("Sy".into(), vec!["Figure:".into()]))
+            .text([roman(self.caption.as_str())])
+            .line(Line::control("Lk".into(), vec![self.src.clone().into()]))
+            .lines()
+    }
 }
 
 impl Line {
@@ -378,39 +2193,127 @@ impl Line {
         Self::Text(parts)
     }
 
+    /// The inline elements of a text line or admonition, if any.
+    pub fn inlines(&self) -> Option<&[Inline]> {
+        match self {
+            Self::Text(inlines) => Some(inlines),
+            Self::Admonition(admonition) => Some(&admonition.text),
+            Self::Changed(line) => line.inlines(),
+            Self::Control { .. } | Self::Figure(_) | Self::Unknown(_) => None,
+        }
+    }
+
+    /// Mutable access to the inline elements of a text line or admonition,
+    /// if any.
+    pub fn inlines_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        match self {
+            Self::Text(inlines) => Some(inlines),
+            Self::Admonition(admonition) => Some(&mut admonition.text),
+            Self::Changed(line) => line.inlines_mut(),
+            Self::Control { .. } | Self::Figure(_) | Self::Unknown(_) => None,
+        }
+    }
+
     /// Generate a **mdoc** line.
+    pub fn render(&self, out: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.render_with(out, &RenderOptions::default())
+    }
+
+    /// Generate a **mdoc** line with the given options.
     ///
     /// All the **mdoc** code generation and special handling happens here.
-    pub fn render(&self, out: &mut dyn Write) -> Result<(), std::io::Error> {
+    pub fn render_with(
+        &self,
+        out: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> Result<(), std::io::Error> {
         match self {
+            Self::Figure(figure) => {
+                for line in figure.lines() {
+                    line.render_with(out, options)?;
+                }
+                return Ok(());
+            }
+            Self::Admonition(admonition) => {
+                for line in admonition.lines() {
+                    line.render_with(out, options)?;
+                }
+                return Ok(());
+            }
+            Self::Unknown(unknown) => return unknown.render_as(SourceLanguage::Mdoc, out),
+            Self::Changed(line) => {
+                writeln!(out, ".mc \\(br")?;
+                line.render_with(out, options)?;
+                writeln!(out, ".mc")?;
+                return Ok(());
+            }
             Self::Control { name, args } => {
                 write!(out, ".{}", name)?;
-                for arg in args {
-                    write!(out, " {}", &arg)?;
+                let mut continued = false;
+                for arg in escape_args(name, args) {
+                    if continued {
+                        write!(out, "{}", &arg)?;
+                    } else {
+                        write!(out, " {}", &arg)?;
+                    }
+                    // Line breaks kept by lossless parsing are followed
+                    // by the indentation of the next line.
+                    continued = arg.starts_with("\\\n");
                 }
             }
             Self::Text(inlines) => {
                 let mut at_line_start = true;
-                for inline in inlines.iter() {
+                let mut after_macro = false;
+                // Bytes at the start of the next element that were already
+                // written as delimiter arguments of a macro.
+                let mut consumed = 0;
+                for (i, inline) in inlines.iter().enumerate() {
+                    let next_is_macro = inlines
+                        .get(i + 1)
+                        .is_some_and(|inline| inline.is_macro(options));
                     // We need to handle line breaking specially: it
                     // introduces a control line to the **mdoc**, and the
                     // leading period of that mustn't be escaped.
                     match inline {
+                        Inline::IndexTerm(_) => continue,
                         Inline::LineBreak => {
-                            if at_line_start {
-                                writeln!(out, ".br")?;
-                            } else {
-                                writeln!(out, "\n.br")?;
+                            if !at_line_start {
+                                writeln!(out)?;
                             }
+                            write!(out, ".br")?;
                         }
-                        Inline::Roman(text) | Inline::Italic(text) | Inline::Bold(text) => {
-                            let text = escape_leading_cc(text);
+                        Inline::Roman(text)
+                        | Inline::Roff(text)
+                        | Inline::Italic(text)
+                        | Inline::Bold(text)
+                        | Inline::Acronym(text)
+                            if !inline.is_macro(options) =>
+                        {
+                            let mut text = &text[std::mem::take(&mut consumed)..];
+                            // Only spaces are trimmed, tabs are kept to
+                            // preserve alignment.
+                            if after_macro {
+                                text = text.trim_start_matches(' ');
+                            }
+                            if next_is_macro {
+                                text = text.trim_end_matches(' ');
+                            }
+                            if text.is_empty() {
+                                continue;
+                            }
+                            if after_macro {
+                                writeln!(out)?;
+                            }
+                            let text = match inline {
+                                Inline::Roff(_) => escape_leading_cc(text),
+                                _ => escape_leading_cc(&escape_hyphens(text)),
+                            };
                             if let Inline::Bold(_) = inline {
-                                write!(out, r"\n.Sy {}\n", text)?;
+                                write!(out, r"\fB{}\fR", text)?;
                             } else if let Inline::Italic(_) = inline {
-                                write!(out, r"\n.Em {}\n", text)?;
+                                write!(out, r"\fI{}\fR", text)?;
                             } else {
-                                if at_line_start && starts_with_period(&text) {
+                                if (at_line_start || after_macro) && starts_with_period(&text) {
                                     // Line would start with a period, so we
                                     // insert a non-printable, zero-width glyph to
                                     // prevent it from being interpreted as such.
@@ -426,9 +2329,33 @@ impl Line {
                                 }
                                 write!(out, "{}", text)?;
                             }
+                            at_line_start = false;
+                            after_macro = false;
+                            continue;
+                        }
+                        _ => {
+                            if !at_line_start {
+                                writeln!(out)?;
+                            }
+                            let args = inline.macro_args(options);
+                            write!(out, ".{}", args[0])?;
+                            for arg in escape_args(&args[0], &args[1..]) {
+                                write!(out, " {}", arg)?;
+                            }
+                            if let Some(Inline::Roman(text) | Inline::Roff(text)) =
+                                inlines.get(i + 1)
+                            {
+                                consumed = closing_delimiters(text);
+                                for c in text[..consumed].chars() {
+                                    write!(out, " {}", c)?;
+                                }
+                            }
                         }
                     }
+                    // Control lines are terminated lazily, by the next
+                    // element or at the end of the text line.
                     at_line_start = false;
+                    after_macro = true;
                 }
             }
         };
@@ -436,6 +2363,8 @@ impl Line {
         Ok(())
     }
 
+    /// An `Xr` line, without checking the section; see [`Xr`] for a
+    /// validated reference.
     pub fn cross_reference(title: Str, section: Str) -> Self {
         Self::Control {
             name: "Xr".into(),
@@ -444,6 +2373,127 @@ impl Line {
     }
 }
 
+/// Length of the leading closing punctuation of `text` that can be passed
+/// to a preceding macro as delimiter arguments.
+///
+/// Only punctuation followed by whitespace or the end of the text counts,
+/// since anything else is part of a word.
+fn closing_delimiters(text: &str) -> usize {
+    let len = text
+        .find(|c| !matches!(c, '.' | ',' | ':' | ';' | ')' | ']' | '?' | '!'))
+        .unwrap_or(text.len());
+    if text[len..].starts_with(char::is_whitespace) || len == text.len() {
+        len
+    } else {
+        0
+    }
+}
+
+/// The macros whose arguments starting with a dash are options or flags,
+/// as in `Bl -tag` and `Fl -verbose`.
+const OPTION_MACROS: [&str; 9] = ["An", "Bd", "Bf", "Bk", "Bl", "Ex", "Fl", "Rv", "St"];
+
+/// The macros whose argument is the rest of the line.
+const LINE_MACROS: [&str; 20] = [
+    "Dd", "Nd", "Sh", "Ss", "%A", "%B", "%C", "%D", "%I", "%J", "%N", "%O", "%P", "%Q", "%R", "%T",
+    "%U", "%V", "Rs", "Re",
+];
+
+/// Escape and quote the arguments of a control line so that they are read
+/// back as they are meant.
+///
+/// Arguments that are empty or contain spaces are enclosed in double
+/// quotes, with the double quotes inside them doubled. Arguments already
+/// enclosed in double quotes, as kept by the parser, are written as
+/// quoted arguments too. The arguments of the macros whose argument is
+/// the rest of the line, such as `Sh` and `Nd`, and the cells of the
+/// items of column lists are only quoted if one of their words would
+/// otherwise be called as a macro.
+///
+/// The arguments of **mdoc** macros are otherwise written as they are, so
+/// escape sequences such as `\(em` can be used in them. What roff would
+/// misread is escaped:
+///
+/// - backslashes that don't start an escape sequence, such as a trailing
+///   one, or one before a quote, which would start a comment, are written
+///   as `\e`,
+/// - a double quote starting an unquoted argument is written as `\(dq`,
+/// - a leading dash is written as `\-`, a minus sign, unless the argument
+///   belongs to a macro taking options or flags such as `Bl` or `Fl`.
+///
+/// Comments are left alone, as are line breaks kept by lossless parsing.
+fn escape_args<'a>(name: &str, args: &'a [Str]) -> Vec<Cow<'a, str>> {
+    if name == "\\\"" {
+        return args.iter().map(|arg| Cow::Borrowed(arg.as_ref())).collect();
+    }
+    let is_mdoc = {
+        let mut chars = name.chars();
+        matches!(
+            (chars.next(), chars.next(), chars.next()),
+            (Some('A'..='Z' | '%'), Some('a'..='z' | 'A'..='Z'), None)
+        ) && !name.bytes().all(|b| b.is_ascii_uppercase())
+    };
+    // The macro the next argument belongs to.
+    let mut current = name;
+    args.iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            if is_mdoc && (phrase::is_callable(arg) || arg == "Ta") {
+                current = arg;
+                return Cow::Borrowed(arg);
+            }
+            if arg.starts_with("\\\n") {
+                return Cow::Borrowed(arg);
+            }
+            let quoted = arg.len() > 1 && arg.starts_with('"') && arg.ends_with('"');
+            let inner = if quoted { &arg[1..arg.len() - 1] } else { arg };
+            let wrap = quoted
+                || if LINE_MACROS.contains(&name) || matches!(current, "It" | "Ta") {
+                    inner.split_whitespace().skip(1).any(phrase::is_callable)
+                } else {
+                    inner.is_empty() || inner.contains([' ', '\t'])
+                };
+            let mut ret = String::with_capacity(arg.len() + 2);
+            if wrap {
+                ret.push('"');
+            }
+            let mut chars = inner.chars().peekable();
+            if is_mdoc && inner.starts_with('-') && !OPTION_MACROS.contains(&current) {
+                ret.push_str("\\-");
+                chars.next();
+            }
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' if is_mdoc => match chars.peek() {
+                        None | Some('"' | '#' | ' ' | '\t') => ret.push_str("\\e"),
+                        Some(_) => {
+                            ret.push(c);
+                            ret.extend(chars.next());
+                        }
+                    },
+                    '"' if wrap => {
+                        ret.push_str("\"\"");
+                        // Quotes that the parser kept doubled stay so.
+                        if quoted && chars.peek() == Some(&'"') {
+                            chars.next();
+                        }
+                    }
+                    '"' if is_mdoc && ret.is_empty() => ret.push_str("\\(dq"),
+                    c => ret.push(c),
+                }
+            }
+            if wrap {
+                ret.push('"');
+            }
+            if ret == arg {
+                Cow::Borrowed(arg)
+            } else {
+                Cow::Owned(ret)
+            }
+        })
+        .collect()
+}
+
 /// Does line start with a control character?
 #[inline]
 pub fn starts_with_period(line: &str) -> bool {
@@ -457,3 +2507,49 @@ pub fn starts_with_period(line: &str) -> bool {
 pub fn escape_leading_cc(s: &str) -> String {
     s.replace("\n.", "\n\\&.").replace("\n'", "\n\\&'")
 }
+
+/// Write hyphens as `\-`, which groff(1) renders as the ASCII hyphen-minus
+/// that can be copied and pasted into command lines, rather than as a
+/// typographic hyphen. Hyphens of escape sequences, as in `\-` or
+/// `\*[foo-bar]`, are left alone.
+pub fn escape_hyphens(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '-' => ret.push_str("\\-"),
+            '\\' => {
+                ret.push(c);
+                let Some(mut escape) = chars.next() else {
+                    break;
+                };
+                ret.push(escape);
+                // The name of a string, font, register etc. follows some
+                // escapes, as in `\*(foo` or `\f[B]`.
+                if matches!(escape, '*' | 'f' | 'F' | 'g' | 'm' | 'M' | 'n' | 'V' | 'Y') {
+                    match chars.next() {
+                        Some(name) => {
+                            ret.push(name);
+                            escape = name;
+                        }
+                        None => break,
+                    }
+                }
+                match escape {
+                    '(' => ret.extend(chars.by_ref().take(2)),
+                    '[' => {
+                        for c in chars.by_ref() {
+                            ret.push(c);
+                            if c == ']' {
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            c => ret.push(c),
+        }
+    }
+    ret
+}
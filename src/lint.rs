@@ -0,0 +1,1306 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Check pages for structural mistakes.
+//!
+//! [`lint`] checks a single page: its prologue, its NAME section, the
+//! nesting of its blocks and lists, whether the flags of its SYNOPSIS and
+//! of its options list agree, and the content that can't be rendered
+//! faithfully. [`lint_tree`] runs it over a directory of installed pages,
+//! such as `/usr/share/man`, reading gzipped pages and checking that
+//! `.so` stubs point to existing pages, and collects the diagnostics of
+//! every file in a [`Report`].
+//!
+//! Each check is a [`LintRule`]; a [`Linter`] runs a set of them, so tools
+//! can drop the checks that don't apply to their pages and add their own.
+//!
+//! The mistakes with a mechanical [`Fix`], such as sections out of order or
+//! an unsorted SEE ALSO section, are corrected by [`apply_fixes`], and in
+//! place by [`fix_file`].
+//!
+//! Organizations can enforce their own documentation standards on top of
+//! these checks with a [`Policy`], loaded from a TOML file such as:
+//!
+//! ```toml
+//! required-sections = ["AUTHORS", "SEE ALSO"]
+//! max-line-length = 80
+//! require-spdx = true
+//! forbid-conditionals = true
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let page = parser::parse(".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Bl -tag\n");
+//! let mut warnings = Warnings::new();
+//! lint::lint(&page, &mut warnings);
+//! let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+//! assert_eq!(messages, ["NAME: missing Nd", "NAME: Bl is not closed"]);
+//! ```
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+/// Options for [`lint_tree`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Paths, relative to the root of the tree, of files and directories
+    /// that are skipped.
+    pub ignore: Vec<PathBuf>,
+    /// Rules checked in addition to the structural checks.
+    pub policy: Option<Policy>,
+}
+
+/// Rules a distribution or an organization requires its pages to follow.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::lint::*;
+/// let policy = Policy::parse("required-sections = [\"AUTHORS\"]\nmax-line-length = 20\n").unwrap();
+/// let source = ".Sh NAME\n.Nm foo\n.Nd a description that is too long\n";
+/// let mut warnings = Warnings::new();
+/// policy.check(source, &parser::parse(source), &mut warnings);
+/// let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     messages,
+///     ["missing AUTHORS section", "line 3: longer than 20 characters"]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// Sections every page must have, such as AUTHORS.
+    pub required_sections: Vec<String>,
+    /// The maximum length of the source lines, in characters.
+    pub max_line_length: Option<usize>,
+    /// Whether pages must state their license with an
+    /// `SPDX-License-Identifier` comment.
+    pub require_spdx: bool,
+    /// Whether the roff conditionals `if` and `ie`, used to tell typesetters
+    /// and terminals apart with `.if t`, are forbidden.
+    pub forbid_conditionals: bool,
+}
+
+impl Policy {
+    /// Read a policy from a TOML file.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}: {err}", path.display()),
+            )
+        })
+    }
+
+    /// Parse a policy from TOML.
+    ///
+    /// Only the keys of the policy are understood, at the top level or in a
+    /// `[policy]` table, with integer, boolean, string and single-line
+    /// string array values.
+    pub fn parse(toml: &str) -> Result<Self, String> {
+        let mut ret = Self::default();
+        for (i, line) in toml.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {message}", i + 1);
+            let line = strip_comment(line).trim();
+            if line.is_empty() || line == "[policy]" {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `key = value`"))?;
+            let value = value.trim();
+            let boolean = || match value {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(error("expected true or false")),
+            };
+            match key.trim() {
+                "required-sections" => {
+                    ret.required_sections =
+                        strings(value).ok_or_else(|| error("expected an array of strings"))?;
+                }
+                "max-line-length" => {
+                    ret.max_line_length =
+                        Some(value.parse().map_err(|_| error("expected a number"))?);
+                }
+                "require-spdx" => ret.require_spdx = boolean()?,
+                "forbid-conditionals" => ret.forbid_conditionals = boolean()?,
+                key => return Err(error(&format!("unknown key `{key}`"))),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Check `page`, parsed from `source`, reporting the rules it breaks to
+    /// `warnings`.
+    pub fn check(&self, source: &str, page: &Mdoc, warnings: &mut Warnings) {
+        let sections = page.sections();
+        for required in &self.required_sections {
+            // Overview pages describe a topic, which has no synopsis.
+            if page.is_overview() && required.eq_ignore_ascii_case("SYNOPSIS") {
+                continue;
+            }
+            if !sections
+                .iter()
+                .any(|(title, _)| parser::unquote(title).eq_ignore_ascii_case(required))
+            {
+                warnings.push(format!("missing {required} section"));
+            }
+        }
+        if self.require_spdx && !source.contains("SPDX-License-Identifier:") {
+            warnings.push("missing SPDX-License-Identifier");
+        }
+        for (i, line) in source.lines().enumerate() {
+            if let Some(max) = self.max_line_length {
+                if line.chars().count() > max {
+                    warnings.push(format!("line {}: longer than {max} characters", i + 1));
+                }
+            }
+            let request = line
+                .strip_prefix(['.', '\''])
+                .map(|l| l.split_whitespace().next().unwrap_or_default());
+            if self.forbid_conditionals && matches!(request, Some("if" | "ie")) {
+                warnings.push(format!("line {}: roff conditional", i + 1));
+            }
+        }
+    }
+}
+
+/// `line` without its comment, if any.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// The strings of a TOML array of strings.
+fn strings(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    inner
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// The diagnostics of the files of a tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The files that were checked, in order of their paths.
+    pub files: Vec<FileReport>,
+}
+
+/// The diagnostics of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Warning>,
+}
+
+impl Report {
+    /// The number of diagnostics of all files.
+    pub fn len(&self) -> usize {
+        self.files.iter().map(|f| f.diagnostics.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for file in &self.files {
+            for diagnostic in &file.diagnostics {
+                writeln!(f, "{}: {diagnostic}", file.path.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the pages under `root`, or the page `root` if it is a file.
+///
+/// Files whose extension is a section number, such as `ls.1` or
+/// `ls.1.gz`, are read as **mdoc** or, if they start with `TH`, as man(7)
+/// pages. Files and directories whose names start with a dot are skipped.
+pub fn lint_tree(root: &Path, options: &LintOptions) -> Report {
+    lint_tree_with_progress(root, options, |_| {})
+}
+
+/// [`lint_tree`], calling `progress` after each page is checked.
+///
+/// ```no_run
+/// # use mdoc::lint::*;
+/// let report = lint_tree_with_progress("/usr/share/man".as_ref(), &LintOptions::default(), |progress| {
+///     eprintln!("{progress}");
+/// });
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options, progress)))]
+pub fn lint_tree_with_progress(
+    root: &Path,
+    options: &LintOptions,
+    mut progress: impl FnMut(&Progress),
+) -> Report {
+    let mut report = Report::default();
+    let mut paths = vec![];
+    if root.is_file() {
+        paths.push(root.to_path_buf());
+    } else if let Err(err) = walk(root, root, options, &mut paths) {
+        report.files.push(FileReport {
+            path: root.to_path_buf(),
+            diagnostics: vec![Warning {
+                context: None,
+                message: format!("could not read the directory: {err}"),
+            }],
+        });
+    }
+    paths.sort();
+    let base = if root.is_file() {
+        root.parent().unwrap_or(root)
+    } else {
+        root
+    };
+    let total = paths.len();
+    for (path, current) in paths.into_iter().zip(1..) {
+        let mut warnings = Warnings::new();
+        lint_file(base, &path, options, &mut warnings);
+        progress(&Progress {
+            current,
+            total,
+            path: &path,
+            status: PageStatus::Checked {
+                problems: warnings.len(),
+            },
+        });
+        report.files.push(FileReport {
+            path,
+            diagnostics: warnings.into_vec(),
+        });
+    }
+    report
+}
+
+/// Collect the paths of the pages under `dir`.
+fn walk(
+    root: &Path,
+    dir: &Path,
+    options: &LintOptions,
+    paths: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        let ignored = path
+            .strip_prefix(root)
+            .is_ok_and(|p| options.ignore.iter().any(|i| i == p));
+        if hidden || ignored {
+            continue;
+        }
+        if path.is_dir() {
+            walk(root, &path, options, paths)?;
+        } else if is_page(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether the extension of `path`, ignoring `.gz`, is a section number.
+fn is_page(path: &Path) -> bool {
+    let path = match path.extension() {
+        Some(ext) if ext == "gz" => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.starts_with(|c: char| c.is_ascii_digit()) || e == "n")
+}
+
+fn lint_file(root: &Path, path: &Path, options: &LintOptions, warnings: &mut Warnings) {
+    let source = match manual::read_source(path) {
+        Ok(source) => source,
+        Err(err) => {
+            warnings.push(format!("could not read the page: {err}"));
+            return;
+        }
+    };
+    let input = import::Input::new(path, &source);
+    let first = input.first_line();
+    if let Some(target) = first.strip_prefix(".so ") {
+        // Stubs name their target relative to the root of the tree, as in
+        // `.so man1/ls.1`.
+        let target = target.trim();
+        let dir = path.parent().unwrap_or(root);
+        let exists = [root.join(target), dir.join(target)]
+            .iter()
+            .any(|p| p.exists() || p.with_extension(format!("{}.gz", extension(p))).exists());
+        if !exists {
+            warnings.push(format!(".so target {target} does not exist"));
+        }
+        return;
+    }
+    for (i, line) in source.lines().enumerate() {
+        if line.ends_with([' ', '\t']) && !line.ends_with("\\ ") {
+            warnings.push(format!("line {}: trailing whitespace", i + 1));
+        }
+    }
+    let page = if first.starts_with(".TH") {
+        from_man::from_man_with_warnings(&source, warnings)
+    } else {
+        parser::parse(&source)
+    };
+    lint(&page, warnings);
+    if let Some(policy) = &options.policy {
+        policy.check(&source, &page, warnings);
+    }
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+}
+
+/// Check a page, reporting its mistakes to `warnings`.
+///
+/// This runs the rules of [`Linter::default`].
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn lint(page: &Mdoc, warnings: &mut Warnings) {
+    Linter::default().check(page, warnings);
+}
+
+/// A check run by a [`Linter`].
+///
+/// Projects enforce their own conventions by implementing it:
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::lint::*;
+/// struct RequireExamples;
+///
+/// impl LintRule for RequireExamples {
+///     fn name(&self) -> &str {
+///         "require-examples"
+///     }
+///
+///     fn check(&self, context: &Context, warnings: &mut Warnings) {
+///         if !context.sections.iter().any(|(title, _)| title == "EXAMPLES") {
+///             warnings.push("missing EXAMPLES section");
+///         }
+///     }
+/// }
+///
+/// let linter = Linter::new().rule(RequireExamples).rule(EmptySections);
+/// let mut warnings = Warnings::new();
+/// linter.check(&parser::parse(".Sh NAME\n.Sh DESCRIPTION\nfoo\n"), &mut warnings);
+/// let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+/// assert_eq!(messages, ["missing EXAMPLES section", "NAME: empty section"]);
+/// ```
+pub trait LintRule {
+    /// The name of the rule, such as `empty-sections`, used to remove it
+    /// with [`Linter::without`].
+    fn name(&self) -> &str;
+
+    /// Check a page, reporting its mistakes to `warnings`.
+    fn check(&self, context: &Context, warnings: &mut Warnings);
+}
+
+/// A page being checked by a [`Linter`].
+#[derive(Debug)]
+pub struct Context<'a> {
+    /// The page.
+    pub page: &'a Mdoc,
+    /// The lines of the page, as [`Mdoc::document_lines`] returns them.
+    pub lines: &'a [Line],
+    /// The title and the range of lines of each section of the page, the
+    /// `Sh` line included.
+    pub sections: &'a [(String, Range<usize>)],
+}
+
+impl<'a> Context<'a> {
+    /// The lines of the sections titled `title`, `Sh` lines included.
+    pub fn section(&self, title: &'a str) -> impl Iterator<Item = &'a Line> + 'a {
+        let lines = self.lines;
+        self.sections
+            .iter()
+            .filter(move |(t, _)| parser::unquote(t) == title)
+            .flat_map(move |(_, range)| lines[range.clone()].iter())
+    }
+
+    /// The macros of the page, without comments.
+    fn macros(&self) -> impl Iterator<Item = (&'a str, &'a [Str])> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Control { name, args } if name != "\\\"" => {
+                Some((name.as_ref(), args.as_slice()))
+            }
+            _ => None,
+        })
+    }
+}
+
+/// A set of [`LintRule`]s.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::lint::*;
+/// let linter = Linter::default().without("prologue").without("render");
+/// let mut warnings = Warnings::new();
+/// linter.check(&parser::parse(".Sh NAME\n.Nm foo\n.Nd bar\n.Sh NAME\n"), &mut warnings);
+/// let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+/// assert_eq!(messages, ["NAME: duplicate section", "NAME: empty section"]);
+/// ```
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Linter {
+    /// A linter without rules.
+    pub fn new() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// Add a rule, run after the rules already added.
+    pub fn rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Remove the rules named `name`.
+    pub fn without(mut self, name: &str) -> Self {
+        self.rules.retain(|rule| rule.name() != name);
+        self
+    }
+
+    /// The names of the rules, in the order they run.
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name())
+    }
+
+    /// Check a page with every rule, reporting its mistakes to `warnings`.
+    pub fn check(&self, page: &Mdoc, warnings: &mut Warnings) {
+        let lines = page.document_lines();
+        let sections = Mdoc::sections_of(&lines);
+        let context = Context {
+            page,
+            lines: &lines,
+            sections: &sections,
+        };
+        for rule in &self.rules {
+            rule.check(&context, warnings);
+        }
+    }
+}
+
+/// The rules of [`lint`].
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+            .rule(Prologue)
+            .rule(NameSection)
+            .rule(DuplicateSections)
+            .rule(EmptySections)
+            .rule(Synopsis)
+            .rule(FlagReferences)
+            .rule(SectionOrder)
+            .rule(SeeAlsoOrder)
+            .rule(RawCharacters)
+            .rule(Blocks)
+            .rule(Render)
+    }
+}
+
+impl std::fmt::Debug for Linter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.rules()).finish()
+    }
+}
+
+/// `Dd`, `Dt` and `Os` are the first macros of the page, and the date of
+/// `Dd` is a calendar day.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Prologue;
+
+impl LintRule for Prologue {
+    fn name(&self) -> &str {
+        "prologue"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        let macros: Vec<&str> = context.macros().map(|(name, _)| name).collect();
+        for (i, name) in ["Dd", "Dt", "Os"].into_iter().enumerate() {
+            match macros.iter().position(|m| *m == name) {
+                None => warnings.push(format!("missing {name}")),
+                Some(position) if position != i => {
+                    warnings.push(format!("{name} is not in the prologue"))
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(Err(err)) = context.page.date.as_ref().map(DocumentDate::ymd) {
+            warnings.push(format!("Dd: {err}"));
+        }
+    }
+}
+
+/// The first section is NAME, with an `Nm` and a short enough `Nd`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NameSection;
+
+impl LintRule for NameSection {
+    fn name(&self) -> &str {
+        "name-section"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        match context.sections.first() {
+            None => warnings.push("missing NAME section"),
+            Some((title, _)) if title != "NAME" => warnings.push("the first section is not NAME"),
+            Some(_) => {}
+        }
+        if !context.sections.iter().any(|(title, _)| title == "NAME") {
+            return;
+        }
+        for name in ["Nm", "Nd"] {
+            let found = context
+                .section("NAME")
+                .any(|l| matches!(l, Line::Control { name: n, .. } if n == name));
+            if !found {
+                warnings.push_in("NAME", format!("missing {name}"));
+            }
+        }
+        let description = context.page.one_line_description().unwrap_or_default();
+        if description.chars().count() > Description::MAX_LENGTH {
+            warnings.push_in(
+                "NAME",
+                format!(
+                    "the description is longer than {} characters",
+                    Description::MAX_LENGTH
+                ),
+            );
+        }
+    }
+}
+
+/// No two sections have the same title.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuplicateSections;
+
+impl LintRule for DuplicateSections {
+    fn name(&self) -> &str {
+        "duplicate-sections"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        let mut seen: Vec<&str> = vec![];
+        for (title, _) in context.sections {
+            if seen.contains(&title.as_str()) {
+                warnings.push_in(title, "duplicate section");
+            }
+            seen.push(title);
+        }
+    }
+}
+
+/// Every section has content.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptySections;
+
+impl LintRule for EmptySections {
+    fn name(&self) -> &str {
+        "empty-sections"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        for (title, range) in context.sections {
+            if range.len() == 1 {
+                warnings.push_in(title, "empty section");
+            }
+        }
+    }
+}
+
+/// The flags of the SYNOPSIS are the documented options.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Synopsis;
+
+impl LintRule for Synopsis {
+    fn name(&self) -> &str {
+        "synopsis"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        check_synopsis(context.lines, context.sections, warnings);
+    }
+}
+
+/// The flags mentioned in the text are options of the page.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlagReferences;
+
+impl LintRule for FlagReferences {
+    fn name(&self) -> &str {
+        "flag-references"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        check_flag_references(context.lines, context.sections, warnings);
+    }
+}
+
+/// The sections are in the conventional order, see
+/// [`Fix::ReorderSections`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionOrder;
+
+impl LintRule for SectionOrder {
+    fn name(&self) -> &str {
+        "section-order"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        if Fix::ReorderSections.applies(context.page) {
+            warnings.push("the sections are not in the conventional order");
+        }
+    }
+}
+
+/// The references of SEE ALSO are sorted, see [`Fix::SortSeeAlso`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeeAlsoOrder;
+
+impl LintRule for SeeAlsoOrder {
+    fn name(&self) -> &str {
+        "see-also-order"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        if Fix::SortSeeAlso.applies(context.page) {
+            warnings.push_in("SEE ALSO", "the references are not sorted");
+        }
+    }
+}
+
+/// Typographic characters are escaped, see [`Fix::EscapeCharacters`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawCharacters;
+
+impl LintRule for RawCharacters {
+    fn name(&self) -> &str {
+        "raw-characters"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        if Fix::EscapeCharacters.applies(context.page) {
+            warnings.push("raw characters that should be escaped");
+        }
+    }
+}
+
+/// Blocks and lists are closed, `It` is in a list and `Xr` has a section.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blocks;
+
+impl LintRule for Blocks {
+    fn name(&self) -> &str {
+        "blocks"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        // The open blocks, with the section they were opened in.
+        let mut blocks: Vec<(&str, String)> = vec![];
+        let mut section = String::new();
+        for (name, args) in context.macros() {
+            match name {
+                "Sh" => section = args.join(" "),
+                "Bl" | "Bd" | "Bf" | "Bk" | "Rs" => blocks.push((name, section.clone())),
+                "El" | "Ed" | "Ef" | "Ek" | "Re" => {
+                    let open = match name {
+                        "El" => "Bl",
+                        "Ed" => "Bd",
+                        "Ef" => "Bf",
+                        "Ek" => "Bk",
+                        _ => "Rs",
+                    };
+                    match blocks.iter().rposition(|(b, _)| *b == open) {
+                        Some(position) => {
+                            for (block, opened_in) in blocks.drain(position..).skip(1) {
+                                warnings.push_in(opened_in, format!("{block} is not closed"));
+                            }
+                        }
+                        None => warnings.push_in(&section, format!("{name} without {open}")),
+                    }
+                }
+                "It" if !blocks.iter().any(|(b, _)| *b == "Bl") => {
+                    warnings.push_in(&section, "It outside of a list")
+                }
+                "Xr" if args.len() < 2 => warnings.push_in(&section, "Xr without a section"),
+                _ => {}
+            }
+        }
+        for (block, opened_in) in blocks {
+            warnings.push_in(opened_in, format!("{block} is not closed"));
+        }
+    }
+}
+
+/// The page renders without losing content.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Render;
+
+impl LintRule for Render {
+    fn name(&self) -> &str {
+        "render"
+    }
+
+    fn check(&self, context: &Context, warnings: &mut Warnings) {
+        context
+            .page
+            .render_with_warnings(&RenderOptions::default(), warnings);
+    }
+}
+
+/// Check that the flags of the SYNOPSIS are the flags documented in the
+/// lists of the DESCRIPTION and OPTIONS sections.
+///
+/// Pages whose SYNOPSIS has no flags, such as those with `Op Ar options`,
+/// aren't checked. Flags grouped in the SYNOPSIS, as in `Op Fl alv`, are
+/// documented one by one.
+fn check_synopsis(lines: &[Line], sections: &[(String, Range<usize>)], warnings: &mut Warnings) {
+    let section = |title: &'static str| {
+        sections
+            .iter()
+            .filter(move |(t, _)| parser::unquote(t) == title)
+            .flat_map(|(_, range)| lines[range.clone()].iter())
+    };
+    let mut synopsis: Vec<String> = vec![];
+    for line in section("SYNOPSIS") {
+        synopsis.extend(flags(line));
+    }
+    if synopsis.is_empty() {
+        return;
+    }
+    let documented: Vec<(String, &str)> = documented_options(lines, sections)
+        .into_iter()
+        .flat_map(|(flags, title)| flags.into_iter().map(move |f| (f, title)))
+        .collect();
+    let is_documented = |flag: &str| documented.iter().any(|(f, _)| f == flag);
+    let mut reported: Vec<&str> = vec![];
+    for flag in &synopsis {
+        let grouped = flag.len() > 2
+            && !flag.starts_with("--")
+            && flag[1..].chars().all(|c| c.is_ascii_alphanumeric());
+        let missing: Vec<String> = if is_documented(flag) || !grouped {
+            vec![flag.clone()]
+        } else {
+            flag[1..].chars().map(|c| format!("-{c}")).collect()
+        };
+        for flag in missing {
+            if !is_documented(&flag) && !reported.contains(&flag.as_str()) {
+                warnings.push_in("SYNOPSIS", format!("{flag} is not documented"));
+            }
+        }
+        reported.push(flag);
+    }
+    for (flag, title) in &documented {
+        let in_synopsis = synopsis.iter().any(|f| {
+            f == flag
+                || (flag.len() == 2
+                    && !f.starts_with("--")
+                    && f.len() > 2
+                    && f[1..].contains(&flag[1..]))
+        });
+        if !in_synopsis {
+            warnings.push_in(*title, format!("{flag} is not in the SYNOPSIS"));
+        }
+    }
+}
+
+/// The flags of the items of the lists of the DESCRIPTION and OPTIONS
+/// sections, item by item, with the section they are documented in.
+pub(crate) fn documented_options(
+    lines: &[Line],
+    sections: &[(String, Range<usize>)],
+) -> Vec<(Vec<String>, &'static str)> {
+    let mut ret = vec![];
+    for title in ["DESCRIPTION", "OPTIONS"] {
+        for (_, range) in sections.iter().filter(|(t, _)| parser::unquote(t) == title) {
+            for line in &lines[range.clone()] {
+                if matches!(line, Line::Control { name, .. } if name == "It") {
+                    let flags = flags(line);
+                    if !flags.is_empty() {
+                        ret.push((flags, title));
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Check that the flags mentioned in the DESCRIPTION and OPTIONS sections,
+/// such as those of [`Mdoc::flag_reference`], are options of the page.
+///
+/// Pages without documented options aren't checked.
+fn check_flag_references(
+    lines: &[Line],
+    sections: &[(String, Range<usize>)],
+    warnings: &mut Warnings,
+) {
+    let mut known: Vec<String> = documented_options(lines, sections)
+        .into_iter()
+        .flat_map(|(flags, _)| flags)
+        .collect();
+    for (_, range) in sections.iter().filter(|(t, _)| t == "SYNOPSIS") {
+        known.extend(lines[range.clone()].iter().flat_map(flags));
+    }
+    if known.is_empty() {
+        return;
+    }
+    let is_known = |flag: &str| {
+        known.iter().any(|f| f == flag)
+            || flag.len() > 2
+                && !flag.starts_with("--")
+                && flag[1..].chars().all(|c| known.contains(&format!("-{c}")))
+    };
+    let mut reported: Vec<String> = vec![];
+    for (title, range) in sections {
+        let title = parser::unquote(title);
+        if title != "DESCRIPTION" && title != "OPTIONS" {
+            continue;
+        }
+        for line in &lines[range.clone()] {
+            let mentioned = match line {
+                Line::Control { name, .. } if name == "It" => continue,
+                Line::Control { .. } => flags(line),
+                _ => line
+                    .inlines()
+                    .unwrap_or_default()
+                    .iter()
+                    .flat_map(|inline| match inline {
+                        Inline::Flag { short, long, .. } => short
+                            .map(|c| format!("-{c}"))
+                            .into_iter()
+                            .chain(long.as_ref().map(|l| format!("--{l}")))
+                            .collect(),
+                        _ => vec![],
+                    })
+                    .collect(),
+            };
+            for flag in mentioned {
+                if !is_known(&flag) && !reported.contains(&flag) {
+                    warnings.push_in(&title, format!("{flag} is not an option of the page"));
+                    reported.push(flag);
+                }
+            }
+        }
+    }
+}
+
+/// The flags of the `Fl` macros of a control line, with their dash.
+pub(crate) fn flags(line: &Line) -> Vec<String> {
+    let Line::Control { name, args } = line else {
+        return vec![];
+    };
+    let args: Vec<String> = args
+        .iter()
+        .filter(|a| !a.starts_with("\\\n"))
+        .map(|a| parser::unquote(a))
+        .collect();
+    let tokens: Vec<&str> = std::iter::once(name.as_ref())
+        .chain(args.iter().map(String::as_str))
+        .collect();
+    let mut ret = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        if *token != "Fl" {
+            continue;
+        }
+        match tokens.get(i + 1) {
+            Some(next) if !phrase::is_callable(next) && !phrase::is_delimiter(next) => {
+                ret.push(format!("-{next}"));
+            }
+            // A lone `Fl` is a dash, as in `Fl` for the standard input.
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// A mechanical fix for a mistake [`lint`] reports.
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::lint::*;
+/// let mut page = parser::parse(".Sh NAME\nfoo \\- do things\n.Sh SEE ALSO\n.Xr passwd 5 ,\n.Xr ls 1\n");
+/// assert_eq!(apply_fixes(&mut page), [Fix::SplitName, Fix::SortSeeAlso]);
+/// assert_eq!(
+///     page.render(),
+///     ".Sh NAME\n.Nm foo\n.Nd do things\n.Sh SEE ALSO\n.Xr ls 1 ,\n.Xr passwd 5\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fix {
+    /// Turn a `name \- description` line of the NAME section into `Nm` and
+    /// `Nd` macros.
+    SplitName,
+    /// Write typographic characters, such as curly quotes and dashes, as
+    /// escapes and drop control characters.
+    EscapeCharacters,
+    /// Move the sections into the order of [`SECTION_ORDER`]. Sections
+    /// with other titles move with the section they follow.
+    ReorderSections,
+    /// Sort the cross references of the SEE ALSO section by section and
+    /// then by name.
+    SortSeeAlso,
+}
+
+impl Fix {
+    /// Every fix, in the order [`apply_fixes`] applies them.
+    pub const ALL: [Self; 4] = [
+        Self::SplitName,
+        Self::EscapeCharacters,
+        Self::ReorderSections,
+        Self::SortSeeAlso,
+    ];
+
+    /// Apply the fix to `page`, returning whether it changed it.
+    pub fn apply(self, page: &mut Mdoc) -> bool {
+        match self {
+            Self::SplitName => split_name(page),
+            Self::EscapeCharacters => escape_characters(page),
+            Self::ReorderSections => reorder_sections(page),
+            Self::SortSeeAlso => sort_see_also(page),
+        }
+    }
+
+    /// Whether the fix would change `page`.
+    pub fn applies(self, page: &Mdoc) -> bool {
+        self.apply(&mut page.clone())
+    }
+}
+
+impl std::fmt::Display for Fix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::SplitName => "split the NAME line into Nm and Nd",
+            Self::EscapeCharacters => "escape raw characters",
+            Self::ReorderSections => "reorder the sections",
+            Self::SortSeeAlso => "sort the SEE ALSO references",
+        })
+    }
+}
+
+/// The fixes that would change `page`.
+pub fn fixes(page: &Mdoc) -> Vec<Fix> {
+    Fix::ALL.into_iter().filter(|f| f.applies(page)).collect()
+}
+
+/// Apply every fix to `page`, returning those that changed it.
+pub fn apply_fixes(page: &mut Mdoc) -> Vec<Fix> {
+    Fix::ALL.into_iter().filter(|f| f.apply(page)).collect()
+}
+
+/// Apply every fix to the **mdoc** page at `path`, rewriting it if any of
+/// them changed it, and return those that did.
+///
+/// Gzipped pages, man(7) pages and `.so` stubs are left as they are.
+pub fn fix_file(path: &Path) -> std::io::Result<Vec<Fix>> {
+    let Some(mut page) = read_fixable(path)? else {
+        return Ok(vec![]);
+    };
+    let fixes = apply_fixes(&mut page);
+    if !fixes.is_empty() {
+        std::fs::write(path, page.render())?;
+    }
+    Ok(fixes)
+}
+
+/// Read the **mdoc** page at `path` so that it can be fixed and written back
+/// with [`Mdoc::render`], keeping the layout of its continued lines.
+///
+/// Gzipped pages, man(7) pages and `.so` stubs are not read, and `None` is
+/// returned instead.
+pub fn read_fixable(path: &Path) -> std::io::Result<Option<Mdoc>> {
+    let bytes = std::fs::read(path)?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(None);
+    }
+    let (source, _) = parser::decode(&bytes, None);
+    let input = import::Input::new(path, &source);
+    let first = input.first_line();
+    if first.starts_with(".TH") || first.starts_with(".so ") {
+        return Ok(None);
+    }
+    Ok(Some(parser::parse_with(
+        &source,
+        &parser::ParseOptions { lossless: true },
+    )))
+}
+
+/// The range of the lines of the section titled `title`.
+fn section(page: &Mdoc, title: &str) -> Option<Range<usize>> {
+    Mdoc::sections_of(&page.lines)
+        .into_iter()
+        .find(|(t, _)| parser::unquote(t).eq_ignore_ascii_case(title))
+        .map(|(_, range)| range)
+}
+
+fn split_name(page: &mut Mdoc) -> bool {
+    let Some(range) = section(page, "NAME") else {
+        return false;
+    };
+    let lines = &page.lines[range.clone()];
+    if lines
+        .iter()
+        .any(|l| matches!(l, Line::Control { name, .. } if name == "Nd"))
+    {
+        return false;
+    }
+    for i in range.skip(1) {
+        let split: Option<(Vec<String>, Vec<Str>)> = match &page.lines[i] {
+            // `foo, bar \- description`
+            Line::Text(inlines) => {
+                let text: Option<String> = inlines
+                    .iter()
+                    .map(|i| match i {
+                        Inline::Roman(text) | Inline::Roff(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                text.as_deref()
+                    .and_then(|t| t.split_once(" \\- ").or_else(|| t.split_once(" - ")))
+                    .map(|(names, description)| {
+                        (
+                            names.split(',').map(|n| n.trim().to_string()).collect(),
+                            description
+                                .split_whitespace()
+                                .map(|w| w.to_string().into())
+                                .collect(),
+                        )
+                    })
+            }
+            // `.Nm foo \- description`
+            Line::Control { name, args } if name == "Nm" => args
+                .iter()
+                .position(|a| a == "\\-" || a == "-")
+                .map(|dash| {
+                    (
+                        args[..dash]
+                            .iter()
+                            .map(|a| a.trim_end_matches(',').to_string())
+                            .collect(),
+                        args[dash + 1..].to_vec(),
+                    )
+                }),
+            _ => None,
+        };
+        let Some((names, description)) = split else {
+            continue;
+        };
+        let names: Vec<String> = names.into_iter().filter(|n| !n.is_empty()).collect();
+        if names.is_empty() || description.is_empty() {
+            continue;
+        }
+        let mut lines = vec![];
+        for (j, name) in names.iter().enumerate() {
+            let mut args: Vec<Str> = vec![name.clone().into()];
+            if j + 1 < names.len() {
+                args.push(",".into());
+            }
+            lines.push(Line::control("Nm".into(), args));
+        }
+        lines.push(Line::control("Nd".into(), description));
+        page.lines.splice(i..=i, lines);
+        return true;
+    }
+    false
+}
+
+/// The escape of a character that should not be written as it is, or
+/// `Some("")` if it should be dropped.
+fn escape(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{2018}' => "\\(oq",
+        '\u{2019}' => "\\(cq",
+        '\u{201c}' => "\\(lq",
+        '\u{201d}' => "\\(rq",
+        '\u{2014}' => "\\(em",
+        '\u{2013}' => "\\(en",
+        '\u{2022}' => "\\(bu",
+        '\u{a9}' => "\\(co",
+        '\u{a0}' => "\\~",
+        '\t' => return None,
+        c if c.is_control() => "",
+        _ => return None,
+    })
+}
+
+/// `text` with its raw characters escaped, if it has any.
+fn escaped(text: &str) -> Option<String> {
+    if !text.chars().any(|c| escape(c).is_some()) {
+        return None;
+    }
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        match escape(c) {
+            Some(escape) => ret.push_str(escape),
+            None => ret.push(c),
+        }
+    }
+    Some(ret)
+}
+
+fn escape_characters(page: &mut Mdoc) -> bool {
+    let mut changed = false;
+    for line in &mut page.lines {
+        match line {
+            Line::Text(inlines) => {
+                for inline in inlines {
+                    if let Inline::Roman(text)
+                    | Inline::Roff(text)
+                    | Inline::Bold(text)
+                    | Inline::Italic(text) = inline
+                    {
+                        if let Some(escaped) = escaped(text) {
+                            *text = escaped;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            Line::Control { name, args } if name != "\\\"" => {
+                for arg in args {
+                    if let Some(escaped) = escaped(arg) {
+                        *arg = escaped.into();
+                        changed = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+fn reorder_sections(page: &mut Mdoc) -> bool {
+    if page.is_overview() {
+        return false;
+    }
+    let sections = Mdoc::sections_of(&page.lines);
+    let Some(start) = sections.first().map(|(_, range)| range.start) else {
+        return false;
+    };
+    let mut previous = 0;
+    let mut ranked: Vec<(usize, Range<usize>)> = sections
+        .into_iter()
+        .map(|(title, range)| {
+            let title = parser::unquote(&title);
+            if let Some(rank) = SECTION_ORDER
+                .iter()
+                .position(|t| t.eq_ignore_ascii_case(&title))
+            {
+                previous = rank;
+            }
+            (previous, range)
+        })
+        .collect();
+    if ranked.is_sorted_by_key(|(rank, _)| *rank) {
+        return false;
+    }
+    ranked.sort_by_key(|(rank, _)| *rank);
+    let mut lines = page.lines[..start].to_vec();
+    for (_, range) in ranked {
+        lines.extend_from_slice(&page.lines[range]);
+    }
+    page.lines = lines;
+    true
+}
+
+fn sort_see_also(page: &mut Mdoc) -> bool {
+    let Some(range) = section(page, "SEE ALSO") else {
+        return false;
+    };
+    let is_reference = |line: &Line| matches!(line, Line::Control { name, .. } if name == "Xr");
+    let mut changed = false;
+    let mut i = range.start + 1;
+    while i < range.end {
+        let end = (i..range.end)
+            .find(|&j| !is_reference(&page.lines[j]))
+            .unwrap_or(range.end);
+        changed |= sort_references(&mut page.lines[i..end]);
+        i = end + 1;
+    }
+    changed
+}
+
+/// Sort a run of `Xr` lines, each but the last ending with a comma.
+fn sort_references(lines: &mut [Line]) -> bool {
+    let mut references = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let Line::Control { args, .. } = line else {
+            return false;
+        };
+        let [name, section, rest @ ..] = args.as_slice() else {
+            return false;
+        };
+        if i + 1 < lines.len() && rest != [","] {
+            return false;
+        }
+        references.push((name.clone(), section.clone()));
+    }
+    let Some(Line::Control { args, .. }) = lines.last() else {
+        return false;
+    };
+    let last = args[2..].to_vec();
+    let key = |(name, section): &(Str, Str)| xr::order_key(name, section);
+    if references.is_sorted_by_key(key) {
+        return false;
+    }
+    references.sort_by_key(key);
+    let count = references.len();
+    for (i, (line, (name, section))) in lines.iter_mut().zip(references).enumerate() {
+        let mut args = vec![name, section];
+        if i + 1 < count {
+            args.push(",".into());
+        } else {
+            args.extend(last.iter().cloned());
+        }
+        *line = Line::control("Xr".into(), args);
+    }
+    true
+}
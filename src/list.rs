@@ -0,0 +1,205 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use super::*;
+
+/// The type of a list, see [`ListBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListKind {
+    /// Items with a tag, such as a flag, followed by a description.
+    Tag,
+    Bullet,
+    /// Numbered items.
+    Enum,
+    Dash,
+    /// A table with columns as wide as the given strings.
+    Column(Vec<String>),
+}
+
+/// A `Bl` list, which is always closed with `El`.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.list(
+///     ListBuilder::new(ListKind::Tag)
+///         .width("Fl v")
+///         .text_item(["Fl", "v"], [roman("Be verbose.")])
+///         .item(
+///             ["Fl", "q"],
+///             ListBuilder::new(ListKind::Bullet)
+///                 .compact()
+///                 .entry([roman("Be quiet.")])
+///                 .lines(),
+///         ),
+/// );
+/// assert_eq!(
+///     doc.to_mdoc(),
+///     ".Bl -tag -width \"Fl v\"\n.It Fl v\nBe verbose.\n.It Fl q\n.Bl -bullet -compact\n.It\nBe quiet.\n.El\n.El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListBuilder {
+    kind: ListKind,
+    width: Option<String>,
+    offset: Option<String>,
+    compact: bool,
+    items: Vec<(Vec<Str>, Vec<Line>)>,
+}
+
+impl ListBuilder {
+    pub fn new(kind: ListKind) -> Self {
+        Self {
+            kind,
+            width: None,
+            offset: None,
+            compact: false,
+            items: vec![],
+        }
+    }
+
+    /// Set the indentation of the item bodies, as a string whose width is
+    /// used, such as `Fl verbose`, or a scaled width such as `8n`.
+    ///
+    /// Tag lists default to `Ds`, about six columns.
+    pub fn width(self, width: impl Into<String>) -> Self {
+        Self {
+            width: Some(width.into()),
+            ..self
+        }
+    }
+
+    /// Indent the list, e.g. by `indent`.
+    pub fn offset(self, offset: impl Into<String>) -> Self {
+        Self {
+            offset: Some(offset.into()),
+            ..self
+        }
+    }
+
+    /// Don't put blank lines between items.
+    pub fn compact(self) -> Self {
+        Self {
+            compact: true,
+            ..self
+        }
+    }
+
+    /// Add an item with the `It` arguments `head`, which may contain
+    /// macros, followed by the `body` lines.
+    pub fn item<S: Into<Str>>(
+        mut self,
+        head: impl IntoIterator<Item = S>,
+        body: impl Into<Vec<Line>>,
+    ) -> Self {
+        self.items
+            .push((head.into_iter().map(Into::into).collect(), body.into()));
+        self
+    }
+
+    /// Add an item whose body is a single text line.
+    pub fn text_item<S: Into<Str>>(
+        self,
+        head: impl IntoIterator<Item = S>,
+        text: impl Into<Vec<Inline>>,
+    ) -> Self {
+        self.item(head, vec![Line::text(text.into())])
+    }
+
+    /// Add an item without a head, for bullet, dash and enumerated lists.
+    pub fn entry(self, text: impl Into<Vec<Inline>>) -> Self {
+        self.text_item(Vec::<Str>::new(), text)
+    }
+
+    /// Add a row to a column list, one cell per string.
+    pub fn row<'a>(self, cells: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut head: Vec<Str> = vec![];
+        for (i, cell) in cells.into_iter().enumerate() {
+            if i > 0 {
+                head.push("Ta".into());
+            }
+            head.extend(cell.split_whitespace().map(|w| Str::from(w.to_string())));
+        }
+        self.item(head, vec![])
+    }
+
+    /// The lines of the list, see [`ListBuilder::lines`], if they are
+    /// within `limits`.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// # use mdoc::parser::{LimitError, Limits};
+    /// let mut list = ListBuilder::new(ListKind::Bullet).entry([roman("innermost")]);
+    /// for _ in 0..3 {
+    ///     list = ListBuilder::new(ListKind::Bullet).item(Vec::<String>::new(), list.lines());
+    /// }
+    /// let limits = Limits {
+    ///     max_depth: 3,
+    ///     ..Limits::default()
+    /// };
+    /// assert_eq!(list.try_lines(&limits), Err(LimitError::TooDeep { line: 7, max: 3 }));
+    /// ```
+    pub fn try_lines(&self, limits: &parser::Limits) -> Result<Vec<Line>, parser::LimitError> {
+        let lines = self.lines();
+        limits.check(&lines)?;
+        Ok(lines)
+    }
+
+    /// The `Bl`, `It` and `El` lines of the list.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut args: Vec<Str> = vec![match self.kind {
+            ListKind::Tag => "-tag".into(),
+            ListKind::Bullet => "-bullet".into(),
+            ListKind::Enum => "-enum".into(),
+            ListKind::Dash => "-dash".into(),
+            ListKind::Column(_) => "-column".into(),
+        }];
+        let width = match (&self.kind, &self.width) {
+            (_, Some(width)) => Some(width.as_str()),
+            (ListKind::Tag, None) => Some("Ds"),
+            _ => None,
+        };
+        if let Some(width) = width {
+            args.push("-width".into());
+            args.push(width.to_string().into());
+        }
+        if let Some(offset) = &self.offset {
+            args.push("-offset".into());
+            args.push(offset.clone().into());
+        }
+        if self.compact {
+            args.push("-compact".into());
+        }
+        if let ListKind::Column(columns) = &self.kind {
+            args.extend(columns.iter().map(|c| c.clone().into()));
+        }
+        let mut lines = vec![Line::control("Bl".into(), args)];
+        for (head, body) in &self.items {
+            lines.push(Line::control("It".into(), head.clone()));
+            lines.extend(body.iter().cloned());
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+}
@@ -39,3 +39,22 @@ vec![
 
     }};
 }
+
+/// A page for the crate being compiled, from the Cargo metadata of its
+/// package, see [`Mdoc::from_cargo_metadata`](crate::Mdoc::from_cargo_metadata).
+///
+/// ```
+/// let page = mdoc::from_cargo_env!();
+/// assert_eq!(page.name().0, "mdoc");
+/// ```
+#[macro_export]
+macro_rules! from_cargo_env {
+    () => {
+        $crate::Mdoc::from_cargo_metadata(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_DESCRIPTION"),
+            env!("CARGO_PKG_AUTHORS"),
+        )
+    };
+}
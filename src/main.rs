@@ -20,31 +20,619 @@
 //
 // SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
 
+use std::{
+    io::{IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, SystemTime},
+};
+
 use mdoc::*;
 
-fn main() {
-    let mut m = Mdoc::new(
-        None,
-        DocumentTitle {
-            title: title! {"test"},
-            section: section! { "1" },
-            arch: None,
-        },
-        name! { "name" },
-        description! { "This is a description in one line." },
-        None,
-    );
-    m.add_section(
-        "synopsis",
-        std::iter::once(Line::Text(vec![roman(
-            "The mandoc utility formats manual pages for display.",
-        )])),
-    );
-
-    // let lines = mdoc::mdoc! {
-    //     { roman("mplasfdsafasm df asdf amf asmfasdm fmas mms fasd") } ; Line::NAME.clone() ; roman("mfasdfa sdfa."),
-    //     roman("lorem ipsum")
-    // };
-    // m.lines.extend(lines.into_iter());
-    println!("{}", m.render());
+const USAGE: &str =
+    "usage: mdoc convert [--from FORMAT] [--to FORMAT] [--git-date] INPUT [-o OUTPUT [--force]]
+       mdoc watch [--from FORMAT] [--to FORMAT] [--git-date] INPUT [-o OUTPUT [--force]]
+       mdoc check [--from FORMAT] INPUT PAGE [INPUT PAGE ...]
+       mdoc extract --section TITLE [--from FORMAT] [--to FORMAT] PAGE [-o OUTPUT]
+       mdoc merge PAGE OVERRIDES [-o OUTPUT [--force]]
+       mdoc lint [--policy FILE] [--fix] PATH [PATH ...]
+       mdoc review [--policy FILE] PATH [PATH ...]
+
+INPUT, PAGE and OUTPUT can be `-` for the standard input and output.
+
+Input formats: mdoc, man, markdown, json, script (shell script header),
+systemd (unit file), annotations (source file with `man:` comment blocks),
+formatted (output of man(1) with overstrike, for recovering lost sources).
+The format is detected from the file name and contents unless given with
+--from.
+
+Output formats: mdoc, man, html, text, markdown (in the dialect of ronn(1)),
+pdf (with groff(1)) and json (the syntax tree of the page, for web
+frontends). The format is chosen from the extension of OUTPUT, such as .1 or
+any other section for mdoc, .html, .txt, .md, .pdf and .json, unless given
+with --to, and is mdoc otherwise. Outputs ending with .gz, such as
+foo.1.gz, are compressed with gzip(1).
+
+--git-date, available with the `git` feature, dates pages by the last
+commit of INPUT, so that regenerated pages only change when it does.
+Pages without a date are dated by SOURCE_DATE_EPOCH when it is set.
+
+--strictness lenient|warn|deny sets whether questionable input is converted
+silently, converted with a warning (the default) or rejected.
+
+lint checks pages, or the pages of directories such as /usr/share/man,
+and fails if it finds mistakes or, with --policy, breaks of the rules of a
+TOML policy file. With --fix, the mistakes with mechanical fixes, such as
+sections out of order, are corrected in place first.
+
+review, available with the `tui` feature, shows the lint findings of each
+page next to a preview of it and asks whether to apply each fix in turn.
+
+Written pages are stamped with their provenance. An existing OUTPUT without
+a stamp, or edited since it was generated, is only overwritten with --force.";
+
+type Error = Box<dyn std::error::Error>;
+
+#[derive(Debug, Default)]
+struct Args {
+    command: String,
+    from: Option<String>,
+    paths: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    section: Option<String>,
+    strictness: Strictness,
+    to: Option<String>,
+    force: bool,
+    policy: Option<PathBuf>,
+    fix: bool,
+    git_date: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Error> {
+        let mut ret = Self {
+            command: args.next().ok_or(USAGE)?,
+            ..Self::default()
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--from" => ret.from = Some(args.next().ok_or(USAGE)?),
+                "-o" | "--output" => ret.output = Some(args.next().ok_or(USAGE)?.into()),
+                "--section" => ret.section = Some(args.next().ok_or(USAGE)?),
+                "--strictness" => ret.strictness = args.next().ok_or(USAGE)?.parse()?,
+                "--to" => ret.to = Some(args.next().ok_or(USAGE)?),
+                "-f" | "--force" => ret.force = true,
+                "--policy" => ret.policy = Some(args.next().ok_or(USAGE)?.into()),
+                "--fix" => ret.fix = true,
+                "--git-date" => ret.git_date = true,
+                "-h" | "--help" => return Err(USAGE.into()),
+                _ => ret.paths.push(arg.into()),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// The single input path of commands that take one.
+    fn input(&self) -> Result<&Path, Error> {
+        match self.paths.as_slice() {
+            [input] => Ok(input),
+            _ => Err(USAGE.into()),
+        }
+    }
+}
+
+/// Convert `input` to a manual page.
+fn convert(input: &Path, args: &Args) -> Result<Mdoc, Error> {
+    let (source, encoding) = read_input(input)?;
+    let mut warnings = Warnings::with_strictness(args.strictness);
+    let registry = import::Registry::default();
+    let input = import::Input {
+        path: input,
+        source: &source,
+        encoding,
+    };
+    let importer = match &args.from {
+        Some(name) => registry
+            .get(name)
+            .ok_or_else(|| format!("unsupported input format `{name}`"))?,
+        None => registry.detect(&input).ok_or_else(|| {
+            format!(
+                "could not detect the format of {}, use --from",
+                input.path.display()
+            )
+        })?,
+    };
+    let mut page = importer.import(&input, &mut warnings)?;
+    warnings.check()?;
+    if args.git_date && input.path != Path::new("-") {
+        date_from_git(&mut page, input.path)?;
+    }
+    for warning in &warnings {
+        eprintln!("{}: warning: {warning}", input.path.display());
+    }
+    Ok(page)
+}
+
+/// Date `page` by the last commit of its source `path`.
+#[cfg(feature = "git")]
+fn date_from_git(page: &mut Mdoc, path: &Path) -> Result<(), Error> {
+    if let Some(date) = git::last_commit_date(path)? {
+        page.set_date(Some(date));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+fn date_from_git(_: &mut Mdoc, _: &Path) -> Result<(), Error> {
+    Err("--git-date requires the `git` feature".into())
+}
+
+/// Read and decode a file, or the standard input if `path` is `-`.
+fn read_input(path: &Path) -> std::io::Result<(String, parser::Encoding)> {
+    let bytes = if path == Path::new("-") {
+        let mut bytes = vec![];
+        std::io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        std::fs::read(path)?
+    };
+    Ok(parser::decode(&bytes, None))
+}
+
+/// Write to a file, or to the standard output if `output` is `-` or unset.
+fn write_bytes(output: Option<&Path>, bytes: &[u8]) -> std::io::Result<()> {
+    match output {
+        Some(path) if path != Path::new("-") => std::fs::write(path, bytes),
+        _ => std::io::stdout().write_all(bytes),
+    }
+}
+
+/// The output formats of pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Mdoc,
+    Man,
+    Html,
+    Text,
+    Markdown,
+    Pdf,
+    Json,
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Self, Error> {
+        match name {
+            "mdoc" => Ok(Self::Mdoc),
+            "man" => Ok(Self::Man),
+            "html" => Ok(Self::Html),
+            "text" | "txt" => Ok(Self::Text),
+            "pdf" => Ok(Self::Pdf),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!("unsupported output format `{other}`").into()),
+        }
+    }
+
+    /// The format of the output file `path` from its extension, ignoring a
+    /// `.gz` one: a section such as `1` or `3p` for **mdoc**.
+    fn detect(path: &Path) -> Result<Option<Self>, Error> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let name = name.strip_suffix(".gz").unwrap_or(name);
+        match name.rsplit_once('.') {
+            Some((_, extension)) if extension.starts_with(|c: char| c.is_ascii_digit()) => {
+                Ok(Some(Self::Mdoc))
+            }
+            Some((_, "htm")) => Ok(Some(Self::Html)),
+            Some((_, extension @ ("html" | "txt" | "pdf" | "json" | "md" | "markdown"))) => {
+                Self::parse(extension).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The format given with `--to` or detected from the output file, see
+/// [`Format`].
+fn output_format(args: &Args, output: Option<&Path>) -> Result<Format, Error> {
+    match (&args.to, output) {
+        (Some(to), _) => Format::parse(to),
+        (None, Some(path)) => Ok(Format::detect(path)?.unwrap_or(Format::Mdoc)),
+        (None, None) => Ok(Format::Mdoc),
+    }
+}
+
+/// The options pages generated from `source` are rendered with: dated by
+/// SOURCE_DATE_EPOCH if undated and, if `stamped`, stamped with their
+/// provenance.
+fn render_options(source: Option<&Path>, stamped: bool) -> RenderOptions {
+    let source = source
+        .filter(|path| *path != Path::new("-"))
+        .map(|path| path.display().to_string());
+    RenderOptions {
+        provenance: stamped.then(|| provenance::Provenance::new(source)),
+        default_date: DocumentDate::from_source_date_epoch(),
+        ..RenderOptions::default()
+    }
+}
+
+/// Render a page in `format`.
+fn render(page: &Mdoc, format: Format, options: RenderOptions) -> Result<Vec<u8>, Error> {
+    Ok(match format {
+        Format::Mdoc => parser::encode(&page.render_with(&options), page.encoding()),
+        Format::Man => parser::encode(
+            &man::ManRenderer::new(options).render(page),
+            page.encoding(),
+        ),
+        Format::Html => html::HtmlRenderer::new(options).render(page).into_bytes(),
+        Format::Text => text::TextRenderer::new(options).render(page).into_bytes(),
+        #[cfg(feature = "markdown")]
+        Format::Markdown => markdown::MarkdownRenderer::new(options)
+            .render(page)
+            .into_bytes(),
+        #[cfg(not(feature = "markdown"))]
+        Format::Markdown => {
+            return Err("conversion to markdown requires the `markdown` feature".into())
+        }
+        Format::Pdf => {
+            let mut buf = vec![];
+            groff::to_pdf(page, &mut buf).map_err(|err| format!("groff: {err}"))?;
+            buf
+        }
+        Format::Json => page.render_json().into_bytes(),
+    })
+}
+
+/// Write a page in the format given with `--to` or detected from the
+/// output file, see [`Format`].
+///
+/// **mdoc** and man(7) pages written to a file are stamped with their
+/// provenance, and an output file that was written or edited by hand is not
+/// overwritten unless `--force` is given.
+fn write_output(page: &Mdoc, args: &Args) -> Result<(), Error> {
+    let output = args
+        .output
+        .as_deref()
+        .filter(|path| *path != Path::new("-"));
+    let format = output_format(args, output)?;
+    if let Some(path) = output.filter(|_| matches!(format, Format::Mdoc | Format::Man)) {
+        if !args.force {
+            provenance::check_overwrite(path)?;
+        }
+    }
+    let options = render_options(args.paths.first().map(PathBuf::as_path), output.is_some());
+    let mut bytes = render(page, format, options)?;
+    if output.is_some_and(|path| path.extension().is_some_and(|e| e == "gz")) {
+        bytes = gzip(&bytes)?;
+    }
+    Ok(write_bytes(output, &bytes)?)
+}
+
+/// Re-convert `input` whenever it changes.
+///
+/// Without an output file, the terminal is cleared and the page is printed
+/// again on every change, as text unless `--to` is given; with one, the
+/// file is rewritten so that a viewer watching it can reload.
+fn watch(args: &Args) -> Result<(), Error> {
+    let input = args.input()?;
+    if input == Path::new("-") {
+        return Err("cannot watch the standard input".into());
+    }
+    let preview = args
+        .output
+        .as_deref()
+        .is_none_or(|path| path == Path::new("-"));
+    let mut last_modified: Option<SystemTime> = None;
+    loop {
+        let modified = match std::fs::metadata(input) {
+            Ok(metadata) => Some(metadata.modified()?),
+            // Editors that save by renaming a new file over the old one
+            // leave it missing for a moment.
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        if modified.is_some() && last_modified != modified {
+            last_modified = modified;
+            if preview {
+                print!("\x1b[2J\x1b[H");
+            }
+            match convert(input, args) {
+                Ok(page) if preview && args.to.is_none() => {
+                    let preview = text::TextRenderer::new(render_options(Some(input), false))
+                        .fill(term::FillOptions::from_env())
+                        .charset(term::Charset::from_env())
+                        .color(term::ColorChoice::Auto);
+                    print!("{}", preview.render(&page));
+                }
+                Ok(page) => write_output(&page, args)?,
+                Err(err) => eprintln!("mdoc: {}: {err}", input.display()),
+            }
+            std::io::stdout().flush()?;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// A line diff of `old` and `new`, with removed lines prefixed by `-`,
+/// added lines by `+` and unchanged lines by a space.
+fn diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    // lcs[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut ret = String::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ret.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ret.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            ret.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    ret
+}
+
+/// Regenerate each page from its input and report pages that differ from
+/// the committed ones.
+fn check(args: &Args) -> Result<(), Error> {
+    let pairs = &args.paths;
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return Err(USAGE.into());
+    }
+    let mut outdated = 0;
+    for pair in pairs.chunks(2) {
+        let (input, page) = (&pair[0], &pair[1]);
+        let generated = convert(input, args)?.render_with(&render_options(Some(input), false));
+        // Installed pages may be gzipped.
+        let committed = read_source(page).unwrap_or_default();
+        // Compare without the provenance stamp of written pages, which names
+        // the input as it was spelled then.
+        let committed = match provenance::Stamp::read(&committed) {
+            Some(stamp) => stamp.content().to_string(),
+            None => committed,
+        };
+        if generated != committed {
+            outdated += 1;
+            println!("--- {}", page.display());
+            println!("+++ {}", input.display());
+            print!("{}", diff(&committed, &generated));
+        }
+    }
+    if outdated > 0 {
+        return Err(format!("{outdated} page(s) are out of date").into());
+    }
+    Ok(())
+}
+
+/// Print a section of an existing page, read as by `convert`, in the
+/// format given with `--to` or detected from the output file.
+fn extract(args: &Args) -> Result<(), Error> {
+    let title = args.section.as_deref().ok_or(USAGE)?;
+    let path = args.input()?;
+    let page = convert(path, args)?;
+    let (_, range) = page
+        .sections()
+        .into_iter()
+        .find(|(t, _)| t.eq_ignore_ascii_case(title))
+        .ok_or_else(|| format!("{}: no section titled {title}", path.display()))?;
+    let mut section = Mdoc::default();
+    section.set_encoding(page.encoding());
+    section.lines = page.lines[range].to_vec();
+    let output = args
+        .output
+        .as_deref()
+        .filter(|path| *path != Path::new("-"));
+    let format = output_format(args, output)?;
+    let mut bytes = render(&section, format, render_options(Some(path), false))?;
+    if format == Format::Text {
+        // Drop the header and footer lines, which are blank without a title.
+        let text = String::from_utf8(bytes)?;
+        bytes = format!("{}\n", text.trim_matches(|c| c == ' ' || c == '\n')).into_bytes();
+    }
+    Ok(write_bytes(output, &bytes)?)
+}
+
+/// Read an **mdoc** page as a sequence of control and text lines.
+fn read_page(path: &Path) -> Result<Mdoc, Error> {
+    let (source, encoding) = read_input(path)?;
+    let mut page = parser::parse(&source);
+    page.set_encoding(encoding);
+    Ok(page)
+}
+
+/// Replace or add the sections of a page with those of an overrides file.
+fn merge(args: &Args) -> Result<(), Error> {
+    let [page, overrides] = args.paths.as_slice() else {
+        return Err(USAGE.into());
+    };
+    let mut page = read_page(page)?;
+    page.merge(read_page(overrides)?);
+    write_output(&page, args)
+}
+
+/// Check pages and directories of pages.
+fn lint(args: &Args) -> Result<(), Error> {
+    if args.paths.is_empty() {
+        return Err(USAGE.into());
+    }
+    let options = lint::LintOptions {
+        policy: args.policy.as_deref().map(lint::Policy::load).transpose()?,
+        ..lint::LintOptions::default()
+    };
+    let mut diagnostics = 0;
+    for path in &args.paths {
+        if args.fix {
+            let report = lint::lint_tree_with_progress(path, &options, show_progress);
+            for file in report.files.iter().filter(|f| !f.diagnostics.is_empty()) {
+                for fix in lint::fix_file(&file.path)? {
+                    println!("{}: fixed: {fix}", file.path.display());
+                }
+            }
+        }
+        let report = lint::lint_tree_with_progress(path, &options, show_progress);
+        print!("{report}");
+        diagnostics += report.len();
+    }
+    if diagnostics > 0 {
+        return Err(format!("{diagnostics} problem(s) found").into());
+    }
+    Ok(())
+}
+
+/// Show the progress of a batch operation on the status line of the
+/// terminal, if the standard error is one.
+fn show_progress(progress: &Progress) {
+    let mut stderr = std::io::stderr();
+    if !stderr.is_terminal() {
+        return;
+    }
+    if progress.is_last() {
+        let _ = write!(stderr, "\r\x1b[K");
+    } else {
+        let _ = write!(stderr, "\r\x1b[K{progress}");
+    }
+    let _ = stderr.flush();
+}
+
+/// Go through the fixes of the pages of `args` one by one.
+#[cfg(feature = "tui")]
+fn review(args: &Args) -> Result<(), Error> {
+    if args.paths.is_empty() {
+        return Err(USAGE.into());
+    }
+    let options = lint::LintOptions {
+        policy: args.policy.as_deref().map(lint::Policy::load).transpose()?,
+        ..lint::LintOptions::default()
+    };
+    for path in &args.paths {
+        let report = lint::lint_tree(path, &options);
+        for file in report.files.iter().filter(|f| !f.diagnostics.is_empty()) {
+            if !review_page(&file.path, &options)? {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ask whether to apply each fix of the page at `path`, writing it back if
+/// any was accepted. Returns whether to go on with the next page.
+#[cfg(feature = "tui")]
+fn review_page(path: &Path, options: &lint::LintOptions) -> Result<bool, Error> {
+    let Some(mut page) = lint::read_fixable(path)? else {
+        return Ok(true);
+    };
+    let columns = term::terminal_columns().unwrap_or(160);
+    let rows: usize = std::env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(24);
+    let preview_width = (columns / 2).saturating_sub(2);
+    let findings_width = columns.saturating_sub(preview_width + 3);
+    let mut declined = vec![];
+    let mut applied = 0;
+    let mut next = true;
+    while let Some(fix) = lint::fixes(&page)
+        .into_iter()
+        .find(|f| !declined.contains(f))
+    {
+        let mut fixed = page.clone();
+        fix.apply(&mut fixed);
+        let source = page.render();
+        let mut warnings = Warnings::new();
+        lint::lint(&page, &mut warnings);
+        if let Some(policy) = &options.policy {
+            policy.check(&source, &page, &mut warnings);
+        }
+        let mut findings = vec![path.display().to_string(), String::new()];
+        findings.extend(warnings.iter().map(|w| format!("* {w}")));
+        findings.push(String::new());
+        findings.push(format!("Fix: {fix}"));
+        findings.extend(
+            diff(&source, &fixed.render())
+                .lines()
+                .filter(|l| !l.starts_with(' '))
+                .map(str::to_string),
+        );
+        let preview = text::TextRenderer::default()
+            .charset(term::Charset::from_env())
+            .width(preview_width)
+            .render(&fixed);
+        print!("\x1b[2J\x1b[H");
+        for i in 0..rows.saturating_sub(2) {
+            let left: String = findings
+                .get(i)
+                .map(|l| l.chars().take(findings_width).collect())
+                .unwrap_or_default();
+            let right = preview.lines().nth(i).unwrap_or_default();
+            println!("{left:<findings_width$} | {right}");
+        }
+        print!("Apply the fix? [y]es, [n]o, [s]kip the page, [q]uit: ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            answer = "q".into();
+        }
+        match answer.trim() {
+            "y" | "yes" => {
+                page = fixed;
+                applied += 1;
+            }
+            "n" | "no" => declined.push(fix),
+            "s" | "skip" => break,
+            "q" | "quit" => {
+                next = false;
+                break;
+            }
+            _ => {}
+        }
+    }
+    if applied > 0 {
+        std::fs::write(path, page.render())?;
+        println!("{}: applied {applied} fix(es)", path.display());
+    }
+    Ok(next)
+}
+
+fn run() -> Result<(), Error> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    match args.command.as_str() {
+        "convert" => write_output(&convert(args.input()?, &args)?, &args),
+        "watch" => watch(&args),
+        "check" => check(&args),
+        "extract" => extract(&args),
+        "merge" => merge(&args),
+        "lint" => lint(&args),
+        #[cfg(feature = "tui")]
+        "review" => review(&args),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("mdoc: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,600 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Render to legacy **man**(7) macros.
+//!
+//! Some systems ship a roff without working **mdoc** support. For them,
+//! a [`ManRenderer`] lowers the document model to the older `TH`, `SH`,
+//! `TP` and `IP` macros, with fonts selected by escapes: semantic markup
+//! such as `Fl` and `Ar` becomes bold and italic text, lists become tagged
+//! and indented paragraphs, and display blocks become no-fill regions.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! let page = Mdoc::new(
+//!     None,
+//!     DocumentTitle {
+//!         title: title!("FOO"),
+//!         section: section!("1"),
+//!         arch: None,
+//!     },
+//!     name!("foo"),
+//!     description!("frobnicate"),
+//!     None,
+//! );
+//! assert_eq!(
+//!     page.render_man(),
+//!     ".TH FOO 1 \"\" \"\"\n.SH NAME\nfoo \\- frobnicate\n"
+//! );
+//! ```
+
+use super::*;
+
+/// Renders documents as **man**(7) source text.
+#[derive(Debug, Clone, Default)]
+pub struct ManRenderer {
+    options: RenderOptions,
+}
+
+impl ManRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options }
+    }
+
+    /// Render `page` as **man** source text.
+    pub fn render(&self, page: &Mdoc) -> String {
+        let mut buf = vec![];
+        self.to_writer(page, &mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Write `page` as **man** source text to a writer.
+    pub fn to_writer(&self, page: &Mdoc, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render_man", document = %page.name.0).entered();
+        let lines = page.rendered_lines(&self.options);
+        let name = if page.name.0.is_empty() {
+            lines
+                .iter()
+                .find_map(|line| match line {
+                    Line::Control { name, args } if name == "Nm" => args.first().cloned(),
+                    _ => None,
+                })
+                .unwrap_or_default()
+        } else {
+            page.name.0.clone()
+        };
+        let mut lowering = Lowering {
+            options: &self.options,
+            name,
+            out: String::new(),
+            header: None,
+            section: String::new(),
+            names: vec![],
+            lists: vec![],
+            displays: vec![],
+            reference: None,
+        };
+        for line in lines.iter() {
+            lowering.line(line);
+        }
+        lowering.flush_names();
+        lowering.header();
+        let w = &mut self.options.newline.writer(w);
+        match &self.options.provenance {
+            Some(provenance) => provenance.write_stamped(lowering.out.as_bytes(), w),
+            None => w.write_all(lowering.out.as_bytes()),
+        }
+    }
+}
+
+/// An open `Bl` list.
+struct List {
+    kind: String,
+    width: Option<String>,
+    compact: bool,
+    nested: bool,
+    count: usize,
+}
+
+/// An open `Bd` display.
+struct Display {
+    no_fill: bool,
+    centered: bool,
+    offset: bool,
+}
+
+/// The state of the lowering of one document.
+struct Lowering<'a> {
+    options: &'a RenderOptions,
+    /// The name of the page, for `Nm` without arguments.
+    name: Str,
+    out: String,
+    /// The `TH` arguments gathered from the prologue, until written.
+    header: Option<[String; 4]>,
+    section: String,
+    /// The `Nm` names of the NAME section, waiting for its `Nd`.
+    names: Vec<String>,
+    lists: Vec<List>,
+    displays: Vec<Display>,
+    /// The fields of an open `Rs` reference.
+    reference: Option<Vec<(String, String)>>,
+}
+
+impl Lowering<'_> {
+    fn line(&mut self, line: &Line) {
+        match line {
+            Line::Figure(figure) => figure.lines().iter().for_each(|l| self.line(l)),
+            Line::Admonition(admonition) => admonition.lines().iter().for_each(|l| self.line(l)),
+            Line::Unknown(unknown) => {
+                self.header();
+                let mut buf = vec![];
+                unknown.render_as(SourceLanguage::Man, &mut buf).unwrap(); // writing to a Vec always works
+                self.out.push_str(&String::from_utf8_lossy(&buf));
+            }
+            Line::Changed(line) => {
+                self.header();
+                self.request(".mc \\(br");
+                self.line(line);
+                self.request(".mc");
+            }
+            Line::Text(inlines) => {
+                self.header();
+                let text = self.inlines(inlines);
+                self.text(&text);
+            }
+            Line::Control { name, args } => self.control(name, args),
+        }
+    }
+
+    fn control(&mut self, name: &str, args: &[Str]) {
+        let args: Vec<Str> = args
+            .iter()
+            .filter(|arg| !arg.starts_with("\\\n"))
+            .cloned()
+            .collect();
+        let joined = || args.join(" ");
+        match name {
+            "Dd" => {
+                let date = joined();
+                let date = date
+                    .trim_start_matches("$Mdocdate")
+                    .trim_start_matches(':')
+                    .trim_end_matches('$')
+                    .trim();
+                self.header.get_or_insert_with(Default::default)[2] = date.to_string();
+                return;
+            }
+            "Dt" => {
+                let header = self.header.get_or_insert_with(Default::default);
+                for (field, arg) in header.iter_mut().zip(args.iter().take(2)) {
+                    *field = arg.to_string();
+                }
+                return;
+            }
+            "Os" => {
+                self.header.get_or_insert_with(Default::default)[3] = joined();
+                return;
+            }
+            _ => {}
+        }
+        self.header();
+        if name == "Rs" {
+            self.reference = Some(vec![]);
+            return;
+        }
+        if let Some(fields) = self.reference.as_mut() {
+            if let Some(field) = name.strip_prefix('%') {
+                fields.push((field.to_string(), joined()));
+                return;
+            }
+            if name == "Re" {
+                let fields = self.reference.take().unwrap();
+                let text = reference(&fields);
+                self.text(&text);
+                return;
+            }
+        }
+        match name {
+            "Sh" | "Ss" => {
+                self.flush_names();
+                if name == "Sh" {
+                    self.section = joined();
+                }
+                self.request(&format!(".{} {}", name.to_uppercase(), quote(&joined())));
+            }
+            "Pp" | "Lp" => self.request(".PP"),
+            "Nm" if self.section == "NAME" => self.names.extend(
+                args.iter()
+                    .filter(|a| !phrase::is_closing(a))
+                    .map(|a| a.to_string()),
+            ),
+            "Nd" => {
+                let names = std::mem::take(&mut self.names);
+                let names = if names.is_empty() {
+                    self.name.to_string()
+                } else {
+                    names.join(", ")
+                };
+                self.text(&format!("{names} \\- {}", self.phrase(&args)));
+            }
+            "Nm" if self.section == "SYNOPSIS" => {
+                if !self.out.ends_with(".SH SYNOPSIS\n") {
+                    self.request(".br");
+                }
+                let text = phrase::macro_line(self, "Nm", &args);
+                self.text(&text);
+            }
+            "Bd" => {
+                let display = Display {
+                    no_fill: args.iter().any(|a| a == "-literal" || a == "-unfilled"),
+                    centered: args.iter().any(|a| a == "-centered"),
+                    offset: args.iter().any(|a| a == "-offset"),
+                };
+                if !args.iter().any(|a| a == "-compact") {
+                    self.request(".sp");
+                }
+                if display.offset {
+                    self.request(".RS");
+                }
+                if display.no_fill {
+                    self.request(".nf");
+                }
+                if display.centered {
+                    self.request(".ce 1000");
+                }
+                self.displays.push(display);
+            }
+            "Ed" => {
+                let Some(display) = self.displays.pop() else {
+                    return;
+                };
+                if display.centered {
+                    self.request(".ce 0");
+                }
+                if display.no_fill {
+                    self.request(".fi");
+                }
+                if display.offset {
+                    self.request(".RE");
+                }
+            }
+            "D1" | "Dl" => {
+                self.request(".RS");
+                if name == "Dl" {
+                    self.request(".nf");
+                }
+                let text = self.phrase(&args);
+                self.text(&text);
+                if name == "Dl" {
+                    self.request(".fi");
+                }
+                self.request(".RE");
+            }
+            "Bl" => self.begin_list(&args),
+            "It" => self.item(&args),
+            "El" => {
+                let Some(list) = self.lists.pop() else {
+                    return;
+                };
+                if list.kind == "-column" {
+                    self.request(".fi");
+                }
+                if list.compact {
+                    self.request(".PD");
+                }
+                if list.nested {
+                    self.request(".RE");
+                }
+            }
+            // Keeps and spacing mode have no man(7) equivalent.
+            "Bk" | "Ek" | "Sm" | "Tg" => {}
+            _ if !name.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                // Roff requests and comments are the same in both languages.
+                let mut buf = vec![];
+                Line::control(name.to_string().into(), args)
+                    .render_with(&mut buf, self.options)
+                    .unwrap(); // writing to a Vec always works
+                self.out.push_str(&String::from_utf8_lossy(&buf));
+            }
+            _ => {
+                let text = phrase::macro_line(self, name, &args);
+                self.text(&text);
+            }
+        }
+    }
+
+    fn begin_list(&mut self, args: &[Str]) {
+        let kind = args
+            .iter()
+            .find(|a| {
+                a.starts_with('-') && !matches!(a.as_ref(), "-width" | "-offset" | "-compact")
+            })
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "-tag".to_string());
+        let width = args
+            .iter()
+            .position(|a| a == "-width")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|w| indent(w));
+        let list = List {
+            compact: args.iter().any(|a| a == "-compact"),
+            nested: !self.lists.is_empty(),
+            count: 0,
+            width,
+            kind,
+        };
+        if list.nested {
+            self.request(".RS");
+        }
+        if list.compact {
+            self.request(".PD 0");
+        }
+        if list.kind == "-column" {
+            // Each column is as wide as its width argument, plus a gap.
+            let mut stop = 0;
+            let mut stops = vec![];
+            for width in args.iter().skip_while(|a| *a != "-column").skip(1) {
+                if width.starts_with('-') {
+                    break;
+                }
+                stop += width.chars().count() + 2;
+                stops.push(format!("{stop}n"));
+            }
+            self.request(".nf");
+            if !stops.is_empty() {
+                self.request(&format!(".ta {}", stops.join(" ")));
+            }
+        }
+        self.lists.push(list);
+    }
+
+    fn item(&mut self, args: &[Str]) {
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        list.count += 1;
+        let (kind, width, count) = (list.kind.clone(), list.width.clone(), list.count);
+        let request = match kind.as_str() {
+            "-bullet" => ".IP \\(bu 2".to_string(),
+            "-dash" | "-hyphen" => ".IP \\- 2".to_string(),
+            "-enum" => format!(".IP {count}. 4"),
+            "-item" => ".IP".to_string(),
+            "-column" => {
+                let cells: Vec<String> = args
+                    .split(|a| a == "Ta")
+                    .map(|cell| self.phrase(cell))
+                    .collect();
+                self.text(&cells.join("\t"));
+                return;
+            }
+            _ => match width {
+                Some(width) => format!(".TP {width}"),
+                None => ".TP".to_string(),
+            },
+        };
+        let tagged = request.starts_with(".TP");
+        self.request(&request);
+        if tagged {
+            let head = self.phrase(args);
+            self.text(if head.is_empty() { "\\&" } else { &head });
+        }
+    }
+
+    /// Write the `TH` line, if it hasn't been written yet.
+    fn header(&mut self) {
+        let Some([title, section, date, os]) = self.header.take() else {
+            return;
+        };
+        self.out.push_str(&format!(
+            ".TH {} {} {} {}\n",
+            quote(&title),
+            quote(&section),
+            quote(&date),
+            quote(&os)
+        ));
+    }
+
+    /// Write the NAME line for `Nm` names without an `Nd`.
+    fn flush_names(&mut self) {
+        if !self.names.is_empty() {
+            let names = std::mem::take(&mut self.names).join(", ");
+            self.text(&names);
+        }
+    }
+
+    fn request(&mut self, request: &str) {
+        self.out.push_str(request);
+        self.out.push('\n');
+    }
+
+    /// Write a text line, escaping a leading control character.
+    ///
+    /// Control characters after line breaks inside `text` are escaped by
+    /// [`Lowering::inlines`], which knows the requests it writes apart.
+    fn text(&mut self, text: &str) {
+        if text.starts_with(['.', '\'']) {
+            self.out.push_str("\\&");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// The inline elements of a text line as man source text.
+    fn inlines(&self, inlines: &[Inline]) -> String {
+        let mut ret = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Roman(text) | Inline::Roff(text) | Inline::Acronym(text) => {
+                    let mut text = text.as_str();
+                    // Text after a request starts a line of its own.
+                    if ret.ends_with('\n') {
+                        text = text.trim_start_matches(' ');
+                        if text.starts_with(['.', '\'']) {
+                            ret.push_str("\\&");
+                        }
+                    }
+                    ret.push_str(&escape_leading_cc(text))
+                }
+                Inline::Bold(text) | Inline::Italic(text)
+                    if self.options.font_style == FontStyle::Macros =>
+                {
+                    let request = if let Inline::Bold(_) = inline {
+                        "B"
+                    } else {
+                        "I"
+                    };
+                    ret.push_str(&format!("\n.{request} {}\n", quote(text)));
+                }
+                Inline::Bold(text) => ret.push_str(&format!("\\fB{}\\fR", escape_leading_cc(text))),
+                Inline::Italic(text) => {
+                    ret.push_str(&format!("\\fI{}\\fR", escape_leading_cc(text)))
+                }
+                Inline::LineBreak => ret.push_str("\n.br\n"),
+                Inline::Name | Inline::Flag { .. } => {
+                    ret.push_str(&self.phrase(&inline.macro_args(self.options)))
+                }
+                Inline::IndexTerm(_) => {}
+            }
+        }
+        ret.replace(" \n", "\n").replace("\n ", "\n")
+    }
+
+    fn phrase(&self, args: &[Str]) -> String {
+        phrase::phrase(self, args)
+    }
+}
+
+impl phrase::Markup for Lowering<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn enclosure(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        Some(match name {
+            "Op" | "Bq" => ("[", "]"),
+            "Brq" => ("{", "}"),
+            "Pq" => ("(", ")"),
+            "Aq" => ("<", ">"),
+            "Dq" => ("\\(lq", "\\(rq"),
+            "Sq" | "Ql" => ("\\(oq", "\\(cq"),
+            "Qq" => ("\"", "\""),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, name: &str, words: &[&str]) -> String {
+        let styled = |font: char, words: &[&str]| {
+            if words.is_empty() {
+                String::new()
+            } else {
+                format!("\\f{font}{}\\fR", words.join(" "))
+            }
+        };
+        match name {
+            "Fl" if words.is_empty() => "\\fB\\-\\fR".to_string(),
+            "Fl" => words
+                .iter()
+                .map(|w| format!("\\fB\\-{}\\fR", w.replace('-', "\\-")))
+                .collect::<Vec<_>>()
+                .join(" "),
+            "Ar" if words.is_empty() => "\\fIfile ...\\fR".to_string(),
+            "Nm" if words.is_empty() => styled('B', &[&self.name]),
+            "Xr" => match words {
+                [page, section] => format!("\\fB{page}\\fR({section})"),
+                _ => styled('B', words),
+            },
+            "Fn" => match words.split_first() {
+                Some((function, args)) => format!(
+                    "\\fB{function}\\fR({})",
+                    args.iter()
+                        .map(|a| format!("\\fI{a}\\fR"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None => String::new(),
+            },
+            "Lk" => match words.split_first() {
+                Some((url, [])) => url.to_string(),
+                Some((url, text)) => format!("{}: {url}", text.join(" ")),
+                None => String::new(),
+            },
+            "In" => styled('B', &[&format!("#include <{}>", words.join(" "))]),
+            "Nm" | "Cm" | "Ic" | "Sy" | "Fd" | "Cd" => styled('B', words),
+            "Ar" | "Em" | "Pa" | "Va" | "Fa" | "Ft" | "Ad" | "Vt" => styled('I', words),
+            _ => words.join(" "),
+        }
+    }
+}
+
+/// The text of an `Rs` reference: its authors, then its other fields in
+/// order, with titles in italics.
+fn reference(fields: &[(String, String)]) -> String {
+    let authors: Vec<&str> = fields
+        .iter()
+        .filter(|(field, _)| field == "A")
+        .map(|(_, value)| value.as_str())
+        .collect();
+    let mut parts = vec![];
+    match authors.split_last() {
+        Some((last, [])) => parts.push(last.to_string()),
+        Some((last, rest)) => parts.push(format!("{} and {last}", rest.join(", "))),
+        None => {}
+    }
+    for (field, value) in fields.iter().filter(|(field, _)| field != "A") {
+        match field.as_str() {
+            "T" | "B" | "J" => parts.push(format!("\\fI{value}\\fR")),
+            _ => parts.push(value.clone()),
+        }
+    }
+    format!("{}.", parts.join(", "))
+}
+
+/// The indentation of a `TP` list for a `Bl -width` argument: scaled
+/// widths as-is and the widths of other strings in ens.
+fn indent(width: &str) -> Option<String> {
+    match width {
+        "Ds" | "indent" => None,
+        w if w.starts_with(|c: char| c.is_ascii_digit())
+            && w.ends_with(|c: char| c.is_ascii_alphabetic()) =>
+        {
+            Some(w.to_string())
+        }
+        w => Some(format!("{}n", w.chars().count() + 1)),
+    }
+}
+
+/// Quote an argument of a request if it is empty or contains spaces.
+fn quote(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(' ') {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        arg.to_string()
+    }
+}
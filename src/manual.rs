@@ -0,0 +1,463 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use std::{
+    collections::BTreeMap,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use super::*;
+
+/// A collection of pages documenting a project.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Manual {
+    pub pages: Vec<Mdoc>,
+    /// Shared content the pages include, by name, see [`Snippet`].
+    pub snippets: BTreeMap<String, Snippet>,
+    /// Compress generated pages with gzip(1).
+    pub compress: bool,
+    /// Overwrite pages that were written by hand or edited after they were
+    /// generated, see [`provenance::check_overwrite`].
+    pub force: bool,
+    /// Other names of the pages, such as `egrep` for `grep`, with the name
+    /// of the page they refer to, see [`Manual::alias`].
+    pub aliases: BTreeMap<String, String>,
+    /// How the pages of the aliases are written.
+    pub alias_style: AliasStyle,
+}
+
+/// How the pages of the [aliases](Manual::alias) of a page are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AliasStyle {
+    /// A `.so man1/grep.1` stub that man(1) follows to the page.
+    #[default]
+    So,
+    /// A copy of the page, for systems whose man(1) doesn't follow `.so`
+    /// requests or when stubs aren't wanted in packages.
+    Copy,
+}
+
+/// Content shared by several pages of a [`Manual`], such as the paragraph
+/// on proxy settings of every network tool of a project.
+///
+/// Pages include a snippet with [`Mdoc::include`], and the inclusion is
+/// replaced with the lines of the snippet when the manual is generated.
+/// In the text and the arguments of the lines, `{{var}}` is replaced by
+/// the value given to the inclusion, or by the `name` and `section` of the
+/// including page.
+///
+/// ```
+/// # use mdoc::*;
+/// let mut manual = Manual::new();
+/// manual.snippet(
+///     "proxy",
+///     Snippet::new(vec![
+///         Line::text(vec![roman("The proxy of {{name}} is read from")]),
+///         Line::control("Ev".into(), vec!["{{variable}}".into(), ".".into()]),
+///     ]),
+/// );
+/// let mut page = Mdoc::parse(".Sh NAME\n.Nm curl\n.Nd transfer a URL\n");
+/// page.control("Sh".into(), ["ENVIRONMENT"])
+///     .include("proxy", [("variable", "HTTPS_PROXY")]);
+/// let page = manual.expand_snippets(&page, &mut Warnings::new());
+/// assert!(page
+///     .to_mdoc()
+///     .ends_with(".Sh ENVIRONMENT\nThe proxy of curl is read from\n.Ev HTTPS_PROXY .\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Snippet {
+    pub lines: Vec<Line>,
+}
+
+/// The comment that marks the inclusion of a snippet in a page.
+const INCLUDE: &str = "mdoc-include";
+
+impl Snippet {
+    pub fn new(lines: Vec<Line>) -> Self {
+        Self { lines }
+    }
+
+    /// The lines of the snippet, with the variables in `vars` replaced by
+    /// their values. Variables without a value are reported to `warnings`
+    /// and left as they are.
+    pub fn expand(&self, vars: &BTreeMap<String, String>, warnings: &mut Warnings) -> Vec<Line> {
+        let mut substitute = |text: &str| -> String {
+            let mut ret = String::new();
+            let mut rest = text;
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                let var = &rest[start + 2..start + end];
+                ret.push_str(&rest[..start]);
+                match vars.get(var.trim()) {
+                    Some(value) => ret.push_str(value),
+                    None => {
+                        warnings.push(format!("snippet variable {var} is not set"));
+                        ret.push_str(&rest[start..start + end + 2]);
+                    }
+                }
+                rest = &rest[start + end + 2..];
+            }
+            ret.push_str(rest);
+            ret
+        };
+        let mut lines = self.lines.clone();
+        for line in &mut lines {
+            if let Line::Control { args, .. } = line {
+                for arg in args.iter_mut() {
+                    if arg.contains("{{") {
+                        *arg = substitute(arg).into();
+                    }
+                }
+            }
+            for inline in line.inlines_mut().into_iter().flatten() {
+                if let Inline::Roman(text)
+                | Inline::Roff(text)
+                | Inline::Bold(text)
+                | Inline::Italic(text)
+                | Inline::Acronym(text) = inline
+                {
+                    if text.contains("{{") {
+                        *text = substitute(text);
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl Mdoc {
+    /// Include the [`Snippet`] `name` of the manual of this page, with the
+    /// values of its variables.
+    ///
+    /// The inclusion is written as a comment until the page is generated
+    /// with [`Manual::generate_incremental`] or expanded with
+    /// [`Manual::expand_snippets`].
+    pub fn include<'a>(
+        &mut self,
+        name: &str,
+        vars: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> &mut Self {
+        let mut args: Vec<Str> = vec![INCLUDE.into(), name.to_string().into()];
+        args.extend(vars.into_iter().map(|(k, v)| format!("{k}={v}").into()));
+        self.lines.push(Line::control("\\\"".into(), args));
+        self
+    }
+}
+
+/// The pages written and skipped by [`Manual::generate_incremental`].
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Generated {
+    pub written: Vec<PathBuf>,
+    /// Pages that were up to date.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Name of the file in the output directory that records the hashes of the
+/// generated pages.
+const CACHE: &str = ".mdoc-cache";
+
+impl Manual {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a page.
+    pub fn page(&mut self, page: Mdoc) -> &mut Self {
+        self.pages.push(page);
+        self
+    }
+
+    /// Add `alias` as another name of the page `target`, for utilities
+    /// installed under several names with hard links or symbolic links.
+    ///
+    /// A page for `alias` in the section of `target` is written along with
+    /// the pages by [`Manual::generate_incremental`], as set by
+    /// [`Manual::alias_style`].
+    pub fn alias(&mut self, alias: impl Into<String>, target: impl Into<String>) -> &mut Self {
+        self.aliases.insert(alias.into(), target.into());
+        self
+    }
+
+    /// Add a snippet the pages can include, see [`Snippet`].
+    pub fn snippet(&mut self, name: impl Into<String>, snippet: Snippet) -> &mut Self {
+        self.snippets.insert(name.into(), snippet);
+        self
+    }
+
+    /// `page` with its inclusions of snippets replaced by their lines.
+    ///
+    /// Inclusions of snippets the manual doesn't have are reported to
+    /// `warnings` and kept.
+    pub fn expand_snippets(&self, page: &Mdoc, warnings: &mut Warnings) -> Mdoc {
+        let mut ret = page.clone();
+        ret.lines.clear();
+        for line in &page.lines {
+            let args = match line {
+                Line::Control { name, args }
+                    if name == "\\\"" && args.len() > 1 && args[0] == INCLUDE =>
+                {
+                    args
+                }
+                _ => {
+                    ret.lines.push(line.clone());
+                    continue;
+                }
+            };
+            let snippet = &args[1];
+            let Some(snippet_lines) = self.snippets.get(snippet.as_ref()) else {
+                warnings.push(format!("unknown snippet {snippet}"));
+                ret.lines.push(line.clone());
+                continue;
+            };
+            let mut vars = BTreeMap::from([
+                ("name".to_string(), page.name.0.to_string()),
+                ("section".to_string(), page.title.section.0.to_string()),
+            ]);
+            vars.extend(args[2..].iter().filter_map(|arg| {
+                let (key, value) = arg.split_once('=')?;
+                Some((key.to_string(), value.to_string()))
+            }));
+            ret.lines.extend(snippet_lines.expand(&vars, warnings));
+        }
+        ret
+    }
+
+    /// The keyword index of the manual: each [index
+    /// term](Inline::IndexTerm) with the `(name, section)` of the pages it
+    /// appears in.
+    ///
+    /// This is meant for generating the index of an HTML site, or for
+    /// enriching the keywords known to apropos(1).
+    pub fn index(&self) -> BTreeMap<String, Vec<(String, String)>> {
+        let mut index: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        for page in &self.pages {
+            for term in page.index_terms() {
+                index
+                    .entry(term)
+                    .or_default()
+                    .push((page.name.0.to_string(), page.title.section.0.to_string()));
+            }
+        }
+        index
+    }
+
+    /// Write the pages to `out_dir`, in `manN` subdirectories by section,
+    /// skipping the pages that haven't changed since the last run.
+    ///
+    /// A page is only compressed and written if the hash of its rendered
+    /// output and of the compression and alias settings differs from the
+    /// one recorded in the `.mdoc-cache` file of `out_dir` or if its file
+    /// is missing, which keeps large documentation builds fast. Since the
+    /// output is stamped with the version of the generator, upgrading it
+    /// rewrites every page.
+    ///
+    /// Pages are stamped with their [provenance], and unless
+    /// [`force`](Manual::force) is set, existing files without a stamp or
+    /// edited since they were generated are not overwritten: an error is
+    /// returned instead.
+    pub fn generate_incremental(&self, out_dir: &Path) -> std::io::Result<Generated> {
+        self.generate_incremental_with_progress(out_dir, |_| {})
+    }
+
+    /// [`Manual::generate_incremental`], calling `progress` after each page
+    /// and alias is written or skipped.
+    ///
+    /// ```no_run
+    /// # use mdoc::*;
+    /// # let manual = Manual::new();
+    /// manual.generate_incremental_with_progress("target/man".as_ref(), |progress| {
+    ///     eprintln!("{progress}");
+    /// })?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn generate_incremental_with_progress(
+        &self,
+        out_dir: &Path,
+        mut progress: impl FnMut(&Progress),
+    ) -> std::io::Result<Generated> {
+        let cache_path = out_dir.join(CACHE);
+        let cache = std::fs::read_to_string(&cache_path).unwrap_or_default();
+        let mut hashes: BTreeMap<String, String> = cache
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, path)| (path.to_string(), hash.to_string()))
+            .collect();
+        let mut generated = Generated::default();
+        let options = RenderOptions {
+            provenance: Some(provenance::Provenance::default()),
+            ..RenderOptions::default()
+        };
+        let pages: Vec<Mdoc> = self
+            .pages
+            .iter()
+            .map(|page| self.expand_snippets(page, &mut Warnings::new()))
+            .collect();
+        let total = pages.len() + self.aliases.len();
+        let mut current = 0;
+        let mut report = |file: &str, status| {
+            current += 1;
+            progress(&Progress {
+                current,
+                total,
+                path: &out_dir.join(file),
+                status,
+            });
+        };
+        for page in &pages {
+            let file = self.file_name(&page.name.0, &page.title.section.0);
+            let bytes = parser::encode(&page.render_with(&options), page.encoding());
+            let status = self.write(out_dir, &file, bytes, false, &mut hashes, &mut generated)?;
+            report(&file, status);
+        }
+        for (alias, target) in &self.aliases {
+            let Some(page) = pages.iter().find(|page| page.name.0 == *target) else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("alias {alias}: the manual has no page {target}"),
+                ));
+            };
+            let section = &page.title.section.0;
+            let file = self.file_name(alias, section);
+            let bytes = match self.alias_style {
+                AliasStyle::So => format!(".so man{section}/{target}.{section}\n").into_bytes(),
+                AliasStyle::Copy => parser::encode(&page.render_with(&options), page.encoding()),
+            };
+            let status = self.write(out_dir, &file, bytes, true, &mut hashes, &mut generated)?;
+            report(&file, status);
+        }
+        let cache: String = hashes
+            .iter()
+            .map(|(path, hash)| format!("{hash} {path}\n"))
+            .collect();
+        std::fs::write(cache_path, cache)?;
+        Ok(generated)
+    }
+}
+
+impl Manual {
+    /// The path of the page `name` relative to the output directory.
+    fn file_name(&self, name: &str, section: &str) -> String {
+        let mut file = format!("man{section}/{name}.{section}");
+        if self.compress {
+            file.push_str(".gz");
+        }
+        file
+    }
+
+    /// The key of `bytes` in the cache: a hash of the rendered output,
+    /// whose stamp names the version of the generator, and of the settings
+    /// that change how it is written, including the alias style for
+    /// aliases.
+    fn cache_key(&self, bytes: &[u8], alias: bool) -> String {
+        let mut settings = format!("compress={}\n", self.compress);
+        if alias {
+            settings.push_str(match self.alias_style {
+                AliasStyle::So => "aliases=so\n",
+                AliasStyle::Copy => "aliases=copy\n",
+            });
+        }
+        let hash = provenance::fnv1a(&[settings.as_bytes(), bytes].concat());
+        format!("{hash:016x}")
+    }
+
+    /// Write the rendered page or alias `bytes` to `file` in `out_dir`,
+    /// unless their cache key is the one recorded in `hashes`.
+    fn write(
+        &self,
+        out_dir: &Path,
+        file: &str,
+        mut bytes: Vec<u8>,
+        alias: bool,
+        hashes: &mut BTreeMap<String, String>,
+        generated: &mut Generated,
+    ) -> std::io::Result<PageStatus> {
+        let hash = self.cache_key(&bytes, alias);
+        let path = out_dir.join(file);
+        if path.exists() && hashes.get(file) == Some(&hash) {
+            generated.skipped.push(path);
+            return Ok(PageStatus::Skipped);
+        }
+        // `.so` stubs have no stamp, since the request must be their first
+        // line.
+        let is_stub = read_source(&path).is_ok_and(|text| text.starts_with(".so "));
+        if !self.force && !is_stub {
+            provenance::check_overwrite(&path)?;
+        }
+        std::fs::create_dir_all(path.parent().unwrap_or(out_dir))?;
+        if self.compress {
+            bytes = gzip(&bytes)?;
+        }
+        std::fs::write(&path, bytes)?;
+        hashes.insert(file.to_string(), hash);
+        generated.written.push(path);
+        Ok(PageStatus::Written)
+    }
+}
+
+/// Compress `bytes` with gzip(1), without a timestamp so that the output
+/// is reproducible.
+pub fn gzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    run_gzip(&["-9", "-n", "-c"], bytes)
+}
+
+/// Read and decode a page, decompressing it first if it is gzipped, as
+/// installed pages often are.
+pub fn read_source(path: &std::path::Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        gunzip(&bytes)?
+    } else {
+        bytes
+    };
+    Ok(parser::decode(&bytes, None).0)
+}
+
+/// Decompress `bytes` with gzip(1).
+pub(crate) fn gunzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    run_gzip(&["-d", "-c"], bytes)
+}
+
+fn run_gzip(args: &[&str], bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("gzip")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = bytes.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer.join().expect("writer thread panicked")?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "gzip exited with {}",
+            output.status
+        )));
+    }
+    Ok(output.stdout)
+}
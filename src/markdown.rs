@@ -0,0 +1,1267 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Convert Markdown to **mdoc**, and render pages as Markdown with
+//! [`MarkdownRenderer`].
+//!
+//! The dialect is the one of ronn(1) and md2man: a level 1 heading such as
+//! `# ls(1) -- list directory contents` names the page, level 2 headings
+//! are sections and deeper ones are subsections. An optional front matter
+//! block between `---` lines can give the `title`, `section` and `date`
+//! instead.
+//!
+//! Options are written as definition lists, either in the style of ronn,
+//! a bullet item whose first line ends with a colon followed by indented
+//! text, or in the style of pandoc, a term followed by a line starting
+//! with `: `. Code fences become literal displays.
+//!
+//! Inline code starting with a dash becomes `Fl`, other inline code `Li`,
+//! emphasis `Em`, strong emphasis `Sy`, links `Lk` and page references
+//! such as `ls(1)` become `Xr`. In the SYNOPSIS, the names of the page
+//! become `Nm`, other inline code `Cm`, bracketed groups `Op` and emphasis,
+//! plain words and ronn's `<placeholders>` `Ar`. Tables, images and raw
+//! HTML are kept as text and reported as warnings.
+//!
+//! Rendered pages are written in the same dialect, so that they read back
+//! as the same macros where Markdown can tell them apart.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::markdown::*;
+//! let page = from_markdown(
+//!     "# frob(1) -- frobnicate files\n\n## OPTIONS\n\n\
+//!      * `-v`, `--verbose`:\n  Print *more*.\n",
+//! );
+//! assert_eq!(
+//!     page.to_mdoc(),
+//!     ".Dd $Mdocdate$\n.Dt FROB 1\n.Os\n.Sh NAME\n.Nm frob\n.Nd frobnicate files\n\
+//!      .Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v , Fl -verbose\nPrint\n.Em more .\n.El\n",
+//! );
+//! ```
+
+use super::*;
+
+/// Convert Markdown source to a page.
+pub fn from_markdown(source: &str) -> Mdoc {
+    from_markdown_with_warnings(source, &mut Warnings::new())
+}
+
+/// Like [`from_markdown`], reporting the Markdown that couldn't be
+/// converted to `warnings`.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn from_markdown_with_warnings(source: &str, warnings: &mut Warnings) -> Mdoc {
+    let mut conversion = Conversion {
+        page: Mdoc::default(),
+        warnings,
+        section: String::new(),
+        paragraph: vec![],
+        list: None,
+        item: false,
+        pending_break: false,
+        line: 1,
+        paragraph_line: 1,
+        attributed: 0,
+    };
+    // The lines with their numbers, without the `\r` of `\r\n` line
+    // endings.
+    let mut lines = source
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .zip(1..)
+        .peekable();
+    if lines.peek().is_some_and(|(line, _)| *line == "---") {
+        lines.next();
+        let mut meta = std::collections::HashMap::new();
+        for (line, _) in lines.by_ref() {
+            if line == "---" {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                meta.insert(
+                    key.trim().to_string(),
+                    value.trim().trim_matches('"').to_string(),
+                );
+            }
+        }
+        if let Some(title) = meta.get("title") {
+            let section = meta.get("section").map(String::as_str).unwrap_or("1");
+            conversion.prologue(title, section, meta.get("date").map(String::as_str));
+        }
+    }
+    while let Some((line, number)) = lines.next() {
+        conversion.at(number);
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.is_empty() {
+            conversion.flush();
+            conversion.pending_break = true;
+            continue;
+        }
+        // A list ends at the first unindented block after a blank line that
+        // isn't an item.
+        if indent == 0
+            && conversion.pending_break
+            && list_item(trimmed).is_none()
+            && !trimmed.starts_with(": ")
+        {
+            conversion.close_list();
+        }
+        if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+            conversion.flush();
+            conversion.block_break();
+            conversion
+                .page
+                .control("Bd".into(), ["-literal", "-offset", "indent"]);
+            for (line, number) in lines.by_ref() {
+                conversion.at(number);
+                if line.trim_start().starts_with(fence) {
+                    break;
+                }
+                conversion.page.text([roman(line.replace('\\', "\\e"))]);
+            }
+            conversion.page.control("Ed".into(), []);
+            continue;
+        }
+        if indent == 0 && trimmed.starts_with('#') {
+            let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+            let title = trimmed[level..].trim().trim_end_matches('#').trim();
+            conversion.flush();
+            conversion.close_list();
+            conversion.heading(level, title);
+            continue;
+        }
+        if let Some((kind, rest)) = list_item(trimmed) {
+            if indent == 0 || conversion.list.is_none() {
+                conversion.flush();
+                let continued = lines
+                    .peek()
+                    .is_some_and(|(l, _)| l.starts_with([' ', '\t']));
+                match rest.strip_suffix(':') {
+                    Some(term) if kind == "bullet" && continued => {
+                        conversion.item("tag", words(term));
+                    }
+                    _ => {
+                        conversion.item(kind, vec![]);
+                        conversion.push_paragraph(rest);
+                    }
+                }
+                continue;
+            }
+        }
+        if let Some(definition) = trimmed.strip_prefix(": ") {
+            // The term of a pandoc definition is the last paragraph line.
+            if let Some(term) = conversion.paragraph.pop() {
+                conversion.flush();
+                conversion.item("tag", words(&term));
+                conversion.push_paragraph(definition);
+                continue;
+            }
+        }
+        if trimmed.starts_with('|')
+            || trimmed.starts_with("![")
+            || trimmed.starts_with("<") && !trimmed.starts_with("<http")
+        {
+            conversion
+                .warnings
+                .push_in(&conversion.section, format!("kept as text: {trimmed}"));
+        }
+        conversion.block_break();
+        conversion.push_paragraph(trimmed);
+    }
+    conversion.flush();
+    conversion.close_list();
+    conversion.attribute(conversion.line);
+    let mut page = conversion.page;
+    parser::prologue(&mut page);
+    page
+}
+
+/// The state of a conversion.
+struct Conversion<'w> {
+    page: Mdoc,
+    warnings: &'w mut Warnings,
+    /// The title of the current section.
+    section: String,
+    /// The lines of the current paragraph.
+    paragraph: Vec<String>,
+    /// The kind of the open list.
+    list: Option<&'static str>,
+    /// Whether the body of a list item is empty so far.
+    item: bool,
+    /// Whether a blank line precedes the next block.
+    pending_break: bool,
+    /// The number of the source line being converted.
+    line: usize,
+    /// The number of the first source line of the current paragraph.
+    paragraph_line: usize,
+    /// The number of lines of the page whose origin is recorded.
+    attributed: usize,
+}
+
+impl Conversion<'_> {
+    fn prologue(&mut self, title: &str, section: &str, date: Option<&str>) {
+        let date: Vec<&str> = date
+            .map(|d| d.split_whitespace().collect())
+            .unwrap_or_else(|| vec!["$Mdocdate$"]);
+        self.page.control("Dd".into(), date);
+        self.page
+            .control("Dt".into(), [title.to_uppercase().as_str(), section]);
+        self.page.control("Os".into(), []);
+    }
+
+    fn heading(&mut self, level: usize, title: &str) {
+        self.pending_break = false;
+        if level == 1 {
+            // `# name(section) -- description`
+            let (reference, description) = title
+                .split_once(" -- ")
+                .or_else(|| title.split_once(" - "))
+                .unwrap_or((title, ""));
+            let (name, section) = reference
+                .strip_suffix(')')
+                .and_then(|r| r.split_once('('))
+                .unwrap_or((reference, "1"));
+            if !self
+                .page
+                .lines
+                .iter()
+                .any(|l| matches!(l, Line::Control { name, .. } if name == "Dt"))
+            {
+                self.prologue(name, section, None);
+            }
+            self.section = "NAME".to_string();
+            self.page.control("Sh".into(), ["NAME"]);
+            self.page.control("Nm".into(), [name]);
+            self.page.control("Nd".into(), [description]);
+        } else if level == 2 {
+            self.section = title.to_uppercase();
+            self.page.control("Sh".into(), [self.section.as_str()]);
+        } else {
+            self.page.control("Ss".into(), [title]);
+        }
+    }
+
+    /// Start an item of a list of `kind`, opening the list if needed.
+    fn item(&mut self, kind: &'static str, args: Vec<String>) {
+        if self.list != Some(kind) {
+            self.close_list();
+            self.block_break();
+            // A list needs no paragraph break before it.
+            if matches!(self.page.lines.last(), Some(Line::Control { name, .. }) if name == "Pp") {
+                self.page.lines.pop();
+            }
+            match kind {
+                "tag" => self.page.control("Bl".into(), ["-tag", "-width", "Ds"]),
+                kind => self
+                    .page
+                    .control("Bl".into(), [format!("-{kind}").as_str()]),
+            };
+            self.list = Some(kind);
+        }
+        self.pending_break = false;
+        self.item = true;
+        self.page.lines.push(Line::control(
+            "It".into(),
+            args.into_iter().map(Into::into).collect(),
+        ));
+    }
+
+    fn close_list(&mut self) {
+        if self.list.take().is_some() {
+            self.page.control("El".into(), []);
+        }
+    }
+
+    /// Separate the next block from the previous one with a paragraph
+    /// break, if there was a blank line between them.
+    fn block_break(&mut self) {
+        if !std::mem::take(&mut self.pending_break) || !self.paragraph.is_empty() {
+            return;
+        }
+        let after_heading = matches!(
+            self.page.lines.last(),
+            Some(Line::Control { name, .. }) if matches!(name.as_ref(), "Sh" | "Ss" | "Bl" | "El")
+        );
+        if !after_heading && !self.item {
+            self.page.control("Pp".into(), []);
+        }
+    }
+
+    /// Write the current paragraph out.
+    /// Record the lines of the page added so far as coming from the
+    /// current source line, and move on to source line `line`.
+    fn at(&mut self, line: usize) {
+        self.attribute(self.line);
+        self.line = line;
+    }
+
+    /// Record the lines of the page added since the last call as coming
+    /// from source line `line`.
+    fn attribute(&mut self, line: usize) {
+        // Lines may have been removed since, such as a paragraph break
+        // before a list.
+        let end = self.page.lines.len();
+        let start = self.attributed.min(end);
+        self.page.set_origins(start..end, Origin::Line(line));
+        self.attributed = end;
+    }
+
+    fn push_paragraph(&mut self, text: &str) {
+        if self.paragraph.is_empty() {
+            self.paragraph_line = self.line;
+        }
+        self.paragraph.push(text.to_string());
+    }
+
+    fn flush(&mut self) {
+        if self.paragraph.is_empty() {
+            return;
+        }
+        self.attribute(self.line);
+        self.write_paragraph();
+        self.attribute(self.paragraph_line);
+    }
+
+    fn write_paragraph(&mut self) {
+        let text = std::mem::take(&mut self.paragraph).join(" ");
+        self.item = false;
+        if self.section == "NAME" {
+            // `name, other - description`
+            if let Some((names, description)) = text.split_once(" - ") {
+                let names: Vec<&str> = names.split(',').map(str::trim).collect();
+                for (i, name) in names.iter().enumerate() {
+                    if i + 1 < names.len() {
+                        self.page.control("Nm".into(), [*name, ","]);
+                    } else {
+                        self.page.control("Nm".into(), [*name]);
+                    }
+                }
+                self.page.control("Nd".into(), [description.trim()]);
+                return;
+            }
+        }
+        if self.section == "SYNOPSIS" {
+            self.synopsis(&text);
+            return;
+        }
+        let mut text_line = String::new();
+        let mut pieces = pieces(&text).into_iter().peekable();
+        while let Some(piece) = pieces.next() {
+            match piece {
+                Piece::Text(text) => text_line.push_str(&text),
+                Piece::Macro(mut words) => {
+                    let text = std::mem::take(&mut text_line);
+                    if !text.trim().is_empty() {
+                        self.page.text([roman(text.trim())]);
+                    }
+                    // Closing punctuation right after the macro is passed
+                    // to it as delimiters.
+                    if let Some(Piece::Text(next)) = pieces.peek_mut() {
+                        let end = next.find(' ').unwrap_or(next.len());
+                        let delimiters = &next[..end];
+                        if !delimiters.is_empty()
+                            && delimiters
+                                .chars()
+                                .all(|c| phrase::is_closing(&c.to_string()))
+                        {
+                            words.extend(delimiters.chars().map(|c| c.to_string()));
+                            *next = next[end..].to_string();
+                        }
+                    }
+                    let name = words.remove(0);
+                    self.page.lines.push(Line::control(
+                        name.into(),
+                        words.into_iter().map(Into::into).collect(),
+                    ));
+                }
+            }
+        }
+        if !text_line.trim().is_empty() {
+            self.page.text([roman(text_line.trim())]);
+        }
+    }
+
+    /// Write a SYNOPSIS paragraph out, with the names of the page as `Nm`,
+    /// bracketed groups as `Op`, or `Oo` and `Oc` around nested groups,
+    /// emphasis, plain words and `<placeholders>` as `Ar` and other inline
+    /// code as `Cm`.
+    fn synopsis(&mut self, text: &str) {
+        let names: Vec<String> = self
+            .page
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Control { name, args } if name == "Nm" => args.first(),
+                _ => None,
+            })
+            .map(|name| name.to_string())
+            .collect();
+        // Macro words, with brackets, ellipses and bars on their own.
+        let mut tokens: Vec<Vec<String>> = vec![];
+        for piece in pieces(text) {
+            match piece {
+                Piece::Text(text) => {
+                    for word in text.split_whitespace() {
+                        let inner = word.trim_start_matches('[');
+                        tokens.extend((inner.len()..word.len()).map(|_| vec!["[".to_string()]));
+                        let bare = inner.trim_end_matches(']');
+                        let closes = inner.len() - bare.len();
+                        let (bare, ellipsis) = match bare.strip_suffix("...") {
+                            Some(bare) => (bare, true),
+                            None => (bare, false),
+                        };
+                        // ronn writes placeholders as `<file>`.
+                        let bare = bare
+                            .strip_prefix('<')
+                            .and_then(|bare| bare.strip_suffix('>'))
+                            .unwrap_or(bare);
+                        match bare {
+                            "" => {}
+                            "|" => tokens.push(vec![bare.to_string()]),
+                            w => tokens.push(vec!["Ar".to_string(), w.to_string()]),
+                        }
+                        if ellipsis {
+                            tokens.push(vec!["...".to_string()]);
+                        }
+                        tokens.extend((0..closes).map(|_| vec!["]".to_string()]));
+                    }
+                }
+                Piece::Macro(mut words) => {
+                    match words[0].as_str() {
+                        "Li" if words.len() == 2 && names.contains(&words[1]) => {
+                            words[0] = "Nm".to_string();
+                            // The first name is the default of `Nm`.
+                            if names.first() == Some(&words[1]) {
+                                words.truncate(1);
+                            }
+                        }
+                        "Li" => words[0] = "Cm".to_string(),
+                        "Em" => words[0] = "Ar".to_string(),
+                        _ => {}
+                    }
+                    tokens.push(words);
+                }
+            }
+        }
+        let mut groups = brackets::Groups::new();
+        for words in tokens {
+            match words.as_slice() {
+                [open] if open == "[" => groups.open(),
+                [close] if close == "]" => {
+                    if !groups.close() {
+                        self.warnings
+                            .push_in("SYNOPSIS", format!("unmatched bracket ignored: {text}"));
+                    }
+                }
+                [word] if word == "..." || word == "|" => groups.follow(word.clone().into()),
+                _ => groups.push(words.into_iter().map(Into::into).collect()),
+            }
+        }
+        if groups.is_open() {
+            self.warnings.push_in(
+                "SYNOPSIS",
+                format!("unclosed bracket closed at the end: {text}"),
+            );
+            groups.close_all();
+        }
+        self.page.lines.extend(groups.lines());
+    }
+}
+
+/// The kind and the text of a list item line.
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    if let Some(rest) = line
+        .strip_prefix(['*', '-', '+'])
+        .and_then(|r| r.strip_prefix(' '))
+    {
+        return Some(("bullet", rest.trim_start()));
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = line[digits..].strip_prefix(['.', ')'])?.strip_prefix(' ')?;
+    (digits > 0).then_some(("enum", rest.trim_start()))
+}
+
+/// A piece of inline text.
+enum Piece {
+    /// Text in **mdoc** source, with escapes.
+    Text(String),
+    /// The name and arguments of a macro.
+    Macro(Vec<String>),
+}
+
+/// The words of `text` as macro arguments, such as the term of a
+/// definition.
+fn words(text: &str) -> Vec<String> {
+    let mut ret = vec![];
+    for piece in pieces(text) {
+        match piece {
+            Piece::Text(text) => ret.extend(text.split_whitespace().map(str::to_string)),
+            Piece::Macro(words) => ret.extend(words),
+        }
+    }
+    ret
+}
+
+/// Split inline Markdown into text and macros.
+fn pieces(text: &str) -> Vec<Piece> {
+    let mut ret = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let at_word_start = plain.is_empty() || plain.ends_with(char::is_whitespace);
+        let span = |delimiter: &str| -> Option<(&str, &str)> {
+            let inner = rest.strip_prefix(delimiter)?;
+            let end = inner.find(delimiter).filter(|&end| end > 0)?;
+            Some((&inner[..end], &inner[end + delimiter.len()..]))
+        };
+        let mut found = None;
+        if c == '\\' {
+            if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                plain.push_str(&roff_text(&escaped.to_string()));
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+        } else if c == '`' {
+            found = span("`").map(|(code, after)| (code_macro(code), after));
+        } else if c == '*' || c == '_' && at_word_start {
+            let strong = if c == '*' { "**" } else { "__" };
+            let emphasis = if c == '*' { "*" } else { "_" };
+            found = span(strong)
+                .map(|(text, after)| (macro_words("Sy", text), after))
+                .or_else(|| span(emphasis).map(|(text, after)| (macro_words("Em", text), after)));
+        } else if c == '[' {
+            found = link(rest);
+        } else if c == '<' {
+            found = rest[1..]
+                .split_once('>')
+                .filter(|(url, _)| url.contains("://"))
+                .map(|(url, after)| (vec!["Lk".to_string(), url.to_string()], after));
+        }
+        match found {
+            Some((words, after)) => {
+                if !plain.is_empty() {
+                    ret.extend(references(&std::mem::take(&mut plain)));
+                }
+                ret.push(Piece::Macro(words));
+                rest = after;
+            }
+            None => {
+                plain.push_str(&roff_text(&c.to_string()));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        ret.extend(references(&plain));
+    }
+    ret
+}
+
+/// A link, `[text](url)`, and the text after it.
+fn link(text: &str) -> Option<(Vec<String>, &str)> {
+    let (label, rest) = text[1..].split_once("](")?;
+    let (url, after) = rest.split_once(')')?;
+    let mut words = vec!["Lk".to_string(), url.to_string()];
+    words.extend(label.split_whitespace().map(roff_text));
+    Some((words, after))
+}
+
+/// The macro of inline code: a flag if it starts with a dash.
+fn code_macro(code: &str) -> Vec<String> {
+    let Some(flag) = code.strip_prefix('-') else {
+        return macro_words("Li", code);
+    };
+    let mut words = vec!["Fl".to_string()];
+    let mut args = flag.split_whitespace();
+    let first = args.next().unwrap_or_default();
+    match first.split_once('=') {
+        Some((flag, value)) => {
+            words.extend([flag, "Ns", "=", "Ns", "Ar", value].map(roff_text));
+        }
+        None => words.push(roff_text(first)),
+    }
+    for arg in args {
+        words.extend(["Ar".to_string(), roff_text(arg)]);
+    }
+    words
+}
+
+fn macro_words(name: &str, text: &str) -> Vec<String> {
+    std::iter::once(name.to_string())
+        .chain(text.split_whitespace().map(roff_text))
+        .collect()
+}
+
+/// Split page references, such as `ls(1)`, out of plain text.
+fn references(text: &str) -> Vec<Piece> {
+    let mut ret = vec![];
+    let mut plain = String::new();
+    for (i, word) in text.split(' ').enumerate() {
+        let reference = word.split_once('(').and_then(|(name, rest)| {
+            let (section, after) = rest.split_once(')')?;
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_.".contains(c))
+                && section.starts_with(|c: char| c.is_ascii_digit());
+            valid.then_some((name, section, after))
+        });
+        if i > 0 {
+            plain.push(' ');
+        }
+        match reference {
+            Some((name, section, after)) => {
+                if !plain.is_empty() {
+                    ret.push(Piece::Text(std::mem::take(&mut plain)));
+                }
+                ret.push(Piece::Macro(vec![
+                    "Xr".to_string(),
+                    name.to_string(),
+                    section.to_string(),
+                ]));
+                plain.push_str(after);
+            }
+            None => plain.push_str(word),
+        }
+    }
+    if !plain.is_empty() {
+        ret.push(Piece::Text(plain));
+    }
+    ret
+}
+
+/// Escape the backslashes of text for roff.
+fn roff_text(text: &str) -> String {
+    text.replace('\\', "\\e")
+}
+
+/// Renders documents as Markdown in the dialect read by [`from_markdown`].
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownRenderer {
+    options: RenderOptions,
+}
+
+impl MarkdownRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options }
+    }
+
+    /// Render `page` as Markdown.
+    pub fn render(&self, page: &Mdoc) -> String {
+        let mut buf = vec![];
+        self.to_writer(page, &mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Write `page` as Markdown to a writer.
+    pub fn to_writer(&self, page: &Mdoc, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render_markdown", document = %page.name.0).entered();
+        let lines = page.rendered_lines(&self.options);
+        let mut lowering = Lowering {
+            renderer: self,
+            glyphs: term::Glyphs {
+                minus: "-",
+                ..term::Charset::Utf8.glyphs()
+            },
+            name: page.name.0.to_string(),
+            out: String::new(),
+            date: String::new(),
+            section: None,
+            section_title: String::new(),
+            paragraph: vec![],
+            names: vec![],
+            lists: vec![],
+            marker: None,
+            tight: false,
+            displays: vec![],
+            reference: None,
+        };
+        if lowering.name.is_empty() {
+            lowering.name = lines
+                .iter()
+                .find_map(|line| match line {
+                    Line::Control { name, args } if name == "Nm" => {
+                        args.first().map(ToString::to_string)
+                    }
+                    _ => None,
+                })
+                .unwrap_or_default();
+        }
+        for line in lines.iter() {
+            lowering.line(line);
+        }
+        lowering.flush_names();
+        lowering.flush();
+        lowering.close_displays();
+        self.options
+            .newline
+            .writer(w)
+            .write_all(lowering.out.as_bytes())
+    }
+}
+
+/// An open `Bl` list.
+struct List {
+    kind: String,
+    /// The number of items so far.
+    items: usize,
+}
+
+/// The state of the lowering of one document.
+struct Lowering<'a> {
+    renderer: &'a MarkdownRenderer,
+    glyphs: term::Glyphs,
+    name: String,
+    out: String,
+    /// The `Dd` date of the page, if it isn't a placeholder.
+    date: String,
+    /// The `Dt` section of the page, e.g. `1`.
+    section: Option<String>,
+    /// The title of the current section.
+    section_title: String,
+    /// The lines of the current paragraph.
+    paragraph: Vec<String>,
+    /// The `Nm` names of the NAME section, waiting for its `Nd`.
+    names: Vec<String>,
+    lists: Vec<List>,
+    /// The marker of a list item waiting for its first block, such as `* `.
+    marker: Option<String>,
+    /// Whether the next block follows the previous one without a blank
+    /// line, as the body of a ronn definition follows its term.
+    tight: bool,
+    /// Whether each open `Bd` display is literal.
+    displays: Vec<bool>,
+    /// The fields of an open `Rs` reference.
+    reference: Option<Vec<(String, String)>>,
+}
+
+impl Lowering<'_> {
+    fn line(&mut self, line: &Line) {
+        match line {
+            Line::Figure(figure) => {
+                let image = format!("![{}]({})", escape(&figure.caption), figure.src);
+                self.block(&[image]);
+            }
+            Line::Admonition(admonition) => {
+                let text = format!(
+                    "> **{}** {}",
+                    admonition.kind.label(),
+                    self.inlines(&admonition.text)
+                );
+                self.block(&[text]);
+            }
+            Line::Changed(line) => self.line(line),
+            Line::Unknown(unknown) => {
+                // Nothing else can be read as Markdown, so the source is kept
+                // as a comment.
+                let comment = format!("<!-- {} -->", unknown.source.replace("--", "- -"));
+                self.block(&[comment]);
+            }
+            Line::Text(inlines) => {
+                let text = self.inlines(inlines);
+                self.text(&text);
+            }
+            Line::Control { name, args } => self.control(name, args),
+        }
+    }
+
+    fn control(&mut self, name: &str, args: &[Str]) {
+        let args: Vec<Str> = args
+            .iter()
+            .filter(|arg| !arg.starts_with("\\\n"))
+            .cloned()
+            .collect();
+        if name == "Rs" {
+            self.reference = Some(vec![]);
+            return;
+        }
+        if let Some(fields) = self.reference.as_mut() {
+            if let Some(field) = name.strip_prefix('%') {
+                fields.push((field.to_string(), args.join(" ")));
+                return;
+            }
+            if name == "Re" {
+                let fields = self.reference.take().unwrap();
+                let text = self.reference_text(&fields);
+                self.text(&text);
+                return;
+            }
+        }
+        match name {
+            "Dt" => self.section = args.get(1).map(ToString::to_string),
+            "Dd" => {
+                self.date = args
+                    .join(" ")
+                    .trim_start_matches("$Mdocdate")
+                    .trim_start_matches(':')
+                    .trim_end_matches('$')
+                    .trim()
+                    .to_string()
+            }
+            "Os" => {}
+            "Sh" | "Ss" => {
+                self.flush_names();
+                self.flush();
+                self.close_displays();
+                self.lists.clear();
+                self.marker = None;
+                let title = self.unescape(&args.join(" "));
+                if name == "Sh" {
+                    self.section_title = title.clone();
+                    // The NAME section is the level 1 heading of the page.
+                    if title == "NAME" {
+                        return;
+                    }
+                }
+                let level = if name == "Sh" { "##" } else { "###" };
+                self.block(&[format!("{level} {title}")]);
+            }
+            "Pp" | "Lp" | "sp" => self.flush(),
+            "br" => {
+                if let Some(line) = self.paragraph.last_mut() {
+                    line.push_str("  ");
+                }
+            }
+            "Nm" if self.section_title == "NAME" => {
+                let names: Vec<String> = args
+                    .iter()
+                    .filter(|a| !phrase::is_closing(a))
+                    .map(|a| self.unescape(a))
+                    .collect();
+                self.names.extend(names);
+            }
+            "Nd" => {
+                let description = self.phrase(&args);
+                self.heading(&description);
+            }
+            "Nm" if self.section_title == "SYNOPSIS" => {
+                self.flush();
+                let text = phrase::macro_line(self, "Nm", &args);
+                self.text(&text);
+            }
+            "Bd" => {
+                self.flush();
+                let literal = args.iter().any(|a| a == "-literal" || a == "-unfilled");
+                if literal {
+                    let fence = format!("{}```", self.indent());
+                    self.block(&[fence]);
+                }
+                self.displays.push(literal);
+            }
+            "Ed" => {
+                self.flush();
+                if self.displays.pop() == Some(true) {
+                    let fence = format!("{}```\n", self.indent());
+                    self.out.push_str(&fence);
+                }
+            }
+            "D1" => {
+                self.flush();
+                let text = self.phrase(&args);
+                self.text(&text);
+                self.flush();
+            }
+            "Dl" => {
+                self.flush();
+                self.displays.push(true);
+                let text = self.phrase(&args);
+                self.displays.pop();
+                let indent = self.indent();
+                self.block(&[
+                    format!("{indent}```"),
+                    format!("{indent}{text}"),
+                    format!("{indent}```"),
+                ]);
+            }
+            "Bl" => {
+                self.flush();
+                let kind = args
+                    .iter()
+                    .find(|a| {
+                        a.starts_with('-')
+                            && !matches!(a.as_ref(), "-width" | "-offset" | "-compact")
+                    })
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| "-tag".to_string());
+                self.lists.push(List { kind, items: 0 });
+            }
+            "It" => self.item(&args),
+            "El" => {
+                self.flush();
+                if let Some(marker) = self.marker.take() {
+                    self.block(&[marker]);
+                }
+                self.lists.pop();
+            }
+            // Keeps, spacing mode and roff requests don't apply to Markdown.
+            "Bk" | "Ek" | "Sm" | "Tg" => {}
+            _ if !name.starts_with(|c: char| c.is_ascii_uppercase()) => {}
+            _ => {
+                let text = phrase::macro_line(self, name, &args);
+                self.text(&text);
+            }
+        }
+    }
+
+    fn item(&mut self, args: &[Str]) {
+        self.flush();
+        if let Some(marker) = self.marker.take() {
+            self.block(&[marker]);
+        }
+        let depth = self.lists.len();
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        list.items += 1;
+        let (kind, items) = (list.kind.clone(), list.items);
+        let indent = "  ".repeat(depth - 1);
+        match kind.as_str() {
+            "-bullet" | "-dash" | "-hyphen" | "-item" => self.marker = Some(format!("{indent}* ")),
+            "-enum" => self.marker = Some(format!("{indent}{items}. ")),
+            "-column" => {
+                let first = items == 1;
+                let cells: Vec<String> = args
+                    .split(|a| a == "Ta")
+                    .map(|cell| self.phrase(cell).replace('|', "\\|"))
+                    .collect();
+                let row = format!("{indent}| {} |", cells.join(" | "));
+                if first {
+                    // The first row is the header of the table.
+                    let rule = vec!["---"; cells.len()].join(" | ");
+                    self.block(&[row, format!("{indent}| {rule} |")]);
+                } else {
+                    self.out.push_str(&row);
+                    self.out.push('\n');
+                }
+            }
+            _ => {
+                let head = self.phrase(args);
+                self.block(&[format!("{indent}* {head}:")]);
+                self.tight = true;
+            }
+        }
+    }
+
+    /// Write the level 1 heading of the page, `# name(section) --
+    /// description`, or a NAME section for a page with several names,
+    /// after a front matter with its date or names if it has any.
+    fn heading(&mut self, description: &str) {
+        let mut names = std::mem::take(&mut self.names);
+        if names.is_empty() {
+            names.push(self.name.clone());
+        }
+        let section = self.section.clone();
+        if self.out.is_empty() && (names.len() > 1 || !self.date.is_empty()) {
+            self.out.push_str(&format!("---\ntitle: {}\n", names[0]));
+            if let Some(section) = &section {
+                self.out.push_str(&format!("section: {section}\n"));
+            }
+            if !self.date.is_empty() {
+                self.out.push_str(&format!("date: {}\n", self.date));
+            }
+            self.out.push_str("---\n");
+        }
+        if let [name] = names.as_slice() {
+            let mut heading = format!("# {name}");
+            if let Some(section) = &section {
+                heading.push_str(&format!("({section})"));
+            }
+            if !description.is_empty() {
+                heading.push_str(&format!(" -- {description}"));
+            }
+            self.block(&[heading]);
+            return;
+        }
+        self.block(&["## NAME".to_string()]);
+        self.block(&[format!("{} - {description}", names.join(", "))]);
+    }
+
+    /// Write the heading for `Nm` names without an `Nd`.
+    fn flush_names(&mut self) {
+        if !self.names.is_empty() {
+            self.heading("");
+        }
+    }
+
+    /// Add a line of text to the current paragraph, or write it as is in a
+    /// literal display.
+    fn text(&mut self, text: &str) {
+        if self.literal() {
+            for line in text.split('\n') {
+                let line = format!("{}{line}", self.indent());
+                self.out.push_str(line.trim_end());
+                self.out.push('\n');
+            }
+            return;
+        }
+        self.paragraph.extend(text.split('\n').map(str::to_string));
+    }
+
+    /// Write the current paragraph out.
+    fn flush(&mut self) {
+        if self.paragraph.is_empty() {
+            return;
+        }
+        let indent = self.indent();
+        let lines: Vec<String> = std::mem::take(&mut self.paragraph)
+            .iter()
+            .map(|line| format!("{indent}{}", escape_start(line)))
+            .collect();
+        self.block(&lines);
+    }
+
+    /// Write a block out, after a blank line and with the marker of a new
+    /// list item.
+    fn block(&mut self, lines: &[String]) {
+        if !self.out.is_empty() && !std::mem::take(&mut self.tight) {
+            self.out.push('\n');
+        }
+        for (i, line) in lines.iter().enumerate() {
+            match self.marker.take().filter(|_| i == 0) {
+                Some(marker) => {
+                    self.out.push_str(&marker);
+                    self.out.push_str(line.trim_start());
+                }
+                None => self.out.push_str(line),
+            }
+            self.out.push('\n');
+        }
+    }
+
+    fn close_displays(&mut self) {
+        while let Some(literal) = self.displays.pop() {
+            if literal {
+                let fence = format!("{}```\n", self.indent());
+                self.out.push_str(&fence);
+            }
+        }
+    }
+
+    /// The indentation of the body of the current list item.
+    fn indent(&self) -> String {
+        "  ".repeat(self.lists.len())
+    }
+
+    fn literal(&self) -> bool {
+        self.displays.last() == Some(&true)
+    }
+
+    /// The inline elements of a text line as Markdown.
+    fn inlines(&self, inlines: &[Inline]) -> String {
+        let mut ret = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Roman(text) | Inline::Roff(text) | Inline::Acronym(text) => {
+                    ret.push_str(&phrase::Markup::text(self, text))
+                }
+                Inline::Bold(text) => ret.push_str(&self.emphasis("**", text)),
+                Inline::Italic(text) => ret.push_str(&self.emphasis("*", text)),
+                Inline::LineBreak if self.literal() => ret.push('\n'),
+                Inline::LineBreak => ret.push_str("  \n"),
+                Inline::Name | Inline::Flag { .. } => {
+                    ret.push_str(&self.phrase(&inline.macro_args(&self.renderer.options)))
+                }
+                Inline::IndexTerm(_) => {}
+            }
+        }
+        ret
+    }
+
+    /// `text` between the `delimiter`s of emphasis, outside of literal
+    /// displays.
+    fn emphasis(&self, delimiter: &str, text: &str) -> String {
+        let text = phrase::Markup::text(self, text);
+        if self.literal() || text.is_empty() {
+            return text;
+        }
+        format!("{delimiter}{text}{delimiter}")
+    }
+
+    /// The text of an `Rs` reference: its authors, then its other fields in
+    /// order, with titles in italics.
+    fn reference_text(&self, fields: &[(String, String)]) -> String {
+        let authors: Vec<String> = fields
+            .iter()
+            .filter(|(field, _)| field == "A")
+            .map(|(_, value)| escape(&self.unescape(value)))
+            .collect();
+        let mut parts = vec![];
+        match authors.split_last() {
+            Some((last, [])) => parts.push(last.to_string()),
+            Some((last, rest)) => parts.push(format!("{} and {last}", rest.join(", "))),
+            None => {}
+        }
+        for (field, value) in fields.iter().filter(|(field, _)| field != "A") {
+            let value = self.unescape(value);
+            match field.as_str() {
+                "T" | "B" | "J" => parts.push(format!("*{}*", escape(&value))),
+                "U" => parts.push(format!("<{value}>")),
+                _ => parts.push(escape(&value)),
+            }
+        }
+        format!("{}.", parts.join(", "))
+    }
+
+    fn unescape(&self, text: &str) -> String {
+        self.glyphs.unescape(text)
+    }
+
+    fn phrase(&self, args: &[Str]) -> String {
+        phrase::phrase(self, args)
+    }
+}
+
+impl phrase::Markup for Lowering<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text(&self, text: &str) -> String {
+        let text = self.unescape(text);
+        if self.literal() {
+            return text;
+        }
+        escape(&text)
+    }
+
+    fn enclosure(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        Some(match name {
+            "Op" | "Bq" => ("[", "]"),
+            "Brq" => ("{", "}"),
+            "Pq" => ("(", ")"),
+            "Aq" if self.literal() => ("<", ">"),
+            "Aq" => ("\\<", "\\>"),
+            "Dq" => self.glyphs.double_quotes,
+            "Sq" | "Ql" => self.glyphs.single_quotes,
+            "Qq" => ("\"", "\""),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, name: &str, words: &[&str]) -> String {
+        let words: Vec<String> = words.iter().map(|w| self.unescape(w)).collect();
+        let text = match name {
+            "Fl" if words.is_empty() => "-".to_string(),
+            "Fl" => words
+                .iter()
+                .map(|w| format!("-{w}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            "Ar" if words.is_empty() => "file ...".to_string(),
+            "Nm" if words.is_empty() => self.name.clone(),
+            "Xr" => match words.as_slice() {
+                [page, section] => format!("{page}({section})"),
+                _ => words.join(" "),
+            },
+            "Fn" => match words.split_first() {
+                Some((function, args)) if !self.literal() => {
+                    return format!(
+                        "{}({})",
+                        code(function),
+                        args.iter()
+                            .map(|a| format!("*{}*", escape(a)))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                Some((function, args)) => format!("{function}({})", args.join(", ")),
+                None => String::new(),
+            },
+            "Lk" => match words.split_first() {
+                Some((url, [])) if !self.literal() => return format!("<{url}>"),
+                Some((url, text)) if !self.literal() => {
+                    return format!("[{}]({url})", escape(&text.join(" ")))
+                }
+                Some((url, [])) => url.clone(),
+                Some((url, text)) => format!("{}: {url}", text.join(" ")),
+                None => String::new(),
+            },
+            "In" => format!("#include <{}>", words.join(" ")),
+            _ => words.join(" "),
+        };
+        if self.literal() || text.is_empty() {
+            return text;
+        }
+        match name {
+            // Each flag is code of its own, as the Markdown reader takes
+            // code starting with a dash for a flag.
+            "Fl" => text.split(' ').map(code).collect::<Vec<_>>().join(" "),
+            "Nm" | "Cm" | "Ic" | "Li" | "Ev" | "Dv" | "Er" | "Fd" | "Cd" | "In" | "Pa" => {
+                code(&text)
+            }
+            "Ar" | "Va" | "Fa" | "Ft" | "Vt" | "Em" => format!("*{}*", escape(&text)),
+            "Sy" => format!("**{}**", escape(&text)),
+            "Mt" => format!("[{}](mailto:{text})", escape(&text)),
+            _ => escape(&text),
+        }
+    }
+}
+
+/// Inline code, delimited by enough backticks for the ones it contains.
+fn code(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let delimiter = "`".repeat(longest + 1);
+    if longest > 0 {
+        format!("{delimiter} {text} {delimiter}")
+    } else {
+        format!("{delimiter}{text}{delimiter}")
+    }
+}
+
+/// Escape the characters of text that Markdown reads as markup. Underscores
+/// only start or end emphasis at the edges of words.
+pub fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut previous = ' ';
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied().unwrap_or(' ');
+        match c {
+            '\\' | '`' | '*' | '[' | ']' | '<' | '>' => ret.push('\\'),
+            '_' if !previous.is_alphanumeric() || !next.is_alphanumeric() => ret.push('\\'),
+            _ => {}
+        }
+        ret.push(c);
+        previous = c;
+    }
+    ret
+}
+
+/// Escape the start of a paragraph line that Markdown would read as a
+/// heading, a quote, a list item or a table.
+fn escape_start(line: &str) -> String {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if line.starts_with(['#', '>', '-', '+', '=', '|', ':', '~']) {
+        format!("\\{line}")
+    } else if digits > 0 && line[digits..].starts_with(['.', ')']) {
+        format!("{}\\{}", &line[..digits], &line[digits..])
+    } else {
+        line.to_string()
+    }
+}
@@ -0,0 +1,109 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use super::*;
+
+/// Where a line of a generated page comes from.
+///
+/// Importers record the origin of the lines they produce, so that problems
+/// found in the page, for example by [`lint`], can be reported against the
+/// source the page is generated from instead of the generated roff.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.control("Sh".into(), ["DESCRIPTION"]);
+/// doc.text([roman("Frobnicate the bar.")]);
+/// doc.set_origin(1, Origin::Line(12));
+/// let origins: Vec<String> = doc
+///     .lines_with_origins()
+///     .filter_map(|(_, origin)| origin.map(ToString::to_string))
+///     .collect();
+/// assert_eq!(origins, ["line 12"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// A line of the source file, counting from 1.
+    Line(usize),
+    /// The clap argument with this id.
+    Arg(String),
+    /// The boilerplate section with this title.
+    Template(String),
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Line(line) => write!(f, "line {line}"),
+            Self::Arg(id) => write!(f, "argument `{id}`"),
+            Self::Template(title) => write!(f, "the {title} template"),
+        }
+    }
+}
+
+/// The origins of the lines of a page, by their index.
+///
+/// The origins are not part of the content of a page, so they are ignored
+/// when comparing pages.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Origins(std::collections::BTreeMap<usize, Origin>);
+
+impl PartialEq for Origins {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Origins {}
+
+impl Mdoc {
+    /// Record that the line at `index` of [`Mdoc::lines`] comes from
+    /// `origin`.
+    ///
+    /// Origins are kept by index, so inserting or removing lines through
+    /// [`Mdoc::lines`] before `index` leaves them pointing at other lines.
+    pub fn set_origin(&mut self, index: usize, origin: Origin) {
+        self.origins.0.insert(index, origin);
+    }
+
+    /// Record that the lines in `range` come from `origin`.
+    pub fn set_origins(&mut self, range: std::ops::Range<usize>, origin: Origin) {
+        for index in range {
+            self.set_origin(index, origin.clone());
+        }
+    }
+
+    /// The origin of the line at `index`, if it is known.
+    pub fn origin(&self, index: usize) -> Option<&Origin> {
+        self.origins.0.get(&index)
+    }
+
+    /// The lines of the document with their origins.
+    pub fn lines_with_origins(&self) -> impl Iterator<Item = (&Line, Option<&Origin>)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| (line, self.origin(index)))
+    }
+}
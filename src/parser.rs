@@ -0,0 +1,579 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Read **mdoc** source text.
+//!
+//! Many legacy pages are not UTF-8. Source is read from bytes with
+//! [`decode`], which detects Latin-1 input, and the encoding is recorded in
+//! the [`Mdoc`] so that the page can be written back in kind with
+//! [`Mdoc::to_bytes`].
+//!
+//! Macro definitions, ignored blocks, preprocessor input such as tbl(1)
+//! tables and control lines with the no-break control character are kept
+//! verbatim as [`Unknown`] lines.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::parser::*;
+//! let page = read(b".Dd $Mdocdate$\n.Sh NAME\nCaf\xe9\n", None);
+//! assert_eq!(page.encoding(), Encoding::Latin1);
+//! assert_eq!(page.to_bytes(), b".Dd $Mdocdate$\n.Sh NAME\nCaf\xe9\n");
+//! ```
+
+use super::*;
+
+/// The character encoding of **mdoc** source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO 8859-1.
+    Latin1,
+}
+
+impl Encoding {
+    /// The name of the encoding, as used in coding tags.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "latin-1",
+        }
+    }
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "iso-latin-1" => Ok(Self::Latin1),
+            _ => Err(format!("unknown encoding: {s}")),
+        }
+    }
+}
+
+/// The encoding declared by an Emacs-style coding tag, such as
+/// `.\" -*- coding: latin-1 -*-`, on one of the first two lines, as
+/// understood by preconv(1).
+pub fn coding_tag(bytes: &[u8]) -> Option<Encoding> {
+    bytes.split(|&b| b == b'\n').take(2).find_map(|line| {
+        let line = String::from_utf8_lossy(line);
+        let (_, tag) = line.split_once("-*-")?;
+        let (tag, _) = tag.split_once("-*-")?;
+        tag.split(';').find_map(|variable| {
+            let (name, value) = variable.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("coding") {
+                value.trim().parse().ok()
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// Decode source text, returning it along with its encoding.
+///
+/// The encoding is `hint` if given, else the one declared by a
+/// [coding tag](coding_tag), else UTF-8 if the input is valid UTF-8 and
+/// Latin-1 otherwise.
+pub fn decode(bytes: &[u8], hint: Option<Encoding>) -> (String, Encoding) {
+    let encoding = hint.or_else(|| coding_tag(bytes)).unwrap_or_else(|| {
+        if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Latin1
+        }
+    });
+    let text = match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Encoding::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+    (text, encoding)
+}
+
+/// Encode text in `encoding`.
+///
+/// Characters that can't be represented in Latin-1 are written as
+/// `\[uXXXX]` escapes.
+pub fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Utf8 => text.as_bytes().to_vec(),
+        Encoding::Latin1 => {
+            let mut bytes = Vec::with_capacity(text.len());
+            for c in text.chars() {
+                match u8::try_from(u32::from(c)) {
+                    Ok(b) => bytes.push(b),
+                    Err(_) => bytes.extend(format!("\\[u{:04X}]", u32::from(c)).bytes()),
+                }
+            }
+            bytes
+        }
+    }
+}
+
+/// Options for [`parse_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// Keep the physical layout of control lines continued with a trailing
+    /// backslash, instead of joining them into one line.
+    ///
+    /// The line breaks are kept as arguments consisting of a backslash and
+    /// a newline, followed by the indentation of the next physical line.
+    pub lossless: bool,
+}
+
+/// Split **mdoc** source text into control and text lines.
+pub fn parse(source: &str) -> Mdoc {
+    parse_with(source, &ParseOptions::default())
+}
+
+/// Split **mdoc** source text into control and text lines with the given
+/// options.
+pub fn parse_with(source: &str, options: &ParseOptions) -> Mdoc {
+    parse_within(source, options, None).expect("parsing without limits succeeds")
+}
+
+/// Split **mdoc** source text into control and text lines, stopping with
+/// an error as soon as the page exceeds `limits`.
+///
+/// ```
+/// # use mdoc::parser::*;
+/// let limits = Limits {
+///     max_depth: 2,
+///     ..Limits::default()
+/// };
+/// let source = ".Bl -tag\n.It a\n.Bl -tag\n.It b\n.Bl -tag\n";
+/// assert_eq!(
+///     try_parse_with(source, &ParseOptions::default(), &limits),
+///     Err(LimitError::TooDeep { line: 5, max: 2 })
+/// );
+/// ```
+pub fn try_parse_with(
+    source: &str,
+    options: &ParseOptions,
+    limits: &Limits,
+) -> Result<Mdoc, LimitError> {
+    parse_within(source, options, Some(limits))
+}
+
+/// Limits on the size and nesting of pages, which keep pathological input
+/// from exhausting memory or the stack of the renderers.
+///
+/// They are enforced while parsing by [`try_parse_with`], and checked on
+/// pages and lists built in code with [`Mdoc::check_limits`] and
+/// [`ListBuilder::try_lines`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The most lines a page can have.
+    pub max_lines: usize,
+    /// The deepest nesting of blocks, such as `Bl` lists and `Bd`
+    /// displays, of roff conditionals and of enclosures, such as `Op`,
+    /// within a line.
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_lines: 100_000,
+            max_depth: 64,
+        }
+    }
+}
+
+impl Limits {
+    /// Check lines against the limits.
+    pub fn check(&self, lines: &[Line]) -> Result<(), LimitError> {
+        if lines.len() > self.max_lines {
+            return Err(LimitError::TooManyLines {
+                max: self.max_lines,
+            });
+        }
+        let mut nesting = Nesting::default();
+        for (line, index) in lines.iter().zip(1..) {
+            if nesting.line(line) > self.max_depth {
+                return Err(LimitError::TooDeep {
+                    line: index,
+                    max: self.max_depth,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a page exceeds its [`Limits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// The page has more than `max` lines.
+    TooManyLines { max: usize },
+    /// The line at position `line`, counting from 1, is nested more than
+    /// `max` deep.
+    TooDeep { line: usize, max: usize },
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyLines { max } => write!(f, "the page has more than {max} lines"),
+            Self::TooDeep { line, max } => {
+                write!(f, "line {line}: blocks are nested more than {max} deep")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+/// The macros that enclose the rest of their line, so that each one nests
+/// the following ones.
+const ENCLOSURES: &[&str] = &["Aq", "Bq", "Brq", "Dq", "Op", "Pq", "Qq", "Sq"];
+
+/// The nesting of the lines of a page, followed line by line.
+#[derive(Debug, Default)]
+pub(crate) struct Nesting {
+    depth: usize,
+}
+
+impl Nesting {
+    /// Follow `line`, returning the deepest nesting within it.
+    pub(crate) fn line(&mut self, line: &Line) -> usize {
+        let mut deepest = self.depth;
+        match line {
+            Line::Control { name, .. } if name == "\\\"" => {}
+            Line::Control { name, args } => {
+                if validate::BLOCKS.iter().any(|(open, _)| open == name) {
+                    self.depth += 1;
+                } else if validate::BLOCKS.iter().any(|(_, close)| close == name) {
+                    self.depth = self.depth.saturating_sub(1);
+                }
+                deepest = deepest.max(self.depth);
+                let enclosures = std::iter::once(name)
+                    .chain(args)
+                    .filter(|arg| ENCLOSURES.contains(&arg.as_ref()))
+                    .count();
+                deepest = deepest.max(self.depth + enclosures);
+                // Conditionals, as in `.if n \{\`, nest until `\}`.
+                let conditional = matches!(name.as_ref(), "if" | "ie" | "el");
+                for arg in args {
+                    self.braces(arg, conditional, &mut deepest);
+                }
+            }
+            Line::Unknown(unknown) => {
+                for line in unknown.source.lines() {
+                    let conditional = line
+                        .strip_prefix(['.', '\''])
+                        .and_then(|request| request.trim_start().split([' ', '\t']).next())
+                        .is_some_and(|request| matches!(request, "if" | "ie" | "el"));
+                    self.braces(line, conditional, &mut deepest);
+                }
+            }
+            _ => {
+                for inline in line.inlines().unwrap_or_default() {
+                    if let Inline::Roman(text) | Inline::Roff(text) = inline {
+                        self.braces(text, false, &mut deepest);
+                    }
+                }
+            }
+        }
+        deepest
+    }
+
+    /// Follow the conditional braces of `text`, counting the opening ones
+    /// only if `opens`.
+    fn braces(&mut self, text: &str, opens: bool, deepest: &mut usize) {
+        for (i, _) in text.match_indices('\\') {
+            match text[i + 1..].chars().next() {
+                Some('{') if opens => {
+                    self.depth += 1;
+                    *deepest = (*deepest).max(self.depth);
+                }
+                Some('}') => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(bytes = source.len())))]
+fn parse_within(
+    source: &str,
+    options: &ParseOptions,
+    limits: Option<&Limits>,
+) -> Result<Mdoc, LimitError> {
+    let mut page = Mdoc::default();
+    // Whether each open list is a `-column` list.
+    let mut lists = vec![];
+    // Pages written on Windows end their lines with `\r\n`, whose `\r`
+    // `lines` keeps on a last line without a line feed.
+    let mut lines = source
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line));
+    // The lines checked against the limits, and their nesting.
+    let mut checked = 0;
+    let mut nesting = Nesting::default();
+    loop {
+        if let Some(limits) = limits {
+            while let Some(line) = page.lines.get(checked) {
+                checked += 1;
+                if checked > limits.max_lines {
+                    return Err(LimitError::TooManyLines {
+                        max: limits.max_lines,
+                    });
+                }
+                if nesting.line(line) > limits.max_depth {
+                    return Err(LimitError::TooDeep {
+                        line: checked,
+                        max: limits.max_depth,
+                    });
+                }
+            }
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        if line.starts_with('\'') {
+            // Control lines with the no-break control character aren't
+            // modeled.
+            page.lines
+                .push(Line::Unknown(Unknown::new(SourceLanguage::Roff, line)));
+            continue;
+        }
+        match line.strip_prefix('.') {
+            Some(control) => {
+                let control = control.trim_start();
+                let (name, args) = control.split_once([' ', '\t']).unwrap_or((control, ""));
+                if let Some(end) = block_end(name, args) {
+                    let mut source = line.to_string();
+                    for next in lines.by_ref() {
+                        source.push('\n');
+                        source.push_str(next);
+                        if next.trim_end() == end {
+                            break;
+                        }
+                    }
+                    page.lines
+                        .push(Line::Unknown(Unknown::new(SourceLanguage::Roff, source)));
+                    continue;
+                }
+                // A control line ending with an unescaped backslash continues
+                // on the next physical line.
+                let mut pieces = vec![args];
+                while let Some(piece) = pieces.last().and_then(|p| continued(p)) {
+                    *pieces.last_mut().unwrap() = piece;
+                    match lines.next() {
+                        Some(next) => pieces.push(next),
+                        None => break,
+                    }
+                }
+                let column = lists.last() == Some(&true) && name == "It";
+                match name {
+                    "Bl" => lists.push(
+                        pieces
+                            .iter()
+                            .any(|p| p.split_whitespace().any(|a| a == "-column")),
+                    ),
+                    "El" => {
+                        lists.pop();
+                    }
+                    _ => {}
+                }
+                let mut args: Vec<Str> = vec![];
+                for (i, piece) in pieces.iter().enumerate() {
+                    if i > 0 && options.lossless {
+                        let indent = &piece[..piece.len() - piece.trim_start().len()];
+                        args.push(format!("\\\n{indent}").into());
+                    }
+                    args.extend(split_args(piece, column).into_iter().map(Into::into));
+                }
+                page.lines
+                    .push(Line::control(name.to_string().into(), args));
+            }
+            None => {
+                page.text([roff(line)]);
+            }
+        }
+    }
+    prologue(&mut page);
+    Ok(page)
+}
+
+/// The line ending the block of roff source started by the request `name`,
+/// for blocks that are kept verbatim: macro definitions, ignored text and
+/// preprocessor input.
+fn block_end(name: &str, args: &str) -> Option<String> {
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let end = |custom: Option<&&str>| format!(".{}", custom.copied().unwrap_or("."));
+    match name {
+        "de" | "de1" | "am" | "am1" => Some(end(args.get(1))),
+        "ig" => Some(end(args.first())),
+        "TS" => Some(".TE".to_string()),
+        "EQ" => Some(".EN".to_string()),
+        "PS" => Some(".PE".to_string()),
+        _ => None,
+    }
+}
+
+/// The line without its trailing backslash, if it is continued on the next
+/// line.
+fn continued(line: &str) -> Option<&str> {
+    let escapes = line.len() - line.trim_end_matches('\\').len();
+    (escapes % 2 == 1).then(|| &line[..line.len() - 1])
+}
+
+/// Split the arguments of a control line.
+///
+/// An argument starting with a double quote extends to the matching
+/// closing quote, spaces included; a doubled quote inside it stands for a
+/// literal one. Quoted arguments are kept with their quotes so that they
+/// are written back unchanged.
+///
+/// In the `It` lines of `-column` lists, tabs separate cells just like
+/// `Ta` does, so literal tabs and `\t` escapes are kept as `Ta` arguments
+/// to preserve the columns. Elsewhere they are whitespace.
+///
+/// ```
+/// # use mdoc::parser::split_args;
+/// assert_eq!(
+///     split_args(r#"Linux "6.1 LTS" "say ""hi""""#, false),
+///     ["Linux", "\"6.1 LTS\"", r#""say ""hi""""#]
+/// );
+/// ```
+pub fn split_args(args: &str, column: bool) -> Vec<String> {
+    if !column {
+        return words(args);
+    }
+    let mut ret = vec![];
+    for (i, cell) in args.replace("\\t", "\t").split('\t').enumerate() {
+        if i > 0 {
+            ret.push("Ta".to_string());
+        }
+        ret.extend(words(cell));
+    }
+    ret
+}
+
+/// Split `s` on whitespace outside of quoted arguments.
+fn words(s: &str) -> Vec<String> {
+    let mut ret = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut end = s.len();
+        if c == '"' {
+            chars.next();
+            while let Some((i, c)) = chars.next() {
+                if c == '"' && chars.next_if(|&(_, c)| c == '"').is_none() {
+                    end = i + 1;
+                    break;
+                }
+            }
+        } else {
+            while let Some(&(i, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    end = i;
+                    break;
+                }
+                chars.next();
+            }
+        }
+        ret.push(s[start..end].to_string());
+    }
+    ret
+}
+
+/// The value of an argument without its quotes.
+pub fn unquote(arg: &str) -> String {
+    match arg.strip_prefix('"') {
+        Some(quoted) => quoted
+            .strip_suffix('"')
+            .unwrap_or(quoted)
+            .replace("\"\"", "\""),
+        None => arg.to_string(),
+    }
+}
+
+/// Fill in the prologue and the NAME section fields of a parsed page from
+/// its `Dd`, `Dt`, `Os`, `Nm` and `Nd` lines.
+pub(crate) fn prologue(page: &mut Mdoc) {
+    let mut in_name = false;
+    for line in &page.lines {
+        let Line::Control { name, args } = line else {
+            continue;
+        };
+        let mut args: Vec<String> = args
+            .iter()
+            .filter(|a| !a.starts_with("\\\n"))
+            .map(|a| unquote(a))
+            .collect();
+        if name == "Dd" {
+            // `$Mdocdate: July 1 2024 $` is expanded by CVS.
+            args.retain(|a| !a.starts_with("$Mdocdate") && a != "$");
+        }
+        match (name.as_ref(), args.as_slice()) {
+            ("Dd", [month, day, year]) => {
+                page.date = Some(DocumentDate {
+                    month: Month::new(month.clone()),
+                    day: Day::new(day.trim_end_matches(',').to_string()),
+                    year: Year::new(year.clone()),
+                });
+            }
+            ("Dt", [title, section, arch @ ..]) => {
+                page.title = DocumentTitle {
+                    title: Title::new(title.clone()),
+                    section: Section::new(section.clone()),
+                    arch: arch.first().map(|a| Arch::new(a.clone())),
+                };
+            }
+            ("Os", [system, version @ ..]) => {
+                page.os = Some(OperatingSystem {
+                    system: System::new(system.clone()),
+                    version: version.first().map(|v| Version::new(v.clone())),
+                });
+            }
+            ("Sh", _) => in_name = args.join(" ") == "NAME",
+            ("Nm", [first, ..]) if in_name && page.name.0.is_empty() => {
+                page.name = Name::new(first.clone());
+            }
+            ("Nd", _) if in_name => {
+                page.description = Description::new(args.join(" "));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Decode and parse **mdoc** source, recording its encoding in the page.
+pub fn read(bytes: &[u8], hint: Option<Encoding>) -> Mdoc {
+    let (source, encoding) = decode(bytes, hint);
+    let mut page = parse(&source);
+    page.set_encoding(encoding);
+    page
+}
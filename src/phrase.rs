@@ -0,0 +1,223 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Lowering of the arguments of **mdoc** macro lines, which can call other
+//! macros, to the text of a backend.
+
+use super::*;
+
+/// How a backend marks up the parts of a phrase.
+pub(crate) trait Markup {
+    /// The name of the page, for `Nm` without arguments.
+    fn name(&self) -> &str;
+
+    /// A word or delimiter that isn't a macro.
+    fn text(&self, text: &str) -> String;
+
+    /// The delimiters of the enclosure macro `name`, such as `Op`.
+    fn enclosure(&self, name: &str) -> Option<(&'static str, &'static str)>;
+
+    /// A call of the macro `name` with its words, up to the next macro or
+    /// delimiter.
+    fn call(&self, name: &str, words: &[&str]) -> String;
+}
+
+/// Lower the arguments of a macro line to text.
+pub(crate) fn phrase(markup: &dyn Markup, args: &[Str]) -> String {
+    lower(markup, args, false)
+}
+
+/// Lower a macro line, whose macro need not be callable, to text.
+pub(crate) fn macro_line(markup: &dyn Markup, name: &str, args: &[Str]) -> String {
+    lower(markup, &[&[name.to_string().into()], args].concat(), true)
+}
+
+fn lower(markup: &dyn Markup, args: &[Str], macro_line: bool) -> String {
+    let mut ret = String::new();
+    // Whether a space separates the next word from the previous one.
+    let mut space = false;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_ref();
+        i += 1;
+        if arg == "Ns" {
+            space = false;
+            continue;
+        }
+        if is_closing(arg) {
+            ret.push_str(&markup.text(arg));
+            space = true;
+            continue;
+        }
+        if space {
+            ret.push(' ');
+        }
+        if matches!(arg, "(" | "[") {
+            ret.push_str(&markup.text(arg));
+            space = false;
+            continue;
+        }
+        if is_callable(arg) || (macro_line && i == 1) {
+            let (text, used) = call(markup, arg, &args[i..]);
+            ret.push_str(&text);
+            i += used;
+        } else {
+            ret.push_str(&markup.text(arg));
+        }
+        space = true;
+    }
+    ret
+}
+
+/// Lower a call of the macro `name`, returning the text and the number of
+/// arguments of `rest` it consumed.
+fn call(markup: &dyn Markup, name: &str, rest: &[Str]) -> (String, usize) {
+    if let Some((open, close)) = markup.enclosure(name) {
+        // Trailing punctuation goes after the closing delimiter.
+        let n = rest.len() - rest.iter().rev().take_while(|a| is_closing(a)).count();
+        return (format!("{open}{}{close}", phrase(markup, &rest[..n])), n);
+    }
+    let mut n = rest
+        .iter()
+        .take_while(|a| !is_callable(a) && !is_delimiter(a))
+        .count();
+    if name == "Xr" {
+        n = n.min(2);
+    }
+    let words: Vec<&str> = rest[..n].iter().map(AsRef::as_ref).collect();
+    let text = match name {
+        "Ex" | "Rv" => standard(markup, name, &words),
+        _ => match system(name, &words) {
+            Some(system) => markup.text(&system),
+            None => markup.call(name, &words),
+        },
+    };
+    (text, n)
+}
+
+/// The names of operating systems written with `Ux`, `Bx` and the like.
+fn system(name: &str, words: &[&str]) -> Option<String> {
+    let version = words.join(" ");
+    let system = match name {
+        "Ux" => "UNIX",
+        "At" => "AT&T UNIX",
+        "Bx" => return Some(format!("{version}BSD")),
+        "Nx" => "NetBSD",
+        "Fx" => "FreeBSD",
+        "Ox" => "OpenBSD",
+        "Dx" => "DragonFly",
+        _ => return None,
+    };
+    Some(format!("{system} {version}").trim_end().to_string())
+}
+
+/// The standard sentences of `Ex -std` and `Rv -std`.
+fn standard(markup: &dyn Markup, name: &str, words: &[&str]) -> String {
+    let mut names: Vec<&str> = words.iter().copied().filter(|w| *w != "-std").collect();
+    if names.is_empty() {
+        names.push(markup.name());
+    }
+    let plural = names.len() > 1;
+    let names: Vec<String> = names
+        .iter()
+        .map(|n| match name {
+            "Rv" => markup.call("Fn", &[n]),
+            _ => markup.call("Nm", &[n]),
+        })
+        .collect();
+    let names = match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => unreachable!(),
+    };
+    let sentence = match (name, plural) {
+        ("Rv", false) => " function returns the value 0 if successful; otherwise the value -1 is returned and the global variable ",
+        ("Rv", true) => " functions return the value 0 if successful; otherwise the value -1 is returned and the global variable ",
+        (_, false) => " utility exits 0 on success, and >0 if an error occurs.",
+        (_, true) => " utilities exit 0 on success, and >0 if an error occurs.",
+    };
+    let mut ret = format!("{}{names}{}", markup.text("The "), markup.text(sentence));
+    if name == "Rv" {
+        ret.push_str(&markup.call("Va", &["errno"]));
+        ret.push_str(&markup.text(" is set to indicate the error."));
+    }
+    ret
+}
+
+pub(crate) fn is_closing(arg: &str) -> bool {
+    matches!(arg, "." | "," | ":" | ";" | ")" | "]" | "?" | "!")
+}
+
+pub(crate) fn is_delimiter(arg: &str) -> bool {
+    is_closing(arg) || matches!(arg, "(" | "[" | "|")
+}
+
+/// Whether `arg` is the name of a macro that can be called from the
+/// arguments of another.
+pub(crate) fn is_callable(arg: &str) -> bool {
+    matches!(
+        arg,
+        "Ad" | "An"
+            | "Aq"
+            | "Ar"
+            | "At"
+            | "Bq"
+            | "Brq"
+            | "Bx"
+            | "Cd"
+            | "Cm"
+            | "Dq"
+            | "Dv"
+            | "Dx"
+            | "Em"
+            | "Er"
+            | "Ev"
+            | "Fa"
+            | "Fl"
+            | "Fn"
+            | "Ft"
+            | "Fx"
+            | "Ic"
+            | "Li"
+            | "Lk"
+            | "Ms"
+            | "Mt"
+            | "Nm"
+            | "No"
+            | "Ns"
+            | "Nx"
+            | "Op"
+            | "Ox"
+            | "Pa"
+            | "Pq"
+            | "Ql"
+            | "Qq"
+            | "Sq"
+            | "Sx"
+            | "Sy"
+            | "Tn"
+            | "Ux"
+            | "Va"
+            | "Vt"
+            | "Xr"
+    )
+}
@@ -0,0 +1,85 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Progress of batch operations over many pages.
+
+use std::path::Path;
+
+/// The progress of an operation over the pages of a [`Manual`](crate::Manual)
+/// or a tree of pages, reported once per page.
+///
+/// ```
+/// # use mdoc::*;
+/// # use std::path::Path;
+/// let progress = Progress {
+///     current: 3,
+///     total: 120,
+///     path: Path::new("man1/ls.1"),
+///     status: PageStatus::Checked { problems: 2 },
+/// };
+/// assert_eq!(progress.to_string(), "[3/120] man1/ls.1: 2 problem(s)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress<'a> {
+    /// The position of the page, starting at 1.
+    pub current: usize,
+    /// The number of pages of the operation.
+    pub total: usize,
+    /// The file of the page.
+    pub path: &'a Path,
+    pub status: PageStatus,
+}
+
+/// What was done to a page, see [`Progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageStatus {
+    /// The page was rendered and written.
+    Written,
+    /// The page was up to date.
+    Skipped,
+    /// The page was checked, and has `problems` diagnostics.
+    Checked { problems: usize },
+}
+
+impl Progress<'_> {
+    /// Whether this is the last page of the operation.
+    pub fn is_last(&self) -> bool {
+        self.current == self.total
+    }
+}
+
+impl std::fmt::Display for Progress<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}/{}] {}: ",
+            self.current,
+            self.total,
+            self.path.display()
+        )?;
+        match self.status {
+            PageStatus::Written => write!(f, "written"),
+            PageStatus::Skipped => write!(f, "up to date"),
+            PageStatus::Checked { problems: 0 } => write!(f, "ok"),
+            PageStatus::Checked { problems } => write!(f, "{problems} problem(s)"),
+        }
+    }
+}
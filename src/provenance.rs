@@ -0,0 +1,208 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Provenance stamps of generated pages.
+//!
+//! With [`RenderOptions::provenance`] set, rendered pages start with
+//! comment lines naming the generator, the source the page was generated
+//! from and a hash of the rest of the output. Tools can [`check`] the stamp
+//! of a file before regenerating it, to avoid overwriting hand edits, as
+//! [`check_overwrite`] does for the files written by this crate.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::provenance::*;
+//! let mut page = Mdoc::default();
+//! page.control("Sh".into(), ["NAME"]);
+//! let options = RenderOptions {
+//!     provenance: Some(Provenance::new(Some("cli.rs"))),
+//!     ..RenderOptions::default()
+//! };
+//! let output = page.render_with(&options);
+//! let stamp = Stamp::read(&output).unwrap();
+//! assert_eq!(stamp.source.as_deref(), Some("cli.rs"));
+//! assert_eq!(check(&output), Status::Unmodified);
+//! assert_eq!(check(&output.replace("NAME", "EMAN")), Status::Modified);
+//! ```
+
+use super::*;
+
+/// The generator and source of a page, stamped on it when rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// Name and version of the generator, this crate by default.
+    pub generator: String,
+    /// The file the page was generated from, if any.
+    pub source: Option<String>,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self {
+            generator: concat!("mdoc ", env!("CARGO_PKG_VERSION")).to_string(),
+            source: None,
+        }
+    }
+}
+
+impl Provenance {
+    pub fn new(source: Option<impl Into<String>>) -> Self {
+        Self {
+            source: source.map(Into::into),
+            ..Self::default()
+        }
+    }
+
+    /// Write the stamp for `content`, followed by `content`.
+    pub fn write_stamped(&self, content: &[u8], w: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(w, ".\\\" Generator: {}", self.generator)?;
+        if let Some(source) = &self.source {
+            writeln!(w, ".\\\" Source: {source}")?;
+        }
+        writeln!(w, ".\\\" Content-Hash: {}", hash(content))?;
+        w.write_all(content)
+    }
+}
+
+/// A provenance stamp read back from a rendered page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamp {
+    pub generator: String,
+    pub source: Option<String>,
+    /// The hash of the output after the stamp when it was generated.
+    pub hash: String,
+    /// The output after the stamp.
+    content: String,
+}
+
+impl Stamp {
+    /// Read the stamp at the start of `text`, if any.
+    pub fn read(text: &str) -> Option<Self> {
+        let mut generator = None;
+        let mut source = None;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let Some(field) = line.strip_prefix(".\\\" ") else {
+                break;
+            };
+            let (key, value) = field.trim_end().split_once(": ")?;
+            offset += line.len();
+            match key {
+                "Generator" => generator = Some(value.to_string()),
+                "Source" => source = Some(value.to_string()),
+                "Content-Hash" => {
+                    return Some(Self {
+                        generator: generator?,
+                        source,
+                        hash: value.to_string(),
+                        content: text[offset..].to_string(),
+                    })
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Whether the output after the stamp is unchanged since it was
+    /// generated.
+    pub fn is_intact(&self) -> bool {
+        hash(self.content.as_bytes()) == self.hash
+    }
+
+    /// The output after the stamp.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// The number of lines of the stamp at the start of `lines`, such as those
+/// of a generated page that was parsed back, which a new stamp replaces.
+pub(crate) fn stamp_len(lines: &[Line]) -> usize {
+    for (i, line) in lines.iter().enumerate() {
+        let key = match line {
+            Line::Control { name, args } if name == "\\\"" => args.first(),
+            _ => None,
+        };
+        match key.map(|key| key.as_ref()) {
+            Some("Generator:" | "Source:") => {}
+            Some("Content-Hash:") => return i + 1,
+            _ => return 0,
+        }
+    }
+    0
+}
+
+/// Whether a page was generated and left as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The page has no provenance stamp, so it was written by hand or by
+    /// another tool.
+    Unstamped,
+    /// The page was edited after it was generated.
+    Modified,
+    Unmodified,
+}
+
+/// Check the provenance stamp of a page.
+pub fn check(text: &str) -> Status {
+    match Stamp::read(text) {
+        None => Status::Unstamped,
+        Some(stamp) if stamp.is_intact() => Status::Unmodified,
+        Some(_) => Status::Modified,
+    }
+}
+
+/// Refuse to overwrite `path` if it was written by hand or edited after it
+/// was generated, that is unless it is missing or its stamp is intact.
+///
+/// Gzip-compressed files are decompressed to be checked.
+pub fn check_overwrite(path: &std::path::Path) -> std::io::Result<()> {
+    let text = match manual::read_source(path) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        text => text?,
+    };
+    let reason = match check(&text) {
+        Status::Unmodified => return Ok(()),
+        Status::Unstamped => "it has no provenance stamp",
+        Status::Modified => "it was edited after it was generated",
+    };
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("refusing to overwrite {}: {reason}", path.display()),
+    ))
+}
+
+/// The content hash written in stamps.
+fn hash(content: &[u8]) -> String {
+    format!("fnv1a:{:016x}", fnv1a(content))
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which unlike the hashers of the
+/// standard library is stable across releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
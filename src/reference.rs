@@ -0,0 +1,155 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use super::*;
+
+/// A bibliographic reference, rendered as an `Rs`/`Re` block.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// doc.control("Sh".into(), ["SEE ALSO"]);
+/// Reference::new("The UNIX Time-Sharing System")
+///     .author("Dennis M. Ritchie")
+///     .author("Ken Thompson")
+///     .journal("Communications of the ACM")
+///     .volume("17")
+///     .date("July 1974")
+///     .add_to(&mut doc, "SEE ALSO");
+/// assert_eq!(
+///     doc.to_mdoc(),
+///     ".Sh SEE ALSO\n.Rs\n.%A Dennis M. Ritchie\n.%A Ken Thompson\n.%T The UNIX Time-Sharing System\n.%J Communications of the ACM\n.%V 17\n.%D July 1974\n.Re\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Reference {
+    /// `%A`
+    pub authors: Vec<String>,
+    /// `%T`, the title of an article, chapter or page.
+    pub title: String,
+    /// `%B`
+    pub book: Option<String>,
+    /// `%J`
+    pub journal: Option<String>,
+    /// `%R`, a technical report.
+    pub report: Option<String>,
+    /// `%I`
+    pub publisher: Option<String>,
+    /// `%Q`, an institutional author.
+    pub institution: Option<String>,
+    /// `%C`, the city of publication.
+    pub city: Option<String>,
+    /// `%V`
+    pub volume: Option<String>,
+    /// `%N`, the issue number.
+    pub number: Option<String>,
+    /// `%P`
+    pub pages: Option<String>,
+    /// `%D`
+    pub date: Option<String>,
+    /// `%U`
+    pub url: Option<String>,
+    /// `%O`, any other information.
+    pub other: Option<String>,
+}
+
+macro_rules! setters {
+    ($($(#[$doc:meta])* $field:ident),*$(,)?) => {
+        $(
+            $(#[$doc])*
+            pub fn $field(self, value: impl Into<String>) -> Self {
+                Self {
+                    $field: Some(value.into()),
+                    ..self
+                }
+            }
+        )*
+    };
+}
+
+impl Reference {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add an author.
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.authors.push(author.into());
+        self
+    }
+
+    setters! {
+        book,
+        journal,
+        report,
+        publisher,
+        institution,
+        city,
+        volume,
+        number,
+        pages,
+        date,
+        url,
+        other,
+    }
+
+    /// The `Rs`, `%` and `Re` lines of the reference.
+    pub fn lines(&self) -> Vec<Line> {
+        let field = |name: &'static str, value: &str| {
+            Line::control(name.into(), vec![value.to_string().into()])
+        };
+        let mut lines = vec![Line::control("Rs".into(), vec![])];
+        lines.extend(self.authors.iter().map(|a| field("%A", a)));
+        lines.push(field("%T", &self.title));
+        for (name, value) in [
+            ("%B", &self.book),
+            ("%J", &self.journal),
+            ("%R", &self.report),
+            ("%I", &self.publisher),
+            ("%Q", &self.institution),
+            ("%C", &self.city),
+            ("%V", &self.volume),
+            ("%N", &self.number),
+            ("%P", &self.pages),
+            ("%D", &self.date),
+            ("%U", &self.url),
+            ("%O", &self.other),
+        ] {
+            if let Some(value) = value {
+                lines.push(field(name, value));
+            }
+        }
+        lines.push(Line::control("Re".into(), vec![]));
+        lines
+    }
+
+    /// Append the reference to the section `title` of `doc`, usually SEE
+    /// ALSO or STANDARDS, which is added if it is missing.
+    pub fn add_to(&self, doc: &mut Mdoc, title: &str) {
+        doc.append_to_section(title, self.lines());
+    }
+}
@@ -0,0 +1,255 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+//! Parse pages from untrusted sources, such as pages submitted to a web
+//! service, within resource limits.
+//!
+//! A [`Sandbox`] rejects pages that are too large or too deeply nested to
+//! render cheaply, see [`parser::Limits`], and pages that read other files or run commands when
+//! formatted with groff(1), such as with `.so` or `.sy` requests. The pages
+//! it accepts can be rendered with any of the renderers of the crate.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::sandbox::*;
+//! let sandbox = Sandbox::default();
+//! assert!(sandbox.parse(".Sh NAME\n.Nm foo\n.Nd frobnicate\n").is_ok());
+//! assert_eq!(
+//!     sandbox.parse(".Sh NAME\n.so /etc/passwd\n").unwrap_err().to_string(),
+//!     "line 2: so requests are not allowed"
+//! );
+//! ```
+
+use super::*;
+
+/// Limits for parsing untrusted pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sandbox {
+    /// The largest accepted source, in bytes.
+    pub max_size: usize,
+    /// The most lines a page can have.
+    pub max_lines: usize,
+    /// The deepest accepted nesting of blocks, such as `Bl` lists and `Bd`
+    /// displays, and of roff conditionals.
+    pub max_depth: usize,
+    /// Whether requests that include other files or run commands, and
+    /// inclusions of [snippets](crate::Snippet), are allowed.
+    pub allow_includes: bool,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            max_size: 1024 * 1024,
+            max_lines: parser::Limits::default().max_lines,
+            max_depth: 16,
+            allow_includes: false,
+        }
+    }
+}
+
+/// Why a [`Sandbox`] rejected a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The source is larger than [`Sandbox::max_size`].
+    TooLarge { size: usize, max: usize },
+    /// The page has more than [`Sandbox::max_lines`] lines.
+    TooManyLines { max: usize },
+    /// The block opened on `line` is nested deeper than
+    /// [`Sandbox::max_depth`].
+    TooDeep { line: usize, max: usize },
+    /// The `request` on `line` includes a file or runs a command.
+    Include { line: usize, request: String },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge { size, max } => {
+                write!(f, "the page is {size} bytes long, more than {max}")
+            }
+            Self::TooManyLines { max } => write!(f, "the page has more than {max} lines"),
+            Self::TooDeep { line, max } => {
+                write!(f, "line {line}: blocks are nested more than {max} deep")
+            }
+            Self::Include { line, request } => {
+                write!(f, "line {line}: {request} requests are not allowed")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl From<parser::LimitError> for Violation {
+    fn from(err: parser::LimitError) -> Self {
+        match err {
+            parser::LimitError::TooManyLines { max } => Self::TooManyLines { max },
+            parser::LimitError::TooDeep { line, max } => Self::TooDeep { line, max },
+        }
+    }
+}
+
+/// The roff requests that read files, write files or run commands.
+const INCLUDES: &[&str] = &[
+    "so",
+    "soquiet",
+    "mso",
+    "msoquiet",
+    "pso",
+    "sy",
+    "nx",
+    "rd",
+    "cf",
+    "trf",
+    "open",
+    "opena",
+    "pi",
+    "mdoc-include",
+];
+
+/// The roff requests that rename, alias or define requests and macros,
+/// which could make an include request available under another name.
+const DEFINITIONS: &[&str] = &[
+    "rn", "als", "am", "am1", "ami", "ami1", "de", "de1", "dei", "dei1",
+];
+
+/// The requests invoked by a request line of `words`, the first of which
+/// is the name of the request, and the requests it renames or defines.
+///
+/// The request of a `do` request is invoked in compatibility mode, and the
+/// bodies of conditionals, and lines continued with a trailing backslash as
+/// in `.if n \{\`, are requests that start with `.` or `'`.
+fn requests<'a>(words: &[&'a str]) -> Vec<&'a str> {
+    let [name, args @ ..] = words else {
+        return vec![];
+    };
+    if *name == "do" {
+        return requests(args);
+    }
+    let mut ret = vec![*name];
+    if DEFINITIONS.contains(name) {
+        ret.extend(args.iter().take(2));
+    }
+    for (i, arg) in args.iter().enumerate() {
+        let arg = arg.trim_start_matches("\\{");
+        if let Some(request) = arg.strip_prefix(['.', '\'']).filter(|r| !r.is_empty()) {
+            let mut body = vec![request];
+            body.extend(&args[i + 1..]);
+            ret.extend(requests(&body));
+            break;
+        }
+    }
+    ret
+}
+
+/// Whether `request` includes a file or runs a command, or could do so
+/// because its name is interpolated from a string, as in `.\*x`.
+fn is_include(request: &str) -> bool {
+    INCLUDES.contains(&request) || request.starts_with("\\*")
+}
+
+impl Sandbox {
+    /// Parse `source`, see [`parser::parse`], if it is within the limits.
+    pub fn parse(&self, source: &str) -> Result<Mdoc, Violation> {
+        self.check_size(source.len())?;
+        let page = parser::try_parse_with(source, &parser::ParseOptions::default(), &self.limits())
+            .map_err(Violation::from)?;
+        self.check(&page)?;
+        Ok(page)
+    }
+
+    /// Decode and parse `bytes`, see [`parser::read`], if they are within
+    /// the limits.
+    pub fn read(&self, bytes: &[u8]) -> Result<Mdoc, Violation> {
+        self.check_size(bytes.len())?;
+        let (source, encoding) = parser::decode(bytes, None);
+        let mut page = self.parse(&source)?;
+        page.set_encoding(encoding);
+        Ok(page)
+    }
+
+    /// Check a page that was imported rather than parsed, such as a page
+    /// converted with [`from_man`](crate::from_man) or
+    /// [`markdown`](crate::markdown), against the limits.
+    pub fn check(&self, page: &Mdoc) -> Result<(), Violation> {
+        self.limits().check(&page.lines)?;
+        if self.allow_includes {
+            return Ok(());
+        }
+        for (line, index) in page.lines.iter().zip(1..) {
+            let requests: Vec<&str> = match line {
+                Line::Control { name, args } if name == "\\\"" => {
+                    // Inclusions of snippets are comments, see
+                    // `Mdoc::include`.
+                    args.first()
+                        .filter(|arg| *arg == "mdoc-include")
+                        .map(|arg| arg.as_ref())
+                        .into_iter()
+                        .collect()
+                }
+                Line::Control { name, args } => {
+                    let words: Vec<&str> = std::iter::once(name.as_ref())
+                        .chain(args.iter().map(|arg| arg.as_ref()))
+                        .collect();
+                    requests(&words)
+                }
+                Line::Unknown(unknown) => unknown
+                    .source
+                    .lines()
+                    .flat_map(|line| {
+                        let words: Vec<&str> = line
+                            .strip_prefix(['.', '\''])
+                            .map(|request| request.split_whitespace().collect())
+                            .unwrap_or_default();
+                        requests(&words)
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            if let Some(request) = requests.into_iter().find(|r| is_include(r)) {
+                return Err(Violation::Include {
+                    line: index,
+                    request: request.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn limits(&self) -> parser::Limits {
+        parser::Limits {
+            max_lines: self.max_lines,
+            max_depth: self.max_depth,
+        }
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), Violation> {
+        if size > self.max_size {
+            return Err(Violation::TooLarge {
+                size,
+                max: self.max_size,
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Convert the comment header of a shell script to a manual page.
+//!
+//! The header is the first block of `#` comments of the script, after the
+//! optional `#!` line. It is divided in sections by lines consisting of an
+//! upper case heading, and must contain at least a NAME section:
+//!
+//! ```sh
+//! #!/bin/sh
+//! #
+//! # NAME
+//! #   backup - copy files to the backup server
+//! #
+//! # SYNOPSIS
+//! #   backup [-n] [-d dir] file ...
+//! #
+//! # DESCRIPTION
+//! #   Copies each file to the backup server.
+//! #
+//! # OPTIONS
+//! #   -n        Print what would be copied.
+//! #   -d dir    Destination directory.
+//! ```
+//!
+//! The SYNOPSIS is converted to `Op`, `Fl` and `Ar` macros, and the OPTIONS
+//! to a `Bl -tag` list at the end of the DESCRIPTION. Every other section is
+//! copied as text, with blank lines starting new paragraphs.
+//!
+//! # Example
+//!
+//! ```
+//! let script = "#!/bin/sh\n# NAME\n#   backup - copy files\n# SYNOPSIS\n#   backup [-d dir] file ...\n";
+//! let page = mdoc::script::from_script(script).unwrap();
+//! assert!(page
+//!     .render()
+//!     .ends_with(".Sh SYNOPSIS\n.Nm\n.Op Fl d Ar dir\n.Ar file ...\n"));
+//! ```
+
+use super::*;
+
+/// An error encountered while converting a script header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(pub String);
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Split the comment header of `source` into `(heading, lines)` sections.
+///
+/// The lines of each section are dedented by the smallest indentation of
+/// the section.
+pub fn header_sections(source: &str) -> Vec<(String, Vec<String>)> {
+    let mut sections: Vec<(String, Vec<String>)> = vec![];
+    for (i, line) in source.lines().enumerate() {
+        if i == 0 && line.starts_with("#!") {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix('#') else {
+            break;
+        };
+        let comment = comment.strip_prefix(' ').unwrap_or(comment);
+        let is_heading = !comment.is_empty()
+            && !comment.starts_with(char::is_whitespace)
+            && comment.chars().any(|c| c.is_alphabetic())
+            && !comment.chars().any(|c| c.is_lowercase());
+        if is_heading {
+            sections.push((comment.trim().to_string(), vec![]));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(comment.trim_end().to_string());
+        }
+    }
+    for (_, lines) in sections.iter_mut() {
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        let indent = lines
+            .iter()
+            .filter(|l| !l.is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        for line in lines.iter_mut() {
+            *line = line.get(indent..).unwrap_or_default().to_string();
+        }
+    }
+    sections
+}
+
+/// Convert the comment header of a shell script to a section 1 page.
+pub fn from_script(source: &str) -> Result<Mdoc, Error> {
+    from_script_with_warnings(source, &mut Warnings::new())
+}
+
+/// Like [`from_script`], reporting the parts of the header that couldn't
+/// be converted to `warnings`, or failing if they are
+/// [denied](Strictness::Deny).
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn from_script_with_warnings(source: &str, warnings: &mut Warnings) -> Result<Mdoc, Error> {
+    let sections = header_sections(source);
+    let name_section = sections
+        .iter()
+        .find(|(heading, _)| heading == "NAME")
+        .ok_or_else(|| Error("the script header has no NAME section".into()))?;
+    let name_line = name_section.1.join(" ");
+    let (name, description) = name_line
+        .split_once(" - ")
+        .map(|(n, d)| (n.trim(), d.trim()))
+        .ok_or_else(|| Error("the NAME section must be of the form `name - description`".into()))?;
+
+    let mut page = Mdoc::new(
+        None,
+        DocumentTitle {
+            title: Title::new(name.to_uppercase()),
+            section: Section::new("1"),
+            arch: None,
+        },
+        Name::new(name.to_string()),
+        Description::new(description.to_string()),
+        None,
+    );
+    let options = sections
+        .iter()
+        .find(|(heading, _)| heading == "OPTIONS")
+        .map(|(_, lines)| options(lines, warnings));
+    for (heading, lines) in &sections {
+        match heading.as_str() {
+            "NAME" | "OPTIONS" => {}
+            "SYNOPSIS" => page.add_section(heading.as_str(), synopsis(name, lines, warnings)),
+            "DESCRIPTION" => {
+                let mut body = paragraphs(lines);
+                body.extend(options.clone().unwrap_or_default());
+                page.add_section(heading.as_str(), body);
+            }
+            _ => page.add_section(heading.as_str(), paragraphs(lines)),
+        }
+    }
+    if let Some(options) = options {
+        if !sections.iter().any(|(heading, _)| heading == "DESCRIPTION") {
+            page.add_section("DESCRIPTION", options);
+        }
+    }
+    warnings
+        .check()
+        .map_err(|denied| Error(denied.to_string()))?;
+    Ok(page)
+}
+
+/// Text lines, with blank lines converted to `Pp`.
+pub(crate) fn paragraphs(lines: &[String]) -> Vec<Line> {
+    let mut ret = vec![];
+    for line in lines {
+        if line.trim().is_empty() {
+            if !matches!(ret.last(), None | Some(Line::Control { .. })) {
+                ret.push(Line::control("Pp".into(), vec![]));
+            }
+        } else {
+            ret.push(Line::text(vec![roman(line.trim())]));
+        }
+    }
+    ret
+}
+
+/// `Fl` arguments for a word of the form `-x` or `--long`.
+fn flag(word: &str) -> Vec<Str> {
+    vec!["Fl".into(), word[1..].to_string().into()]
+}
+
+/// Convert each synopsis line to `Nm`, `Op`, `Fl` and `Ar` macros, with
+/// `Oo` and `Oc` around groups that nest another group before their end.
+fn synopsis(name: &str, lines: &[String], warnings: &mut Warnings) -> Vec<Line> {
+    let mut ret = vec![];
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        let mut words = line.split_whitespace().peekable();
+        if words.peek() == Some(&name) {
+            words.next();
+        }
+        ret.push(Line::control("Nm".into(), vec![]));
+        let mut groups = brackets::Groups::new();
+        for word in words {
+            let opening = word.trim_start_matches('[');
+            for _ in opening.len()..word.len() {
+                groups.open();
+            }
+            let word = opening.trim_end_matches(']');
+            let closes = opening.len() - word.len();
+            match word {
+                "" => {}
+                // Ellipses follow the preceding argument.
+                "..." => groups.follow("...".into()),
+                w if w.starts_with('-') => groups.push(flag(w)),
+                w => groups.push(vec!["Ar".into(), w.to_string().into()]),
+            }
+            for _ in 0..closes {
+                if !groups.close() {
+                    warnings.push_in(
+                        "SYNOPSIS",
+                        format!("unmatched bracket ignored: {}", line.trim()),
+                    );
+                    break;
+                }
+            }
+        }
+        if groups.is_open() {
+            warnings.push_in(
+                "SYNOPSIS",
+                format!("unclosed bracket dropped: {}", line.trim()),
+            );
+        }
+        ret.extend(groups.lines());
+    }
+    ret
+}
+
+/// Convert an OPTIONS section, where each option starts on an unindented
+/// line and is separated from its description by two or more spaces, to a
+/// `Bl -tag` list.
+fn options(lines: &[String], warnings: &mut Warnings) -> Vec<Line> {
+    let mut ret = vec![Line::control(
+        "Bl".into(),
+        vec!["-tag".into(), "-width".into(), "Ds".into()],
+    )];
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        if !line.starts_with('-') {
+            ret.push(Line::text(vec![roman(line.trim())]));
+            continue;
+        }
+        let (spec, description) = line.split_once("  ").unwrap_or_else(|| {
+            warnings.push_in(
+                "OPTIONS",
+                format!("no two spaces before the description of {}", line.trim()),
+            );
+            (line, "")
+        });
+        let mut it: Vec<Str> = vec![];
+        for (i, form) in spec.split(',').enumerate() {
+            if i > 0 {
+                it.push(",".into());
+            }
+            let mut words = form.split(['=', ' ']).filter(|w| !w.is_empty());
+            if let Some(flag_word) = words.next() {
+                it.extend(flag(flag_word));
+            }
+            for value in words {
+                it.extend(["Ar".into(), value.to_string().into()]);
+            }
+        }
+        ret.push(Line::control("It".into(), it));
+        if !description.trim().is_empty() {
+            ret.push(Line::text(vec![roman(description.trim())]));
+        }
+    }
+    ret.push(Line::control("El".into(), vec![]));
+    ret
+}
@@ -0,0 +1,1069 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Builders for structured sections: tables, tagged lists and other
+//! content that follows a fixed layout.
+//!
+//! Each builder produces the body of a section with `lines()` and a
+//! complete [`Boilerplate`] section with a conventional title with
+//! `section()`.
+
+use super::*;
+use crate::boilerplate::Boilerplate;
+
+/// `text` as a single macro argument, such as a table cell: line breaks
+/// become spaces, and an empty cell or a first word that would be called as
+/// a macro, taken as a delimiter or start a quoted argument is escaped with
+/// `\&`.
+fn cell(text: &str) -> String {
+    let text = text.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+    match text.split_whitespace().next() {
+        Some(first)
+            if !phrase::is_callable(first)
+                && first != "Ta"
+                && !phrase::is_delimiter(first)
+                && !first.starts_with('"') =>
+        {
+            text
+        }
+        _ => format!("\\&{text}"),
+    }
+}
+
+/// A text line with `text`, or none if it is blank, which roff would
+/// render as a blank line.
+fn text_line(text: &str) -> Option<Line> {
+    (!text.trim().is_empty()).then(|| Line::text(vec![roman(text)]))
+}
+
+/// A `Bl -column` list header, sized to fit the widest cell of each column.
+///
+/// If `fill_last` is set, the last column is sized after the first row
+/// only, for columns holding prose that should fill the rest of the line.
+fn column_list(rows: &[Vec<String>], fill_last: bool) -> Line {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut args: Vec<Str> = vec!["-column".into()];
+    for i in 0..columns {
+        if fill_last && i + 1 == columns {
+            args.push(rows[0].get(i).cloned().unwrap_or_default().into());
+            break;
+        }
+        // Iterate in reverse so that ties resolve to the first row.
+        let widest = rows
+            .iter()
+            .rev()
+            .filter_map(|row| row.get(i))
+            .max_by_key(|cell| cell.chars().count())
+            .cloned()
+            .unwrap_or_default();
+        args.push(widest.into());
+    }
+    Line::control("Bl".into(), args)
+}
+
+/// An `It` line with cells separated by `Ta`.
+fn column_row<'a>(macros: Option<&'static str>, cells: impl IntoIterator<Item = &'a str>) -> Line {
+    let mut args: Vec<Str> = vec![];
+    for (i, cell) in cells.into_iter().enumerate() {
+        if i > 0 {
+            args.push("Ta".into());
+        }
+        if let Some(m) = macros {
+            args.push(m.into());
+        }
+        args.push(cell.to_string().into());
+    }
+    Line::control("It".into(), args)
+}
+
+/// Level of support of a feature on a platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Support {
+    Yes,
+    No,
+    Partial,
+    /// Free-form note, e.g. a minimum version.
+    Note(String),
+}
+
+impl std::fmt::Display for Support {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Yes => write!(f, "yes"),
+            Self::No => write!(f, "no"),
+            Self::Partial => write!(f, "partial"),
+            Self::Note(note) => write!(f, "{note}"),
+        }
+    }
+}
+
+/// A feature-by-platform support matrix, rendered as a `Bl -column` table
+/// in a COMPATIBILITY section.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let matrix = CompatibilityMatrix::new(["Linux", "macOS"])
+///     .feature("inotify", [Support::Yes, Support::No])
+///     .feature("kqueue", [Support::No, Support::Yes]);
+/// let mut doc = mdoc::Mdoc::default();
+/// matrix.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh COMPATIBILITY\n\
+///      .Bl -column Feature Linux macOS\n\
+///      .It Sy Feature Ta Sy Linux Ta Sy macOS\n\
+///      .It inotify Ta yes Ta no\n\
+///      .It kqueue Ta no Ta yes\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityMatrix {
+    platforms: Vec<String>,
+    features: Vec<(String, Vec<Support>)>,
+}
+
+impl CompatibilityMatrix {
+    pub fn new<S: Into<String>>(platforms: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            platforms: platforms.into_iter().map(Into::into).collect(),
+            features: vec![],
+        }
+    }
+
+    /// Add a feature row, with one support entry per platform.
+    pub fn feature(
+        mut self,
+        name: impl Into<String>,
+        support: impl IntoIterator<Item = Support>,
+    ) -> Self {
+        self.features
+            .push((name.into(), support.into_iter().collect()));
+        self
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![std::iter::once("Feature".to_string())
+            .chain(self.platforms.iter().map(|platform| cell(platform)))
+            .collect::<Vec<_>>()];
+        for (name, support) in &self.features {
+            rows.push(
+                std::iter::once(cell(name))
+                    .chain(support.iter().map(|support| cell(&support.to_string())))
+                    .collect(),
+            );
+        }
+        rows
+    }
+
+    /// The table, or nothing if there are no features.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.features.is_empty() {
+            return vec![];
+        }
+        let rows = self.rows();
+        let mut lines = vec![column_list(&rows, false)];
+        for (i, row) in rows.iter().enumerate() {
+            let macros = (i == 0).then_some("Sy");
+            lines.push(column_row(macros, row.iter().map(String::as_str)));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("COMPATIBILITY", self.lines())
+    }
+}
+
+/// The macro used for the terms of a [`Glossary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TermStyle {
+    /// `Em`, for stress emphasis.
+    #[default]
+    Emphasis,
+    /// `Sy`, for symbolic emphasis.
+    Symbolic,
+}
+
+/// Terminology definitions, rendered as a `Bl -tag` list sorted
+/// alphabetically by term.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let glossary = Glossary::new()
+///     .term("window", "The number of unacknowledged packets.")
+///     .term("ack", "An acknowledgement packet.");
+/// let mut doc = mdoc::Mdoc::default();
+/// glossary.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh GLOSSARY\n\
+///      .Bl -tag -width Ds\n\
+///      .It Em ack\n\
+///      An acknowledgement packet.\n\
+///      .It Em window\n\
+///      The number of unacknowledged packets.\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glossary {
+    entries: Vec<(String, String)>,
+    style: TermStyle,
+    sort: bool,
+}
+
+impl Default for Glossary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Glossary {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            style: TermStyle::default(),
+            sort: true,
+        }
+    }
+
+    /// Add a term and its definition.
+    pub fn term(mut self, term: impl Into<String>, definition: impl Into<String>) -> Self {
+        self.entries.push((term.into(), definition.into()));
+        self
+    }
+
+    /// Set the macro used for terms.
+    pub fn style(mut self, style: TermStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Keep the terms in insertion order instead of sorting them.
+    pub fn unsorted(mut self) -> Self {
+        self.sort = false;
+        self
+    }
+
+    /// The list, or nothing if there are no terms.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.entries.is_empty() {
+            return vec![];
+        }
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        if self.sort {
+            entries.sort_by_cached_key(|(term, _)| term.to_lowercase());
+        }
+        let style = match self.style {
+            TermStyle::Emphasis => "Em",
+            TermStyle::Symbolic => "Sy",
+        };
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for (term, definition) in entries {
+            lines.push(Line::control(
+                "It".into(),
+                vec![style.into(), cell(term).into()],
+            ));
+            lines.extend(text_line(definition));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("GLOSSARY", self.lines())
+    }
+}
+
+/// The macro used for the keys of a [`Keybindings`] table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyStyle {
+    /// `Ic`, for internal commands.
+    #[default]
+    Ic,
+    /// `Cm`, for command modifiers.
+    Cm,
+}
+
+/// Keybindings of an interactive program, rendered as a `Bl -column` table
+/// in a COMMANDS section.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let keys = Keybindings::new()
+///     .key("q", "quit", "Exit the program.")
+///     .key("^L", "redraw", "Repaint the screen.");
+/// let mut doc = mdoc::Mdoc::default();
+/// keys.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh COMMANDS\n\
+///      .Bl -column Key Action Description\n\
+///      .It Sy Key Ta Sy Action Ta Sy Description\n\
+///      .It Ic q Ta quit Ta Exit the program.\n\
+///      .It Ic ^L Ta redraw Ta Repaint the screen.\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keybindings {
+    bindings: Vec<(String, String, String)>,
+    style: KeyStyle,
+}
+
+impl Keybindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key, the name of the action it performs, and a description.
+    pub fn key(
+        mut self,
+        key: impl Into<String>,
+        action: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.bindings
+            .push((key.into(), action.into(), description.into()));
+        self
+    }
+
+    /// Set the macro used for keys.
+    pub fn style(mut self, style: KeyStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// The table, or nothing if there are no keys.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.bindings.is_empty() {
+            return vec![];
+        }
+        let header = ["Key", "Action", "Description"].map(String::from).to_vec();
+        let rows = std::iter::once(header)
+            .chain(
+                self.bindings
+                    .iter()
+                    .map(|(k, a, d)| vec![cell(k), cell(a), cell(d)]),
+            )
+            .collect::<Vec<_>>();
+        let style = match self.style {
+            KeyStyle::Ic => "Ic",
+            KeyStyle::Cm => "Cm",
+        };
+        let mut lines = vec![
+            column_list(&rows, true),
+            column_row(Some("Sy"), rows[0].iter().map(String::as_str)),
+        ];
+        for row in &rows[1..] {
+            lines.push(Line::control(
+                "It".into(),
+                vec![
+                    style.into(),
+                    row[0].clone().into(),
+                    "Ta".into(),
+                    row[1].clone().into(),
+                    "Ta".into(),
+                    row[2].clone().into(),
+                ],
+            ));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("COMMANDS", self.lines())
+    }
+}
+
+/// Layout of a binary structure or wire format, rendered as a `Bl -column`
+/// table with one row per field, as found in section 5 pages.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let header = FieldTable::new()
+///     .hex()
+///     .field(0, 4, "magic", "File signature.")
+///     .field(4, 2, "version", "Format version.");
+/// let mut doc = mdoc::Mdoc::default();
+/// header.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh FORMAT\n\
+///      .Bl -column Offset Size version Meaning\n\
+///      .It Sy Offset Ta Sy Size Ta Sy Field Ta Sy Meaning\n\
+///      .It 0x00 Ta 4 Ta Va magic Ta File signature.\n\
+///      .It 0x04 Ta 2 Ta Va version Ta Format version.\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldTable {
+    fields: Vec<(usize, usize, String, String)>,
+    hex: bool,
+}
+
+impl FieldTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field at byte `offset`, `size` bytes long.
+    pub fn field(
+        mut self,
+        offset: usize,
+        size: usize,
+        name: impl Into<String>,
+        meaning: impl Into<String>,
+    ) -> Self {
+        self.fields
+            .push((offset, size, name.into(), meaning.into()));
+        self
+    }
+
+    /// Print offsets in hexadecimal.
+    pub fn hex(mut self) -> Self {
+        self.hex = true;
+        self
+    }
+
+    fn offset(&self, offset: usize) -> String {
+        if self.hex {
+            format!("{offset:#04x}")
+        } else {
+            offset.to_string()
+        }
+    }
+
+    /// The table, or nothing if there are no fields.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.fields.is_empty() {
+            return vec![];
+        }
+        let header = ["Offset", "Size", "Field", "Meaning"]
+            .map(String::from)
+            .to_vec();
+        let rows = std::iter::once(header)
+            .chain(self.fields.iter().map(|(offset, size, name, meaning)| {
+                vec![
+                    self.offset(*offset),
+                    size.to_string(),
+                    cell(name),
+                    cell(meaning),
+                ]
+            }))
+            .collect::<Vec<_>>();
+        let mut lines = vec![
+            column_list(&rows, true),
+            column_row(Some("Sy"), rows[0].iter().map(String::as_str)),
+        ];
+        for row in &rows[1..] {
+            lines.push(Line::control(
+                "It".into(),
+                vec![
+                    row[0].clone().into(),
+                    "Ta".into(),
+                    row[1].clone().into(),
+                    "Ta".into(),
+                    "Va".into(),
+                    row[2].clone().into(),
+                    "Ta".into(),
+                    row[3].clone().into(),
+                ],
+            ));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("FORMAT", self.lines())
+    }
+}
+
+/// The `errno` values set by each function of a library, rendered as one
+/// `Bl -tag` list per function in an ERRORS section.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let errors = Errors::new().function(
+///     "frob",
+///     [("EINVAL", "The flags are invalid."), ("ENOMEM", "Out of memory.")],
+/// );
+/// let mut doc = mdoc::Mdoc::default();
+/// errors.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh ERRORS\n\
+///      The\n\
+///      .Fn frob\n\
+///      function will fail if:\n\
+///      .Bl -tag -width Er\n\
+///      .It Bq Er EINVAL\n\
+///      The flags are invalid.\n\
+///      .It Bq Er ENOMEM\n\
+///      Out of memory.\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Errors {
+    functions: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Errors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a function and the `errno` values it can fail with, each with a
+    /// description of the condition.
+    pub fn function<E: Into<String>, D: Into<String>>(
+        mut self,
+        name: impl Into<String>,
+        errors: impl IntoIterator<Item = (E, D)>,
+    ) -> Self {
+        self.functions.push((
+            name.into(),
+            errors
+                .into_iter()
+                .map(|(e, d)| (e.into(), d.into()))
+                .collect(),
+        ));
+        self
+    }
+
+    /// The lists, leaving out functions without errors.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut lines = vec![];
+        for (function, errors) in self.functions.iter().filter(|(_, e)| !e.is_empty()) {
+            lines.push(Line::text(vec![roman("The")]));
+            lines.push(Line::control("Fn".into(), vec![cell(function).into()]));
+            lines.push(Line::text(vec![roman("function will fail if:")]));
+            lines.push(Line::control(
+                "Bl".into(),
+                vec!["-tag".into(), "-width".into(), "Er".into()],
+            ));
+            for (errno, description) in errors {
+                lines.push(Line::control(
+                    "It".into(),
+                    vec!["Bq".into(), "Er".into(), cell(errno).into()],
+                ));
+                lines.extend(text_line(description));
+            }
+            lines.push(Line::control("El".into(), vec![]));
+        }
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("ERRORS", self.lines())
+    }
+}
+
+/// The return value of a library function, as documented by
+/// [`ReturnValues`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReturnValue {
+    function: String,
+    success: Option<String>,
+    failure: Option<String>,
+    errno: bool,
+}
+
+impl ReturnValue {
+    pub fn new(function: impl Into<String>) -> Self {
+        Self {
+            function: function.into(),
+            ..Self::default()
+        }
+    }
+
+    /// A function that returns 0 on success and -1 with `errno` set on
+    /// failure, documented with the `Rv -std` macro.
+    pub fn standard(function: impl Into<String>) -> Self {
+        Self::new(function).success("0").failure("-1").errno()
+    }
+
+    /// What the function returns on success, as a noun phrase, e.g. `a
+    /// file descriptor`.
+    pub fn success(mut self, value: impl Into<String>) -> Self {
+        self.success = Some(value.into());
+        self
+    }
+
+    /// The sentinel returned on failure, e.g. `-1` or `NULL`.
+    pub fn failure(mut self, value: impl Into<String>) -> Self {
+        self.failure = Some(value.into());
+        self
+    }
+
+    /// The function sets `errno` on failure.
+    pub fn errno(mut self) -> Self {
+        self.errno = true;
+        self
+    }
+
+    /// A failure sentinel is written with `Dv` if it's a constant name such
+    /// as `NULL`, and as plain text otherwise.
+    fn sentinel(value: &str) -> Line {
+        if value.starts_with(|c: char| c.is_ascii_uppercase()) {
+            Line::control("Dv".into(), vec![cell(value).into()])
+        } else {
+            // A leading dash is a minus sign, as in -1.
+            let value = match value.strip_prefix('-') {
+                Some(number) => format!("\\-{number}"),
+                None => value.to_string(),
+            };
+            Line::text(vec![roman(format!("the value {value}"))])
+        }
+    }
+
+    pub fn lines(&self) -> Vec<Line> {
+        if self.success.as_deref() == Some("0")
+            && self.failure.as_deref() == Some("-1")
+            && self.errno
+        {
+            return vec![Line::control(
+                "Rv".into(),
+                vec!["-std".into(), cell(&self.function).into()],
+            )];
+        }
+        let mut lines = vec![];
+        if let Some(success) = &self.success {
+            lines.push(Line::text(vec![roman("Upon successful completion,")]));
+            lines.push(Line::control(
+                "Fn".into(),
+                vec![cell(&self.function).into()],
+            ));
+            lines.push(Line::text(vec![roman(format!("returns {success}."))]));
+        }
+        if let Some(failure) = &self.failure {
+            lines.push(Line::text(vec![roman(if self.success.is_some() {
+                "Otherwise,"
+            } else {
+                "On failure,"
+            })]));
+            lines.push(Self::sentinel(failure));
+            if self.errno {
+                lines.push(Line::text(vec![roman(
+                    "is returned and the global variable",
+                )]));
+                lines.push(Line::control("Va".into(), vec!["errno".into()]));
+                lines.push(Line::text(vec![roman("is set to indicate the error.")]));
+            } else {
+                lines.push(Line::text(vec![roman("is returned.")]));
+            }
+        }
+        lines
+    }
+}
+
+/// Return values of the functions of a library, in a RETURN VALUES section.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let values = ReturnValues::new()
+///     .function(ReturnValue::standard("frob_close"))
+///     .function(
+///         ReturnValue::new("frob_open")
+///             .success("a frob handle")
+///             .failure("NULL")
+///             .errno(),
+///     );
+/// let mut doc = mdoc::Mdoc::default();
+/// values.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh RETURN VALUES\n\
+///      .Rv -std frob_close\n\
+///      .Pp\n\
+///      Upon successful completion,\n\
+///      .Fn frob_open\n\
+///      returns a frob handle.\n\
+///      Otherwise,\n\
+///      .Dv NULL\n\
+///      is returned and the global variable\n\
+///      .Va errno\n\
+///      is set to indicate the error.\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReturnValues {
+    functions: Vec<ReturnValue>,
+}
+
+impl ReturnValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn function(mut self, value: ReturnValue) -> Self {
+        self.functions.push(value);
+        self
+    }
+
+    /// The paragraphs of each function are separated with `Pp`; functions
+    /// without a documented return value are left out.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut lines = vec![];
+        for function in self.functions.iter().map(ReturnValue::lines) {
+            if function.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(Line::control("Pp".into(), vec![]));
+            }
+            lines.extend(function);
+        }
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("RETURN VALUES", self.lines())
+    }
+}
+
+/// A callback or hook point, as documented by [`Callbacks`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Callback {
+    name: String,
+    when: String,
+    args: Vec<(String, String)>,
+}
+
+impl Callback {
+    /// A callback `name`, with a description of when it is invoked.
+    pub fn new(name: impl Into<String>, when: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            when: when.into(),
+            args: vec![],
+        }
+    }
+
+    /// Add an argument passed to the callback.
+    pub fn arg(mut self, name: impl Into<String>, description: impl Into<String>) -> Self {
+        self.args.push((name.into(), description.into()));
+        self
+    }
+
+    pub fn lines(&self) -> Vec<Line> {
+        let mut it: Vec<Str> = vec!["Fn".into(), cell(&self.name).into()];
+        it.extend(self.args.iter().map(|(name, _)| Str::from(cell(name))));
+        let mut lines = vec![Line::control("It".into(), it)];
+        lines.extend(text_line(&self.when));
+        if !self.args.is_empty() {
+            lines.push(Line::control(
+                "Bl".into(),
+                vec!["-tag".into(), "-width".into(), "Ds".into()],
+            ));
+            for (name, description) in &self.args {
+                lines.push(Line::control(
+                    "It".into(),
+                    vec!["Fa".into(), cell(name).into()],
+                ));
+                lines.extend(text_line(description));
+            }
+            lines.push(Line::control("El".into(), vec![]));
+        }
+        lines
+    }
+}
+
+/// Callbacks and hook points of a plugin interface, rendered as a
+/// `Bl -tag` list with a nested list of arguments for each callback.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::sections::*;
+/// let callbacks = Callbacks::new().callback(
+///     Callback::new("on_connect", "Invoked after a connection is established.")
+///         .arg("conn", "The new connection.")
+///         .arg("arg", "The user data pointer."),
+/// );
+/// let mut doc = mdoc::Mdoc::default();
+/// callbacks.section().add_to(&mut doc);
+/// assert_eq!(
+///     doc.render(),
+///     ".Sh CALLBACKS\n\
+///      .Bl -tag -width Ds\n\
+///      .It Fn on_connect conn arg\n\
+///      Invoked after a connection is established.\n\
+///      .Bl -tag -width Ds\n\
+///      .It Fa conn\n\
+///      The new connection.\n\
+///      .It Fa arg\n\
+///      The user data pointer.\n\
+///      .El\n\
+///      .El\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Callbacks {
+    callbacks: Vec<Callback>,
+}
+
+impl Callbacks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn callback(mut self, callback: Callback) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    /// The list, or nothing if there are no callbacks.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.callbacks.is_empty() {
+            return vec![];
+        }
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for callback in &self.callbacks {
+            lines.extend(callback.lines());
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("CALLBACKS", self.lines())
+    }
+}
+
+/// An index of related pages, rendered as a `Bl -tag` list of `Xr`
+/// references with a short description of each page, as found in section 7
+/// overview pages.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::sections::*;
+/// let mut doc = Mdoc::overview(None, name!("frob"), description!("overview of frob"));
+/// PageIndex::new()
+///     .page("frob", "1", "frobnicate files")
+///     .page("frob.conf", "5", "frob configuration file")
+///     .section()
+///     .add_to(&mut doc);
+/// assert!(doc.render().ends_with(
+///     ".Sh PAGES\n\
+///      .Bl -tag -width Ds\n\
+///      .It Xr frob 1\n\
+///      frobnicate files\n\
+///      .It Xr frob.conf 5\n\
+///      frob configuration file\n\
+///      .El\n"
+/// ));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageIndex {
+    pages: Vec<(String, String, String)>,
+}
+
+impl PageIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a page by name and section, with a short description.
+    pub fn page(
+        mut self,
+        name: impl Into<String>,
+        section: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.pages
+            .push((name.into(), section.into(), description.into()));
+        self
+    }
+
+    /// The list, or nothing if there are no pages.
+    pub fn lines(&self) -> Vec<Line> {
+        if self.pages.is_empty() {
+            return vec![];
+        }
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for (name, section, description) in &self.pages {
+            lines.push(Line::control(
+                "It".into(),
+                vec!["Xr".into(), cell(name).into(), cell(section).into()],
+            ));
+            lines.extend(text_line(description));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        lines
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("PAGES", self.lines())
+    }
+}
+
+/// A SYNOPSIS of a utility, composed of flags and arguments.
+///
+/// Optional elements are wrapped in brackets with `Op`, alternatives are
+/// separated by pipes and repeatable arguments are followed by an
+/// ellipsis. Each usage form starts with `Nm`.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// # use mdoc::sections::*;
+/// let mut doc = Mdoc::new(
+///     None,
+///     DocumentTitle {
+///         title: title!("FROB"),
+///         section: section!("1"),
+///         arch: None,
+///     },
+///     name!("frob"),
+///     description!("frobnicate files"),
+///     None,
+/// );
+/// doc.control("Sh".into(), ["DESCRIPTION"]);
+/// doc.synopsis(
+///     SynopsisBuilder::new()
+///         .flag(Flag::combined("lv").optional())
+///         .flag(Flag::short('o').value("file").optional())
+///         .alternatives([Flag::short('x'), Flag::short('c')], false)
+///         .args("input", false)
+///         .form()
+///         .flag(Flag::long("version")),
+/// );
+/// assert!(doc.render().ends_with(
+///     ".Nd frobnicate files\n\
+///      .Sh SYNOPSIS\n\
+///      .Nm\n\
+///      .Op Fl lv\n\
+///      .Op Fl o Ar file\n\
+///      .Brq Fl x | Fl c\n\
+///      .Ar input ...\n\
+///      .Nm\n\
+///      .Fl -version\n\
+///      .Sh DESCRIPTION\n"
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SynopsisBuilder {
+    lines: Vec<Line>,
+}
+
+impl Default for SynopsisBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SynopsisBuilder {
+    pub fn new() -> Self {
+        Self {
+            lines: vec![Line::NAME],
+        }
+    }
+
+    /// Start another usage form of the utility.
+    pub fn form(mut self) -> Self {
+        self.lines.push(Line::NAME);
+        self
+    }
+
+    /// Add a flag, which is optional if [`Flag::optional`] is set.
+    pub fn flag(mut self, flag: Flag) -> Self {
+        self.lines.push(flag.line());
+        self
+    }
+
+    fn arg(mut self, name: &str, optional: bool, repeated: bool) -> Self {
+        let mut args: Vec<Str> = vec!["Ar".into(), name.to_string().into()];
+        if repeated {
+            args.push("...".into());
+        }
+        self.lines.push(if optional {
+            Line::control("Op".into(), args)
+        } else {
+            args.remove(0);
+            Line::control("Ar".into(), args)
+        });
+        self
+    }
+
+    /// Add an argument.
+    pub fn argument(self, name: &str, optional: bool) -> Self {
+        self.arg(name, optional, false)
+    }
+
+    /// Add an argument that can be repeated.
+    pub fn args(self, name: &str, optional: bool) -> Self {
+        self.arg(name, optional, true)
+    }
+
+    /// Add mutually exclusive flags, in brackets if `optional` and in
+    /// braces otherwise. Whether each flag is optional is ignored.
+    pub fn alternatives(mut self, flags: impl IntoIterator<Item = Flag>, optional: bool) -> Self {
+        let mut args: Vec<Str> = vec![];
+        for (i, flag) in flags.into_iter().enumerate() {
+            if i > 0 {
+                args.push("|".into());
+            }
+            args.extend(flag.args());
+        }
+        let name = if optional { "Op" } else { "Brq" };
+        self.lines.push(Line::control(name.into(), args));
+        self
+    }
+
+    pub fn lines(&self) -> Vec<Line> {
+        self.lines.clone()
+    }
+
+    pub fn section(&self) -> Boilerplate {
+        Boilerplate::new("SYNOPSIS", self.lines())
+    }
+}
@@ -0,0 +1,330 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Import systemd unit files.
+//!
+//! A [`Unit`] read from a `.service` or `.socket` file provides the
+//! ENVIRONMENT, FILES and SEE ALSO content of the page of the daemon it
+//! starts, so that the unit file and the manual page don't drift apart.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::systemd::*;
+//! let unit = Unit::parse(
+//!     "frobd.service",
+//!     "[Unit]\n\
+//!      Description=frobnication daemon\n\
+//!      Documentation=man:frobd.conf(5)\n\
+//!      [Service]\n\
+//!      Environment=FROB_LEVEL=3\n\
+//!      PIDFile=/run/frobd.pid\n\
+//!      ExecStart=/usr/sbin/frobd -d\n",
+//! )
+//! .unwrap();
+//! let page = unit.page();
+//! let text = page.render();
+//! assert!(text.contains(".Nm frobd\n.Nd frobnication daemon\n"));
+//! assert!(text.contains(".It Ev FROB_LEVEL\n"));
+//! assert!(text.contains(".It Pa /run/frobd.pid\n"));
+//! assert!(text.contains(".Xr frobd.conf 5 ,\n"));
+//! ```
+
+use super::*;
+use crate::{boilerplate::Boilerplate, daemon::Daemon};
+
+/// An error encountered while parsing a unit file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Line number, starting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The type of a unit, from the suffix of its file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitKind {
+    Service,
+    Socket,
+    Other,
+}
+
+/// A parsed systemd unit file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    /// File name of the unit, e.g. `frobd.service`.
+    pub name: String,
+    pub kind: UnitKind,
+    /// `(section, key, value)` entries, in file order.
+    pub entries: Vec<(String, String, String)>,
+}
+
+impl Unit {
+    /// Parse the contents of the unit file `name`.
+    pub fn parse(name: &str, source: &str) -> Result<Self, Error> {
+        let kind = match name.rsplit_once('.').map(|(_, suffix)| suffix) {
+            Some("service") => UnitKind::Service,
+            Some("socket") => UnitKind::Socket,
+            _ => UnitKind::Other,
+        };
+        let mut entries = vec![];
+        let mut section = None;
+        let mut lines = source.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let mut line = line.trim().to_string();
+            // Lines ending with a backslash are continued on the next line.
+            while line.ends_with('\\') {
+                line.pop();
+                line.push(' ');
+                match lines.next() {
+                    Some((_, next)) => line.push_str(next.trim()),
+                    None => break,
+                }
+            }
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(name.to_string());
+                continue;
+            }
+            let error = |message: &str| Error {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected a key=value assignment"));
+            };
+            let Some(section) = &section else {
+                return Err(error("assignment outside of a section"));
+            };
+            entries.push((
+                section.clone(),
+                key.trim().to_string(),
+                value.trim().to_string(),
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            entries,
+        })
+    }
+
+    /// All values of `key` in `section`.
+    pub fn get_all<'a>(&'a self, section: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.entries
+            .iter()
+            .filter(move |(s, k, _)| s == section && k == key)
+            .map(|(_, _, v)| v.as_str())
+    }
+
+    /// The last value of `key` in `section`.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rfind(|(s, k, _)| s == section && k == key)
+            .map(|(_, _, v)| v.as_str())
+    }
+
+    /// The name of the program started by the unit, from `ExecStart`, or
+    /// else the unit name without its suffix.
+    pub fn program(&self) -> String {
+        self.get("Service", "ExecStart")
+            .and_then(|exec| exec.split_whitespace().next())
+            .map(|path| path.trim_start_matches(['@', '-', ':', '+', '!']))
+            .and_then(|path| path.rsplit('/').next())
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                self.name
+                    .rsplit_once('.')
+                    .map_or(self.name.as_str(), |(stem, _)| stem)
+                    .to_string()
+            })
+    }
+
+    /// Variables set with `Environment`, as `(name, value)` pairs.
+    pub fn environment_variables(&self) -> Vec<(String, String)> {
+        let mut vars = vec![];
+        for value in self.get_all("Service", "Environment") {
+            for assignment in split_words(value) {
+                if let Some((name, value)) = assignment.split_once('=') {
+                    vars.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+        vars
+    }
+
+    /// An ENVIRONMENT section documenting the variables set by the unit.
+    pub fn environment(&self) -> Boilerplate {
+        let mut lines = vec![Line::control(
+            "Bl".into(),
+            vec!["-tag".into(), "-width".into(), "Ds".into()],
+        )];
+        for (name, value) in self.environment_variables() {
+            lines.push(Line::control("It".into(), vec!["Ev".into(), name.into()]));
+            lines.push(Line::text(vec![roman(format!(
+                "Set to {value} by the {} unit.",
+                self.name
+            ))]));
+        }
+        for file in self.get_all("Service", "EnvironmentFile") {
+            lines.push(Line::control("It".into(), vec!["Ev".into(), "*".into()]));
+            lines.push(Line::text(vec![roman("Variables are also read from")]));
+            lines.push(Line::control(
+                "Pa".into(),
+                vec![file.trim_start_matches('-').to_string().into(), ".".into()],
+            ));
+        }
+        lines.push(Line::control("El".into(), vec![]));
+        Boilerplate::new("ENVIRONMENT", lines)
+    }
+
+    /// A [`Daemon`] descriptor with the files and sockets used by the unit.
+    pub fn daemon(&self) -> Daemon {
+        let mut daemon = Daemon::new(self.program());
+        if let Some(pid_file) = self.get("Service", "PIDFile") {
+            daemon = daemon.pid_file(pid_file);
+        }
+        for key in ["ListenStream", "ListenDatagram", "ListenSequentialPacket"] {
+            for socket in self.get_all("Socket", key) {
+                if socket.starts_with('/') {
+                    daemon = daemon.socket(socket);
+                }
+            }
+        }
+        match self.kind {
+            UnitKind::Service | UnitKind::Socket => daemon.systemd_unit(self.name.clone()),
+            UnitKind::Other => daemon,
+        }
+    }
+
+    /// A FILES section with the pid file, sockets and environment files
+    /// used by the unit.
+    pub fn files(&self) -> Boilerplate {
+        let mut files = self.daemon().files();
+        let end = files.lines.pop();
+        for file in self.get_all("Service", "EnvironmentFile") {
+            files.lines.push(Line::control(
+                "It".into(),
+                vec!["Pa".into(), file.trim_start_matches('-').to_string().into()],
+            ));
+            files
+                .lines
+                .push(Line::text(vec![roman("Environment variables.")]));
+        }
+        files.lines.extend(end);
+        files
+    }
+
+    /// A SEE ALSO section with the pages listed in `Documentation` and the
+    /// systemd page of the unit type.
+    pub fn see_also(&self) -> Boilerplate {
+        let mut references = vec![];
+        for docs in self.get_all("Unit", "Documentation") {
+            for uri in split_words(docs) {
+                let Some(page) = uri.strip_prefix("man:") else {
+                    continue;
+                };
+                if let Some((name, section)) =
+                    page.strip_suffix(')').and_then(|page| page.split_once('('))
+                {
+                    references.push((name.to_string(), section.to_string()));
+                }
+            }
+        }
+        match self.kind {
+            UnitKind::Service => references.push(("systemd.service".into(), "5".into())),
+            UnitKind::Socket => references.push(("systemd.socket".into(), "5".into())),
+            UnitKind::Other => references.push(("systemd.unit".into(), "5".into())),
+        }
+        let count = references.len();
+        let lines = references
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, section))| {
+                let mut args: Vec<Str> = vec![name.into(), section.into()];
+                if i + 1 < count {
+                    args.push(",".into());
+                }
+                Line::control("Xr".into(), args)
+            })
+            .collect();
+        Boilerplate::new("SEE ALSO", lines)
+    }
+
+    /// A section 8 page skeleton for the daemon started by the unit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(unit = %self.name)))]
+    pub fn page(&self) -> Mdoc {
+        let description = self.get("Unit", "Description").unwrap_or_default();
+        let mut page = self
+            .daemon()
+            .page(Description::new(description.to_string()));
+        // Replace the FILES section of the daemon skeleton with the more
+        // complete one of the unit.
+        if let Some(files) = page.lines.iter().position(
+            |l| matches!(l, Line::Control { name, args } if name == "Sh" && args.first().is_some_and(|a| a == "FILES")),
+        ) {
+            page.lines.truncate(files);
+        }
+        if !self.environment_variables().is_empty()
+            || self.get("Service", "EnvironmentFile").is_some()
+        {
+            self.environment().add_to(&mut page);
+        }
+        self.files().add_to(&mut page);
+        self.see_also().add_to(&mut page);
+        page
+    }
+}
+
+/// Split a unit file value into words, honoring double quotes.
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
@@ -0,0 +1,558 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Filling text for terminal output.
+//!
+//! These are the building blocks of the plain-text renderer: [`fill`]
+//! breaks a paragraph into lines of at most [`FillOptions::width`]
+//! columns, optionally justified and hyphenated.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::term::*;
+//! let options = FillOptions {
+//!     width: 14,
+//!     adjust: Adjust::Both,
+//!     hyphenate: true,
+//! };
+//! assert_eq!(
+//!     fill("Hyphenation makes justified paragraphs look considerably better.", &options),
+//!     [
+//!         "Hyphenation",
+//!         "makes   justi-",
+//!         "fied",
+//!         "paragraphs",
+//!         "look  conside-",
+//!         "rably better.",
+//!     ]
+//! );
+//! ```
+
+/// How filled lines are adjusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Adjust {
+    /// Ragged right.
+    #[default]
+    Left,
+    /// Justified: spaces are added between words so that all lines but the
+    /// last reach the full width.
+    Both,
+}
+
+/// Options for [`fill`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillOptions {
+    /// Maximum number of columns of a line.
+    pub width: usize,
+    pub adjust: Adjust,
+    /// Break long words that don't fit at the end of a line.
+    pub hyphenate: bool,
+}
+
+impl Default for FillOptions {
+    /// The defaults of mandoc for an 80-column terminal: 78 columns, ragged
+    /// right, no hyphenation.
+    fn default() -> Self {
+        Self {
+            width: 78,
+            adjust: Adjust::Left,
+            hyphenate: false,
+        }
+    }
+}
+
+impl FillOptions {
+    /// The defaults, with the width of the page on the terminal, see
+    /// [`columns`], less a right margin of two columns.
+    pub fn from_env() -> Self {
+        Self {
+            width: columns().saturating_sub(2),
+            ..Self::default()
+        }
+    }
+}
+
+/// The number of columns to format pages for, as man(1) does: `MANWIDTH`
+/// if set, else the width of the terminal up to 80 columns, else 80.
+pub fn columns() -> usize {
+    let manwidth = std::env::var("MANWIDTH").ok();
+    columns_from(manwidth.as_deref(), terminal_columns())
+}
+
+/// The number of columns given the value of `MANWIDTH` and the width of
+/// the terminal, see [`columns`].
+pub fn columns_from(manwidth: Option<&str>, terminal: Option<usize>) -> usize {
+    manwidth
+        .and_then(|w| w.trim().parse().ok())
+        .filter(|&w| w > 0)
+        .or_else(|| terminal.map(|w| w.min(80)))
+        .unwrap_or(80)
+}
+
+/// The width of the terminal the standard output is connected to, if any.
+///
+/// This is `COLUMNS` if set, or else asked from stty(1).
+pub fn terminal_columns() -> Option<usize> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let output = std::process::Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .output()
+        .ok()?;
+    // The output is `rows columns`.
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// The characters a terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    Utf8,
+}
+
+impl Charset {
+    /// The charset of the locale, from the first of `LC_ALL`, `LC_CTYPE`
+    /// and `LANG` that is set.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .map_or(Self::Ascii, |locale| Self::from_locale(&locale))
+    }
+
+    /// The charset of a locale name such as `en_US.UTF-8`.
+    pub fn from_locale(locale: &str) -> Self {
+        let codeset = locale
+            .split_once('.')
+            .map_or("", |(_, c)| c.split('@').next().unwrap_or_default());
+        if codeset.eq_ignore_ascii_case("utf-8") || codeset.eq_ignore_ascii_case("utf8") {
+            Self::Utf8
+        } else {
+            Self::Ascii
+        }
+    }
+
+    /// The glyphs used for typographic characters in this charset.
+    pub fn glyphs(self) -> Glyphs {
+        match self {
+            Self::Ascii => Glyphs {
+                double_quotes: ("\"", "\""),
+                single_quotes: ("'", "'"),
+                em_dash: "--",
+                en_dash: "-",
+                minus: "-",
+                bullet: "o",
+            },
+            Self::Utf8 => Glyphs {
+                double_quotes: ("\u{201c}", "\u{201d}"),
+                single_quotes: ("\u{2018}", "\u{2019}"),
+                em_dash: "\u{2014}",
+                en_dash: "\u{2013}",
+                minus: "\u{2212}",
+                bullet: "\u{2022}",
+            },
+        }
+    }
+}
+
+/// Glyphs substituted for typographic characters, such as the quotes of
+/// `Dq`, depending on the [`Charset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Opening and closing double quotes.
+    pub double_quotes: (&'static str, &'static str),
+    /// Opening and closing single quotes.
+    pub single_quotes: (&'static str, &'static str),
+    pub em_dash: &'static str,
+    pub en_dash: &'static str,
+    pub minus: &'static str,
+    pub bullet: &'static str,
+}
+
+impl Glyphs {
+    /// Replace the common roff escapes of **mdoc** source by the characters
+    /// they stand for.
+    pub fn unescape(&self, text: &str) -> String {
+        let mut ret = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                ret.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('&') | None => {}
+                Some('-') => ret.push_str(self.minus),
+                Some('e') => ret.push('\\'),
+                Some(' ') => ret.push(' '),
+                // Font changes are dropped.
+                Some('f') => {
+                    chars.next();
+                }
+                Some('(') => {
+                    let name: String = chars.by_ref().take(2).collect();
+                    ret.push_str(match name.as_str() {
+                        "lq" => self.double_quotes.0,
+                        "rq" => self.double_quotes.1,
+                        "oq" => self.single_quotes.0,
+                        "cq" => self.single_quotes.1,
+                        "em" => self.em_dash,
+                        "en" => self.en_dash,
+                        "bu" => self.bullet,
+                        "co" => "(C)",
+                        "aq" => "'",
+                        "dq" => "\"",
+                        _ => "",
+                    });
+                }
+                Some(other) => {
+                    ret.push('\\');
+                    ret.push(other);
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// Columns taken by `text`, not counting SGR escape sequences.
+pub fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Byte offsets where `word` may be hyphenated.
+///
+/// This is a simple heuristic rather than a dictionary-based algorithm:
+/// words of at least six letters are broken before a consonant followed by
+/// a vowel or between two consonants surrounded by vowels, leaving at
+/// least three letters on each side, and words with hyphens are broken
+/// after them.
+pub fn hyphenation_points(word: &str) -> Vec<usize> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let is_vowel = |c: char| "aeiouyAEIOUY".contains(c);
+    if chars.iter().any(|&(_, c)| c == '-') {
+        return chars
+            .iter()
+            .zip(chars.iter().skip(1))
+            .filter(|((_, c), _)| *c == '-')
+            .map(|(_, &(i, _))| i)
+            .collect();
+    }
+    if chars.len() < 6 || !chars.iter().all(|(_, c)| c.is_alphabetic()) {
+        return vec![];
+    }
+    (3..=chars.len() - 3)
+        .filter(|&i| {
+            let [a, b, c, d] = [i - 2, i - 1, i, i + 1].map(|i| is_vowel(chars[i].1));
+            // Consonants followed by l, r or h, as in "ta-ble", "pa-tron" or
+            // "ma-chine", are kept together.
+            let cluster = "lrhLRH".contains(chars[i].1);
+            !c && d && (b || (a && !cluster))
+        })
+        .map(|i| chars[i].0)
+        .collect()
+}
+
+/// Break `text` into lines, collapsing whitespace.
+pub fn fill(text: &str, options: &FillOptions) -> Vec<String> {
+    let mut lines: Vec<Vec<String>> = vec![];
+    let mut line: Vec<String> = vec![];
+    let mut line_width = 0;
+    let mut words: Vec<String> = text.split_whitespace().rev().map(str::to_string).collect();
+    while let Some(word) = words.pop() {
+        let space = usize::from(!line.is_empty());
+        if line.is_empty() || line_width + space + width(&word) <= options.width {
+            line_width += space + width(&word);
+            line.push(word);
+            continue;
+        }
+        if options.hyphenate {
+            let available = options.width.saturating_sub(line_width + space);
+            let split = hyphenation_points(&word).into_iter().rev().find(|&i| {
+                let hyphen = usize::from(!word[..i].ends_with('-'));
+                width(&word[..i]) + hyphen <= available
+            });
+            if let Some(i) = split {
+                let (head, tail) = word.split_at(i);
+                let mut head = head.to_string();
+                if !head.ends_with('-') {
+                    head.push('-');
+                }
+                words.push(tail.to_string());
+                line.push(head);
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+                continue;
+            }
+        }
+        lines.push(std::mem::take(&mut line));
+        line_width = width(&word);
+        line.push(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    let last = lines.len().saturating_sub(1);
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(n, words)| match options.adjust {
+            Adjust::Both if n != last => justify(&words, options.width),
+            _ => words.join(" "),
+        })
+        .collect()
+}
+
+/// Join `words` with spaces distributed so that the line is `width`
+/// columns wide, the extra spaces going to the rightmost gaps first.
+fn justify(words: &[String], width: usize) -> String {
+    let gaps = words.len().saturating_sub(1);
+    if gaps == 0 {
+        return words.concat();
+    }
+    let text: usize = words.iter().map(|w| self::width(w)).sum();
+    let spaces = width.saturating_sub(text).max(gaps);
+    let mut ret = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let gap = i - 1;
+            let wide = spaces % gaps;
+            let n = spaces / gaps + usize::from(gap >= gaps - wide);
+            ret.extend(std::iter::repeat_n(' ', n));
+        }
+        ret.push_str(word);
+    }
+    ret
+}
+
+/// A set of SGR attributes, as the parameters of the escape sequence, e.g.
+/// `1;36` for bold cyan.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Style(pub String);
+
+impl Style {
+    pub const BOLD: &'static str = "1";
+    pub const UNDERLINE: &'static str = "4";
+
+    pub fn new(parameters: impl Into<String>) -> Self {
+        Self(parameters.into())
+    }
+
+    /// Wrap `text` in the escape sequences that turn this style on and off.
+    pub fn paint(&self, text: &str) -> String {
+        if self.0.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{text}\x1b[0m", self.0)
+        }
+    }
+
+    /// Paint each word of `text` separately, so that the style doesn't
+    /// carry over to the margin when the text is filled.
+    pub fn paint_words(&self, text: &str) -> String {
+        text.split(' ')
+            .map(|word| {
+                if word.is_empty() {
+                    String::new()
+                } else {
+                    self.paint(word)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.split(';')
+            .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+        {
+            Ok(Self::new(s))
+        } else {
+            Err(format!("invalid SGR parameters: {s}"))
+        }
+    }
+}
+
+/// Whether to style terminal output with escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// If the standard output is a terminal, `NO_COLOR` is unset and
+    /// `TERM` isn't `dumb`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::env::var("TERM").is_ok_and(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("expected auto, always or never: {s}")),
+        }
+    }
+}
+
+/// The styles of the elements of a page in ANSI terminal output.
+///
+/// The theme can be configured with the `MDOC_COLORS` environment variable,
+/// a colon-separated list of `key=style` entries in the manner of
+/// `LS_COLORS`, where keys are named after the **mdoc** macros of the
+/// elements and styles are SGR parameters, e.g. `fl=1;36:ar=4:sh=1`.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::term::*;
+/// let theme = Theme::parse("fl=1;36:sh=1;4").unwrap();
+/// assert_eq!(theme.flag.paint("-v"), "\x1b[1;36m-v\x1b[0m");
+/// assert_eq!(theme.argument, Style::new(Style::UNDERLINE));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// Section headings, key `sh`.
+    pub heading: Style,
+    /// Subsection headings, key `ss`.
+    pub subheading: Style,
+    /// The name of the utility, key `nm`.
+    pub name: Style,
+    /// Command-line flags, key `fl`.
+    pub flag: Style,
+    /// Command arguments, key `ar`.
+    pub argument: Style,
+    /// Bold text, key `sy`.
+    pub bold: Style,
+    /// Italic text, key `em`.
+    pub italic: Style,
+    /// Links and cross references, key `lk`.
+    pub link: Style,
+}
+
+impl Default for Theme {
+    /// Bold and underline only, as man(1) shows pages.
+    fn default() -> Self {
+        let bold = Style::new(Style::BOLD);
+        let underline = Style::new(Style::UNDERLINE);
+        Self {
+            heading: bold.clone(),
+            subheading: bold.clone(),
+            name: bold.clone(),
+            flag: bold.clone(),
+            argument: underline.clone(),
+            bold,
+            italic: underline.clone(),
+            link: underline,
+        }
+    }
+}
+
+impl Theme {
+    /// The default theme with the entries of `spec` applied, see [`Theme`].
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut theme = Self::default();
+        for entry in spec.split(':').filter(|e| !e.is_empty()) {
+            let (key, style) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=style: {entry}"))?;
+            // An empty style turns styling of the element off.
+            let style = if style.is_empty() {
+                Style::default()
+            } else {
+                style.parse()?
+            };
+            *theme
+                .get_mut(key)
+                .ok_or_else(|| format!("unknown key: {key}"))? = style;
+        }
+        Ok(theme)
+    }
+
+    /// The theme configured by `MDOC_COLORS`, or the default theme if it is
+    /// unset or invalid.
+    pub fn from_env() -> Self {
+        std::env::var("MDOC_COLORS")
+            .ok()
+            .and_then(|spec| Self::parse(&spec).ok())
+            .unwrap_or_default()
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Style> {
+        Some(match key {
+            "sh" => &mut self.heading,
+            "ss" => &mut self.subheading,
+            "nm" => &mut self.name,
+            "fl" => &mut self.flag,
+            "ar" => &mut self.argument,
+            "sy" => &mut self.bold,
+            "em" => &mut self.italic,
+            "lk" => &mut self.link,
+            _ => return None,
+        })
+    }
+}
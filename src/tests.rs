@@ -39,6 +39,17 @@ fn test_render_dash() {
     assert_eq!(text, "foo\\-bar\n");
 }
 
+#[test]
+fn test_render_dash_escapes() {
+    assert_eq!(
+        escape_hyphens("\\- \\(en \\*[foo-bar] \\*(-x \\f[B-I] \\&-a"),
+        "\\- \\(en \\*[foo-bar] \\*(-x \\f[B-I] \\&\\-a"
+    );
+    // Parsed text is written as it was read.
+    let source = ".Sh NAME\n.Nm foo\n.Nd frobnicate\n.Sh DESCRIPTION\nfoo-bar \\- baz\n";
+    assert_eq!(Mdoc::parse(source).to_mdoc(), source);
+}
+
 #[test]
 fn test_render_italic() {
     let text = Mdoc::default().text([italic("foo")]).to_mdoc();
@@ -83,3 +94,2961 @@ fn test_render_control() {
         .to_mdoc();
     assert_eq!(text, ".foo bar \"foo and bar\"\n");
 }
+
+#[test]
+fn test_boilerplate_localized_title() {
+    let mut doc = Mdoc::default();
+    boilerplate::copyright_gpl(2024, "Jane Doe")
+        .with_title("DROITS D'AUTEUR")
+        .with_texts(["Copyright \\(co 2024 Jane Doe."])
+        .add_to(&mut doc);
+    let text = doc.to_mdoc();
+    assert!(text.starts_with(".Sh DROITS D'AUTEUR\nCopyright \\(co 2024 Jane Doe.\n"));
+    assert!(text.contains("GNU General Public License"));
+}
+
+#[test]
+fn test_boilerplate_copyright() {
+    let mut doc = Mdoc::default();
+    boilerplate::copyright(
+        [
+            boilerplate::Holder::new("Jane Doe", 2019).until(2024),
+            boilerplate::Holder::new("John Doe", 2024).until(2024),
+        ],
+        "MIT OR Apache-2.0",
+    )
+    .add_to(&mut doc);
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh COPYRIGHT\nCopyright \\(co 2019\\(en2024 Jane Doe.\n.br\nCopyright \\(co 2024 John Doe.\nThis program is available under your choice of the following licenses:\n.Bl -bullet\n.It\nthe MIT License, see\n.Lk https://spdx.org/licenses/MIT.html .\n.It\nthe Apache License 2.0, see\n.Lk https://spdx.org/licenses/Apache-2.0.html .\n.El\n"
+    );
+
+    let mut doc = Mdoc::default();
+    boilerplate::copyright(
+        [boilerplate::Holder::new("Jane Doe", 2000)],
+        "GPL-2.0-or-later WITH Classpath-exception-2.0",
+    )
+    .add_to(&mut doc);
+    let text = doc.to_mdoc();
+    assert!(text.starts_with(".Sh COPYRIGHT\nCopyright \\(co 2000\\(en20"));
+    assert!(text.ends_with("the GNU General Public License version 2 or later with the Classpath\\-exception\\-2.0, see\n.Lk https://spdx.org/licenses/GPL-2.0-or-later.html .\n"));
+
+    let mut doc = Mdoc::default();
+    boilerplate::copyright([], "(MIT OR Apache-2.0) AND ISC").add_to(&mut doc);
+    assert!(doc
+        .to_mdoc()
+        .ends_with(".Ql \"(MIT OR Apache-2.0) AND ISC\" .\n"));
+}
+
+#[test]
+fn test_boilerplate_xdg_files() {
+    let mut doc = Mdoc::default();
+    boilerplate::xdg_files("foo", "foo.toml").add_to(&mut doc);
+    let text = doc.to_mdoc();
+    assert!(text.starts_with(
+        ".Sh FILES\n.Bl -tag -width Ds\n.It Pa $XDG_CONFIG_HOME/foo/foo.toml\nConfiguration file.\nIf\n.Ev XDG_CONFIG_HOME\nis unset,\n.Pa ~/.config/foo/foo.toml\nis used instead.\n"
+    ));
+    assert!(text.contains(".It Pa $XDG_CACHE_HOME/foo/\n"));
+    assert!(text.ends_with(".El\n"));
+}
+
+#[test]
+fn test_boilerplate_shell_completions() {
+    use boilerplate::Shell;
+
+    let mut doc = Mdoc::default();
+    boilerplate::shell_completions(
+        "foo",
+        &[
+            (Shell::Bash, Shell::Bash.default_path("foo")),
+            (Shell::Zsh, "/opt/foo/_foo".to_string()),
+        ],
+    )
+    .add_to(&mut doc);
+    let text = doc.to_mdoc();
+    assert!(text.contains(".It Cm bash\n.Pa /usr/share/bash-completion/completions/foo\n"));
+    assert!(text.contains(".It Cm zsh\n.Pa /opt/foo/_foo\n"));
+}
+
+#[test]
+fn test_render_name() {
+    let text = Mdoc::default()
+        .text([Inline::Name, roman(" formats pages.")])
+        .to_mdoc();
+    assert_eq!(text, ".Nm\nformats pages.\n");
+    let text = Mdoc::default()
+        .text([roman("Run "), Inline::Name, roman(", then stop.")])
+        .to_mdoc();
+    assert_eq!(text, "Run\n.Nm ,\nthen stop.\n");
+    let text = Mdoc::default()
+        .text([roman("Use "), Inline::Name, roman(").")])
+        .to_mdoc();
+    assert_eq!(text, "Use\n.Nm ) .\n");
+    let text = Mdoc::default()
+        .text([roman("See "), Inline::Name, roman(".conf files")])
+        .to_mdoc();
+    assert_eq!(text, "See\n.Nm\n\\&.conf files\n");
+}
+
+#[test]
+fn test_render_flag() {
+    let text = Mdoc::default()
+        .text([
+            roman("Pass "),
+            Inline::Flag {
+                short: Some('v'),
+                long: Some("verbose".into()),
+                value: None,
+            },
+            roman(" for more output."),
+        ])
+        .to_mdoc();
+    assert_eq!(text, "Pass\n.Fl v , Fl -verbose\nfor more output.\n");
+    assert_eq!(
+        flag_args(
+            Some('v'),
+            Some("verbose"),
+            None,
+            FlagContext::Synopsis,
+            FlagStyle::default()
+        ),
+        ["Fl", "v", "|", "Fl", "-verbose"]
+    );
+    assert_eq!(
+        flag_args(
+            None,
+            Some("help"),
+            None,
+            FlagContext::Synopsis,
+            FlagStyle::default()
+        ),
+        ["Fl", "-help"]
+    );
+}
+
+#[test]
+fn test_render_flag_style() {
+    let options = RenderOptions {
+        flag_style: FlagStyle {
+            long: LongFlags::Bsd,
+            separator: Some(FlagSeparator::Pipe),
+            ..FlagStyle::default()
+        },
+        ..RenderOptions::default()
+    };
+    let mut doc = Mdoc::default();
+    doc.text([Inline::Flag {
+        short: Some('h'),
+        long: Some("help".into()),
+        value: None,
+    }]);
+    assert_eq!(doc.render_with(&options), ".Fl h | Fl help\n");
+}
+
+#[test]
+fn test_flag_value_style() {
+    let assign = FlagStyle {
+        value: ValueStyle::Assign,
+        ..FlagStyle::default()
+    };
+    assert_eq!(
+        flag_args(
+            Some('o'),
+            Some("output"),
+            Some("file"),
+            FlagContext::Synopsis,
+            assign
+        )
+        .join(" "),
+        "Fl o Ar file | Fl -output Ns = Ns Ar file"
+    );
+    assert_eq!(
+        flag_args(
+            Some('o'),
+            Some("output"),
+            Some("file"),
+            FlagContext::Synopsis,
+            FlagStyle::default()
+        )
+        .join(" "),
+        "Fl o | Fl -output Ar file"
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_value_style() {
+    let cmd = clap::Command::new("foo").arg(
+        clap::Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_name("FILE"),
+    );
+    let doc = from_clap::to_mdoc(
+        cmd,
+        FlagStyle {
+            value: ValueStyle::Assign,
+            ..FlagStyle::default()
+        },
+    );
+    assert!(doc
+        .to_mdoc()
+        .contains(".Op Fl o Ar FILE | Fl -output Ns = Ns Ar FILE\n"));
+}
+
+#[test]
+fn test_overview_page() {
+    let doc = Mdoc::overview(None, name!("frob"), description!("overview"));
+    assert!(doc.is_overview());
+    assert!(doc.to_mdoc().contains(".Dt FROB 7\n"));
+
+    let mut doc = doc;
+    doc.add_section("CONCEPTS", [Line::text(vec![roman("Frobs.")])]);
+    doc.see_also([Line::cross_reference("frob".into(), "1".into())]);
+    doc.add_section("DESCRIPTION", [Line::text(vec![roman("Late.")])]);
+    let source = doc.to_mdoc();
+    assert!(source
+        .ends_with(".Sh CONCEPTS\nFrobs.\n.Sh DESCRIPTION\nLate.\n.Sh SEE ALSO\n.Xr frob 1\n"));
+    let page = Mdoc::parse(&source);
+    assert!(page.is_overview());
+    assert!(!lint::Fix::ReorderSections.applies(&page));
+    let policy = lint::Policy::parse("required-sections = [\"SYNOPSIS\", \"EXAMPLES\"]\n").unwrap();
+    let mut warnings = Warnings::new();
+    policy.check(&source, &page, &mut warnings);
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["missing EXAMPLES section"]);
+}
+
+#[test]
+fn test_systemd_unit_parse() {
+    let unit = systemd::Unit::parse(
+        "frobd.socket",
+        "# comment\n[Socket]\nListenStream=/run/frobd.sock\nListenStream=8080\n[Service]\nEnvironment=\"A=1 2\" \\\n  B=3\n",
+    )
+    .unwrap();
+    assert_eq!(unit.kind, systemd::UnitKind::Socket);
+    assert_eq!(
+        unit.environment_variables(),
+        [("A".into(), "1 2".into()), ("B".into(), "3".into())]
+    );
+    assert_eq!(unit.daemon().sockets, ["/run/frobd.sock"]);
+    let err = systemd::Unit::parse("x.service", "[Unit]\nbogus\n").unwrap_err();
+    assert_eq!(err.line, 2);
+}
+
+#[test]
+fn test_script_options() {
+    let script = "#!/bin/sh\n#\n# NAME\n#   backup - copy files to the backup server\n#\n# DESCRIPTION\n#   Copies files.\n#\n#   Twice.\n#\n# OPTIONS\n#   -n          Dry run.\n#   -d, --dir=dir   Destination\n#               directory.\n\nset -e\n# not part of the header\n";
+    let page = script::from_script(script).unwrap();
+    assert!(page.to_mdoc().ends_with(
+        ".Sh DESCRIPTION\nCopies files.\n.Pp\nTwice.\n.Bl -tag -width Ds\n.It Fl n\nDry run.\n.It Fl d , Fl -dir Ar dir\nDestination\ndirectory.\n.El\n"
+    ));
+    assert!(script::from_script("# SYNOPSIS\n#  foo\n").is_err());
+}
+
+#[test]
+fn test_annotations_merge_blocks() {
+    let source = "//! man: environment\n//! FOO is read.\nfn foo() {}\n    //! man: ENVIRONMENT\n    //! BAR is read too.\n//! plain comment\n";
+    let blocks = annotations::scan(source);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[1].line, 4);
+    let mut page = Mdoc::default();
+    annotations::assemble(&mut page, blocks);
+    assert_eq!(
+        page.to_mdoc(),
+        ".Sh ENVIRONMENT\nFOO is read.\n.Pp\nBAR is read too.\nplain comment\n"
+    );
+}
+
+#[test]
+fn test_merge_sections() {
+    let mut page = Mdoc::default();
+    page.add_section("NAME", [Line::control("Nm".into(), vec!["foo".into()])]);
+    page.add_section("DESCRIPTION", [Line::text(vec![roman("generated")])]);
+    page.add_section("AUTHORS", [Line::text(vec![roman("someone")])]);
+    let mut overrides = Mdoc::default();
+    overrides.control("Dd".into(), ["$Mdocdate$"]);
+    overrides.add_section("description", [Line::text(vec![roman("hand-written")])]);
+    overrides.add_section("BUGS", [Line::text(vec![roman("none")])]);
+    page.merge(overrides);
+    assert_eq!(
+        page.to_mdoc(),
+        ".Sh NAME\n.Nm foo\n.Sh DESCRIPTION\nhand\\-written\n.Sh AUTHORS\nsomeone\n.Sh BUGS\nnone\n"
+    );
+}
+
+#[test]
+fn test_render_figure() {
+    let text = Mdoc::default()
+        .figure(Figure::new("arch.png", "Overall architecture"))
+        .to_mdoc();
+    assert_eq!(
+        text,
+        ".Bd -ragged -offset indent\n.Sy Figure:\nOverall architecture\n.Lk arch.png\n.Ed\n"
+    );
+}
+
+#[test]
+fn test_render_admonition() {
+    let mut doc = Mdoc::default();
+    doc.lines.push(admonition(
+        AdmonitionKind::Warning,
+        [roman("This erases the disk.")],
+    ));
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Bd -filled -offset indent\n.Sy Warning:\nThis erases the disk.\n.Ed\n"
+    );
+}
+
+#[test]
+fn test_index_terms() {
+    let mut page = Mdoc::new(
+        None,
+        DocumentTitle {
+            title: title!("FOO"),
+            section: section!("1"),
+            arch: None,
+        },
+        name!("foo"),
+        description!("frobnicate"),
+        None,
+    );
+    page.text([
+        roman("Uses "),
+        Inline::IndexTerm("TLS".into()),
+        roman("TLS."),
+    ]);
+    assert!(page.to_mdoc().ends_with(".Nd frobnicate\nUses TLS.\n"));
+    assert_eq!(page.index_terms(), ["TLS"]);
+    let mut manual = Manual::new();
+    manual.page(page);
+    assert_eq!(manual.index()["TLS"], [("foo".into(), "1".into())]);
+}
+
+#[test]
+fn test_acronyms() {
+    let mut doc = Mdoc::default();
+    doc.acronym("TLS", "Transport Layer Security")
+        .text([roman("Uses "), Inline::Acronym("TLS".into())])
+        .text([Inline::Acronym("TLS".into()), roman(" 1.3 only.")])
+        .text([Inline::Acronym("UDP".into())]);
+    assert_eq!(
+        doc.to_mdoc(),
+        "Uses TLS (Transport Layer Security)\nTLS 1.3 only.\nUDP\n"
+    );
+    let options = RenderOptions {
+        acronyms: AcronymStyle::Never,
+        ..RenderOptions::default()
+    };
+    assert_eq!(doc.render_with(&options), "Uses TLS\nTLS 1.3 only.\nUDP\n");
+}
+
+#[test]
+fn test_change_bars() {
+    let mut doc = Mdoc::default();
+    doc.control("Sh".into(), ["DESCRIPTION"])
+        .text([roman("Unchanged.")])
+        .changed(Line::text(vec![roman("Changed.")]))
+        .control("Sh".into(), ["FILES"])
+        .text([roman("Unchanged.")]);
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh DESCRIPTION\nUnchanged.\n.mc \\(br\nChanged.\n.mc\n.Sh FILES\nUnchanged.\n"
+    );
+    assert_eq!(doc.changed_sections(), ["DESCRIPTION"]);
+    let changes = doc.changes_section("1.2").unwrap();
+    assert_eq!(changes.title, "CHANGES");
+    doc.accept_changes();
+    assert!(doc.changed_sections().is_empty());
+    assert!(doc.changes_section("1.2").is_none());
+    assert!(!doc.to_mdoc().contains(".mc"));
+}
+
+#[test]
+fn test_encoding() {
+    use parser::*;
+
+    assert_eq!(
+        decode("Café".as_bytes(), None),
+        ("Café".into(), Encoding::Utf8)
+    );
+    assert_eq!(decode(b"Caf\xe9", None), ("Café".into(), Encoding::Latin1));
+    assert_eq!(
+        decode(b"Caf\xc3\xa9", Some(Encoding::Latin1)).1,
+        Encoding::Latin1
+    );
+    assert_eq!(
+        coding_tag(b".\\\" -*- mode: nroff; coding: iso-8859-1 -*-\n.Dd\n"),
+        Some(Encoding::Latin1)
+    );
+    assert_eq!(
+        coding_tag(b".Dd\n.Dt\n.\\\" -*- coding: latin-1 -*-\n"),
+        None
+    );
+    assert_eq!(encode("Café ✓", Encoding::Latin1), b"Caf\xe9 \\[u2713]");
+}
+
+#[test]
+fn test_parse_tabs() {
+    let page = parser::parse(
+        ".Bl -column Name Description\n.It Fl a\tall files\n.It Fl b\\tbrief\n.El\n.Bl -tag\n.It Fl c\tcount\n.El\n.Bd -literal\nkey\tvalue\n.Ed\n",
+    );
+    assert_eq!(
+        page.to_mdoc(),
+        ".Bl -column Name Description\n.It Fl a Ta all files\n.It Fl b Ta brief\n.El\n.Bl -tag\n.It Fl c count\n.El\n.Bd -literal\nkey\tvalue\n.Ed\n"
+    );
+}
+
+#[test]
+fn test_render_tabs() {
+    let mut doc = Mdoc::default();
+    doc.tab_stops(["8n"])
+        .text([roman("key\t"), Inline::Name, roman("\tvalue")]);
+    assert_eq!(doc.to_mdoc(), ".ta 8n\nkey\t\n.Nm\n\tvalue\n");
+}
+
+#[test]
+fn test_parse_continuation() {
+    let source = ".Op Fl a \\\n  Ar file\n.Pa C:\\\\\n";
+    let page = parser::parse(source);
+    assert_eq!(
+        page.lines[0],
+        Line::control(
+            "Op".into(),
+            vec!["Fl".into(), "a".into(), "Ar".into(), "file".into()]
+        )
+    );
+    assert_eq!(page.lines.len(), 2);
+    let page = parser::parse_with(source, &parser::ParseOptions { lossless: true });
+    assert_eq!(page.to_mdoc(), source);
+}
+
+#[test]
+fn test_parse_page() {
+    let source = ".Dd $Mdocdate: July 1 2024 $\n.Dt GREP 1 amd64\n.Os \"GNU grep\" 3.11\n.Sh NAME\n.Nm grep , egrep\n.Nd file pattern searcher\n.Sh DESCRIPTION\n.Nm\nsearches \\fIfile\\fR.\n.Ft \"char *\"\n";
+    let mut page = Mdoc::parse(source);
+    assert_eq!(page.render(), source);
+    assert_eq!(page.title().title, Title::new("GREP"));
+    assert_eq!(page.title().arch, Some(Arch::new("amd64")));
+    assert_eq!(page.name(), &name!("grep"));
+    assert_eq!(page.description(), &description!("file pattern searcher"));
+    assert_eq!(
+        page.lines[9],
+        Line::control("Ft".into(), vec!["\"char *\"".into()])
+    );
+    page.append_to_section("DESCRIPTION", [Line::text(vec![roman("More.")])]);
+    assert!(page.render().ends_with(".Ft \"char *\"\nMore.\n"));
+    assert_eq!(parser::unquote("\"say \"\"hi\"\"\""), "say \"hi\"");
+}
+
+#[test]
+fn test_from_formatted() {
+    let output = "LS(1)   General Commands Manual   LS(1)\n\nN\x08NA\x08AM\x08ME\x08E\n     ls - list directory contents\n\nD\x08DE\x08ES\x08SC\x08CR\x08RI\x08IP\x08PT\x08TI\x08IO\x08ON\x08N\n     List _\x08f_\x08i_\x08l_\x08e information.\n\n     Use -\x08-a\x08a for all.\n\nGNU coreutils 9.4   April 2024   LS(1)\n";
+    let page = formatted::from_formatted(output);
+    assert_eq!(
+        page.lines,
+        vec![
+            Line::control("Dt".into(), vec!["LS".into(), "1".into()]),
+            Line::control("Sh".into(), vec!["NAME".into()]),
+            Line::control("Nm".into(), vec!["ls".into()]),
+            Line::control("Nd".into(), vec!["list directory contents".into()]),
+            Line::control("Sh".into(), vec!["DESCRIPTION".into()]),
+            Line::text(vec![roman("List "), italic("file"), roman(" information.")]),
+            Line::control("Pp".into(), vec![]),
+            Line::text(vec![roman("Use "), bold("-a"), roman(" for all.")]),
+        ]
+    );
+}
+
+#[test]
+fn test_fill() {
+    use term::*;
+
+    let text = "The quick brown fox jumps over the lazy dog.";
+    let options = FillOptions {
+        width: 16,
+        ..FillOptions::default()
+    };
+    assert_eq!(
+        fill(text, &options),
+        ["The quick brown", "fox jumps over", "the lazy dog."]
+    );
+    let options = FillOptions {
+        adjust: Adjust::Both,
+        ..options
+    };
+    assert_eq!(
+        fill(text, &options),
+        ["The quick  brown", "fox  jumps  over", "the lazy dog."]
+    );
+    assert_eq!(hyphenation_points("well-known"), [5]);
+    assert_eq!(hyphenation_points("table"), Vec::<usize>::new());
+    assert_eq!(hyphenation_points("renderer"), [3, 5]);
+}
+
+#[test]
+fn test_term_env() {
+    use term::*;
+
+    assert_eq!(columns_from(Some("100"), Some(200)), 100);
+    assert_eq!(columns_from(Some("wide"), Some(60)), 60);
+    assert_eq!(columns_from(None, Some(200)), 80);
+    assert_eq!(columns_from(None, None), 80);
+    assert_eq!(Charset::from_locale("en_US.UTF-8"), Charset::Utf8);
+    assert_eq!(Charset::from_locale("de_DE.utf8@euro"), Charset::Utf8);
+    assert_eq!(Charset::from_locale("C"), Charset::Ascii);
+    assert_eq!(Charset::Utf8.glyphs().em_dash, "\u{2014}");
+    assert_eq!(Charset::Ascii.glyphs().double_quotes, ("\"", "\""));
+}
+
+#[test]
+fn test_theme() {
+    use term::*;
+
+    let theme = Theme::parse("fl=1;36:ar=:em=3").unwrap();
+    assert_eq!(theme.flag, Style::new("1;36"));
+    assert_eq!(theme.argument.paint("file"), "file");
+    assert_eq!(theme.italic.paint("x"), "\x1b[3mx\x1b[0m");
+    assert_eq!(theme.heading, Theme::default().heading);
+    assert!(Theme::parse("xx=1").is_err());
+    assert!(Theme::parse("fl=bold").is_err());
+    assert!(Theme::parse("fl").is_err());
+}
+
+#[test]
+fn test_flag_builder() {
+    let style = FlagStyle {
+        value: ValueStyle::Assign,
+        ..FlagStyle::default()
+    };
+    assert_eq!(
+        Flag::short('j')
+            .with_long("jobs")
+            .value("n")
+            .style(style)
+            .line(),
+        Line::control(
+            "Fl".into(),
+            ["j", "Ar", "n", "|", "Fl", "-jobs", "Ns", "=", "Ns", "Ar", "n"]
+                .map(Into::into)
+                .to_vec()
+        )
+    );
+    assert_eq!(
+        Flag::combined("xvf").value("archive").optional().line(),
+        Line::control(
+            "Op".into(),
+            ["Fl", "xvf", "Ar", "archive"].map(Into::into).to_vec()
+        )
+    );
+}
+
+#[test]
+fn test_list_builder() {
+    let mut doc = Mdoc::default();
+    doc.list(
+        ListBuilder::new(ListKind::Column(vec!["Name".into(), "Description".into()]))
+            .offset("indent")
+            .row(["Fl a", "all files"])
+            .row(["Fl b", "brief"]),
+    )
+    .list(
+        ListBuilder::new(ListKind::Enum)
+            .compact()
+            .entry([roman("First.")]),
+    );
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Bl -column -offset indent Name Description\n.It Fl a Ta all files\n.It Fl b Ta brief\n.El\n.Bl -enum -compact\n.It\nFirst.\n.El\n"
+    );
+}
+
+#[test]
+fn test_groff_pdf_source() {
+    let page = Mdoc::parse(
+        ".Dd $Mdocdate: Sep 30 2024 $\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n\
+         .Sh \"SEE ALSO\"\n.Ss Using \\-\\-frob \\&and \\(dqquotes\\(dq\n",
+    );
+    let source = groff::pdf_source(&page);
+    assert!(
+        source.contains(".pdfinfo /CreationDate D:20240930\n"),
+        "{source}"
+    );
+    assert!(source.contains(".pdfbookmark 1 SEE ALSO\n.Sh \"SEE ALSO\"\n"));
+    assert!(source.contains(".pdfbookmark 2 Using --frob and \"quotes\"\n.Ss "));
+}
+
+#[test]
+fn test_groff_book() {
+    use groff::*;
+
+    let page = |name: &str, description: &str| {
+        let mut page = Mdoc::new(
+            None,
+            DocumentTitle {
+                title: title!(name.to_uppercase()),
+                section: section!("1"),
+                arch: None,
+            },
+            name!(name.to_string()),
+            description!(description.to_string()),
+            None,
+        );
+        page.control("Sh".into(), ["DESCRIPTION"]);
+        page
+    };
+    let mut manual = Manual::new();
+    manual
+        .page(page("foo", "frobnicate"))
+        .page(page("bar", "barnicate"));
+    let source = book_source(&manual, "Foo Reference", Device::Pdf);
+    assert!(source.starts_with(".pdfinfo /Title Foo Reference\n.sp 3i\n"));
+    assert!(source.contains(".nf\nFOO(1) \\- frobnicate\nBAR(1) \\- barnicate\n.fi\n.bp\n"));
+    assert!(source.contains(".pdfbookmark 1 BAR(1)\n.Dd $Mdocdate$\n"));
+    assert!(source.contains(".pdfbookmark 2 DESCRIPTION\n.Sh DESCRIPTION\n"));
+    assert!(!book_source(&manual, "Foo Reference", Device::PostScript).contains(".pdf"));
+}
+
+#[test]
+fn test_display_block() {
+    let mut doc = Mdoc::default();
+    doc.display(
+        DisplayBlock::new(DisplayKind::Centered)
+            .compact()
+            .text([roman("centered")]),
+    )
+    .display(DisplayBlock::new(DisplayKind::Unfilled).verbatim("'quoted\nplain"));
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Bd -centered -compact\ncentered\n.Ed\n.Bd -unfilled\n\\&'quoted\nplain\n.Ed\n"
+    );
+}
+
+#[test]
+fn test_generate_incremental() {
+    let out_dir = std::env::temp_dir().join(format!("mdoc-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&out_dir);
+    let page = |name: &str| {
+        Mdoc::new(
+            None,
+            DocumentTitle {
+                title: title!(name.to_uppercase()),
+                section: section!("1"),
+                arch: None,
+            },
+            name!(name.to_string()),
+            description!("frobnicate"),
+            None,
+        )
+    };
+    let mut manual = Manual::new();
+    manual.page(page("foo")).page(page("bar"));
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written.len(), 2);
+    let written = std::fs::read_to_string(out_dir.join("man1/foo.1")).unwrap();
+    assert!(written.ends_with(&manual.pages[0].render()));
+    assert_eq!(provenance::check(&written), provenance::Status::Unmodified);
+    manual.pages[1].text([roman("Changed.")]);
+    let mut progress = vec![];
+    let generated = manual
+        .generate_incremental_with_progress(&out_dir, |p| progress.push(p.to_string()))
+        .unwrap();
+    assert_eq!(generated.written, [out_dir.join("man1/bar.1")]);
+    assert_eq!(generated.skipped, [out_dir.join("man1/foo.1")]);
+    assert_eq!(
+        progress,
+        [
+            format!("[1/2] {}: up to date", out_dir.join("man1/foo.1").display()),
+            format!("[2/2] {}: written", out_dir.join("man1/bar.1").display()),
+        ]
+    );
+
+    // Hand edits are only overwritten when forced.
+    let bar = out_dir.join("man1/bar.1");
+    let edited = std::fs::read_to_string(&bar).unwrap() + "Edited.\n";
+    std::fs::write(&bar, &edited).unwrap();
+    manual.pages[1].text([roman("Changed again.")]);
+    let err = manual.generate_incremental(&out_dir).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read_to_string(&bar).unwrap(), edited);
+    manual.force = true;
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written, [bar]);
+
+    // The cache key covers the rendered output and the alias style.
+    manual.alias("baz", "bar");
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written, [out_dir.join("man1/baz.1")]);
+    manual.alias_style = AliasStyle::Copy;
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written, [out_dir.join("man1/baz.1")]);
+    let cache = out_dir.join(".mdoc-cache");
+    let stale = std::fs::read_to_string(&cache)
+        .unwrap()
+        .replace(|c: char| c.is_ascii_hexdigit() && c != 'f', "f");
+    std::fs::write(&cache, stale).unwrap();
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written.len(), 3);
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_reference() {
+    let mut doc = Mdoc::default();
+    doc.control("Sh".into(), ["STANDARDS"])
+        .text([roman("Conforms to POSIX.")])
+        .control("Sh".into(), ["AUTHORS"]);
+    Reference::new("Shell Command Language")
+        .institution("IEEE")
+        .url("https://pubs.opengroup.org/onlinepubs/9799919799/")
+        .add_to(&mut doc, "STANDARDS");
+    Reference::new("Sequence Alignment")
+        .book("Algorithms")
+        .pages("42-51")
+        .add_to(&mut doc, "See also");
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh STANDARDS\nConforms to POSIX.\n.Rs\n.%T Shell Command Language\n.%Q IEEE\n.%U https://pubs.opengroup.org/onlinepubs/9799919799/\n.Re\n.Sh AUTHORS\n.Sh SEE ALSO\n.Rs\n.%T Sequence Alignment\n.%B Algorithms\n.%P 42-51\n.Re\n"
+    );
+}
+
+#[test]
+fn test_synopsis_replaces_section() {
+    let mut doc = Mdoc::default();
+    doc.control("Sh".into(), ["SYNOPSIS"])
+        .control("Nm".into(), ["old"])
+        .control("Sh".into(), ["DESCRIPTION"]);
+    doc.synopsis(sections::SynopsisBuilder::new().argument("file", true));
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh SYNOPSIS\n.Nm\n.Op Ar file\n.Sh DESCRIPTION\n"
+    );
+    assert_eq!(doc.synopsis_lines().len(), 2);
+}
+
+#[test]
+fn test_warnings() {
+    let mut warnings = Warnings::new();
+    let page = script::from_script_with_warnings(
+        "#!/bin/sh\n# NAME\n#   frob - frobnicate\n# SYNOPSIS\n#   frob [-v file\n# OPTIONS\n#   -v verbose\n",
+        &mut warnings,
+    )
+    .unwrap();
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "OPTIONS: no two spaces before the description of -v verbose",
+            "SYNOPSIS: unclosed bracket dropped: frob [-v file",
+        ]
+    );
+    let mut warnings = Warnings::new();
+    let mut page = page;
+    page.control("Sh".into(), ["BUGS"])
+        .text([roman("bell\x07"), Inline::Acronym("TLS".into())]);
+    page.render_with_warnings(&RenderOptions::default(), &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "BUGS: control character '\\u{7}' in text",
+            "BUGS: acronym TLS is not registered"
+        ]
+    );
+}
+
+#[test]
+fn test_script_nested_brackets() {
+    let mut warnings = Warnings::new();
+    let page = script::from_script_with_warnings(
+        "#!/bin/sh\n# NAME\n#   tar - archive\n# SYNOPSIS\n#   tar [-o [file]] [[-v] -q] [-x] -y] dir ...\n",
+        &mut warnings,
+    )
+    .unwrap();
+    assert!(page.to_mdoc().ends_with(
+        ".Sh SYNOPSIS\n.Nm\n.Op Fl o Op Ar file\n.Oo\n.Op Fl v\n.Fl q\n.Oc\n.Op Fl x\n.Fl y\n.Ar dir ...\n"
+    ));
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["SYNOPSIS: unmatched bracket ignored: tar [-o [file]] [[-v] -q] [-x] -y] dir ..."]
+    );
+}
+
+#[test]
+fn test_strictness() {
+    let source = "#!/bin/sh\n# NAME\n#   frob - frobnicate\n# SYNOPSIS\n#   frob [-v\n";
+    let mut warnings = Warnings::with_strictness(Strictness::Lenient);
+    assert!(script::from_script_with_warnings(source, &mut warnings).is_ok());
+    assert!(warnings.is_empty());
+    let mut warnings = Warnings::with_strictness(Strictness::Deny);
+    let err = script::from_script_with_warnings(source, &mut warnings).unwrap_err();
+    assert_eq!(err.0, "SYNOPSIS: unclosed bracket dropped: frob [-v");
+    let mut warnings = Warnings::with_strictness(Strictness::Deny);
+    formatted::from_formatted_with_warnings("no header\n", &mut warnings);
+    assert!(warnings.check().is_err());
+}
+
+#[test]
+fn test_section_fields_order() {
+    let mut doc = Mdoc::default();
+    doc.control("Sh".into(), ["NAME"])
+        .control("Sh".into(), ["DESCRIPTION"])
+        .control("Sh".into(), ["SEE ALSO"])
+        .control("Xr".into(), ["frob.conf", "5", ","])
+        .control("Sh".into(), ["BUGS"]);
+    doc.authors([Line::control(
+        "An".into(),
+        vec!["Jane".into(), "Doe".into()],
+    )])
+    .exit_status([Line::control("Ex".into(), vec!["-std".into()])])
+    .see_also([Line::control("Xr".into(), vec!["frobd".into(), "8".into()])])
+    .environment([Line::text(vec![roman("None.")])]);
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh NAME\n.Sh DESCRIPTION\n.Sh ENVIRONMENT\nNone.\n.Sh EXIT STATUS\n.Ex -std\n.Sh SEE ALSO\n.Xr frob.conf 5 ,\n.Xr frobd 8\n.Sh AUTHORS\n.An Jane Doe\n.Sh BUGS\n"
+    );
+}
+
+#[test]
+fn test_render_man() {
+    let page = parser::parse(concat!(
+        ".Dd $Mdocdate: July 1 2024 $\n",
+        ".Dt FROB 1\n",
+        ".Os\n",
+        ".Sh NAME\n",
+        ".Nm frob ,\n",
+        ".Nm frobd\n",
+        ".Nd frobnicate files\n",
+        ".Sh SYNOPSIS\n",
+        ".Nm\n",
+        ".Op Fl v\n",
+        ".Op Fl o Ar file\n",
+        ".Ar input ...\n",
+        ".Sh DESCRIPTION\n",
+        ".Bl -tag -width Ds\n",
+        ".It Fl o Ar file , Fl -output Ns = Ns Ar file\n",
+        "Write to\n",
+        ".Ar file .\n",
+        ".It Fl v\n",
+        ".Bl -bullet -compact\n",
+        ".It\n",
+        "nested\n",
+        ".El\n",
+        ".El\n",
+        ".Bd -literal -offset indent\n",
+        "\\&.hidden\n",
+        ".Ed\n",
+        ".Sh SEE ALSO\n",
+        ".Xr frob.conf 5 ,\n",
+        ".Rs\n",
+        ".%A Jane Doe\n",
+        ".%T On Frobnication\n",
+        ".%D 2024\n",
+        ".Re\n",
+    ));
+    assert_eq!(
+        page.render_man(),
+        concat!(
+            ".TH FROB 1 \"July 1 2024\" \"\"\n",
+            ".SH NAME\n",
+            "frob, frobd \\- frobnicate files\n",
+            ".SH SYNOPSIS\n",
+            "\\fBfrob\\fR\n",
+            "[\\fB\\-v\\fR]\n",
+            "[\\fB\\-o\\fR \\fIfile\\fR]\n",
+            "\\fIinput ...\\fR\n",
+            ".SH DESCRIPTION\n",
+            ".TP\n",
+            "\\fB\\-o\\fR \\fIfile\\fR, \\fB\\-\\-output\\fR=\\fIfile\\fR\n",
+            "Write to\n",
+            "\\fIfile\\fR.\n",
+            ".TP\n",
+            "\\fB\\-v\\fR\n",
+            ".RS\n",
+            ".PD 0\n",
+            ".IP \\(bu 2\n",
+            "nested\n",
+            ".PD\n",
+            ".RE\n",
+            ".sp\n",
+            ".RS\n",
+            ".nf\n",
+            "\\&.hidden\n",
+            ".fi\n",
+            ".RE\n",
+            ".SH \"SEE ALSO\"\n",
+            "\\fBfrob.conf\\fR(5),\n",
+            "Jane Doe, \\fIOn Frobnication\\fR, 2024.\n",
+        )
+    );
+}
+
+#[test]
+fn test_unknown_round_trip() {
+    let source = concat!(
+        ".Sh DESCRIPTION\n",
+        "'br\n",
+        ".TS\n",
+        "tab(:);\n",
+        "l l.\n",
+        ".  odd:indent\n",
+        ".TE\n",
+        ".ig END\n",
+        "ignored\n",
+        ".END\n",
+    );
+    let mut page = parser::parse(source);
+    assert_eq!(page.to_mdoc(), source);
+    assert!(page
+        .render_man()
+        .ends_with(&source[".Sh DESCRIPTION\n".len()..]));
+
+    page.lines.push(Line::Unknown(Unknown::new(
+        SourceLanguage::Mdoc,
+        ".Bf -symbolic\ntext\n.Ef",
+    )));
+    assert!(page.to_mdoc().ends_with(".Bf -symbolic\ntext\n.Ef\n"));
+    assert!(page
+        .render_man()
+        .ends_with(".\\\" .Bf -symbolic\n.\\\" text\n.\\\" .Ef\n"));
+}
+
+#[test]
+fn test_provenance() {
+    let page = parser::parse(".Dt FROB 1\n.Sh NAME\n");
+    assert_eq!(
+        provenance::check(&page.render()),
+        provenance::Status::Unstamped
+    );
+    let options = RenderOptions {
+        provenance: Some(provenance::Provenance {
+            generator: "frobgen 1.0".into(),
+            source: None,
+        }),
+        ..RenderOptions::default()
+    };
+    let output = man::ManRenderer::new(options).render(&page);
+    assert!(output.starts_with(".\\\" Generator: frobgen 1.0\n.\\\" Content-Hash: fnv1a:"));
+    assert!(output.ends_with(".TH FROB 1 \"\" \"\"\n.SH NAME\n"));
+    let stamp = provenance::Stamp::read(&output).unwrap();
+    assert_eq!(stamp.source, None);
+    assert!(stamp.is_intact());
+}
+
+#[test]
+fn test_render_html() {
+    let mut page = parser::parse(concat!(
+        ".Dd $Mdocdate: July 1 2024 $\n",
+        ".Dt FROB 1\n",
+        ".Os\n",
+        ".Sh NAME\n",
+        ".Nm frob\n",
+        ".Nd frobnicate <files>\n",
+        ".Sh DESCRIPTION\n",
+        "See\n",
+        ".Sx EXIT STATUS .\n",
+        ".Bl -tag -width Ds\n",
+        ".It Fl o Ar file\n",
+        "Write to\n",
+        ".Ar file .\n",
+        ".El\n",
+        ".Bd -literal -offset indent\n",
+        "a < b\n",
+        ".Ed\n",
+        ".Ss Details\n",
+        ".Bl -bullet\n",
+        ".It\n",
+        "one\n",
+        ".El\n",
+        ".Sh EXIT STATUS\n",
+        ".Ex -std\n",
+    ));
+    page.lines
+        .push(admonition(AdmonitionKind::Note, [roman("Be careful.")]));
+    page.lines
+        .push(Line::Figure(Figure::new("frob.png", "A frob")));
+    let html = html::HtmlRenderer::default().render(&page);
+    assert_eq!(
+        html,
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+            "<title>FROB(1)</title>\n</head>\n<body>\n<main>\n",
+            "<section class=\"Sh\">\n<h1 id=\"NAME\">NAME</h1>\n",
+            "<p><code class=\"Nm\">frob</code> — frobnicate &lt;files&gt;</p>\n",
+            "</section>\n",
+            "<section class=\"Sh\">\n<h1 id=\"DESCRIPTION\">DESCRIPTION</h1>\n",
+            "<p>See\n<a class=\"Sx\" href=\"#EXIT_STATUS\">EXIT STATUS</a>.</p>\n",
+            "<dl class=\"Bl-tag\">\n",
+            "<dt><code class=\"Fl\">-o</code> <var class=\"Ar\">file</var></dt>\n",
+            "<dd><p>Write to\n<var class=\"Ar\">file</var>.</p>\n",
+            "</dd>\n</dl>\n",
+            "<pre class=\"Bd Bd-literal Bd-indent\">a &lt; b\n</pre>\n",
+            "<section class=\"Ss\">\n<h2 id=\"Details\">Details</h2>\n",
+            "<ul class=\"Bl-bullet\">\n<li><p>one</p>\n</li>\n</ul>\n",
+            "</section>\n</section>\n",
+            "<section class=\"Sh\">\n<h1 id=\"EXIT_STATUS\">EXIT STATUS</h1>\n",
+            "<p>The <code class=\"Nm\">frob</code> utility exits 0 on success, and &gt;0 if an error occurs.</p>\n",
+            "<aside class=\"note\">\n<p><strong>Note:</strong> Be careful.</p>\n</aside>\n",
+            "<figure>\n<img src=\"frob.png\" alt=\"A frob\">\n<figcaption>A frob</figcaption>\n</figure>\n",
+            "</section>\n",
+            "</main>\n<footer>\n<span class=\"Dd\">July 1 2024</span>\n<span class=\"Os\"></span>\n</footer>\n",
+            "</body>\n</html>\n",
+        )
+    );
+}
+
+#[test]
+fn test_text_renderer() {
+    let page = parser::parse(concat!(
+        ".Dd $Mdocdate: July 1 2024 $\n",
+        ".Dt FROB 1\n",
+        ".Os Frob 1.0\n",
+        ".Sh NAME\n",
+        ".Nm frob\n",
+        ".Nd frobnicate files\n",
+        ".Sh SYNOPSIS\n",
+        ".Nm\n",
+        ".Op Fl v\n",
+        ".Ar file ...\n",
+        ".Sh DESCRIPTION\n",
+        "The\n",
+        ".Nm\n",
+        "utility frobnicates each\n",
+        ".Ar file\n",
+        "in place, as described in\n",
+        ".Xr frob.conf 5 .\n",
+        ".Bl -tag -width indent\n",
+        ".It Fl v\n",
+        "Verbose.\n",
+        ".It Fl -output Ns = Ns Ar file\n",
+        "Write the result to\n",
+        ".Ar file .\n",
+        ".El\n",
+        ".Bd -literal -offset indent\n",
+        "$ frob  -v a\n",
+        ".Ed\n",
+        ".Ss Notes\n",
+        ".Bl -bullet -compact\n",
+        ".It\n",
+        "one\n",
+        ".It\n",
+        "two\n",
+        ".El\n",
+    ));
+    let text = text::TextRenderer::default().width(50).render(&page);
+    assert_eq!(
+        text,
+        concat!(
+            "FROB(1)      General Commands Manual       FROB(1)\n",
+            "\n",
+            "NAME\n",
+            "     frob - frobnicate files\n",
+            "\n",
+            "SYNOPSIS\n",
+            "     frob [-v] file ...\n",
+            "\n",
+            "DESCRIPTION\n",
+            "     The frob utility frobnicates each file in\n",
+            "     place, as described in frob.conf(5).\n",
+            "\n",
+            "     -v    Verbose.\n",
+            "\n",
+            "     --output=file\n",
+            "           Write the result to file.\n",
+            "\n",
+            "           $ frob  -v a\n",
+            "\n",
+            "   Notes\n",
+            "     o one\n",
+            "     o two\n",
+            "\n",
+            "Frob 1.0           July 1 2024             FROB(1)\n",
+        )
+    );
+    let text = text::TextRenderer::default()
+        .charset(term::Charset::Utf8)
+        .render(&page);
+    assert!(text.contains("frob \u{2013} frobnicate files\n"));
+    assert!(text.contains("     \u{2022} one\n"));
+}
+
+#[test]
+fn test_ansi_renderer() {
+    let mut page = parser::parse(concat!(
+        ".Dt FROB 1\n",
+        ".Sh OPTIONS\n",
+        ".Bl -tag -width Ds\n",
+        ".It Fl o Ar file\n",
+        "Write to the file named after the frob.\n",
+        ".El\n",
+    ));
+    page.text([roman("Really "), bold("do"), roman(" it "), italic("now")]);
+    let text = text::TextRenderer::default()
+        .width(40)
+        .theme(term::Theme::default())
+        .render(&page);
+    assert!(text.contains("\n\x1b[1mOPTIONS\x1b[0m\n"));
+    assert!(text.contains(
+        "\n     \x1b[1m-o\x1b[0m \x1b[4mfile\x1b[0m\n           Write to the file named after\n           the frob.\n"
+    ));
+    assert!(text.contains("\n     Really \x1b[1mdo\x1b[0m it \x1b[4mnow\x1b[0m\n"));
+    assert_eq!(term::width("\x1b[1;36m-v\x1b[0m"), 2);
+    let plain = text::TextRenderer::default()
+        .width(40)
+        .color(term::ColorChoice::Never)
+        .render(&page);
+    assert!(!plain.contains('\x1b'));
+    assert_eq!("always".parse(), Ok(term::ColorChoice::Always));
+}
+
+#[test]
+fn test_import_registry() {
+    use std::path::Path;
+
+    let registry = import::Registry::default();
+    let detect = |path: &str, source: &str| {
+        registry
+            .detect(&import::Input::new(Path::new(path), source))
+            .map(|i| i.name().to_string())
+    };
+    assert_eq!(detect("-", ".\\\" comment\n.Dd\n").as_deref(), Some("mdoc"));
+    assert_eq!(
+        detect("frob", "#!/bin/sh\n# man: no\n").as_deref(),
+        Some("script")
+    );
+    assert_eq!(
+        detect("frob.service", "[Unit]\n").as_deref(),
+        Some("systemd")
+    );
+    assert_eq!(
+        detect("-", "N\x08NA\x08AM\x08ME\x08E\n").as_deref(),
+        Some("formatted")
+    );
+    assert_eq!(
+        detect("frob.c", "/* man: frob */\n").as_deref(),
+        Some("annotations")
+    );
+    assert_eq!(detect("-", "plain text\n"), None);
+    assert_eq!(registry.get("md").map(|i| i.name()), Some("markdown"));
+    assert_eq!(detect("frob.md", "Frob.\n").as_deref(), Some("markdown"));
+    assert_eq!(detect("frob.json", "{}\n").as_deref(), Some("json"));
+    let err = registry
+        .get("json")
+        .unwrap()
+        .import(
+            &import::Input::new(Path::new("-"), "{}\n"),
+            &mut Warnings::new(),
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "expected an array of `nodes`");
+    assert_eq!(registry.names()[0], "formatted");
+}
+
+#[test]
+fn test_from_man() {
+    let source = r#".\" Converted by hand
+.TH FROB 8 2024-07-01 "frob 1.2" "System Manager's Manual"
+.SH NAME
+frob, frobctl \- frobnicate
+.SH SYNOPSIS
+.B frob
+[\fB\-v\fR]
+.I file
+.SH DESCRIPTION
+.B frob
+reads
+.IR file .
+.PP
+Options:
+.TP
+.B \-v
+Be verbose.
+.RS
+.IP \(bu
+nested
+.RE
+.IP 1.
+first
+.SH EXAMPLES
+.EX
+frob \-v x
+.EE
+.na
+.UR https://example.org
+home
+.UE .
+.SH "SEE ALSO"
+.BR frobd (8),
+.MS frob 5
+"#;
+    let mut warnings = Warnings::new();
+    let page = from_man::from_man_with_warnings(source, &mut warnings);
+    assert_eq!(
+        page.to_mdoc(),
+        r#".\" Converted by hand
+.Dd 2024-07-01
+.Dt FROB 8
+.Os "frob 1.2"
+.Sh NAME
+.Nm frob ,
+.Nm frobctl
+.Nd frobnicate
+.Sh SYNOPSIS
+.Nm
+[\fB\-v\fR]
+.Ar file
+.Sh DESCRIPTION
+.Nm
+reads
+.Ar file .
+.Pp
+Options:
+.Bl -tag -width Ds
+.It Fl v
+Be verbose.
+.Bd -ragged -offset indent
+.Bl -bullet
+.It
+nested
+.El
+.Ed
+.El
+.Bl -enum
+.It
+first
+.El
+.Sh EXAMPLES
+.Bd -literal
+frob \-v x
+.Ed
+.Lk https://example.org home .
+.Sh SEE ALSO
+.Xr frobd 8 ,
+.\" .MS frob 5
+"#
+    );
+    assert_eq!(page.name(), &name!("frob"));
+    assert_eq!(page.title().section, Section::new("8"));
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["SEE ALSO: macro MS is not supported"]
+    );
+}
+
+#[test]
+fn test_render_json() {
+    let page = Mdoc::parse(
+        ".Dd July 1 2024\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frob\n.Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v\nBe \"verbose\".\n.El\n.Ss Notes\n.Ft \"char *\"\n",
+    );
+    let json = page.render_json();
+    assert!(json.starts_with(
+        "{\n  \"meta\": {\n    \"title\": \"FOO\",\n    \"msec\": \"1\",\n    \"arch\": null,\n    \"os\": \"\",\n    \"date\": \"July 1 2024\",\n    \"name\": \"foo\",\n    \"description\": \"frob\"\n  },\n"
+    ));
+    assert!(json.contains(
+        "\"type\": \"item\",\n              \"name\": \"It\",\n              \"args\": [\"Fl\", \"v\"],\n              \"children\": [\n                {\n                  \"type\": \"text\",\n                  \"text\": \"Be \\\"verbose\\\".\"\n"
+    ));
+    assert!(json.contains(
+        "\"type\": \"subsection\",\n          \"name\": \"Ss\",\n          \"title\": \"Notes\",\n          \"children\": [\n            {\n              \"type\": \"macro\",\n              \"name\": \"Ft\",\n              \"args\": [\"char *\"]\n"
+    ));
+}
+
+#[test]
+fn test_from_json() {
+    let source = concat!(
+        ".Dd July 1 2024\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frob\n",
+        ".Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v\nBe \"verbose\".\n.El\n",
+        ".Ss Notes\n.Ft \"char *\"\n.Sh SEE ALSO\n.Rs\n.%T \"The Title\"\n.Re\n.nr x 1\n",
+    );
+    let page = Mdoc::parse(source);
+    let json = page.render_json();
+    assert_eq!(json::from_json(&json).unwrap().to_mdoc(), source);
+    assert_eq!(
+        json::from_json("{\"nodes\": [{\"type\": \"text\", \"text\": \"\\u00e9\"}]}")
+            .unwrap()
+            .to_mdoc(),
+        "\u{e9}\n"
+    );
+    for (json, error) in [
+        ("[", "at byte 1: expected a value"),
+        (
+            "{\"nodes\": []} x",
+            "at byte 14: expected the end of the document",
+        ),
+        (
+            "{\"nodes\": [{\"type\": \"tree\"}]}",
+            "unknown node type `tree`",
+        ),
+    ] {
+        assert_eq!(json::from_json(json).unwrap_err(), error);
+    }
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn test_markdown_synopsis() {
+    let source = "# frob(1) -- frobnicate files\n\n## SYNOPSIS\n\n\
+                  `frob` [`-o` [*file*]] *input*... [a | b]\n\n\
+                  `frob` `list` [\n\n\
+                  `frob` [[`-a`] <file>...] [<dir>] [[`-b`] | [`-c`]]\n";
+    let mut warnings = Warnings::new();
+    let page = markdown::from_markdown_with_warnings(source, &mut warnings);
+    assert!(page.to_mdoc().ends_with(
+        ".Sh SYNOPSIS\n.Nm\n.Op Fl o Op Ar file\n.Ar input ...\n.Op Ar a | Ar b\n\
+         .Pp\n.Nm\n.Cm list\n.Op\n\
+         .Pp\n.Nm\n.Oo\n.Op Fl a\n.Ar file ...\n.Oc\n.Op Ar dir\n\
+         .Oo\n.Op Fl b\n.No |\n.Op Fl c\n.Oc\n"
+    ));
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["SYNOPSIS: unclosed bracket closed at the end: `frob` `list` ["]
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn test_markdown_renderer() {
+    let source = concat!(
+        ".Dd July 1, 2024\n",
+        ".Dt FROB 1\n",
+        ".Os\n",
+        ".Sh NAME\n",
+        ".Nm frob\n",
+        ".Nd frobnicate files\n",
+        ".Sh SYNOPSIS\n",
+        ".Nm\n",
+        ".Op Fl v\n",
+        ".Op Fl o Ar file\n",
+        ".Ar dir ...\n",
+        ".Sh DESCRIPTION\n",
+        "The\n",
+        ".Nm\n",
+        "utility frobnicates each\n",
+        ".Ar dir ,\n",
+        "see\n",
+        ".Xr ls 1 .\n",
+        "Stars *, snake_case and _under_ are kept.\n",
+        ".Pp\n",
+        "1. isn't a list.\n",
+        ".Bl -tag -width Ds\n",
+        ".It Fl v , Fl -verbose\n",
+        "Print\n",
+        ".Sy more .\n",
+        ".It Fl o Ar file\n",
+        "Write to\n",
+        ".Ar file .\n",
+        ".Pp\n",
+        "Or\n",
+        ".Lk https://example.org the site .\n",
+        ".El\n",
+        ".Bl -bullet\n",
+        ".It\n",
+        "one\n",
+        ".It\n",
+        "two\n",
+        ".El\n",
+        ".Bd -literal -offset indent\n",
+        "$ frob -v *\n",
+        ".Ed\n",
+    );
+    let markdown = markdown::MarkdownRenderer::default().render(&parser::parse(source));
+    assert_eq!(
+        markdown,
+        concat!(
+            "---\ntitle: frob\nsection: 1\ndate: July 1, 2024\n---\n\n",
+            "# frob(1) -- frobnicate files\n\n",
+            "## SYNOPSIS\n\n`frob`\n[`-v`]\n[`-o` *file*]\n*dir ...*\n\n",
+            "## DESCRIPTION\n\nThe\n`frob`\nutility frobnicates each\n*dir*,\nsee\nls(1).\n",
+            "Stars \\*, snake_case and \\_under\\_ are kept.\n\n",
+            "1\\. isn't a list.\n\n",
+            "* `-v`, `--verbose`:\n  Print\n  **more**.\n\n",
+            "* `-o` *file*:\n  Write to\n  *file*.\n\n  Or\n  [the site](https://example.org).\n\n",
+            "* one\n\n* two\n\n",
+            "```\n$ frob -v *\n```\n",
+        )
+    );
+    // The Markdown reader reads the page back.
+    assert_eq!(
+        markdown::from_markdown(&markdown).to_mdoc(),
+        concat!(
+            ".Dd July 1, 2024\n.Dt FROB 1\n.Os\n.Sh NAME\n.Nm frob\n.Nd frobnicate files\n",
+            ".Sh SYNOPSIS\n.Nm\n.Op Fl v\n.Op Fl o Ar file\n.Ar dir ...\n",
+            ".Sh DESCRIPTION\nThe\n.Li frob\nutility frobnicates each\n.Em dir ,\nsee\n.Xr ls 1 .\n",
+            "Stars *, snake_case and _under_ are kept.\n.Pp\n1. isn't a list.\n",
+            ".Bl -tag -width Ds\n.It Fl v , Fl -verbose\nPrint\n.Sy more .\n",
+            ".It Fl o Em file\nWrite to\n.Em file .\n.Pp\nOr\n.Lk https://example.org the site .\n.El\n",
+            ".Bl -bullet\n.It\none\n.It\ntwo\n.El\n",
+            ".Bd -literal -offset indent\n$ frob \\-v *\n.Ed\n",
+        )
+    );
+    // Pages with several names have a NAME section.
+    let page = parser::parse(".Sh NAME\n.Nm frob ,\n.Nm frobctl\n.Nd frobnicate files\n");
+    assert_eq!(
+        markdown::MarkdownRenderer::default().render(&page),
+        "---\ntitle: frob\n---\n\n## NAME\n\nfrob, frobctl - frobnicate files\n"
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn test_from_markdown() {
+    let source = r#"---
+title: frob
+section: 8
+date: July 1, 2024
+---
+
+## NAME
+
+frob, frobctl - frobnicate files
+
+## SYNOPSIS
+
+`frob` [`-v`] [`--color=WHEN`] _file_
+
+## DESCRIPTION
+
+Reads **every** file, see [the site](https://example.org) and frobd(8).
+Backslashes\\ are kept.
+
+Options:
+
+: not a definition
+
+`-q`
+: Be quiet.
+
+1. first
+2. second
+
+```
+frob -v .config
+```
+
+| a | b |
+"#;
+    let mut warnings = Warnings::new();
+    let page = markdown::from_markdown_with_warnings(source, &mut warnings);
+    assert_eq!(
+        page.to_mdoc(),
+        r#".Dd July 1, 2024
+.Dt FROB 8
+.Os
+.Sh NAME
+.Nm frob ,
+.Nm frobctl
+.Nd frobnicate files
+.Sh SYNOPSIS
+.Nm
+.Op Fl v
+.Op Fl -color Ns = Ns Ar WHEN
+.Ar file
+.Sh DESCRIPTION
+Reads
+.Sy every
+file, see
+.Lk https://example.org the site
+and
+.Xr frobd 8 .
+Backslashes\e are kept.
+.Pp
+Options:
+.Pp
+: not a definition
+.Bl -tag -width Ds
+.It Fl q
+Be quiet.
+.El
+.Bl -enum
+.It
+first
+.It
+second
+.El
+.Bd -literal -offset indent
+frob \-v .config
+.Ed
+.Pp
+| a | b |
+"#
+    );
+    assert_eq!(page.name(), &name!("frob"));
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["DESCRIPTION: kept as text: | a | b |"]
+    );
+}
+
+#[test]
+fn test_one_line_description() {
+    let page = Mdoc::parse(
+        ".Dd July 1 2024\n.Dt GREP 1\n.Os\n.Sh NAME\n.Nm grep ,\n.Nm egrep\n.Nd file pattern searcher \\(em\nwith \\fBregular\\fR\nexpressions\n.Sh DESCRIPTION\nText.\n",
+    );
+    assert_eq!(
+        page.one_line_description().as_deref(),
+        Some("file pattern searcher -- with regular expressions")
+    );
+    assert_eq!(page.title_tuple(), Some(("grep".into(), "1".into())));
+    let page = Mdoc::parse(".TH \"TAR\" \"5\"\n.SH \"NAME\"\ntar, tarball \\- archive format\n");
+    assert_eq!(
+        page.one_line_description().as_deref(),
+        Some("archive format")
+    );
+    assert_eq!(page.title_tuple(), Some(("tar".into(), "5".into())));
+    let page = Mdoc::parse(".Dt FOO 3\n.Sh DESCRIPTION\n");
+    assert_eq!(page.one_line_description(), None);
+    assert_eq!(page.title_tuple(), Some(("foo".into(), "3".into())));
+    assert_eq!(Mdoc::default().title_tuple(), None);
+    let page = Mdoc::new(
+        None,
+        DocumentTitle {
+            title: title!("LS"),
+            section: section!("1"),
+            arch: None,
+        },
+        name!("ls"),
+        description!("list \\fIdirectory\\fP contents"),
+        None,
+    );
+    assert_eq!(
+        page.one_line_description().as_deref(),
+        Some("list directory contents")
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_subcommands() {
+    let cmd = clap::Command::new("git")
+        .about("the stupid content tracker")
+        .subcommand(clap::Command::new("commit").about("Record changes"))
+        .subcommand(clap::Command::new("gc"))
+        .subcommand(clap::Command::new("internal").hide(true));
+    let mut warnings = Warnings::new();
+    let pages = from_clap::generate_all_with_warnings(cmd, FlagStyle::default(), &mut warnings);
+    assert_eq!(pages.len(), 3);
+    assert!(warnings.is_empty());
+    assert_eq!(
+        pages[0].1.to_mdoc(),
+        ".Dd $Mdocdate$\n.Dt git 1\n.Os\n.Sh NAME\n.Nm git\n.Nd the stupid content tracker\n.Sh SYNOPSIS\n.Nm\n.Op Ar command\n.Sh DESCRIPTION\n.Sh COMMANDS\n.Bl -tag -width Ds\n.It Cm commit\nRecord changes. See\n.Xr git-commit 1 .\n.It Cm gc\nSee\n.Xr git-gc 1 .\n.El\n.Sh SEE ALSO\n.Xr git-commit 1 ,\n.Xr git-gc 1\n"
+    );
+    assert_eq!(pages[2].0, "git-gc");
+    assert_eq!(
+        pages[2].1.to_mdoc(),
+        ".Dd $Mdocdate$\n.Dt git-gc 1\n.Os\n.Sh NAME\n.Nm git-gc\n.Nd \n.Sh SYNOPSIS\n.Nm\n.Sh DESCRIPTION\n.Sh SEE ALSO\n.Xr git 1\n"
+    );
+}
+
+#[test]
+fn test_lint_tree() {
+    let root = std::env::temp_dir().join(format!("mdoc-lint-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("man1")).unwrap();
+    std::fs::create_dir_all(root.join("man3")).unwrap();
+    std::fs::write(
+        root.join("man1/foo.1"),
+        ".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n.Sh DESCRIPTION\n.Bl -tag\n.It x \n.Ed\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("man1/bar.1"), ".so man1/foo.1\n").unwrap();
+    std::fs::write(root.join("man3/baz.3"), ".so man3/missing.3\n").unwrap();
+    std::fs::write(
+        root.join("man3/qux.3"),
+        ".TH QUX 3\n.SH NAME\nqux \\- quux\n.SH DESCRIPTION\n.MS qux 3\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("man3/notes.txt"), "not a page\n").unwrap();
+    let report = lint::lint_tree(&root, &lint::LintOptions::default());
+    let report = report
+        .to_string()
+        .replace(&format!("{}/", root.display()), "");
+    assert_eq!(
+        report,
+        "man1/foo.1: line 9: trailing whitespace\n\
+         man1/foo.1: DESCRIPTION: Ed without Bd\n\
+         man1/foo.1: DESCRIPTION: Bl is not closed\n\
+         man3/baz.3: .so target man3/missing.3 does not exist\n\
+         man3/qux.3: DESCRIPTION: macro MS is not supported\n"
+    );
+    let options = lint::LintOptions {
+        ignore: vec!["man3".into(), "man1/foo.1".into()],
+        ..lint::LintOptions::default()
+    };
+    let report = lint::lint_tree(&root, &options);
+    let _ = std::fs::remove_dir_all(&root);
+    assert!(report.is_empty());
+    assert_eq!(report.files.len(), 1);
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_positionals() {
+    let cmd = clap::Command::new("cp")
+        .arg(
+            clap::Arg::new("verbose")
+                .short('v')
+                .action(clap::ArgAction::SetTrue)
+                .help("Be verbose"),
+        )
+        .arg(
+            clap::Arg::new("source")
+                .required(true)
+                .num_args(1..)
+                .help("Files to copy"),
+        )
+        .arg(clap::Arg::new("target").value_name("dir"));
+    let mut warnings = Warnings::new();
+    let doc = from_clap::to_mdoc_with_warnings(cmd, FlagStyle::default(), &mut warnings);
+    assert!(doc.to_mdoc().contains(
+        ".Sh SYNOPSIS\n.Nm\n.Op Fl v\n.Ar source ...\n.Op Ar dir\n.Sh DESCRIPTION\nThe arguments are as follows:\n.Bl -tag -width Ds\n.It Ar source\nFiles to copy\n.It Ar dir\n.El\n.Sh OPTIONS\n"
+    ));
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["target: the argument has no help text"]
+    );
+}
+
+#[test]
+fn test_lint_policy() {
+    let policy = lint::Policy::parse(
+        "# house rules\n[policy]\nrequired-sections = [\"AUTHORS\", \"SEE ALSO\"]\nmax-line-length = 30\nrequire-spdx = true\nforbid-conditionals = true # no .if t\n",
+    )
+    .unwrap();
+    assert_eq!(
+        policy,
+        lint::Policy {
+            required_sections: vec!["AUTHORS".into(), "SEE ALSO".into()],
+            max_line_length: Some(30),
+            require_spdx: true,
+            forbid_conditionals: true,
+        }
+    );
+    let source = ".Dd $Mdocdate$\n.Sh NAME\n.Nm foo\n.Nd a description that is far too long\n.Sh \"SEE ALSO\"\n.if t .sp\n";
+    let mut warnings = Warnings::new();
+    policy.check(source, &parser::parse(source), &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "missing AUTHORS section",
+            "missing SPDX-License-Identifier",
+            "line 4: longer than 30 characters",
+            "line 6: roff conditional",
+        ]
+    );
+    assert_eq!(
+        lint::Policy::parse("max-line-length = many\n").unwrap_err(),
+        "line 1: expected a number"
+    );
+    assert_eq!(
+        lint::Policy::parse("colour = true\n").unwrap_err(),
+        "line 1: unknown key `colour`"
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_options() {
+    let cmd = clap::Command::new("ls")
+        .arg(
+            clap::Arg::new("all")
+                .short('a')
+                .long("all")
+                .action(clap::ArgAction::SetTrue)
+                .help("Do not ignore entries")
+                .long_help("Do not ignore entries\nstarting with a dot.\n\nImplies -A."),
+        )
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .default_value("auto")
+                .value_parser(["always", "auto", "never"])
+                .help("Colorize the output"),
+        )
+        .arg(clap::Arg::new("secret").long("secret").hide(true))
+        .arg(clap::Arg::new("width").short('w').value_name("COLS"));
+    let mut warnings = Warnings::new();
+    let doc = from_clap::to_mdoc_with_warnings(cmd, FlagStyle::default(), &mut warnings);
+    assert!(doc.to_mdoc().ends_with(
+        ".Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl a , Fl -all\nDo not ignore entries starting with a dot.\n.Pp\nImplies \\-A.\n.It Fl -color Ar WHEN\nColorize the output.\nThe default is\n.Ql auto .\nThe possible values are\n.Cm always , auto , never .\n.It Fl w Ar COLS\n.El\n"
+    ));
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["width: the option has no help text"]
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_possible_values() {
+    use clap::builder::PossibleValue;
+
+    let cmd = clap::Command::new("ls").arg(
+        clap::Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .value_parser([
+                PossibleValue::new("always").help("Always use colors"),
+                PossibleValue::new("auto").help("Use colors on terminals"),
+                PossibleValue::new("never"),
+                PossibleValue::new("sometimes").hide(true),
+            ])
+            .help("Colorize the output"),
+    );
+    let doc = from_clap::to_mdoc(cmd, FlagStyle::default());
+    assert!(doc.to_mdoc().ends_with(
+        ".It Fl -color Ar WHEN\nColorize the output.\nThe possible values are:\n.Bl -tag -width Ds\n.It Cm always\nAlways use colors\n.It Cm auto\nUse colors on terminals\n.It Cm never\n.El\n.El\n"
+    ));
+}
+
+#[test]
+fn test_lint_fixes() {
+    let source = ".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo , bar \\- frobnicate\n.Sh SEE ALSO\n.Xr zsh 10 ,\n.Xr ls 1 ,\n.Xr bash 1 .\n.Sh HOMEPAGE\nSee the site.\n.Sh DESCRIPTION\nIt\u{2019}s \u{201c}fast\u{201d} \u{2014} really.\n";
+    let page = parser::parse(source);
+    let mut warnings = Warnings::new();
+    lint::lint(&page, &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "NAME: missing Nd",
+            "the sections are not in the conventional order",
+            "SEE ALSO: the references are not sorted",
+            "raw characters that should be escaped",
+        ]
+    );
+    assert_eq!(
+        lint::fixes(&page),
+        [
+            lint::Fix::SplitName,
+            lint::Fix::EscapeCharacters,
+            lint::Fix::ReorderSections,
+            lint::Fix::SortSeeAlso,
+        ]
+    );
+
+    let path = std::env::temp_dir().join(format!("mdoc-fix-{}.1", std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    let fixes = lint::fix_file(&path).unwrap();
+    let fixed = std::fs::read_to_string(&path).unwrap();
+    let again = lint::fix_file(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(fixes.len(), 4);
+    assert!(again.is_empty());
+    assert_eq!(
+        fixed,
+        ".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo ,\n.Nm bar\n.Nd frobnicate\n.Sh DESCRIPTION\nIt\\(cqs \\(lqfast\\(rq \\(em really.\n.Sh SEE ALSO\n.Xr bash 1 ,\n.Xr ls 1 ,\n.Xr zsh 10 .\n.Sh HOMEPAGE\nSee the site.\n"
+    );
+    let mut warnings = Warnings::new();
+    lint::lint(&parser::parse(&fixed), &mut warnings);
+    assert!(warnings.is_empty(), "{warnings:?}");
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_defaults_and_required() {
+    let cmd = clap::Command::new("kill")
+        .arg(
+            clap::Arg::new("signal")
+                .short('s')
+                .value_name("SIG")
+                .default_value("SIGTERM")
+                .help("The signal to send"),
+        )
+        .arg(
+            clap::Arg::new("pid")
+                .short('p')
+                .value_name("PID")
+                .required(true)
+                .help("The process to signal"),
+        )
+        .arg(
+            clap::Arg::new("target")
+                .default_value("init.d")
+                .help("The target"),
+        );
+    let doc = from_clap::to_mdoc(cmd, FlagStyle::default()).to_mdoc();
+    assert!(doc.contains(".Sh SYNOPSIS\n.Nm\n.Op Fl s Ar SIG\n.Fl p Ar PID\n.Op Ar target\n"));
+    assert!(doc.contains(".It Ar target\nThe target.\nThe default is\n.Ql init.d .\n"));
+    assert!(doc.ends_with(
+        ".It Fl s Ar SIG\nThe signal to send.\nThe default is\n.Dv SIGTERM .\n.It Fl p Ar PID\nThe process to signal.\nThis option is required.\n.El\n"
+    ));
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn test_git_date() {
+    let root = std::env::temp_dir().join(format!("mdoc-git-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["-c", "user.name=a", "-c", "user.email=a@example.com"])
+            .args(args)
+            .env("GIT_COMMITTER_DATE", "2024-03-01T12:00:00Z")
+            .output()
+            .unwrap()
+    };
+    let source = root.join("foo.md");
+    std::fs::write(&source, "# foo(1) -- frobnicate\n").unwrap();
+    git(&["init", "-q"]);
+    let untracked = git::last_commit_date(&source).unwrap();
+    git(&["add", "foo.md"]);
+    git(&["commit", "-q", "-m", "Add foo"]);
+    let committed = git::last_commit_date(&source).unwrap();
+    std::fs::write(&source, "# foo(1) -- frobnicate files\n").unwrap();
+    let modified = git::last_commit_date(&source).unwrap();
+    let _ = std::fs::remove_dir_all(&root);
+    assert_eq!(untracked, None);
+    assert_eq!(modified, None);
+    let mut page = parser::parse(".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n");
+    page.set_date(committed);
+    assert_eq!(page.render(), ".Dd March 1, 2024\n.Dt FOO 1\n.Os\n");
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_environment() {
+    let cmd = clap::Command::new("ls")
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .env("LS_COLOR")
+                .help("Colorize the output"),
+        )
+        .arg(
+            clap::Arg::new("token")
+                .long("token")
+                .env("LS_TOKEN")
+                .hide_env(true)
+                .help("The token"),
+        )
+        .arg(
+            clap::Arg::new("dir")
+                .env("LS_DIR")
+                .help("The directory to list"),
+        )
+        .author("Jane Doe");
+    let doc = from_clap::to_mdoc(cmd, FlagStyle::default()).to_mdoc();
+    assert!(doc.ends_with(
+        ".Sh ENVIRONMENT\n.Bl -tag -width Ds\n.It Ev LS_COLOR\nUsed for\n.Fl -color Ar WHEN\nwhen the option is not given.\n.It Ev LS_DIR\nUsed for\n.Ar dir\nwhen the argument is not given.\n.El\n.Sh AUTHORS\n.An Jane Doe\n"
+    ), "{doc}");
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_long_about() {
+    let cmd = clap::Command::new("frob")
+        .about("frobnicate files")
+        .before_help("Frobnication is irreversible.")
+        .long_about("Frobnicate the given files\nin place.\n\nFiles are read once.")
+        .after_help("Examples:\n  frob a.txt\n  frob -v b.txt\n\nFrob twice to unfrob.");
+    let mut warnings = Warnings::new();
+    let doc = from_clap::to_mdoc_with_warnings(cmd, FlagStyle::default(), &mut warnings);
+    assert!(doc.to_mdoc().ends_with(
+        ".Sh DESCRIPTION\nFrobnication is irreversible.\n.Pp\nFrobnicate the given files in place.\n.Pp\nFiles are read once.\n.Sh EXAMPLES\n.Bd -literal -offset indent\nfrob a.txt\nfrob \\-v b.txt\n.Ed\nFrob twice to unfrob.\n"
+    ));
+    assert!(warnings.is_empty());
+
+    let cmd = clap::Command::new("frob").after_help("Frobnication is not\nreversible.");
+    let doc = from_clap::to_mdoc(cmd, FlagStyle::default());
+    assert!(doc
+        .to_mdoc()
+        .ends_with(".Sh DESCRIPTION\n.Sh NOTES\nFrobnication is not reversible.\n"));
+}
+
+#[test]
+fn test_lint_synopsis() {
+    let page = parser::parse(concat!(
+        ".Dd $Mdocdate$\n.Dt LS 1\n.Os\n.Sh NAME\n.Nm ls\n.Nd list\n",
+        ".Sh SYNOPSIS\n.Nm\n.Op Fl al\n.Op Fl -color Ns = Ns Ar when\n.Op Fl x Ar file\n",
+        ".Sh DESCRIPTION\nList.\n.Bl -tag -width Ds\n.It Fl a\nAll.\n.It Fl l\nLong.\n.El\n",
+        ".Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl -color Ar when\nColor.\n.It Fl h , Fl -help\nHelp.\n.El\n",
+    ));
+    let mut warnings = Warnings::new();
+    lint::lint(&page, &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "SYNOPSIS: -x is not documented",
+            "OPTIONS: -h is not in the SYNOPSIS",
+            "OPTIONS: --help is not in the SYNOPSIS",
+        ]
+    );
+}
+
+#[test]
+fn test_linter_rules() {
+    struct NoTodo;
+
+    impl lint::LintRule for NoTodo {
+        fn name(&self) -> &str {
+            "no-todo"
+        }
+
+        fn check(&self, context: &lint::Context, warnings: &mut Warnings) {
+            for line in context.section("DESCRIPTION") {
+                let inlines = line.inlines().unwrap_or_default();
+                if inlines
+                    .iter()
+                    .any(|i| matches!(i, Inline::Roman(text) | Inline::Roff(text) if text.contains("TODO")))
+                {
+                    warnings.push_in("DESCRIPTION", "unfinished text");
+                }
+            }
+        }
+    }
+
+    let page = parser::parse(concat!(
+        ".Sh NAME\n.Nm foo\n.Nd frobnicate\n.Sh DESCRIPTION\nTODO.\n",
+        ".Sh SEE ALSO\n.Xr zsh 1 ,\n.Xr ls 1\n.Sh BUGS\n",
+    ));
+    let linter = lint::Linter::default()
+        .without("prologue")
+        .without("see-also-order")
+        .rule(NoTodo);
+    assert_eq!(linter.rules().last(), Some("no-todo"),);
+    let mut warnings = Warnings::new();
+    linter.check(&page, &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["BUGS: empty section", "DESCRIPTION: unfinished text"]
+    );
+    let mut warnings = Warnings::new();
+    lint::Linter::new()
+        .rule(lint::SeeAlsoOrder)
+        .check(&page, &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["SEE ALSO: the references are not sorted"]
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_options_struct() {
+    let cmd = clap::Command::new("frob")
+        .arg(
+            clap::Arg::new("debug")
+                .long("debug")
+                .action(clap::ArgAction::SetTrue)
+                .hide(true)
+                .help("Print debugging output"),
+        )
+        .subcommand(clap::Command::new("serve").about("serve frobs"));
+    let mut options = from_clap::FromClapOptions {
+        section: "8".into(),
+        os: Some(OperatingSystem {
+            system: System::new("Frob"),
+            version: Some(Version::new("1.0")),
+        }),
+        extra_sections: vec![boilerplate::reporting_bugs("https://example.com")],
+        ..from_clap::FromClapOptions::default()
+    };
+    let pages = from_clap::generate(cmd.clone(), &options);
+    assert_eq!(pages.len(), 1);
+    let page = pages[0].1.to_mdoc();
+    assert!(page.starts_with(".Dd $Mdocdate$\n.Dt frob 8\n.Os Frob 1.0\n"));
+    assert!(!page.contains("debug"));
+    assert!(page.ends_with(".Sh REPORTING BUGS\nReport bugs at\n.Lk https://example.com .\n"));
+
+    options.include_hidden = true;
+    options.subcommand_pages = true;
+    let pages = from_clap::generate(cmd, &options);
+    assert_eq!(pages.len(), 2);
+    let page = pages[0].1.to_mdoc();
+    assert!(page.contains(".Op Fl -debug\n"));
+    assert!(page.contains(".It Fl -debug\nPrint debugging output\n"));
+    assert!(page.contains(".It Cm serve\nserve frobs. See\n.Xr frob-serve 8 .\n"));
+    assert!(pages[1].1.to_mdoc().contains(".Sh SEE ALSO\n.Xr frob 8\n"));
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_drift() {
+    let cmd = clap::Command::new("ls")
+        .arg(
+            clap::Arg::new("all")
+                .short('a')
+                .long("all")
+                .action(clap::ArgAction::SetTrue)
+                .help("Do not ignore entries"),
+        )
+        .arg(
+            clap::Arg::new("width")
+                .short('w')
+                .value_name("COLS")
+                .help("Assume the screen is COLS wide"),
+        );
+    let page = Mdoc::parse(&from_clap::to_mdoc(cmd.clone(), FlagStyle::default()).to_mdoc());
+    assert!(from_clap::drift(&cmd, &page).is_empty());
+
+    let cmd = cmd.arg(
+        clap::Arg::new("color")
+            .long("color")
+            .action(clap::ArgAction::SetTrue),
+    );
+    assert_eq!(
+        from_clap::drift(&cmd, &page),
+        [from_clap::Drift::Added(vec!["--color".to_string()])]
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_from_clap_generator() {
+    let cmd = clap::Command::new("git")
+        .about("the stupid content tracker")
+        .subcommand(clap::Command::new("gc").about("clean up"));
+    let generator = from_clap::Generator::default();
+    assert_eq!(generator.file_name("git"), "git.1");
+    let mut buf = vec![];
+    generator.generate(&cmd, &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        from_clap::to_mdoc(cmd.clone(), FlagStyle::default()).render()
+    );
+
+    let out_dir = std::env::temp_dir().join(format!("mdoc-generator-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let generator = from_clap::Generator::new(from_clap::FromClapOptions {
+        section: "8".into(),
+        subcommand_pages: true,
+        ..from_clap::FromClapOptions::default()
+    });
+    let written = generator.generate_to(&cmd, &out_dir).unwrap();
+    let gc = std::fs::read_to_string(out_dir.join("git-gc.8"));
+    let _ = std::fs::remove_dir_all(&out_dir);
+    assert_eq!(written, [out_dir.join("git.8"), out_dir.join("git-gc.8")]);
+    assert!(gc.unwrap().contains(".Dt git-gc 8\n"));
+}
+
+#[test]
+fn test_build_helper() {
+    let dir = std::env::temp_dir()
+        .join(format!("mdoc-build-{}", std::process::id()))
+        .join("man");
+    let frob = Mdoc::parse(".Dd $Mdocdate$\n.Dt FROB 1\n.Os\n.Sh NAME\n.Nm frob\n.Nd frobnicate\n");
+    let conf = Mdoc::parse(".Dd $Mdocdate$\n.Dt FROB.CONF 5\n.Os\n");
+    let written = build_helper::generate_to(&dir, [&frob, &conf]).unwrap();
+    let again = build_helper::generate_to(&dir, [&frob]);
+    std::fs::write(dir.join("frob.1"), "edited\n").unwrap();
+    let edited = build_helper::generate_to(&dir, [&frob]);
+    let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    assert_eq!(written, [dir.join("frob.1"), dir.join("frob.conf.5")]);
+    assert!(again.is_ok());
+    assert_eq!(
+        edited.unwrap_err().kind(),
+        std::io::ErrorKind::AlreadyExists
+    );
+}
+
+#[test]
+fn test_examples() {
+    let page = Mdoc::parse(concat!(
+        ".Sh EXAMPLES\n",
+        "List the files:\n",
+        ".\\\" mdoc-example\n",
+        ".Bd -literal -offset indent\n",
+        "$ ls \\e\n",
+        "    -l /tmp\n",
+        "total 0\n",
+        "$ printf '%s\\en' \"a b\"\n",
+        ".Ed\n",
+        "Not checked:\n",
+        ".Bd -literal -offset indent\n",
+        "rm -rf /\n",
+        ".Ed\n",
+    ));
+    let examples = examples::examples(&page);
+    let commands: Vec<&str> = examples.iter().map(|e| e.command.as_str()).collect();
+    assert_eq!(commands, ["ls -l /tmp", "printf '%s\\n' \"a b\""]);
+    assert_eq!(examples[0].section, "EXAMPLES");
+    assert_eq!(
+        examples::words(&examples[1].command),
+        ["printf", "%s\\n", "a b"]
+    );
+
+    let mut page = Mdoc::default();
+    page.examples(examples::runnable(["printf 'a\\n'"]));
+    assert_eq!(
+        page.to_mdoc(),
+        ".Sh EXAMPLES\n.\\\" mdoc-example\n.Bd -literal -offset indent\n$ printf 'a\\en'\n.Ed\n"
+    );
+    assert_eq!(examples::examples(&page)[0].command, "printf 'a\\n'");
+}
+
+#[test]
+fn test_from_cargo_env() {
+    // Cargo sets the metadata of the package in the environment of tests
+    // as it does for build scripts.
+    let page = Mdoc::from_cargo_env();
+    assert_eq!(page, from_cargo_env!());
+    assert!(page.to_mdoc().starts_with(&format!(
+        ".Dd $Mdocdate$\n.Dt MDOC 1\n.Os mdoc {}\n",
+        env!("CARGO_PKG_VERSION")
+    )));
+}
+
+#[test]
+#[cfg(all(feature = "markdown", feature = "clap"))]
+fn test_origins() {
+    fn origin_of(page: &Mdoc, line: Line) -> Option<&Origin> {
+        page.lines_with_origins()
+            .find(|(l, _)| **l == line)
+            .and_then(|(_, origin)| origin)
+    }
+
+    let page = markdown::from_markdown(
+        "# foo(1) - frobnicate\n\n## DESCRIPTION\n\nFrobnicate\nthe bar.\n",
+    );
+    assert_eq!(
+        origin_of(
+            &page,
+            Line::control("Sh".into(), vec!["DESCRIPTION".into()])
+        ),
+        Some(&Origin::Line(3))
+    );
+    assert_eq!(
+        origin_of(&page, Line::text(vec![roman("Frobnicate the bar.")])),
+        Some(&Origin::Line(5))
+    );
+
+    let cmd = clap::Command::new("foo")
+        .arg(
+            clap::Arg::new("verbose")
+                .short('v')
+                .help("Be verbose.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(clap::Arg::new("file").help("The file."));
+    let page = from_clap::to_mdoc(cmd, FlagStyle::default());
+    let verbose = Origin::Arg("verbose".to_string());
+    assert_eq!(
+        origin_of(
+            &page,
+            Line::control("It".into(), vec!["Fl".into(), "v".into()])
+        ),
+        Some(&verbose)
+    );
+    assert_eq!(
+        origin_of(&page, Line::text(vec![roman("Be verbose.")])),
+        Some(&verbose)
+    );
+    assert_eq!(
+        origin_of(&page, Line::text(vec![roman("The file.")])),
+        Some(&Origin::Arg("file".to_string()))
+    );
+    assert_eq!(verbose.to_string(), "argument `verbose`");
+
+    let mut page = Mdoc::default();
+    boilerplate::reporting_bugs("https://example.com").add_to(&mut page);
+    assert_eq!(
+        page.origin(0),
+        Some(&Origin::Template("REPORTING BUGS".to_string()))
+    );
+    // Origins are not part of the content.
+    let other = Mdoc {
+        lines: page.lines.clone(),
+        ..Mdoc::default()
+    };
+    assert_eq!(page, other);
+}
+
+#[test]
+fn test_description_summarize() {
+    assert_eq!(
+        Description::summarize("A fast, friendly grep.\n\nIt searches recursively.").0,
+        "a fast, friendly grep"
+    );
+    assert_eq!(Description::summarize("GitHub CLI.").0, "GitHub CLI");
+    let long = "Convert documents between dozens of markup formats, including Markdown, HTML, LaTeX and roff";
+    let summary = Description::summarize(long);
+    assert_eq!(
+        summary.0,
+        "convert documents between dozens of markup formats"
+    );
+    assert!(summary.0.len() <= Description::MAX_LENGTH);
+
+    let source = format!(".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd {long}\n");
+    let mut warnings = Warnings::new();
+    lint::lint(&Mdoc::parse(&source), &mut warnings);
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["NAME: the description is longer than 60 characters"]
+    );
+}
+
+#[test]
+fn test_render_control_escaping() {
+    let text = Mdoc::default()
+        .control("Ql".into(), ["C:\\", "-x", "\"quoted", "\\(em"])
+        .control("Op".into(), ["Fl", "-all", "Ar", "-"])
+        .control("Nd".into(), ["\"say \"hi\" \\\"now\\\"\""])
+        .control("Bl".into(), ["-tag", "-width", "Ds"])
+        .control("ta".into(), ["\\"])
+        .to_mdoc();
+    assert_eq!(
+        text,
+        concat!(
+            ".Ql C:\\e \\-x \\(dqquoted \\(em\n",
+            ".Op Fl -all Ar \\-\n",
+            ".Nd \"say \"\"hi\"\" \\e\"\"now\\e\"\"\"\n",
+            ".Bl -tag -width Ds\n",
+            ".ta \\\n",
+        )
+    );
+}
+
+#[test]
+fn test_flag_references() {
+    let mut page = Mdoc::parse(concat!(
+        ".Dd $Mdocdate$\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frobnicate\n",
+        ".Sh SYNOPSIS\n.Nm\n.Op Fl qv\n.Op Fl -quiet\n",
+        ".Sh DESCRIPTION\nThe options are as follows:\n",
+        ".Bl -tag -width Ds\n.It Fl q , Fl -quiet\nBe quiet.\n.It Fl v\nBe verbose.\n.El\n",
+    ));
+    let quiet = page.flag_reference("--quiet");
+    assert_eq!(
+        quiet,
+        Inline::Flag {
+            short: Some('q'),
+            long: Some("quiet".to_string()),
+            value: None,
+        }
+    );
+    let verbose = page.flag_reference("-v");
+    let dangling = page.flag_reference("--debug");
+    page.text([roman("With "), verbose, roman(", see "), quiet, roman(".")]);
+    page.control("Fl".into(), ["qv"]);
+    page.text([roman("Not "), dangling]);
+    let mut warnings = Warnings::new();
+    lint::lint(&page, &mut warnings);
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["DESCRIPTION: --debug is not an option of the page"]
+    );
+}
+
+#[test]
+fn test_render_control_quoting() {
+    let text = Mdoc::default()
+        .control("Nd".into(), ["list directory contents"])
+        .control("Nd".into(), ["read the Xr page"])
+        .control("Dt".into(), ["FOO", "1", "an \"arch\""])
+        .control("Ql".into(), [""])
+        .control("Sh".into(), ["SEE ALSO"])
+        .to_mdoc();
+    assert_eq!(
+        text,
+        concat!(
+            ".Nd list directory contents\n",
+            ".Nd \"read the Xr page\"\n",
+            ".Dt FOO 1 \"an \"\"arch\"\"\"\n",
+            ".Ql \"\"\n",
+            ".Sh SEE ALSO\n",
+        )
+    );
+}
+
+#[test]
+fn test_render_font_macros() {
+    let mut page = Mdoc::default();
+    page.text([
+        roman("Use "),
+        bold("-a"),
+        roman(", or "),
+        italic("all files"),
+        roman("."),
+        Inline::LineBreak,
+        roman(".hidden too"),
+    ]);
+    let options = RenderOptions {
+        font_style: FontStyle::Macros,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        page.render_with(&options),
+        "Use\n.Sy \\-a ,\nor\n.Em \"all files\" .\n.br\n\\&.hidden too\n"
+    );
+    assert_eq!(
+        page.to_mdoc(),
+        "Use \\fB\\-a\\fR, or \\fIall files\\fR.\n.br\n\\&.hidden too\n"
+    );
+    let man = man::ManRenderer::new(options).render(&page);
+    assert!(man.ends_with("Use\n.B -a\n, or\n.I \"all files\"\n\\&.\n.br\n\\&.hidden too\n"));
+}
+
+#[test]
+fn test_snippets() {
+    let mut manual = Manual::new();
+    manual.snippet(
+        "precedence",
+        Snippet::new(vec![
+            Line::text(vec![roman(
+                "Options given to {{name}} override those of {{file}}.",
+            )]),
+            Line::text(vec![roman("See {{missing}}.")]),
+        ]),
+    );
+    for name in ["frob", "frobctl"] {
+        let mut page = Mdoc::parse(&format!(
+            ".Dd $Mdocdate$\n.Dt {} 1\n.Os\n.Sh NAME\n.Nm {name}\n.Nd frobnicate\n.Sh FILES\n",
+            name.to_uppercase()
+        ));
+        page.include("precedence", [("file", "~/.frobrc")])
+            .include("unknown", []);
+        manual.page(page);
+    }
+    let mut warnings = Warnings::new();
+    let page = manual.expand_snippets(&manual.pages[1], &mut warnings);
+    assert!(page.to_mdoc().ends_with(concat!(
+        ".Sh FILES\n",
+        "Options given to frobctl override those of ~/.frobrc.\n",
+        "See {{missing}}.\n",
+        ".\\\" mdoc-include unknown\n",
+    )));
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "snippet variable missing is not set",
+            "unknown snippet unknown"
+        ]
+    );
+
+    let out_dir = std::env::temp_dir().join(format!("mdoc-snippets-{}", std::process::id()));
+    manual.generate_incremental(&out_dir).unwrap();
+    let written = std::fs::read_to_string(out_dir.join("man1/frob.1")).unwrap();
+    assert!(written.contains("\nOptions given to frob override those of ~/.frobrc.\n"));
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_aliases() {
+    let out_dir = std::env::temp_dir().join(format!("mdoc-aliases-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&out_dir);
+    let mut manual = Manual::new();
+    manual
+        .page(Mdoc::parse(
+            ".Dd $Mdocdate$\n.Dt GREP 1\n.Os\n.Sh NAME\n.Nm grep\n.Nd print lines that match patterns\n",
+        ))
+        .alias("egrep", "grep")
+        .alias("fgrep", "grep");
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.written.len(), 3);
+    assert_eq!(
+        std::fs::read_to_string(out_dir.join("man1/egrep.1")).unwrap(),
+        ".so man1/grep.1\n"
+    );
+    let report = lint::lint_tree(&out_dir, &lint::LintOptions::default());
+    assert!(!report.to_string().contains(".so"));
+
+    // Stubs are replaced by copies of the page.
+    manual.alias_style = AliasStyle::Copy;
+    let generated = manual.generate_incremental(&out_dir).unwrap();
+    assert_eq!(generated.skipped, [out_dir.join("man1/grep.1")]);
+    let copy = std::fs::read_to_string(out_dir.join("man1/fgrep.1")).unwrap();
+    assert_eq!(
+        copy,
+        std::fs::read_to_string(out_dir.join("man1/grep.1")).unwrap()
+    );
+
+    manual.alias("zgrep", "zcat");
+    let err = manual.generate_incremental(&out_dir).unwrap_err();
+    assert_eq!(err.to_string(), "alias zgrep: the manual has no page zcat");
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_validate() {
+    let page = Mdoc::parse(concat!(
+        ".Dd $Mdocdate$\n",
+        ".Dt FOO 1\n",
+        ".Os\n",
+        ".\\\" A comment.\n",
+        ".Sh NAME\n",
+        ".Nm foo\n",
+        ".Nd frobnicate\n",
+        ".Sh DESCRIPTION\n",
+        ".Op Fl v\n",
+        ".Xr ls 1 ,\n",
+        ".Bd -literal\n",
+        ".Bl -tag -width Ds\n",
+        ".It Fl v\n",
+        ".Ed\n",
+        ".El\n",
+        ".Pp x\n",
+        ".SH BUGS\n",
+        ".br\n",
+    ));
+    let diagnostics = page.validate();
+    let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "line 12: Bl is not closed",
+            "line 15: El without Bl",
+            "line 16: Pp takes at most 0 arguments, found 1",
+            "line 17: unknown macro SH",
+        ]
+    );
+    assert_eq!(
+        diagnostics[3].problem,
+        validate::Problem::UnknownMacro("SH".to_string())
+    );
+
+    let mut page = Mdoc::default();
+    page.control("Cm".into(), []);
+    page.set_origin(0, Origin::Arg("color".to_string()));
+    let messages: Vec<String> = page.validate().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["line 1 (from argument `color`): Cm needs at least 1 argument, found 0"]
+    );
+}
+
+#[test]
+fn test_manual_section() {
+    for section in ManualSection::ALL {
+        assert_eq!(section.to_string().parse::<ManualSection>(), Ok(section));
+    }
+    assert_eq!(
+        "3ssl".parse::<ManualSection>(),
+        Ok(ManualSection::Other("3ssl".to_string()))
+    );
+    assert_eq!(section!("1").manual_section(), ManualSection::Commands);
+    assert_eq!(Section::from(ManualSection::Local), section!("l"));
+
+    let page = Mdoc::parse(
+        ".Dt FOO 10
+.Sh SEE ALSO
+.Xr ls 1 ,
+.Xr SSL_new 3ssl ,
+.Xr bar x
+",
+    );
+    let messages: Vec<String> = page.validate().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "line 1: Dt: unknown manual section 10",
+            "line 5: Xr: unknown manual section x",
+        ]
+    );
+}
+
+#[test]
+fn test_sandbox() {
+    use sandbox::{Sandbox, Violation};
+
+    let sandbox = Sandbox {
+        max_size: 200,
+        max_depth: 2,
+        ..Sandbox::default()
+    };
+    let page = sandbox
+        .parse(".Sh NAME\n.Nm foo\n.\\\" so this is a comment\n.Bl -tag\n.It a\n.Bd -literal\nx\n.Ed\n.El\n")
+        .unwrap();
+    assert_eq!(page.lines.len(), 9);
+    assert_eq!(
+        sandbox.parse(&"x\n".repeat(101)),
+        Err(Violation::TooLarge {
+            size: 202,
+            max: 200
+        })
+    );
+    assert_eq!(
+        sandbox.parse(".Bl -tag\n.It a\n.Bl -tag\n.It b\n.Bd -literal\n"),
+        Err(Violation::TooDeep { line: 5, max: 2 })
+    );
+    assert_eq!(
+        sandbox.parse(".if n \\{\\\n.if t \\{\\\n.if t \\{\\\n.\\}\n"),
+        Err(Violation::TooDeep { line: 1, max: 2 })
+    );
+    assert_eq!(
+        sandbox.parse(".de XX\n.sy rm -rf /\n..\n"),
+        Err(Violation::Include {
+            line: 1,
+            request: "sy".to_string()
+        })
+    );
+    assert_eq!(
+        sandbox.parse(".if n \\{\\\n.so /etc/passwd\n.\\}\n"),
+        Err(Violation::Include {
+            line: 1,
+            request: "so".to_string()
+        })
+    );
+    for (source, line, request) in [
+        (".do so /etc/passwd\n", 1, "so"),
+        (".if 1 'so /etc/passwd\n", 1, "so"),
+        (".ie n .ds x y\n.el 'sy id\n", 2, "sy"),
+        (".rn so xx\n.xx /etc/passwd\n", 1, "so"),
+        (".als inc so\n.inc /etc/passwd\n", 1, "so"),
+        (".am so\n..\n", 1, "so"),
+        (".ds x so\n.\\*x /etc/passwd\n", 2, "\\*x"),
+    ] {
+        assert_eq!(
+            sandbox.parse(source),
+            Err(Violation::Include {
+                line,
+                request: request.to_string()
+            }),
+            "{source:?}"
+        );
+    }
+    let mut page = Mdoc::default();
+    page.include("proxy", [("variable", "HTTPS_PROXY")]);
+    assert_eq!(
+        sandbox.check(&page).unwrap_err().to_string(),
+        "line 1: mdoc-include requests are not allowed"
+    );
+    let sandbox = Sandbox {
+        allow_includes: true,
+        ..sandbox
+    };
+    assert_eq!(sandbox.check(&page), Ok(()));
+}
+
+#[test]
+fn test_document_date() {
+    assert_eq!(civil_date(0), (1970, 1, 1));
+    assert_eq!(civil_date(1_709_251_200), (2024, 3, 1));
+    assert_eq!(civil_date(1_709_164_800), (2024, 2, 29));
+    let today = DocumentDate::today();
+    assert!(today.ymd().is_ok());
+    assert_eq!(DocumentDate::from_ymd(2024, 2, 29).unwrap().day.0, "29");
+    assert_eq!(DocumentDate::from_ymd(2100, 2, 29), None);
+    assert_eq!(DocumentDate::from_ymd(2024, 13, 1), None);
+
+    let page = parser::parse(".Dd jul 1, 2024\n.Dt FOO 1\n.Os\n");
+    assert_eq!(
+        page.date.as_ref().unwrap().canonical(),
+        DocumentDate::from_ymd(2024, 7, 1).ok_or_else(String::new)
+    );
+    let page = parser::parse(".Dd April 31, 2024\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frob\n");
+    let mut warnings = Warnings::new();
+    lint::lint(&page, &mut warnings);
+    assert_eq!(
+        warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["Dd: April 2024 has 30 days, not 31"]
+    );
+
+    #[cfg(feature = "time")]
+    assert_eq!(
+        DocumentDate::from(time::Date::from_calendar_date(2024, time::Month::July, 1).unwrap()),
+        DocumentDate::from_ymd(2024, 7, 1).unwrap()
+    );
+}
+
+#[test]
+fn test_parse_limits() {
+    use parser::{LimitError, Limits};
+
+    let options = parser::ParseOptions::default();
+    let limits = Limits {
+        max_lines: 3,
+        max_depth: 2,
+    };
+    assert!(parser::try_parse_with(".Sh NAME\n.Bl -tag\n.It Op Fl v\n", &options, &limits).is_ok());
+    assert_eq!(
+        parser::try_parse_with(".Sh NAME\n.Nm foo\n.Nd bar\nbaz\n", &options, &limits),
+        Err(LimitError::TooManyLines { max: 3 })
+    );
+    assert_eq!(
+        parser::try_parse_with(".Bl -tag\n.It Op Op Fl v\n", &options, &limits),
+        Err(LimitError::TooDeep { line: 2, max: 2 })
+    );
+    assert_eq!(
+        parser::try_parse_with(
+            ".if n \\{\\\n.if t \\{\\\nx\n.\\}\n.\\}\n",
+            &options,
+            &limits
+        )
+        .map(|page| page.lines.len()),
+        Ok(3)
+    );
+    assert_eq!(
+        parser::try_parse_with(
+            ".Bd -literal\n.de XX\n.if n \\{\\\n.if t \\{\\\n..\n",
+            &options,
+            &limits
+        ),
+        Err(LimitError::TooDeep { line: 2, max: 2 })
+    );
+    // The limits hold no matter how long the page is.
+    let source = format!(".Op{}", " Op".repeat(1_000_000));
+    assert_eq!(
+        parser::try_parse_with(&source, &options, &Limits::default()),
+        Err(LimitError::TooDeep { line: 1, max: 64 })
+    );
+
+    let mut page = Mdoc::default();
+    page.control("Sh".into(), ["NAME"])
+        .control("Nm".into(), ["foo"])
+        .control("Nd".into(), ["bar"])
+        .text([roman("baz")]);
+    assert_eq!(
+        page.check_limits(&limits).unwrap_err().to_string(),
+        "the page has more than 3 lines"
+    );
+}
+
+#[test]
+fn test_default_date() {
+    let options = RenderOptions {
+        default_date: Some(DocumentDate::from_unix_time(1_719_792_000)),
+        ..RenderOptions::default()
+    };
+    let undated = Mdoc::parse(".Dd\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd frob\n");
+    assert!(undated
+        .render_with(&options)
+        .starts_with(".Dd July 1, 2024\n.Dt FOO 1\n"));
+    assert!(man::ManRenderer::new(options.clone())
+        .render(&undated)
+        .contains(".TH FOO 1 \"July 1, 2024\""));
+    let dated = Mdoc::parse(".Dd March 1, 2023\n.Dt FOO 1\n.Os\n");
+    assert_eq!(
+        dated.render_with(&options),
+        ".Dd March 1, 2023\n.Dt FOO 1\n.Os\n"
+    );
+}
+
+#[test]
+fn test_crlf() {
+    let source = ".Dd $Mdocdate$\r\n.Dt FOO 1\r\n.Os\r\n.Sh NAME\r\n.Nm foo\r\n.Nd bar\r\n.de XX\r\n..\r\nlast\r";
+    let page = parser::parse(source);
+    let rendered = page.render();
+    assert!(!rendered.contains('\r'));
+    assert!(rendered.ends_with(".de XX\n..\nlast\n"));
+    assert_eq!(page, parser::parse(&source.replace('\r', "")));
+
+    let options = RenderOptions {
+        newline: Newline::CrLf,
+        provenance: Some(provenance::Provenance::default()),
+        ..RenderOptions::default()
+    };
+    for rendered in [
+        page.render_with(&options),
+        man::ManRenderer::new(options.clone()).render(&page),
+        html::HtmlRenderer::new(options.clone()).render(&page),
+    ] {
+        assert!(rendered.ends_with("\r\n"));
+        assert_eq!(
+            rendered.matches('\n').count(),
+            rendered.matches("\r\n").count()
+        );
+        assert!(!rendered.contains("\r\r"));
+    }
+}
+
+#[test]
+fn test_see_also() {
+    assert_eq!(
+        Xr::new("SSL_new", "3ssl"),
+        Ok(Xr {
+            name: "SSL_new".to_string(),
+            section: ManualSection::Other("3ssl".to_string()),
+        })
+    );
+    assert_eq!(
+        Xr::new("foo", "x"),
+        Err("unknown manual section x".to_string())
+    );
+    assert!(Xr::new("foo bar", "1").is_err());
+    assert!("ls 1".parse::<Xr>().is_err());
+    assert_eq!(
+        Line::from(Xr::new("ls", "1").unwrap()),
+        Line::cross_reference("ls".into(), "1".into())
+    );
+
+    let see_also = SeeAlso::new()
+        .reference(Xr::new("init", "8").unwrap())
+        .reference("tclsh(n)".parse().unwrap())
+        .reference("mount(8)".parse().unwrap())
+        .reference("ls(1)".parse().unwrap())
+        .reference("ls(1)".parse().unwrap())
+        .reference("fstab(5)".parse().unwrap());
+    let mut doc = Mdoc::default();
+    see_also.add_to(&mut doc);
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh SEE ALSO
+.Xr ls 1 ,
+.Xr fstab 5 ,
+.Xr init 8 ,
+.Xr mount 8 ,
+.Xr tclsh n
+"
+    );
+    assert!(SeeAlso::new().lines().is_empty());
+}
+
+/// The **mdoc** source of `lines`.
+fn render_lines(lines: Vec<Line>) -> String {
+    Mdoc {
+        lines,
+        ..Mdoc::default()
+    }
+    .to_mdoc()
+}
+
+#[test]
+fn test_compatibility_matrix() {
+    use sections::{CompatibilityMatrix, Support};
+
+    assert!(CompatibilityMatrix::new(["Linux"]).lines().is_empty());
+    let matrix = CompatibilityMatrix::new(["Linux", "No"])
+        .feature("epoll\nfd", [Support::Note("since 2.6\nor later".into())])
+        .feature("Ta", [Support::Partial, Support::Note(String::new())]);
+    assert_eq!(
+        render_lines(matrix.lines()),
+        ".Bl -column \"epoll fd\" \"since 2.6 or later\" \\&No
+.It Sy Feature Ta Sy Linux Ta Sy \\&No
+.It epoll fd Ta since 2.6 or later
+.It \\&Ta Ta partial Ta \\&
+.El
+"
+    );
+}
+
+#[test]
+fn test_glossary() {
+    use sections::{Glossary, TermStyle};
+
+    assert!(Glossary::new().lines().is_empty());
+    let glossary = Glossary::new()
+        .term("No", "A refusal.")
+        .term("-v", "Verbose.\n.Multi-line.")
+        .term("big\nwindow", "")
+        .style(TermStyle::Symbolic);
+    assert_eq!(
+        render_lines(glossary.lines()),
+        ".Bl -tag -width Ds
+.It Sy \\-v
+Verbose.
+\\&.Multi\\-line.
+.It Sy \"big window\"
+.It Sy \\&No
+A refusal.
+.El
+"
+    );
+}
+
+#[test]
+fn test_keybindings() {
+    use sections::{KeyStyle, Keybindings};
+
+    assert!(Keybindings::new().lines().is_empty());
+    let keys = Keybindings::new()
+        .key(".", "repeat", "Repeat the last\nchange.")
+        .key("\\", "Ta", "")
+        .key("\"", "quote", "No such \"key\" Fl")
+        .style(KeyStyle::Cm);
+    assert_eq!(
+        render_lines(keys.lines()),
+        ".Bl -column Key Action Description
+.It Sy Key Ta Sy Action Ta Sy Description
+.It Cm \\&. Ta repeat Ta Repeat the last change.
+.It Cm \\e Ta \\&Ta Ta \\&
+.It Cm \\&\" Ta quote Ta \"\\&No such \"\"key\"\" Fl\"
+.El
+"
+    );
+}
+
+#[test]
+fn test_field_table() {
+    use sections::FieldTable;
+
+    assert!(FieldTable::new().lines().is_empty());
+    let table = FieldTable::new()
+        .field(0, 2, "flags", "Bit 0: compressed.\nBit 1: signed.")
+        .field(2, 0, "No", "")
+        .field(255, 1, "-", "\"Reserved\"");
+    assert_eq!(
+        render_lines(table.lines()),
+        ".Bl -column Offset Size Field Meaning
+.It Sy Offset Ta Sy Size Ta Sy Field Ta Sy Meaning
+.It 0 Ta 2 Ta Va flags Ta Bit 0: compressed. Bit 1: signed.
+.It 2 Ta 0 Ta Va \\&No Ta \\&
+.It 255 Ta 1 Ta Va \\- Ta \\&\"Reserved\"
+.El
+"
+    );
+}
+
+#[test]
+fn test_errors() {
+    use sections::Errors;
+
+    let none: [(&str, &str); 0] = [];
+    assert!(Errors::new().lines().is_empty());
+    assert!(Errors::new().function("frob", none).lines().is_empty());
+    let errors = Errors::new().function(
+        "frob",
+        [
+            (
+                "EINVAL",
+                "The flags are\ninvalid:\n.Dv FROB_ALL\nwith others.",
+            ),
+            ("ENOMEM", ""),
+        ],
+    );
+    assert_eq!(
+        render_lines(errors.lines()),
+        "The
+.Fn frob
+function will fail if:
+.Bl -tag -width Er
+.It Bq Er EINVAL
+The flags are
+invalid:
+\\&.Dv FROB_ALL
+with others.
+.It Bq Er ENOMEM
+.El
+"
+    );
+}
+
+#[test]
+fn test_return_values() {
+    use sections::{ReturnValue, ReturnValues};
+
+    assert!(ReturnValues::new().lines().is_empty());
+    let values = ReturnValues::new()
+        .function(ReturnValue::new("frob_undocumented"))
+        .function(ReturnValue::new("frob_count").success("the number of\n.frob files"))
+        .function(ReturnValue::new("frob_free").failure("-1"))
+        .function(ReturnValue::standard("frob_close"));
+    assert_eq!(
+        render_lines(values.lines()),
+        "Upon successful completion,
+.Fn frob_count
+returns the number of
+\\&.frob files.
+.Pp
+On failure,
+the value \\-1
+is returned.
+.Pp
+.Rv -std frob_close
+"
+    );
+}
+
+#[test]
+fn test_callbacks() {
+    use sections::{Callback, Callbacks};
+
+    assert!(Callbacks::new().lines().is_empty());
+    let callbacks = Callbacks::new()
+        .callback(Callback::new("Ta", "").arg("Op", "The\noption."))
+        .callback(Callback::new("on_close", "Invoked on close.").arg("conn", ""));
+    assert_eq!(
+        render_lines(callbacks.lines()),
+        ".Bl -tag -width Ds
+.It Fn \\&Ta \\&Op
+.Bl -tag -width Ds
+.It Fa \\&Op
+The
+option.
+.El
+.It Fn on_close conn
+Invoked on close.
+.Bl -tag -width Ds
+.It Fa conn
+.El
+.El
+"
+    );
+}
+
+#[test]
+fn test_page_index() {
+    use sections::PageIndex;
+
+    assert!(PageIndex::new().lines().is_empty());
+    let index =
+        PageIndex::new()
+            .page("Nm", "1", "")
+            .page("frob.conf", "5", "frob\nconfiguration file");
+    assert_eq!(
+        render_lines(index.lines()),
+        ".Bl -tag -width Ds
+.It Xr \\&Nm 1
+.It Xr frob.conf 5
+frob
+configuration file
+.El
+"
+    );
+}
+
+#[test]
+fn test_daemon_page() {
+    use daemon::Daemon;
+
+    let text = Daemon::new("frobd")
+        .page(description!("frobnication daemon"))
+        .to_mdoc();
+    assert!(
+        text.ends_with(".Sh DESCRIPTION\n.Nm\nis a daemon.\n"),
+        "{text}"
+    );
+    assert!(!text.contains(".Sh SIGNALS"));
+    assert!(!text.contains(".Sh FILES"));
+
+    let text = Daemon::new("frobd")
+        .config_file("/etc/frobd.conf")
+        .rc_script("/etc/rc.d/frobd")
+        .signal("SIGTERM", "Exit gracefully.")
+        .page(description!("frobnication daemon"))
+        .to_mdoc();
+    assert!(
+        text.ends_with(
+            ".Sh SIGNALS
+.Bl -tag -width Ds
+.It Dv SIGTERM
+Exit gracefully.
+.El
+.Sh FILES
+.Bl -tag -width Ds
+.It Pa /etc/frobd.conf
+Configuration file.
+.It Pa /etc/rc.d/frobd
+Startup script.
+.El
+"
+        ),
+        "{text}"
+    );
+}
+
+#[test]
+fn test_boilerplate_copyright_bsd() {
+    let mut doc = Mdoc::default();
+    boilerplate::copyright_bsd(2024, "Jane Doe").add_to(&mut doc);
+    assert_eq!(
+        doc.to_mdoc(),
+        ".Sh COPYRIGHT
+Copyright \\(co 2024 Jane Doe.
+Redistribution and use in source and binary forms, with or without modification, are permitted provided that the following conditions are met:
+.Bl -enum
+.It
+Redistributions of source code must retain the above copyright notice, this list of conditions and the following disclaimer.
+.It
+Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the following disclaimer in the documentation and/or other materials provided with the distribution.
+.El
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS \"AS IS\" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+"
+    );
+}
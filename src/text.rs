@@ -0,0 +1,737 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Render to filled plain text, as mandoc(1) does with `-T ascii` and
+//! `-T utf8`.
+//!
+//! Applications can show their manual page, or a long `--help`, without
+//! shelling out to man(1). Paragraphs are filled with [`term::fill`] at
+//! the [width](term::FillOptions::width) of the renderer, and typographic
+//! characters use the glyphs of its [`Charset`](term::Charset).
+//!
+//! With [colors](TextRenderer::color) enabled, the output is styled with
+//! ANSI escape sequences according to a [`Theme`]: by default bold for
+//! names, flags and bold text, and underline for arguments and italic
+//! text, as man(1) shows pages.
+//!
+//! # Example
+//!
+//! ```
+//! # use mdoc::*;
+//! # use mdoc::text::TextRenderer;
+//! let page = parser::parse(concat!(
+//!     ".Dt FROB 1\n",
+//!     ".Sh NAME\n",
+//!     ".Nm frob\n",
+//!     ".Nd frobnicate\n",
+//!     ".Sh OPTIONS\n",
+//!     ".Bl -tag -width Ds\n",
+//!     ".It Fl v\n",
+//!     "Print each file name before frobnicating it.\n",
+//!     ".El\n",
+//! ));
+//! let text = TextRenderer::default().width(40).render(&page);
+//! assert_eq!(
+//!     text,
+//!     "FROB(1) General Commands Manual  FROB(1)\n\
+//!      \n\
+//!      NAME\n     frob - frobnicate\n\
+//!      \n\
+//!      OPTIONS\n\
+//!      \x20    -v    Print each file name before\n\
+//!      \x20          frobnicating it.\n\
+//!      \n\
+//!      \x20                                FROB(1)\n"
+//! );
+//! ```
+
+use super::*;
+use term::{Charset, ColorChoice, FillOptions, Glyphs, Style, Theme};
+
+/// Renders documents as filled plain text.
+#[derive(Debug, Clone, Default)]
+pub struct TextRenderer {
+    options: RenderOptions,
+    fill: FillOptions,
+    charset: Charset,
+    /// The theme of styled output, if styling is enabled.
+    theme: Option<Theme>,
+}
+
+/// Columns the body of sections is indented by.
+const INDENT: usize = 5;
+/// Columns subsection headings are indented by.
+const SUBHEADING_INDENT: usize = 3;
+/// Columns displays are indented by with `-offset indent`, and `D1`.
+const OFFSET: usize = 6;
+
+impl TextRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Fill with the given options, of which the width is the width of the
+    /// whole page.
+    pub fn fill(self, fill: FillOptions) -> Self {
+        Self { fill, ..self }
+    }
+
+    /// Fill to `width` columns.
+    pub fn width(self, width: usize) -> Self {
+        Self {
+            fill: FillOptions { width, ..self.fill },
+            ..self
+        }
+    }
+
+    pub fn charset(self, charset: Charset) -> Self {
+        Self { charset, ..self }
+    }
+
+    /// Style the output with the [`Theme`] configured by `MDOC_COLORS` if
+    /// `choice` is [enabled](ColorChoice::enabled).
+    pub fn color(self, choice: ColorChoice) -> Self {
+        Self {
+            theme: choice.enabled().then(Theme::from_env),
+            ..self
+        }
+    }
+
+    /// Style the output with `theme`.
+    pub fn theme(self, theme: Theme) -> Self {
+        Self {
+            theme: Some(theme),
+            ..self
+        }
+    }
+
+    /// A renderer for the terminal of the standard output, with its width,
+    /// the charset of the locale and colors if it supports them.
+    pub fn terminal() -> Self {
+        Self::default()
+            .fill(FillOptions::from_env())
+            .charset(Charset::from_env())
+            .color(ColorChoice::Auto)
+    }
+
+    /// Render `page` as plain text.
+    pub fn render(&self, page: &Mdoc) -> String {
+        let mut buf = vec![];
+        self.to_writer(page, &mut buf).unwrap(); // writing to a Vec always works
+        String::from_utf8(buf)
+            .expect("output is utf8 if all input is utf8 and our API guarantees that")
+    }
+
+    /// Write `page` as plain text to a writer.
+    pub fn to_writer(&self, page: &Mdoc, w: &mut dyn Write) -> Result<(), std::io::Error> {
+        #[cfg(feature = "tracing")]
+        let _document = tracing::debug_span!("render_text", document = %page.name.0).entered();
+        let lines = page.rendered_lines(&self.options);
+        let mut lowering = Lowering {
+            renderer: self,
+            glyphs: self.charset.glyphs(),
+            name: page.name.0.to_string(),
+            out: vec![],
+            header: [String::new(), String::new(), String::new(), String::new()],
+            section: String::new(),
+            indent: 0,
+            paragraph: String::new(),
+            tag: None,
+            blank: false,
+            after_heading: false,
+            names: vec![],
+            lists: vec![],
+            displays: vec![],
+            reference: None,
+        };
+        if lowering.name.is_empty() {
+            lowering.name = lines
+                .iter()
+                .find_map(|line| match line {
+                    Line::Control { name, args } if name == "Nm" => {
+                        args.first().map(ToString::to_string)
+                    }
+                    _ => None,
+                })
+                .unwrap_or_default();
+        }
+        for line in lines.iter() {
+            lowering.line(line);
+        }
+        lowering.flush_names();
+        lowering.flush();
+        let [title, section, date, os] = &lowering.header;
+        let title = if title.is_empty() {
+            lowering.name.to_uppercase()
+        } else {
+            title.clone()
+        };
+        let title = if section.is_empty() {
+            title
+        } else {
+            format!("{title}({section})")
+        };
+        let total = self.fill.width;
+        let mut ret = String::new();
+        ret.push_str(&spread(&[&title, volume(section), &title], total));
+        ret.push_str("\n\n");
+        for line in &lowering.out {
+            ret.push_str(line.trim_end());
+            ret.push('\n');
+        }
+        ret.push('\n');
+        ret.push_str(&spread(&[os, date, &title], total));
+        ret.push('\n');
+        self.options.newline.writer(w).write_all(ret.as_bytes())
+    }
+}
+
+/// The name of the volume of the manual of `section`.
+fn volume(section: &str) -> &'static str {
+    match section {
+        "1" => "General Commands Manual",
+        "2" => "System Calls Manual",
+        "3" => "Library Functions Manual",
+        "4" => "Device Drivers Manual",
+        "5" => "File Formats Manual",
+        "6" => "Games Manual",
+        "7" => "Miscellaneous Information Manual",
+        "8" => "System Manager's Manual",
+        "9" => "Kernel Developer's Manual",
+        _ => "",
+    }
+}
+
+/// Lay out the left, center and right parts of a header or footer line.
+fn spread(parts: &[&str; 3], total: usize) -> String {
+    let [left, center, right] = parts.map(term::width);
+    let center_start = (total.saturating_sub(center) / 2).max(left + 1);
+    let right_start = total.saturating_sub(right).max(center_start + center + 1);
+    let mut ret = parts[0].to_string();
+    ret.extend(std::iter::repeat_n(' ', center_start - left));
+    ret.push_str(parts[1]);
+    ret.extend(std::iter::repeat_n(
+        ' ',
+        right_start - center_start - center,
+    ));
+    ret.push_str(parts[2]);
+    ret
+}
+
+/// An open `Bl` list.
+struct List {
+    kind: String,
+    /// The indentation of the list, where tags start.
+    indent: usize,
+    /// The width of tags, after which bodies start.
+    width: usize,
+    compact: bool,
+    count: usize,
+    /// The widths of the columns of a `-column` list.
+    columns: Vec<usize>,
+}
+
+/// An open `Bd` display.
+struct Display {
+    no_fill: bool,
+    /// The indentation before the display.
+    indent: usize,
+}
+
+/// The state of the lowering of one document.
+struct Lowering<'a> {
+    renderer: &'a TextRenderer,
+    glyphs: Glyphs,
+    name: String,
+    out: Vec<String>,
+    /// The title, section, date and operating system of the page.
+    header: [String; 4],
+    section: String,
+    indent: usize,
+    /// Text waiting to be filled.
+    paragraph: String,
+    /// The tag of a list item waiting for its body, and the column the tag
+    /// starts at.
+    tag: Option<(String, usize)>,
+    /// Whether a blank line goes before the next output.
+    blank: bool,
+    /// Whether the last output line is a heading, which is never followed
+    /// by a blank line.
+    after_heading: bool,
+    /// The `Nm` names of the NAME section, waiting for its `Nd`.
+    names: Vec<String>,
+    lists: Vec<List>,
+    displays: Vec<Display>,
+    /// The fields of an open `Rs` reference.
+    reference: Option<Vec<(String, String)>>,
+}
+
+impl Lowering<'_> {
+    fn line(&mut self, line: &Line) {
+        match line {
+            Line::Figure(figure) => figure.lines().iter().for_each(|l| self.line(l)),
+            Line::Admonition(admonition) => admonition.lines().iter().for_each(|l| self.line(l)),
+            Line::Changed(line) => self.line(line),
+            // Roff source can't be shown faithfully as text.
+            Line::Unknown(_) => {}
+            Line::Text(inlines) => {
+                let text = self.inlines(inlines);
+                self.text(&text);
+            }
+            Line::Control { name, args } => self.control(name, args),
+        }
+    }
+
+    fn control(&mut self, name: &str, args: &[Str]) {
+        let args: Vec<Str> = args
+            .iter()
+            .filter(|arg| !arg.starts_with("\\\n"))
+            .cloned()
+            .collect();
+        let joined = || args.join(" ");
+        if name == "Rs" {
+            self.reference = Some(vec![]);
+            return;
+        }
+        if let Some(fields) = self.reference.as_mut() {
+            if let Some(field) = name.strip_prefix('%') {
+                fields.push((field.to_string(), joined()));
+                return;
+            }
+            if name == "Re" {
+                let fields = self.reference.take().unwrap();
+                let text = self.reference_text(&fields);
+                self.text(&text);
+                return;
+            }
+        }
+        match name {
+            "Dd" => {
+                self.header[2] = joined()
+                    .trim_start_matches("$Mdocdate")
+                    .trim_start_matches(':')
+                    .trim_end_matches('$')
+                    .trim()
+                    .to_string()
+            }
+            "Dt" => {
+                for (field, arg) in self.header.iter_mut().zip(args.iter().take(2)) {
+                    *field = arg.to_string();
+                }
+            }
+            "Os" => self.header[3] = joined(),
+            "Sh" | "Ss" => {
+                self.flush_names();
+                self.flush();
+                self.lists.clear();
+                self.displays.clear();
+                self.blank = !self.out.is_empty();
+                let title = joined();
+                if name == "Sh" {
+                    self.section = title.clone();
+                    let heading = self.paint(|t| &t.heading, &title);
+                    self.emit(heading);
+                } else {
+                    let heading = self.paint(|t| &t.subheading, &title);
+                    self.emit(format!("{}{heading}", " ".repeat(SUBHEADING_INDENT)));
+                }
+                self.after_heading = true;
+                self.indent = INDENT;
+            }
+            "Pp" | "Lp" => {
+                self.flush();
+                self.blank = true;
+            }
+            "br" => self.flush(),
+            "sp" => {
+                self.flush();
+                self.blank = true;
+            }
+            "Nm" if self.section == "NAME" => self.names.extend(
+                args.iter()
+                    .filter(|a| !phrase::is_closing(a))
+                    .map(|a| a.to_string()),
+            ),
+            "Nd" => {
+                let mut names = std::mem::take(&mut self.names);
+                if names.is_empty() {
+                    names.push(self.name.clone());
+                }
+                let names: Vec<String> = names
+                    .iter()
+                    .map(|n| phrase::Markup::call(self, "Nm", &[n]))
+                    .collect();
+                let text = format!(
+                    "{} {} {}",
+                    names.join(", "),
+                    self.glyphs.en_dash,
+                    self.phrase(&args)
+                );
+                self.text(&text);
+            }
+            "Nm" if self.section == "SYNOPSIS" => {
+                self.flush();
+                let text = phrase::macro_line(self, "Nm", &args);
+                self.text(&text);
+            }
+            "Bd" => {
+                self.flush();
+                if !args.iter().any(|a| a == "-compact") {
+                    self.blank = true;
+                }
+                self.displays.push(Display {
+                    no_fill: args.iter().any(|a| a == "-literal" || a == "-unfilled"),
+                    indent: self.indent,
+                });
+                if args.iter().any(|a| a == "-offset") {
+                    self.indent += OFFSET;
+                }
+            }
+            "Ed" => {
+                self.flush();
+                if let Some(display) = self.displays.pop() {
+                    self.indent = display.indent;
+                }
+            }
+            "D1" | "Dl" => {
+                self.flush();
+                let text = self.phrase(&args);
+                self.indent += OFFSET;
+                self.text(&text);
+                self.flush();
+                self.indent -= OFFSET;
+            }
+            "Bl" => self.begin_list(&args),
+            "It" => self.item(&args),
+            "El" => {
+                self.flush();
+                if let Some(list) = self.lists.pop() {
+                    self.indent = list.indent;
+                }
+            }
+            "Bk" | "Ek" | "Sm" | "Tg" => {}
+            _ if !name.starts_with(|c: char| c.is_ascii_uppercase()) => {}
+            _ => {
+                let text = phrase::macro_line(self, name, &args);
+                self.text(&text);
+            }
+        }
+    }
+
+    fn begin_list(&mut self, args: &[Str]) {
+        self.flush();
+        let kind = args
+            .iter()
+            .find(|a| {
+                a.starts_with('-') && !matches!(a.as_ref(), "-width" | "-offset" | "-compact")
+            })
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "-tag".to_string());
+        let width = match args.iter().position(|a| a == "-width") {
+            Some(i) => args.get(i + 1).map_or(OFFSET, |w| tag_width(w)),
+            None => match kind.as_str() {
+                "-bullet" | "-dash" | "-hyphen" => 2,
+                "-enum" => 4,
+                "-item" => 0,
+                _ => OFFSET,
+            },
+        };
+        let compact = args.iter().any(|a| a == "-compact");
+        if !compact {
+            self.blank = true;
+        }
+        let mut indent = self.indent;
+        if args.iter().any(|a| a == "-offset") {
+            indent += OFFSET;
+        }
+        let columns = args
+            .iter()
+            .skip_while(|a| *a != "-column")
+            .skip(1)
+            .take_while(|a| !a.starts_with('-'))
+            .map(|w| tag_width(w))
+            .collect();
+        self.lists.push(List {
+            kind,
+            indent,
+            width,
+            compact,
+            count: 0,
+            columns,
+        });
+    }
+
+    fn item(&mut self, args: &[Str]) {
+        self.flush();
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        list.count += 1;
+        if !list.compact && list.count > 1 {
+            self.blank = true;
+        }
+        let (kind, indent, width, count) = (list.kind.clone(), list.indent, list.width, list.count);
+        let columns = list.columns.clone();
+        self.indent = indent + width;
+        let tag = match kind.as_str() {
+            "-bullet" => self.glyphs.bullet.to_string(),
+            "-dash" | "-hyphen" => "-".to_string(),
+            "-enum" => format!("{count}."),
+            "-item" => return,
+            "-column" => {
+                let mut row = String::new();
+                let cells: Vec<String> =
+                    args.split(|a| a == "Ta").map(|c| self.phrase(c)).collect();
+                for (i, cell) in cells.iter().enumerate() {
+                    row.push_str(cell);
+                    if let Some(column) = columns.get(i).filter(|_| i + 1 < cells.len()) {
+                        let pad = (column + 2).saturating_sub(term::width(cell)).max(1);
+                        row.extend(std::iter::repeat_n(' ', pad));
+                    }
+                }
+                self.indent = indent;
+                self.emit(format!("{}{row}", " ".repeat(indent)));
+                return;
+            }
+            _ => self.phrase(args),
+        };
+        self.tag = Some((tag, indent));
+    }
+
+    /// Write the NAME line for `Nm` names without an `Nd`.
+    fn flush_names(&mut self) {
+        if !self.names.is_empty() {
+            let names: Vec<String> = std::mem::take(&mut self.names)
+                .iter()
+                .map(|n| phrase::Markup::call(self, "Nm", &[n]))
+                .collect();
+            self.text(&names.join(", "));
+        }
+    }
+
+    /// Add text to the paragraph, or write it as is in no-fill displays.
+    fn text(&mut self, text: &str) {
+        if self.displays.last().is_some_and(|d| d.no_fill) {
+            for line in text.lines() {
+                let line = format!("{}{line}", " ".repeat(self.indent));
+                self.emit(line);
+            }
+            return;
+        }
+        if !self.paragraph.is_empty() {
+            self.paragraph.push(' ');
+        }
+        self.paragraph.push_str(text);
+    }
+
+    /// Fill the pending paragraph and write it along with a pending tag.
+    fn flush(&mut self) {
+        let paragraph = std::mem::take(&mut self.paragraph);
+        let tag = self.tag.take();
+        if paragraph.is_empty() && tag.is_none() {
+            return;
+        }
+        let fill = FillOptions {
+            width: self.renderer.fill.width.saturating_sub(self.indent).max(1),
+            ..self.renderer.fill
+        };
+        let mut lines = term::fill(&paragraph, &fill).into_iter();
+        let margin = " ".repeat(self.indent);
+        if let Some((tag, at)) = tag {
+            let tag_line = format!("{}{tag}", " ".repeat(at));
+            // The body starts on the line of the tag if the tag fits
+            // before it with a space to spare.
+            if term::width(&tag_line) < self.indent {
+                let pad = self.indent - term::width(&tag_line);
+                let first = lines.next().unwrap_or_default();
+                self.emit(format!("{tag_line}{}{first}", " ".repeat(pad)));
+            } else {
+                self.emit(tag_line);
+            }
+        }
+        for line in lines {
+            self.emit(format!("{margin}{line}"));
+        }
+    }
+
+    /// Write an output line, after a blank line if one is pending.
+    fn emit(&mut self, line: String) {
+        if std::mem::take(&mut self.blank)
+            && !self.after_heading
+            && self.out.last().is_some_and(|l| !l.is_empty())
+        {
+            self.out.push(String::new());
+        }
+        self.after_heading = false;
+        self.out.push(line);
+    }
+
+    /// The inline elements of a text line as text.
+    fn inlines(&self, inlines: &[Inline]) -> String {
+        let mut ret = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Roman(text) | Inline::Roff(text) | Inline::Acronym(text) => {
+                    ret.push_str(&self.unescape(text))
+                }
+                Inline::Bold(text) => ret.push_str(&self.paint(|t| &t.bold, &self.unescape(text))),
+                Inline::Italic(text) => {
+                    ret.push_str(&self.paint(|t| &t.italic, &self.unescape(text)))
+                }
+                Inline::LineBreak => ret.push('\n'),
+                Inline::Name | Inline::Flag { .. } => {
+                    ret.push_str(&self.phrase(&inline.macro_args(&self.renderer.options)))
+                }
+                Inline::IndexTerm(_) => {}
+            }
+        }
+        ret
+    }
+
+    /// The text of an `Rs` reference: its authors, then its other fields in
+    /// order.
+    fn reference_text(&self, fields: &[(String, String)]) -> String {
+        let authors: Vec<&str> = fields
+            .iter()
+            .filter(|(field, _)| field == "A")
+            .map(|(_, value)| value.as_str())
+            .collect();
+        let mut parts = vec![];
+        match authors.split_last() {
+            Some((last, [])) => parts.push(last.to_string()),
+            Some((last, rest)) => parts.push(format!("{} and {last}", rest.join(", "))),
+            None => {}
+        }
+        for (field, value) in fields.iter().filter(|(field, _)| field != "A") {
+            let (open, close) = self.glyphs.double_quotes;
+            match field.as_str() {
+                "T" => parts.push(format!("{open}{}{close}", self.unescape(value))),
+                _ => parts.push(self.unescape(value)),
+            }
+        }
+        format!("{}.", parts.join(", "))
+    }
+
+    fn unescape(&self, text: &str) -> String {
+        self.glyphs.unescape(text)
+    }
+
+    /// Paint `text` with a style of the theme, if styling is enabled.
+    fn paint(&self, style: fn(&Theme) -> &Style, text: &str) -> String {
+        match &self.renderer.theme {
+            Some(theme) => style(theme).paint_words(text),
+            None => text.to_string(),
+        }
+    }
+
+    fn phrase(&self, args: &[Str]) -> String {
+        phrase::phrase(self, args)
+    }
+}
+
+impl phrase::Markup for Lowering<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text(&self, text: &str) -> String {
+        self.unescape(text)
+    }
+
+    fn enclosure(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        Some(match name {
+            "Op" | "Bq" => ("[", "]"),
+            "Brq" => ("{", "}"),
+            "Pq" => ("(", ")"),
+            "Aq" => ("<", ">"),
+            "Dq" => self.glyphs.double_quotes,
+            "Sq" | "Ql" => self.glyphs.single_quotes,
+            "Qq" => ("\"", "\""),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, name: &str, words: &[&str]) -> String {
+        let words: Vec<String> = words.iter().map(|w| self.unescape(w)).collect();
+        let text = match name {
+            "Fl" if words.is_empty() => "-".to_string(),
+            "Fl" => words
+                .iter()
+                .map(|w| format!("-{w}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            "Ar" if words.is_empty() => "file ...".to_string(),
+            "Nm" if words.is_empty() => self.name.clone(),
+            "Xr" => match words.as_slice() {
+                [page, section] => format!("{page}({section})"),
+                _ => words.join(" "),
+            },
+            "Fn" => match words.split_first() {
+                Some((function, args)) => {
+                    return format!(
+                        "{}({})",
+                        self.paint(|t| &t.name, function),
+                        args.iter()
+                            .map(|a| self.paint(|t| &t.argument, a))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                None => String::new(),
+            },
+            "Lk" => match words.split_first() {
+                Some((url, [])) => url.clone(),
+                Some((url, text)) => {
+                    return format!("{}: {}", text.join(" "), self.paint(|t| &t.link, url))
+                }
+                None => String::new(),
+            },
+            "In" => format!("#include <{}>", words.join(" ")),
+            _ => words.join(" "),
+        };
+        let style: fn(&Theme) -> &Style = match name {
+            "Nm" | "In" | "Fd" | "Cd" => |t| &t.name,
+            "Fl" | "Cm" | "Ic" => |t| &t.flag,
+            "Ar" | "Va" | "Fa" | "Pa" | "Ft" | "Vt" => |t| &t.argument,
+            "Sy" => |t| &t.bold,
+            "Em" => |t| &t.italic,
+            "Xr" | "Lk" | "Mt" | "Sx" => |t| &t.link,
+            _ => return text,
+        };
+        self.paint(style, &text)
+    }
+}
+
+/// The width of a list for a `-width` argument: scaled widths in ens as
+/// is, and the width of other strings plus a gap.
+fn tag_width(width: &str) -> usize {
+    match width {
+        "Ds" | "indent" => OFFSET,
+        w => match w.strip_suffix('n').and_then(|n| n.parse().ok()) {
+            Some(n) => n,
+            None => term::width(w) + 2,
+        },
+    }
+}
@@ -0,0 +1,380 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Validation of the macros of a page against the **mdoc** language, see
+//! [`Mdoc::validate`].
+
+use super::*;
+
+/// A problem found by [`Mdoc::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The position of the line in the document, counting from 1.
+    pub line: usize,
+    /// Where the line comes from, if it is known, see [`Origin`].
+    pub origin: Option<Origin>,
+    pub problem: Problem,
+}
+
+/// What is wrong with a line of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// A macro that isn't an **mdoc** macro or a roff request allowed in
+    /// **mdoc** pages.
+    UnknownMacro(String),
+    /// A macro with fewer arguments than it requires.
+    TooFewArguments {
+        name: String,
+        min: usize,
+        found: usize,
+    },
+    /// A macro with more arguments than it accepts.
+    TooManyArguments {
+        name: String,
+        max: usize,
+        found: usize,
+    },
+    /// A block, such as a `Bl` list, that is never closed.
+    Unclosed(String),
+    /// The end of a block that isn't open, such as an `El` without `Bl`.
+    Unopened { name: String, open: String },
+    /// A `Dt` or `Xr` with a section man(1) doesn't know, see
+    /// [`ManualSection::is_valid`].
+    UnknownSection { name: String, section: String },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " (from {origin})")?;
+        }
+        write!(f, ": {}", self.problem)
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownMacro(name) => write!(f, "unknown macro {name}"),
+            Self::TooFewArguments { name, min, found } => write!(
+                f,
+                "{name} needs at least {min} {}, found {found}",
+                plural(*min)
+            ),
+            Self::TooManyArguments { name, max, found } => write!(
+                f,
+                "{name} takes at most {max} {}, found {found}",
+                plural(*max)
+            ),
+            Self::Unclosed(name) => write!(f, "{name} is not closed"),
+            Self::Unopened { name, open } => write!(f, "{name} without {open}"),
+            Self::UnknownSection { name, section } => {
+                write!(f, "{name}: unknown manual section {section}")
+            }
+        }
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        "argument"
+    } else {
+        "arguments"
+    }
+}
+
+/// The **mdoc** macros, with the least and the most arguments they take.
+///
+/// The arguments are counted up to the first macro called from them, and
+/// delimiters such as `,` aren't counted.
+const MACROS: &[(&str, usize, Option<usize>)] = &[
+    // Prologue.
+    ("Dd", 0, None),
+    ("Dt", 1, Some(3)),
+    ("Os", 0, None),
+    // Sections and paragraphs.
+    ("Sh", 1, None),
+    ("Ss", 1, None),
+    ("Sx", 1, None),
+    ("Pp", 0, Some(0)),
+    ("Lp", 0, Some(0)),
+    ("Tg", 0, Some(1)),
+    // Blocks.
+    ("Bd", 1, None),
+    ("Ed", 0, Some(0)),
+    ("Bl", 1, None),
+    ("El", 0, Some(0)),
+    ("It", 0, None),
+    ("Ta", 0, None),
+    ("Bf", 1, Some(1)),
+    ("Ef", 0, Some(0)),
+    ("Bk", 1, Some(1)),
+    ("Ek", 0, Some(0)),
+    ("D1", 1, None),
+    ("Dl", 1, None),
+    ("Rs", 0, Some(0)),
+    ("Re", 0, Some(0)),
+    ("%A", 1, None),
+    ("%B", 1, None),
+    ("%C", 1, None),
+    ("%D", 1, None),
+    ("%I", 1, None),
+    ("%J", 1, None),
+    ("%N", 1, None),
+    ("%O", 1, None),
+    ("%P", 1, None),
+    ("%Q", 1, None),
+    ("%R", 1, None),
+    ("%T", 1, None),
+    ("%U", 1, None),
+    ("%V", 1, None),
+    // Semantic markup.
+    ("Nm", 0, None),
+    ("Nd", 1, None),
+    ("Ar", 0, None),
+    ("Fl", 0, None),
+    ("Cm", 1, None),
+    ("Op", 1, None),
+    ("Ic", 1, None),
+    ("Pa", 0, None),
+    ("Ev", 1, None),
+    ("Dv", 1, None),
+    ("Er", 1, None),
+    ("Va", 1, None),
+    ("Vt", 1, None),
+    ("Ft", 1, None),
+    ("Fn", 1, None),
+    ("Fa", 1, None),
+    ("Fd", 1, None),
+    ("Fo", 1, Some(1)),
+    ("Fc", 0, None),
+    ("In", 1, Some(1)),
+    ("Cd", 1, None),
+    ("Ad", 1, None),
+    ("An", 1, None),
+    ("Lb", 1, Some(1)),
+    ("Mt", 1, None),
+    ("Lk", 1, None),
+    ("Ms", 1, None),
+    ("Xr", 1, Some(2)),
+    ("Tn", 1, None),
+    ("St", 1, Some(1)),
+    ("Ex", 1, None),
+    ("Rv", 1, None),
+    ("Ot", 0, None),
+    ("At", 0, None),
+    ("Bsx", 0, None),
+    ("Bx", 0, None),
+    ("Dx", 0, None),
+    ("Fx", 0, None),
+    ("Nx", 0, None),
+    ("Ox", 0, None),
+    ("Ux", 0, None),
+    // Physical markup and spacing.
+    ("Em", 1, None),
+    ("Sy", 1, None),
+    ("Li", 1, None),
+    ("No", 0, None),
+    ("Ql", 1, None),
+    ("Ns", 0, None),
+    ("Sm", 0, Some(1)),
+    ("Bt", 0, Some(0)),
+    ("Ud", 0, Some(0)),
+    ("Db", 0, Some(1)),
+    // Enclosures.
+    ("Aq", 0, None),
+    ("Bq", 0, None),
+    ("Brq", 0, None),
+    ("Dq", 0, None),
+    ("Pq", 0, None),
+    ("Qq", 0, None),
+    ("Sq", 0, None),
+    ("Ao", 0, None),
+    ("Ac", 0, None),
+    ("Bo", 0, None),
+    ("Bc", 0, None),
+    ("Bro", 0, None),
+    ("Brc", 0, None),
+    ("Do", 0, None),
+    ("Dc", 0, None),
+    ("Eo", 0, None),
+    ("Ec", 0, None),
+    ("Oo", 0, None),
+    ("Oc", 0, None),
+    ("Po", 0, None),
+    ("Pc", 0, None),
+    ("Qo", 0, None),
+    ("Qc", 0, None),
+    ("So", 0, None),
+    ("Sc", 0, None),
+    ("Xo", 0, None),
+    ("Xc", 0, None),
+    ("Es", 0, Some(2)),
+    ("En", 0, None),
+];
+
+/// The roff requests allowed in **mdoc** pages, and the tbl(1) and eqn(1)
+/// blocks.
+const REQUESTS: &[&str] = &[
+    "ad", "br", "ce", "de", "ds", "el", "fi", "ft", "hy", "ie", "if", "ig", "in", "ll", "mc", "na",
+    "ne", "nf", "nh", "nr", "ns", "rm", "rr", "so", "sp", "ta", "ti", "tr", "EQ", "EN", "TS", "TE",
+];
+
+/// The macros that open blocks, with the macros that close them.
+pub(crate) const BLOCKS: &[(&str, &str)] = &[
+    ("Bl", "El"),
+    ("Bd", "Ed"),
+    ("Bf", "Ef"),
+    ("Bk", "Ek"),
+    ("Rs", "Re"),
+    ("Ao", "Ac"),
+    ("Bo", "Bc"),
+    ("Bro", "Brc"),
+    ("Do", "Dc"),
+    ("Eo", "Ec"),
+    ("Fo", "Fc"),
+    ("Oo", "Oc"),
+    ("Po", "Pc"),
+    ("Qo", "Qc"),
+    ("So", "Sc"),
+    ("Xo", "Xc"),
+];
+
+impl Mdoc {
+    /// Check every control line of the page against the **mdoc**
+    /// language: that its macro exists, that it has as many arguments as
+    /// the macro takes, that the sections of `Dt` and `Xr` are
+    /// [valid](ManualSection::is_valid), and that the blocks it opens, such as `Bl` lists,
+    /// are closed.
+    ///
+    /// Comments are skipped, and macros called from the arguments of
+    /// others, as in `Op Fl v`, aren't checked.
+    ///
+    /// ```
+    /// # use mdoc::*;
+    /// let page = Mdoc::parse(".Sh NAME\n.Nm foo\n.Xr ls 1 x\n.Bl -tag\n.Fo\n.Fc\n");
+    /// let diagnostics: Vec<String> = page.validate().iter().map(ToString::to_string).collect();
+    /// assert_eq!(
+    ///     diagnostics,
+    ///     [
+    ///         "line 3: Xr takes at most 2 arguments, found 3",
+    ///         "line 5: Fo needs at least 1 argument, found 0",
+    ///         "line 4: Bl is not closed",
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let lines = self.document_lines();
+        // The origins are kept by index into `lines`, which sections set
+        // with the section methods shift.
+        let origins = matches!(lines, Cow::Borrowed(_));
+        let diagnostic = |index: usize, problem: Problem| Diagnostic {
+            line: index + 1,
+            origin: if origins {
+                self.origin(index).cloned()
+            } else {
+                None
+            },
+            problem,
+        };
+        let mut ret = vec![];
+        // The open blocks, with the index of the line that opened them.
+        let mut open: Vec<(&str, usize)> = vec![];
+        for (index, line) in lines.iter().enumerate() {
+            let Line::Control { name, args } = line else {
+                continue;
+            };
+            if name == "\\\"" || REQUESTS.contains(&name.as_ref()) {
+                continue;
+            }
+            let Some((_, min, max)) = MACROS.iter().find(|(m, _, _)| m == name) else {
+                ret.push(diagnostic(index, Problem::UnknownMacro(name.to_string())));
+                continue;
+            };
+            let args: Vec<&str> = args
+                .iter()
+                .filter(|a| !a.starts_with("\\\n"))
+                .map(|a| a.as_ref())
+                .collect();
+            let content = args.iter().filter(|a| !phrase::is_delimiter(a)).count();
+            let own = args
+                .iter()
+                .take_while(|a| !phrase::is_callable(a))
+                .filter(|a| !phrase::is_delimiter(a))
+                .count();
+            if content < *min {
+                ret.push(diagnostic(
+                    index,
+                    Problem::TooFewArguments {
+                        name: name.to_string(),
+                        min: *min,
+                        found: content,
+                    },
+                ));
+            } else if let Some(max) = max.filter(|max| own > *max) {
+                ret.push(diagnostic(
+                    index,
+                    Problem::TooManyArguments {
+                        name: name.to_string(),
+                        max,
+                        found: own,
+                    },
+                ));
+            }
+            if matches!(name.as_ref(), "Dt" | "Xr") && own >= 2 {
+                let section = parser::unquote(args[1]);
+                if !section.parse::<ManualSection>().is_ok_and(|s| s.is_valid()) {
+                    ret.push(diagnostic(
+                        index,
+                        Problem::UnknownSection {
+                            name: name.to_string(),
+                            section,
+                        },
+                    ));
+                }
+            }
+            if let Some((opening, _)) = BLOCKS.iter().find(|(o, _)| o == name) {
+                open.push((opening, index));
+            } else if let Some((opening, _)) = BLOCKS.iter().find(|(_, c)| c == name) {
+                match open.iter().rposition(|(o, _)| o == opening) {
+                    Some(position) => {
+                        for (block, index) in open.drain(position..).skip(1) {
+                            ret.push(diagnostic(index, Problem::Unclosed(block.to_string())));
+                        }
+                    }
+                    None => ret.push(diagnostic(
+                        index,
+                        Problem::Unopened {
+                            name: name.to_string(),
+                            open: opening.to_string(),
+                        },
+                    )),
+                }
+            }
+        }
+        for (block, index) in open {
+            ret.push(diagnostic(index, Problem::Unclosed(block.to_string())));
+        }
+        ret
+    }
+}
@@ -0,0 +1,160 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+/// A non-fatal issue found while converting or rendering a page, such as
+/// metadata that was dropped or a heuristic that may have guessed wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// What the warning is about, e.g. a section or an option.
+    pub context: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{context}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// How questionable input is handled by conversions and renderers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Convert it on a best-effort basis, silently.
+    Lenient,
+    /// Convert it on a best-effort basis, and report a warning.
+    #[default]
+    Warn,
+    /// Reject it: conversions that return a [`Result`] fail, and
+    /// [`Warnings::check`] fails for the others.
+    Deny,
+}
+
+impl std::str::FromStr for Strictness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lenient" => Ok(Self::Lenient),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            other => Err(format!("unknown strictness `{other}`")),
+        }
+    }
+}
+
+/// The error of [`Warnings::check`] when warnings are denied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denied(pub Vec<Warning>);
+
+impl std::fmt::Display for Denied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, warning) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{warning}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Denied {}
+
+/// A collector of [`Warning`]s, passed to the `*_with_warnings` variants of
+/// conversion and rendering functions so that the caller decides how to
+/// report them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+    strictness: Strictness,
+}
+
+impl Warnings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A collector that handles warnings according to `strictness`.
+    pub fn with_strictness(strictness: Strictness) -> Self {
+        Self {
+            warnings: vec![],
+            strictness,
+        }
+    }
+
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Record a warning.
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.record(None, message.into());
+    }
+
+    /// Record a warning about `context`.
+    pub fn push_in(&mut self, context: impl Into<String>, message: impl Into<String>) {
+        self.record(Some(context.into()), message.into());
+    }
+
+    fn record(&mut self, context: Option<String>, message: String) {
+        if self.strictness != Strictness::Lenient {
+            self.warnings.push(Warning { context, message });
+        }
+    }
+
+    /// Fail if warnings were recorded and they are denied.
+    pub fn check(&self) -> Result<(), Denied> {
+        if self.strictness == Strictness::Deny && !self.is_empty() {
+            Err(Denied(self.warnings.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Warning> {
+        self.warnings.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Warning> {
+        self.warnings
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
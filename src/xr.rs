@@ -0,0 +1,166 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+//! Typed cross references and the SEE ALSO list.
+
+use super::*;
+use std::{fmt, str::FromStr};
+
+/// A cross reference to another manual page, written as `Xr name section`.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let xr: Xr = "ls(1)".parse().unwrap();
+/// assert_eq!(xr.section, ManualSection::Commands);
+/// assert_eq!(xr.to_string(), "ls(1)");
+/// assert!(Xr::new("ls", "10").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Xr {
+    pub name: String,
+    pub section: ManualSection,
+}
+
+impl Xr {
+    /// A reference to `name` in `section`, which must be a valid manual
+    /// section such as `1`, `3p` or `n`.
+    pub fn new(name: impl Into<String>, section: &str) -> Result<Self, String> {
+        let name = name.into();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("invalid page name {name:?}"));
+        }
+        let section: ManualSection = match section.parse() {
+            Ok(section) => section,
+            Err(never) => match never {},
+        };
+        if !section.is_valid() {
+            return Err(format!("unknown manual section {section}"));
+        }
+        Ok(Self { name, section })
+    }
+
+    /// The `Xr` line.
+    pub fn line(&self) -> Line {
+        Line::control(
+            "Xr".into(),
+            vec![self.name.clone().into(), self.section.to_string().into()],
+        )
+    }
+}
+
+impl fmt::Display for Xr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}({})", self.name, self.section)
+    }
+}
+
+impl FromStr for Xr {
+    type Err = String;
+
+    /// Parse `name(section)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_suffix(')')
+            .and_then(|s| s.rsplit_once('('))
+            .ok_or_else(|| format!("expected name(section), got {s:?}"))
+            .and_then(|(name, section)| Self::new(name, section))
+    }
+}
+
+impl From<Xr> for Line {
+    fn from(xr: Xr) -> Self {
+        xr.line()
+    }
+}
+
+/// The order of references in SEE ALSO: by section, numerically so that 8
+/// comes before 10, then by name.
+pub(crate) fn order_key(name: &str, section: &str) -> (u32, String, String) {
+    let digits: String = section.chars().take_while(char::is_ascii_digit).collect();
+    (
+        digits.parse().unwrap_or(u32::MAX),
+        section.to_string(),
+        name.to_string(),
+    )
+}
+
+/// The cross references of a SEE ALSO section, sorted by section and then
+/// by name and separated by commas.
+///
+/// # Example
+///
+/// ```
+/// # use mdoc::*;
+/// let mut doc = Mdoc::default();
+/// SeeAlso::new()
+///     .reference("stat(2)".parse().unwrap())
+///     .reference("ls(1)".parse().unwrap())
+///     .reference("chmod(1)".parse().unwrap())
+///     .add_to(&mut doc);
+/// assert_eq!(
+///     doc.to_mdoc(),
+///     ".Sh SEE ALSO\n.Xr chmod 1 ,\n.Xr ls 1 ,\n.Xr stat 2\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SeeAlso {
+    pub references: Vec<Xr>,
+}
+
+impl SeeAlso {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reference; duplicates are listed once.
+    pub fn reference(mut self, xr: Xr) -> Self {
+        self.references.push(xr);
+        self
+    }
+
+    /// The sorted `Xr` lines, each but the last followed by a comma.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut references = self.references.clone();
+        references.sort_by_cached_key(|xr| order_key(&xr.name, xr.section.as_str()));
+        references.dedup();
+        let count = references.len();
+        references
+            .into_iter()
+            .enumerate()
+            .map(|(i, xr)| {
+                let mut line = xr.line();
+                if let Line::Control { args, .. } = &mut line {
+                    if i + 1 < count {
+                        args.push(",".into());
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+
+    /// Append the references to the SEE ALSO section of `doc`.
+    pub fn add_to(&self, doc: &mut Mdoc) {
+        doc.see_also(self.lines());
+    }
+}
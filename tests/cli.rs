@@ -0,0 +1,163 @@
+//
+// mdoc
+//
+// Copyright 2024 Emmanouil Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of mdoc.
+//
+// mdoc is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// mdoc is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with mdoc. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: EUPL-1.2 OR GPL-3.0-or-later
+
+use std::{path::Path, process::Command};
+
+/// Run the `mdoc` binary in `dir`, returning its standard output or its
+/// error message.
+fn mdoc(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdoc"))
+        .args(args)
+        .current_dir(dir)
+        .env_remove("SOURCE_DATE_EPOCH")
+        .output()
+        .unwrap();
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout).unwrap())
+    } else {
+        Err(String::from_utf8(output.stderr).unwrap())
+    }
+}
+
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("mdoc-cli-{name}-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const SOURCE: &str = "---
+title: foo
+section: 1
+date: 2024-03-01
+---
+
+# NAME
+
+foo - do things
+
+# DESCRIPTION
+
+Does things.
+";
+
+#[test]
+fn test_convert_check() {
+    let dir = scratch_dir("check");
+    std::fs::write(dir.join("foo.md"), SOURCE).unwrap();
+    mdoc(&dir, &["convert", "foo.md", "-o", "foo.1"]).unwrap();
+    let page = std::fs::read_to_string(dir.join("foo.1")).unwrap();
+    assert!(page.starts_with(".\\\" Generator: mdoc"), "{page}");
+    mdoc(&dir, &["check", "foo.md", "foo.1"]).unwrap();
+
+    std::fs::write(dir.join("foo.md"), SOURCE.replace("Does", "Did")).unwrap();
+    let err = mdoc(&dir, &["check", "foo.md", "foo.1"]).unwrap_err();
+    assert!(err.contains("1 page(s) are out of date"), "{err}");
+
+    // Gzipped pages are compared decompressed.
+    mdoc(&dir, &["convert", "foo.md", "-o", "foo.1.gz"]).unwrap();
+    mdoc(&dir, &["check", "foo.md", "foo.1.gz"]).unwrap();
+    std::fs::write(dir.join("foo.md"), SOURCE).unwrap();
+    let err = mdoc(&dir, &["check", "foo.md", "foo.1.gz"]).unwrap_err();
+    assert!(err.contains("1 page(s) are out of date"), "{err}");
+
+    // Pages printed to the standard output aren't stamped.
+    let out = mdoc(&dir, &["convert", "foo.md"]).unwrap();
+    assert!(!out.contains("Generator"), "{out}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_convert_markdown() {
+    let dir = scratch_dir("markdown");
+    std::fs::write(
+        dir.join("foo.1"),
+        ".Dd March 1, 2024\n.Dt FOO 1\n.Os\n.Sh NAME\n.Nm foo\n.Nd do things\n\
+         .Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v\nBe verbose.\n.El\n",
+    )
+    .unwrap();
+    mdoc(&dir, &["convert", "foo.1", "-o", "foo.md"]).unwrap();
+    let markdown = std::fs::read_to_string(dir.join("foo.md")).unwrap();
+    assert_eq!(
+        markdown,
+        "---\ntitle: foo\nsection: 1\ndate: March 1, 2024\n---\n\n\
+         # foo(1) -- do things\n\n## OPTIONS\n\n* `-v`:\n  Be verbose.\n"
+    );
+    // The Markdown converts back to the same page.
+    let page = mdoc(&dir, &["convert", "foo.md"]).unwrap();
+    assert_eq!(page, std::fs::read_to_string(dir.join("foo.1")).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_restamp() {
+    let dir = scratch_dir("restamp");
+    std::fs::write(dir.join("foo.md"), SOURCE).unwrap();
+    std::fs::write(dir.join("over.1"), ".Sh DESCRIPTION\nDoes other things.\n").unwrap();
+    mdoc(&dir, &["convert", "foo.md", "-o", "foo.1"]).unwrap();
+    mdoc(&dir, &["merge", "foo.1", "over.1", "-o", "merged.1"]).unwrap();
+    mdoc(&dir, &["convert", "foo.1", "-o", "copy.1"]).unwrap();
+    for name in ["merged.1", "copy.1"] {
+        let page = std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(page.matches("Generator:").count(), 1, "{page}");
+        assert_eq!(page.matches("Content-Hash:").count(), 1, "{page}");
+    }
+    let merged = std::fs::read_to_string(dir.join("merged.1")).unwrap();
+    assert!(merged.contains("Does other things."), "{merged}");
+    assert_eq!(
+        mdoc::provenance::check(&merged),
+        mdoc::provenance::Status::Unmodified
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_extract() {
+    let dir = scratch_dir("extract");
+    std::fs::write(
+        dir.join("foo.1"),
+        ".TH FOO 1\n.SH NAME\nfoo \\- do things\n.SH OPTIONS\n.TP\n.B \\-v\nBe verbose.\n",
+    )
+    .unwrap();
+    let extract = |to: &str| {
+        mdoc(
+            &dir,
+            &["extract", "--section", "options", "--to", to, "foo.1"],
+        )
+    };
+    assert_eq!(
+        extract("mdoc").unwrap(),
+        ".Sh OPTIONS\n.Bl -tag -width Ds\n.It Fl v\nBe verbose.\n.El\n"
+    );
+    assert_eq!(
+        extract("text").unwrap(),
+        "OPTIONS\n     -v    Be verbose.\n"
+    );
+    assert_eq!(
+        extract("markdown").unwrap(),
+        "## OPTIONS\n\n* `-v`:\n  Be verbose.\n"
+    );
+    assert!(extract("json").unwrap().contains("\"title\": \"OPTIONS\""));
+    let err = mdoc(&dir, &["extract", "--section", "FILES", "foo.1"]).unwrap_err();
+    assert!(err.contains("no section titled FILES"), "{err}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}